@@ -0,0 +1,154 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use crate::tree::{FileTree, TreeNode};
+
+/// Files last modified within the same span of time, in [AgeReport]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AgeBucket {
+    /// the span, e.g. **last month**
+    pub label: &'static str,
+    /// age in days of the oldest files of the bucket, None for the last bucket which has no limit
+    pub max_days: Option<u64>,
+    /// number of files
+    pub files: usize,
+    /// total size of the files in bytes
+    pub bytes: u64,
+}
+
+/// A directory none of whose files was modified lately, in [AgeReport]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StaleDir {
+    /// path relative to the project directory
+    pub path: PathBuf,
+    /// modification time of its newest file
+    pub last_modified: SystemTime,
+    /// number of files within it
+    pub files: usize,
+}
+
+/// How long ago the project files were modified, returned by [method.age_report]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AgeReport {
+    /// the files by age, the most recent first, each file being counted in the first bucket it fits in
+    pub buckets: Vec<AgeBucket>,
+    /// the directories with no file modified within the staleness period, the stalest first. Directories within a
+    /// stale directory are not listed apart
+    pub stale_dirs: Vec<StaleDir>,
+    /// files whose modification time is unknown, as for projects held in memory
+    pub unknown: usize,
+}
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// The buckets of the histogram, with their upper bound in days
+const BUCKETS: [(&str, Option<u64>); 7] = [
+    ("last week", Some(7)),
+    ("last month", Some(30)),
+    ("last 3 months", Some(91)),
+    ("last 6 months", Some(182)),
+    ("last year", Some(365)),
+    ("last 2 years", Some(730)),
+    ("older", None),
+];
+
+/// Newest modification time & number of the files within a directory, the ignored entries aside. Stale directories
+/// are added to ```stale``` on the way
+fn visit(
+    node: &TreeNode,
+    stale_before: SystemTime,
+    stale: &mut Vec<StaleDir>,
+) -> (Option<SystemTime>, usize) {
+    if node.is_ignored || node.name == ".git" {
+        return (None, 0);
+    }
+    if !node.is_dir {
+        return (node.modified, 1);
+    }
+
+    let (mut newest, mut files) = (None, 0);
+    for child in &node.children {
+        let (modified, count) = visit(child, stale_before, stale);
+        newest = newest.max(modified);
+        files += count;
+    }
+
+    // directories within a stale directory are stale too, only the outermost is kept. A stale project is not a stale
+    // directory of its own
+    let is_root = node.path.as_os_str().is_empty();
+    if let Some(last_modified) = newest.filter(|newest| !is_root && *newest < stale_before) {
+        stale.retain(|dir| !dir.path.starts_with(&node.path));
+        stale.push(StaleDir {
+            path: node.path.clone(),
+            last_modified,
+            files,
+        });
+    }
+
+    (newest, files)
+}
+
+/// Age histogram & stale directories of the files of the tree, ```now``` being the time they are aged against
+pub(crate) fn report(tree: &FileTree, now: SystemTime, stale_after: Duration) -> AgeReport {
+    let mut buckets: Vec<AgeBucket> = BUCKETS
+        .iter()
+        .map(|(label, max_days)| AgeBucket {
+            label,
+            max_days: *max_days,
+            files: 0,
+            bytes: 0,
+        })
+        .collect();
+    let mut unknown = 0;
+
+    let files = tree
+        .iter()
+        .filter(|node| !node.is_dir && !node.is_ignored && !node.path.starts_with(".git"));
+    for node in files {
+        let modified = match node.modified {
+            Some(modified) => modified,
+            None => {
+                unknown += 1;
+                continue;
+            }
+        };
+        // files modified in the future, by a skewed clock, are as recent as can be
+        let days = now.duration_since(modified).unwrap_or_default().as_secs() / DAY;
+        let bucket = buckets
+            .iter_mut()
+            .find(|bucket| bucket.max_days.is_none_or(|max_days| days < max_days));
+        if let Some(bucket) = bucket {
+            bucket.files += 1;
+            bucket.bytes += node.size;
+        }
+    }
+
+    let mut stale_dirs = vec![];
+    let stale_before = now.checked_sub(stale_after).unwrap_or(SystemTime::UNIX_EPOCH);
+    visit(&tree.root, stale_before, &mut stale_dirs);
+    stale_dirs.sort_by_key(|dir| (dir.last_modified, Reverse(dir.files)));
+
+    AgeReport {
+        buckets,
+        stale_dirs,
+        unknown,
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::assets::AssetStats;
+use crate::code::Stats;
+use crate::hooks::GitHook;
+use crate::project::{BuildSystem, IsIgnored, Project, ProjectKind};
+use crate::ruleset::RuleSet;
+use crate::stats::WalkStats;
+
+/// The result of [method.analyze]: a project parsed, with its rules built and its code counted, in one call
+/// Nothing is left to fill in, what was not found is empty rather than None, and nothing can be changed afterwards
+/// ```no_run
+/// let analysis = Project::analyze("/my/project", ParseOptions::default())?;
+/// println!("{:?} {:?}", analysis.langs(), analysis.code_stats());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    project: Project,
+    ruleset: RuleSet,
+    code_stats: Stats,
+    asset_stats: AssetStats,
+    walk_stats: WalkStats,
+}
+
+impl Analysis {
+    /// Wraps a project whose rules have been built
+    pub(crate) fn new(project: Project, ruleset: RuleSet) -> Self {
+        Analysis {
+            code_stats: project.code_stats.clone().unwrap_or_default(),
+            asset_stats: project.asset_stats.clone().unwrap_or_default(),
+            walk_stats: project.walk_stats.unwrap_or_default(),
+            project,
+            ruleset,
+        }
+    }
+
+    /// The project directory
+    pub fn dir(&self) -> &Path {
+        &self.project.dir
+    }
+
+    /// The detected languages, main language first
+    pub fn langs(&self) -> &[String] {
+        self.project.project_langs.as_deref().unwrap_or_default()
+    }
+
+    /// The detected build & development tools
+    pub fn tools(&self) -> &[String] {
+        self.project.project_tools.as_deref().unwrap_or_default()
+    }
+
+    /// The build systems among the detected tools
+    pub fn build_systems(&self) -> &[BuildSystem] {
+        self.project.build_systems.as_deref().unwrap_or_default()
+    }
+
+    /// The refined classification of the detected languages
+    pub fn kinds(&self) -> &[ProjectKind] {
+        self.project.project_kinds.as_deref().unwrap_or_default()
+    }
+
+    /// Whether the project is a git checkout, a linked worktree or a bare repository
+    pub fn is_git(&self) -> bool {
+        self.project.is_git.unwrap_or(false)
+    }
+
+    /// The git hooks configured or installed in the project
+    pub fn git_hooks(&self) -> &[GitHook] {
+        self.project.git_hooks.as_deref().unwrap_or_default()
+    }
+
+    /// The local environment directories found in the project
+    pub fn env_dirs(&self) -> &[String] {
+        self.project.env_dirs.as_deref().unwrap_or_default()
+    }
+
+    /// The compiled gitignore rules
+    pub fn ruleset(&self) -> &RuleSet {
+        &self.ruleset
+    }
+
+    /// Whether a path within the project is ignored, see [method.is_ignored]
+    pub fn is_ignored(&self, path_str: &str) -> Option<IsIgnored> {
+        self.project.is_ignored(path_str)
+    }
+
+    /// Line counts keyed by language
+    pub fn code_stats(&self) -> &Stats {
+        &self.code_stats
+    }
+
+    /// Number & size of non-code files per category
+    pub fn asset_stats(&self) -> &AssetStats {
+        &self.asset_stats
+    }
+
+    /// Counts of the entries walked & skipped while counting
+    pub fn walk_stats(&self) -> &WalkStats {
+        &self.walk_stats
+    }
+
+    /// Non fatal problems met while analyzing
+    pub fn warnings(&self) -> &[String] {
+        &self.project.warnings
+    }
+
+    /// The parsed project, for what the analysis does not expose
+    pub fn project(&self) -> &Project {
+        &self.project
+    }
+
+    /// The parsed project, to be changed further
+    pub fn into_project(self) -> Project {
+        self.project
+    }
+}
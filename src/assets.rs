@@ -0,0 +1,113 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::Serialize;
+use std::{collections::BTreeMap, path::Path};
+
+/// Kind of non-code file, recognized from its name or extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum FileCategory {
+    /// png, jpg, svg, gif...
+    Image,
+    /// ttf, otf, woff...
+    Font,
+    /// csv, json, parquet, sqlite...
+    Data,
+    /// markdown, rst, pdf, txt...
+    Doc,
+    /// yaml, toml, ini, .env...
+    Config,
+    /// the YAML of Helm charts & Kubernetes manifests, told apart from the application config by [dir_stats](crate::code::dir_stats)
+    Infra,
+    /// package manager lockfiles such as Cargo.lock or yarn.lock
+    Lockfile,
+    /// audio & video
+    Media,
+    /// zip, tar, gz...
+    Archive,
+}
+
+/// Number and total size of the files in a [FileCategory]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CategoryStats {
+    /// number of files
+    pub files: usize,
+    /// total size in bytes
+    pub bytes: u64,
+}
+
+/// File counts & sizes keyed by category
+pub type AssetStats = BTreeMap<FileCategory, CategoryStats>;
+
+const LOCKFILES: [&str; 12] = [
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "composer.lock",
+    "Gemfile.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "go.sum",
+    "pubspec.lock",
+];
+
+const EXTENSIONS: [(FileCategory, &[&str]); 7] = [
+    (
+        FileCategory::Image,
+        &["png", "jpg", "jpeg", "gif", "bmp", "svg", "ico", "webp", "tif", "tiff", "psd"],
+    ),
+    (FileCategory::Font, &["ttf", "otf", "woff", "woff2", "eot"]),
+    (
+        FileCategory::Data,
+        &["csv", "tsv", "json", "jsonl", "ndjson", "parquet", "xml", "sqlite", "db", "avro"],
+    ),
+    (
+        FileCategory::Doc,
+        &["md", "markdown", "rst", "adoc", "txt", "pdf", "doc", "docx", "odt", "rtf"],
+    ),
+    (
+        FileCategory::Config,
+        &["yaml", "yml", "toml", "ini", "cfg", "conf", "env", "properties", "editorconfig"],
+    ),
+    (
+        FileCategory::Media,
+        &["mp3", "wav", "ogg", "flac", "mp4", "mov", "avi", "mkv", "webm"],
+    ),
+    (
+        FileCategory::Archive,
+        &["zip", "tar", "gz", "tgz", "bz2", "xz", "7z", "rar", "jar"],
+    ),
+];
+
+/// Category of a file, lockfiles being recognized by name before extensions are looked at
+pub(crate) fn category(path: &Path) -> Option<FileCategory> {
+    let name = path.file_name()?.to_string_lossy();
+    if LOCKFILES.contains(&name.as_ref()) {
+        return Some(FileCategory::Lockfile);
+    }
+
+    // dotfiles such as .env or .editorconfig have no extension, their name stands for it
+    let extension = match path.extension() {
+        Some(extension) => extension.to_string_lossy().to_lowercase(),
+        None => name.trim_start_matches('.').to_lowercase(),
+    };
+
+    EXTENSIONS
+        .iter()
+        .find(|(_, extensions)| extensions.contains(&extension.as_str()))
+        .map(|(category, _)| *category)
+}
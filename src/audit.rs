@@ -0,0 +1,283 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use std::{collections::BTreeMap, ops::Range, path::PathBuf};
+
+use crate::tree::FileTree;
+use crate::vfs::Vfs;
+
+/// Source files without the expected license header, returned by [method.audit_license_headers]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderAudit {
+    /// files starting without any comment, keyed by language
+    pub missing: BTreeMap<String, Vec<PathBuf>>,
+    /// files starting with a comment other than the expected header, keyed by language
+    pub different: BTreeMap<String, Vec<PathBuf>>,
+    /// number of source files audited
+    pub checked: usize,
+}
+
+impl HeaderAudit {
+    /// Whether every source file carries the expected header
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.different.is_empty()
+    }
+}
+
+/// What [method.apply_license_header] does to a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderAction {
+    /// the header is added at the top of the file, below a shebang line
+    Inserted,
+    /// a leading comment mentioning a copyright or license is replaced by the header
+    Updated,
+}
+
+/// A file changed by [method.apply_license_header], or that would be in a dry run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderChange {
+    /// path of the file
+    pub path: PathBuf,
+    /// language, as named by the code stats
+    pub lang: String,
+    /// whether the header is inserted or replaces another one
+    pub action: HeaderAction,
+    /// content of the file with the header
+    pub content: String,
+}
+
+/// Line comment markers and block comment delimiters of a language
+type Syntax = (&'static [&'static str], Option<(&'static str, &'static str)>);
+
+const C_LIKE: Syntax = (&["//"], Some(("/*", "*/")));
+const SLASHES: Syntax = (&["//"], None);
+const BLOCK: Syntax = (&[], Some(("/*", "*/")));
+const HASH: Syntax = (&["#"], None);
+const DASHES: Syntax = (&["--"], None);
+const PERCENT: Syntax = (&["%"], None);
+const MARKUP: Syntax = (&[], Some(("<!--", "-->")));
+
+/// Comment syntax of the languages, as named by the code stats, that can carry a header
+fn syntax(lang: &str) -> Option<Syntax> {
+    let syntax = match lang {
+        "C" | "C++" | "C/C++ Header" | "C#" | "Go" | "Java" | "JavaScript" | "Jsx" | "TypeScript" | "Kotlin"
+        | "Scala" | "Swift" | "Dart" | "Rust" | "PHP" | "Objective-C" => C_LIKE,
+        "Zig" => SLASHES,
+        "CSS" => BLOCK,
+        "Python" | "Ruby" | "Bourne Shell" | "Perl" | "R" | "Elixir" | "Makefile" | "Docker" | "TOML" | "YAML" => {
+            HASH
+        }
+        "SQL" | "Haskell" | "Lua" => DASHES,
+        "Erlang" => PERCENT,
+        "HTML" | "XML" => MARKUP,
+        _ => return None,
+    };
+
+    Some(syntax)
+}
+
+/// Words of the comment lines, without their markers, so that headers compare regardless of layout
+fn words(lines: &[&str], markers: &[&str]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            let mut line = line.trim();
+            // a marker can follow another, as with ```/**``` or `` * `` inside blocks
+            while let Some(marker) = markers.iter().find(|marker| line.starts_with(**marker)) {
+                line = line[marker.len()..].trim_start();
+            }
+            for marker in markers {
+                line = line.strip_suffix(marker).unwrap_or(line).trim_end();
+            }
+            line
+        })
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Range of the lines of the leading comment, after a shebang & blank lines. Empty when the file does not start with
+/// a comment
+fn leading_comment(lines: &[&str], syntax: Syntax) -> Range<usize> {
+    let (line_markers, block) = syntax;
+    let start = lines
+        .iter()
+        .position(|line| !line.starts_with("#!") && !line.trim().is_empty())
+        .unwrap_or(lines.len());
+    let mut end = start;
+
+    while end < lines.len() {
+        let trimmed = lines[end].trim();
+        match block {
+            Some((open, close)) if trimmed.starts_with(open) => {
+                // the whole block is part of the header
+                let block_end = (end..lines.len())
+                    .find(|i| lines[*i].trim_end().ends_with(close) && (*i > end || trimmed.len() > open.len()))
+                    .unwrap_or(lines.len() - 1);
+                end = block_end + 1;
+            }
+            _ if line_markers.iter().any(|marker| trimmed.starts_with(marker)) => end += 1,
+            _ => break,
+        }
+    }
+
+    start..end
+}
+
+/// Markers stripped from the comment lines of a language
+fn markers(syntax: Syntax) -> Vec<&'static str> {
+    let (line_markers, block) = syntax;
+    let mut markers: Vec<&str> = line_markers.to_vec();
+    if let Some((open, close)) = block {
+        markers.extend([open, close, "*"]);
+    }
+    markers
+}
+
+/// Leading comment of a file, skipping a shebang & blank lines. Empty when the file does not start with a comment
+fn header(content: &str, syntax: Syntax) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+
+    words(&lines[leading_comment(&lines, syntax)], &markers(syntax))
+}
+
+/// The header as a comment of the language, line comments being preferred to blocks
+fn render(header: &[&str], syntax: Syntax) -> Vec<String> {
+    match syntax {
+        (&[marker, ..], _) => header
+            .iter()
+            .map(|line| format!("{} {}", marker, line).trim_end().to_string())
+            .collect(),
+        (_, Some((open, close))) => {
+            // C style blocks line their stars up
+            let (inner, close) = match open {
+                "/*" => (" *", format!(" {}", close)),
+                _ => ("", close.to_string()),
+            };
+            let mut lines = vec![open.to_string()];
+            lines.extend(header.iter().map(|line| format!("{} {}", inner, line).trim_end().to_string()));
+            lines.push(close);
+            lines
+        }
+        _ => header.iter().map(|line| line.to_string()).collect(),
+    }
+}
+
+/// Normalized header, with any comment markers it was written with left out
+fn expected_words(expected: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+
+    words(&expected_lines, &["//", "/*", "*/", "*", "#", "--", "%", "<!--", "-->"])
+}
+
+/// Audits the non ignored source files of the tree. Paths are relative to the project directory
+pub(crate) fn license_headers(vfs: &dyn Vfs, tree: &FileTree, expected: &str) -> Result<HeaderAudit> {
+    let expected = expected_words(expected);
+    let mut audit = HeaderAudit::default();
+
+    for node in tree.iter().filter(|node| !node.is_dir && !node.is_ignored) {
+        let (lang, syntax) = match node.lang.as_deref().and_then(|lang| Some((lang, syntax(lang)?))) {
+            Some(lang) => lang,
+            None => continue,
+        };
+
+        let content = match vfs.read_to_string(&node.path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        audit.checked += 1;
+
+        let header = header(&content, syntax);
+        let files = if header.is_empty() {
+            &mut audit.missing
+        } else if !header.contains(&expected) {
+            &mut audit.different
+        } else {
+            continue;
+        };
+        files.entry(lang.to_string()).or_default().push(node.path.clone());
+    }
+
+    Ok(audit)
+}
+
+/// Content of a file with the header, None when the file already carries it
+fn with_header(content: &str, template: &[&str], expected: &str, syntax: Syntax) -> Option<(HeaderAction, String)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let comment = leading_comment(&lines, syntax);
+    let existing = words(&lines[comment.clone()], &markers(syntax));
+
+    if !existing.is_empty() && existing.contains(expected) {
+        return None;
+    }
+
+    let existing = existing.to_lowercase();
+    let (action, replaced) = if existing.contains("copyright") || existing.contains("license") {
+        (HeaderAction::Updated, comment)
+    } else {
+        // other comments, such as module docs, stay below the header
+        let at = usize::from(lines.first().is_some_and(|line| line.starts_with("#!")));
+        (HeaderAction::Inserted, at..at)
+    };
+
+    let mut header = render(template, syntax);
+    if action == HeaderAction::Inserted || lines.get(replaced.end).is_some_and(|line| !line.trim().is_empty()) {
+        header.push(String::new());
+    }
+
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut new_lines: Vec<&str> = lines[..replaced.start].to_vec();
+    new_lines.extend(header.iter().map(String::as_str));
+    new_lines.extend(&lines[replaced.end..]);
+
+    let mut new_content = new_lines.join(newline);
+    if content.ends_with('\n') || lines.is_empty() {
+        new_content.push_str(newline);
+    }
+
+    Some((action, new_content))
+}
+
+/// New contents of the non ignored source files that lack the header. Paths are relative to the project directory
+pub(crate) fn license_header_changes(vfs: &dyn Vfs, tree: &FileTree, template: &str) -> Result<Vec<HeaderChange>> {
+    let expected = expected_words(template);
+    let template: Vec<&str> = template.trim_end().lines().collect();
+    let mut changes = vec![];
+
+    for node in tree.iter().filter(|node| !node.is_dir && !node.is_ignored) {
+        let (lang, syntax) = match node.lang.as_deref().and_then(|lang| Some((lang, syntax(lang)?))) {
+            Some(lang) => lang,
+            None => continue,
+        };
+
+        let content = match vfs.read_to_string(&node.path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        if let Some((action, content)) = with_header(&content, &template, &expected, syntax) {
+            changes.push(HeaderChange {
+                path: node.path.clone(),
+                lang: lang.to_string(),
+                action,
+                content,
+            });
+        }
+    }
+
+    Ok(changes)
+}
@@ -0,0 +1,136 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Badge data in the shields.io [endpoint](https://shields.io/badges/endpoint-badge) format, returned by [method.badges]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Badge {
+    /// always 1
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: u8,
+    /// left hand text, e.g. **language**
+    pub label: String,
+    /// right hand text, e.g. **Rust**
+    pub message: String,
+    /// right hand background, a shields.io color name or a hex code without the leading #
+    pub color: String,
+}
+
+/// GitHub linguist colors of the most common languages
+const COLORS: [(&str, &str); 16] = [
+    ("Rust", "dea584"),
+    ("Python", "3572A5"),
+    ("JavaScript", "f1e05a"),
+    ("TypeScript", "3178c6"),
+    ("Go", "00ADD8"),
+    ("Java", "b07219"),
+    ("Kotlin", "A97BFF"),
+    ("Ruby", "701516"),
+    ("PHP", "4F5D95"),
+    ("Swift", "F05138"),
+    ("Dart", "00B4AB"),
+    ("Elixir", "6e4a7e"),
+    ("Haskell", "5e5086"),
+    ("Scala", "c22d40"),
+    ("C", "555555"),
+    ("C++", "f34b7d"),
+];
+
+/// Count shortened for display, e.g. 12.4k or 3M
+pub(crate) fn short_count(count: u64) -> String {
+    let short = match count {
+        0..=999 => count.to_string(),
+        1_000..=999_999 => format!("{:.1}k", count as f64 / 1_000.0),
+        _ => format!("{:.1}M", count as f64 / 1_000_000.0),
+    };
+
+    short.replace(".0", "")
+}
+
+impl Badge {
+    /// A badge with the given label, message and color
+    pub fn new<L: Into<String>, M: Into<String>, C: Into<String>>(
+        label: L,
+        message: M,
+        color: C,
+    ) -> Self {
+        Badge {
+            schema_version: 1,
+            label: label.into(),
+            message: message.into(),
+            color: color.into(),
+        }
+    }
+
+    pub(crate) fn language(name: &str) -> Self {
+        let color = COLORS
+            .iter()
+            .find(|(lang, _)| *lang == name)
+            .map_or("blue", |(_, color)| color);
+
+        Badge::new("language", name, color)
+    }
+
+    pub(crate) fn lines_of_code(lines: u64) -> Self {
+        Badge::new("lines of code", short_count(lines), "blue")
+    }
+
+    pub(crate) fn license(id: Option<&str>) -> Self {
+        match id {
+            Some(id) => Badge::new("license", id, "green"),
+            None => Badge::new("license", "unknown", "lightgrey"),
+        }
+    }
+
+    /// The badge as shields.io endpoint JSON
+    /// ```no_run
+    /// std::fs::write("language.json", badge.to_json()?)?;
+    /// ```
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// The badge rendered as a flat style SVG, for serving without shields.io
+    /// ```no_run
+    /// std::fs::write("language.svg", badge.to_svg())?;
+    /// ```
+    pub fn to_svg(&self) -> String {
+        // Verdana 11px averages about 7px per character
+        let text_width = |text: &str| text.chars().count() * 7 + 10;
+        let (label_width, message_width) = (text_width(&self.label), text_width(&self.message));
+        let width = label_width + message_width;
+        let color = match self.color.as_str() {
+            "blue" => String::from("#007ec6"),
+            "green" => String::from("#97ca00"),
+            "lightgrey" => String::from("#9f9f9f"),
+            color if color.chars().all(|c| c.is_ascii_hexdigit()) => format!("#{}", color),
+            color => color.to_string(),
+        };
+        let escape = |text: &str| {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+        let (label, message) = (escape(&self.label), escape(&self.message));
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="14">{label}</text><text x="{message_x}" y="14">{message}</text></g></svg>"##,
+            label_x = label_width / 2,
+            message_x = label_width + message_width / 2,
+        )
+    }
+}
@@ -13,101 +13,402 @@
 // limitations under the License.
 
 use anyhow::Result;
-use loc::{Count, Lang};
-use std::{collections::HashMap, path::PathBuf};
-use walkdir::{DirEntry, WalkDir};
+use once_cell::unsync::OnceCell;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
+use crate::assets::{self, AssetStats, FileCategory};
+use crate::events::Events;
+use crate::generated;
+use crate::git;
+use crate::hygiene::HygieneReport;
+use crate::lengths::{LengthTally, LineLengthStats};
+use crate::infra;
+use crate::markdown;
+use crate::minified;
+use crate::notebook;
+use crate::sfc;
+use crate::templating::{self, EmbeddedLang};
+use crate::project::GeneratedCode;
 use crate::ruleset;
+use crate::stats::{Count, StatsLang, WalkStats};
+use crate::vfs::{Vfs, VfsEntry};
 // pub struct S
 
-fn code_stats(e: &DirEntry) -> Result<(Lang, Count)> {
-    let path_str = e.path().to_str().unwrap();
+/// Line counts keyed by language, in alphabetical order of the language names so that reports are stable from run to run
+pub type Stats = BTreeMap<StatsLang, Count>;
 
-    let count: Count;
-    let lang = loc::lang_from_ext(path_str);
+/// Line counts of each language broken down by file extension, see [ext_key]
+pub type ExtStats = BTreeMap<StatsLang, BTreeMap<String, Count>>;
 
-    if lang != Lang::Unrecognized {
-        // count lines
-        count = loc::count(path_str);
+/// What [dir_stats] found walking the project
+#[derive(Debug, Clone, Default)]
+pub struct DirStats {
+    /// line counts keyed by language, None without any file
+    pub stats: Option<Stats>,
+    /// the line counts of each language per file extension, when asked for
+    pub ext_stats: Option<ExtStats>,
+    /// line counts of the generated files, when counted apart
+    pub generated_stats: Option<Stats>,
+    /// number & size of non-code files per category, None without any
+    pub asset_stats: Option<AssetStats>,
+    /// paths that could not be counted
+    pub skipped: Vec<PathBuf>,
+    /// Git LFS pointers left out
+    pub lfs_pointers: Vec<PathBuf>,
+    /// minified files left out
+    pub minified: Vec<PathBuf>,
+    /// generated files counted apart or left out
+    pub generated: Vec<PathBuf>,
+    /// line endings & whitespace of the counted files, when asked for
+    pub hygiene: Option<HygieneReport>,
+    /// line lengths of the counted files per language, when asked for
+    pub line_lengths: Option<LineLengthStats>,
+    /// the walk counts
+    pub walk: WalkStats,
+}
+
+/// How [dir_stats] counts the files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountOptions<'a> {
+    /// also break the counts of each language down by file extension
+    pub by_ext: bool,
+    /// count the fenced code blocks of Markdown files as the languages they declare
+    pub fenced_code: bool,
+    /// count the inline scripts & styles of HTML pages as JavaScript & CSS
+    pub split_html: bool,
+    /// templating formats whose host & embedded languages are counted apart
+    pub embedded: &'a [EmbeddedLang],
+    /// count minified scripts & stylesheets and compiled bundles rather than leaving them out
+    pub count_minified: bool,
+    /// how files marked as generated are counted
+    pub generated: GeneratedCode,
+    /// record the line endings & whitespace of the counted files
+    pub hygiene: bool,
+    /// record the line lengths of the counted files, counting the lines longer than the limit
+    pub line_limit: Option<usize>,
+}
+
+/// The read content of a file, None when it cannot be read
+fn content(vfs: &dyn Vfs, e: &VfsEntry) -> Option<String> {
+    let rel_path = e.path.strip_prefix(vfs.root()).ok()?;
+    vfs.read_to_string(rel_path).ok()
+}
+
+/// The content of a walked file, read the first time a check needs it and shared by the other checks, so that the
+/// files counted are read once
+pub(crate) struct FileContent<'a> {
+    vfs: &'a dyn Vfs,
+    entry: &'a VfsEntry,
+    content: OnceCell<Option<String>>,
+}
+
+impl<'a> FileContent<'a> {
+    pub(crate) fn new(vfs: &'a dyn Vfs, entry: &'a VfsEntry) -> Self {
+        FileContent {
+            vfs,
+            entry,
+            content: OnceCell::new(),
+        }
+    }
+
+    /// The content, None when it cannot be read or is not UTF-8
+    pub(crate) fn get(&self) -> Option<&str> {
+        self.content.get_or_init(|| content(self.vfs, self.entry)).as_deref()
+    }
+}
+
+/// Line counts of a file, per language. Most files are of a single language, Markdown files with fenced code blocks
+/// may hold several
+fn code_stats(
+    vfs: &dyn Vfs,
+    e: &VfsEntry,
+    file: &FileContent,
+    options: CountOptions,
+) -> Result<Option<Vec<(StatsLang, Count)>>> {
+    // loc only takes &str paths, non UTF-8 paths cannot be counted
+    let path_str = match e.path.to_str() {
+        Some(path_str) => path_str,
+        None => return Ok(None),
+    };
+
+    // notebooks are JSON, their cells are counted as the kernel language instead
+    if notebook::is_notebook(&e.path) {
+        if let Some(counted) = file.get().and_then(notebook::count) {
+            return Ok(Some(vec![counted]));
+        }
+    }
+
+    // components mix markup, scripts & styles, each block is counted as its own language
+    if sfc::is_component(&e.path) || (options.split_html && sfc::is_html(&e.path)) {
+        if let Some(content) = file.get() {
+            return Ok(Some(sfc::count(content, sfc::is_astro(&e.path))));
+        }
+    }
+
+    if options.fenced_code && markdown::is_markdown(&e.path) {
+        if let Some(content) = file.get() {
+            return Ok(Some(markdown::count_fenced(content)));
+        }
+    }
+
+    if options.embedded.iter().any(|lang| lang.matches(&e.path)) {
+        if let Some(counts) = file.get().and_then(|content| templating::count(options.embedded, &e.path, content)) {
+            return Ok(Some(counts));
+        }
+    }
+
+    let lang = StatsLang::of_path(path_str);
+
+    let count = if lang != StatsLang::Unrecognized {
+        // count lines, a file that cannot be read counting none
+        match file.get() {
+            Some(content) => vfs.count(e, content)?,
+            None => Count::default(),
+        }
     } else {
-        count = Count {
+        Count {
             code: 0,
             comment: 0,
             blank: 0,
             lines: 0,
         }
-    }
+    };
 
     // let lang_str = lang.to_s().clone();
     // let lang_str = lang.to_s();
 
-    Ok((lang, count))
+    Ok(Some(vec![(lang, count)]))
 }
 
-pub fn is_hidden(entry: &DirEntry) -> bool {
-    entry
+/// The extension a file is broken down by within its language, lowercased: the last one, along with the one before it
+/// when that is a word such as ```test``` or ```min```, so ```app.test.ts``` gives ```.test.ts``` and ```app.ts``` gives
+/// ```.ts```. Files without an extension, such as Dockerfile, go by their name
+pub fn ext_key(path: &Path) -> String {
+    let name = path
         .file_name()
-        .to_str()
-        .map(|s| s.starts_with("."))
-        .unwrap_or(false)
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let parts: Vec<&str> = name.split('.').collect();
+
+    match parts.as_slice() {
+        [_] => name.clone(),
+        [first, .., word, ext] if !first.is_empty() && !word.is_empty() && word.chars().all(char::is_alphabetic) => {
+            format!(".{}.{}", word, ext)
+        }
+        [.., ext] => format!(".{}", ext),
+        [] => name.clone(),
+    }
 }
 
-fn is_file(entry: &DirEntry) -> bool {
-    // entry.metadata().expect("Could not get metadata").is_file();
-    entry.metadata().expect("Could not get metadata").is_file()
+/// Whether a script or stylesheet is minified or bundled, see [minified::is_minified]
+fn is_minified(e: &VfsEntry, file: &FileContent) -> bool {
+    minified::is_candidate(&e.path) && file.get().is_some_and(|content| minified::is_minified(&e.path, content))
 }
 
-pub fn is_ignored(ruleset: &ruleset::RuleSet, entry: &DirEntry) -> bool {
+/// Whether a code file is marked as generated, see [generated::is_generated]
+fn is_generated(file: &FileContent, counts: &[(StatsLang, Count)]) -> bool {
+    counts.iter().any(|(lang, _)| *lang != StatsLang::Unrecognized) && file.get().is_some_and(generated::is_generated)
+}
+
+/// Whether a small file is a Git LFS pointer. Only the files .gitattributes stores with LFS, and those whose
+/// extension would have them counted as code, are read
+fn is_lfs_pointer(lfs: &Option<ruleset::RuleSet>, e: &VfsEntry, file: &FileContent) -> bool {
+    if e.size > git::LFS_POINTER_MAX_SIZE {
+        return false;
+    }
+
+    let is_candidate = lfs.as_ref().is_some_and(|lfs| lfs.is_ignored(&e.path, false))
+        || e.path.to_str().is_some_and(|path_str| StatsLang::of_path(path_str) != StatsLang::Unrecognized);
+    if !is_candidate {
+        return false;
+    }
+
+    file.get().is_some_and(git::is_lfs_pointer)
+}
+
+pub fn is_hidden(entry: &VfsEntry) -> bool {
+    entry
+        .file_name()
+        .to_string_lossy()
+        .starts_with('.')
+}
+
+pub fn is_ignored(ruleset: &ruleset::RuleSet, entry: &VfsEntry) -> bool {
     let e = entry;
-    let is_dir = e.metadata().expect("Could not get metadata").is_dir();
 
-    let is_ignored = ruleset.is_ignored(e.path(), is_dir);
+    let is_ignored = ruleset.is_ignored(&e.path, e.is_dir);
 
     // println!("{:?} -> {:?}", is_ignored, e.path());
 
     is_ignored
 }
 
+/// Walks the project counting lines of code per language, and files & bytes per asset category.
+/// The YAML of Helm charts & Kubernetes manifests is counted as [FileCategory::Infra] rather than as config
+/// Files whose paths are not valid UTF-8 cannot be counted and are returned separately
+/// The ```events``` hooks are called as files are visited and ignored entries are left out
+/// Hidden & ignored directories are pruned before being read, ```walk``` counts them along with the entries walked
+/// Git LFS pointers are not counted as code, they are returned separately, as are minified files unless ```options```
+/// count them, and generated files unless ```options``` count them with the rest
+/// ```options``` can break the counts of each language down by file extension, and count Markdown code blocks apart
+/// They can also record the line endings & whitespace, and the line lengths, of the counted files, generated files aside
 pub fn dir_stats(
-    dir: &PathBuf,
+    vfs: &dyn Vfs,
     ruleset: &Option<ruleset::RuleSet>,
-) -> Result<Option<HashMap<String, Count>>> {
-    let dir_str = dir.to_str().unwrap();
-    let mut stats: HashMap<String, Count> = HashMap::new();
-    let walker = WalkDir::new(dir_str).into_iter();
-
-    match ruleset {
-        Some(ruleset) => {
-
-            for entry in walker.filter_entry(|e| !is_hidden(e) && !is_ignored(&ruleset, e)) {
-                let e = entry?;
-
-                if is_file(&e) {
-                    //
-                    let (lang, count) = code_stats(&e)?;
-                    let lang = lang.clone();
-                    let lang_str = lang.to_s().to_string();
-
-                    // println!("\nlang: {} \n count: {:?}", lang_str, count);
-                    // stats[]
-                    let stat = stats.entry(lang_str).or_insert(Count {
-                        code: 0,
-                        comment: 0,
-                        blank: 0,
-                        lines: 0,
-                    });
-
-                    stat.merge(&count);
-
-                    // println!(">> {:?}", stat);
+    events: &Events,
+    options: CountOptions,
+) -> Result<DirStats> {
+    let mut found = DirStats::default();
+    let mut stats: Stats = BTreeMap::new();
+    let mut ext_stats: ExtStats = BTreeMap::new();
+    let mut generated_stats: Stats = BTreeMap::new();
+    let mut asset_stats: AssetStats = BTreeMap::new();
+    let mut hygiene = HygieneReport::default();
+    let mut lengths: BTreeMap<StatsLang, LengthTally> = BTreeMap::new();
+    let lfs = git::lfs_ruleset(vfs)?;
+    let mut walk = WalkStats::default();
+    let started = Instant::now();
+
+    let entries = vfs.walk(&mut |e| {
+        let keep = if is_hidden(e) {
+            false
+        } else if ruleset.as_ref().is_some_and(|ruleset| is_ignored(ruleset, e)) {
+            events.ignored(&e.path);
+            false
+        } else {
+            true
+        };
+
+        match (keep, e.is_dir) {
+            (true, true) => walk.dirs += 1,
+            (true, false) => walk.files += 1,
+            (false, true) => walk.skipped_dirs += 1,
+            (false, false) => walk.skipped_files += 1,
+        }
+
+        keep
+    })?;
+
+    let charts = infra::chart_dirs(&entries);
+
+    for e in entries {
+        if e.is_file {
+            events.file_visited(&e.path);
+            let file = FileContent::new(vfs, &e);
+
+            if let Some(category) = assets::category(&e.path) {
+                // the YAML deploying the project is not application config
+                let category = if category == FileCategory::Config && infra::is_infra(&e, &charts, &file) {
+                    FileCategory::Infra
+                } else {
+                    category
+                };
+                let stat = asset_stats.entry(category).or_default();
+                stat.files += 1;
+                stat.bytes += e.size;
+            }
+
+            if is_lfs_pointer(&lfs, &e, &file) {
+                walk.lfs_pointers += 1;
+                found.lfs_pointers.push(e.path.clone());
+                continue;
+            }
+
+            if !options.count_minified && is_minified(&e, &file) {
+                walk.minified += 1;
+                found.minified.push(e.path.clone());
+                continue;
+            }
+
+            let counts = match code_stats(vfs, &e, &file, options)? {
+                Some(counts) => counts,
+                None => {
+                    found.skipped.push(e.path.clone());
+                    continue;
+                }
+            };
+
+            if options.generated != GeneratedCode::Count && is_generated(&file, &counts) {
+                walk.generated += 1;
+                found.generated.push(e.path.clone());
+
+                if options.generated == GeneratedCode::Separate {
+                    for (lang, count) in counts {
+                        *generated_stats.entry(lang).or_default() += count;
+                    }
+                }
+                continue;
+            }
+
+            let content = if options.hygiene || options.line_limit.is_some() {
+                file.get()
+            } else {
+                None
+            };
+            if let Some(content) = content {
+                if options.hygiene {
+                    hygiene.add(&e.path, content);
+                }
+                // files holding several languages, such as Markdown with code blocks, go to the one with most lines
+                let lang = counts.iter().max_by_key(|(_, count)| count.lines);
+                if let (Some((lang, _)), Some(_)) = (lang, options.line_limit) {
+                    lengths.entry(lang.clone()).or_default().add(content);
                 }
             }
+
+            for (lang, count) in counts {
+                if options.by_ext && lang != StatsLang::Unrecognized {
+                    *ext_stats
+                        .entry(lang.clone())
+                        .or_default()
+                        .entry(ext_key(&e.path))
+                        .or_default() += count;
+                }
+
+                // println!("\nlang: {} \n count: {:?}", lang_str, count);
+                // stats[]
+                let stat = stats.entry(lang).or_insert(Count {
+                    code: 0,
+                    comment: 0,
+                    blank: 0,
+                    lines: 0,
+                });
+
+                *stat += count;
+
+                // println!(">> {:?}", stat);
+            }
         }
-        _ => (),
     }
 
     // println!("{:#?}", stats);
-    let stats = if stats.len() > 0 { Some(stats) } else { None };
+    events.stats_ready(&stats);
+    found.stats = if !stats.is_empty() { Some(stats) } else { None };
+    found.asset_stats = if !asset_stats.is_empty() {
+        Some(asset_stats)
+    } else {
+        None
+    };
+    found.ext_stats = if options.by_ext { Some(ext_stats) } else { None };
+    found.hygiene = if options.hygiene { Some(hygiene) } else { None };
+    found.line_lengths = options.line_limit.map(|limit| {
+        lengths
+            .iter()
+            .filter_map(|(lang, tally)| Some((lang.clone(), tally.finish(limit)?)))
+            .collect()
+    });
+    found.generated_stats = if !generated_stats.is_empty() {
+        Some(generated_stats)
+    } else {
+        None
+    };
+
+    walk.duration = started.elapsed();
+    found.walk = walk;
 
-    Ok(stats)
+    Ok(found)
 }
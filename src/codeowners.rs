@@ -0,0 +1,194 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobMatcher};
+use std::path::{Path, PathBuf};
+
+use crate::vfs::Vfs;
+
+/// Where GitHub and GitLab look for the CODEOWNERS file, in order
+const LOCATIONS: [&str; 4] = [
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// A parsed CODEOWNERS file, returned by [method.codeowners]
+#[derive(Debug, Clone)]
+pub struct CodeOwners {
+    /// path of the CODEOWNERS file
+    pub path: PathBuf,
+    /// the rules, in file order
+    pub rules: Vec<OwnerRule>,
+    matchers: Vec<OwnerMatcher>,
+}
+
+/// A line of the CODEOWNERS file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnerRule {
+    /// the path pattern, as written
+    pub pattern: String,
+    /// users, teams or emails owning the matching paths. Empty when the rule removes ownership
+    pub owners: Vec<String>,
+    /// the GitLab section the rule belongs to, if any
+    pub section: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct OwnerMatcher {
+    glob: GlobMatcher,
+    dir_only: bool,
+    /// whether the rule also owns the content of matching directories. ```docs/*``` only owns the files directly in docs
+    recursive: bool,
+}
+
+impl OwnerMatcher {
+    /// Patterns follow gitignore rules: they are anchored to the root when they contain a slash, and match anywhere otherwise
+    fn new(pattern: &str) -> Result<Self> {
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+
+        let glob = if anchored || pattern.starts_with("**/") {
+            pattern.to_string()
+        } else {
+            format!("**/{}", pattern)
+        };
+
+        Ok(OwnerMatcher {
+            glob: GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()?
+                .compile_matcher(),
+            dir_only,
+            recursive: !pattern.ends_with("/*"),
+        })
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        if !self.dir_only && self.glob.is_match(path) {
+            return true;
+        }
+
+        self.recursive
+            && path
+                .ancestors()
+                .skip(1)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .any(|dir| self.glob.is_match(dir))
+    }
+}
+
+impl CodeOwners {
+    /// Parses CODEOWNERS content. GitLab sections (```[Section]```, ```^[Optional Section] @default-owner```) are supported
+    pub fn parse(path: PathBuf, content: &str) -> Result<Self> {
+        let mut rules = vec![];
+        let mut matchers = vec![];
+        let mut section: Option<String> = None;
+        let mut section_owners: Vec<String> = vec![];
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+
+            if line.starts_with('[') || line.starts_with("^[") {
+                if let Some(end) = line.find(']') {
+                    let start = line.find('[').unwrap_or(0) + 1;
+                    section = Some(line[start..end].to_string());
+                    // skip the approval count, e.g. [Docs][2]
+                    let rest = line[end + 1..].trim_start();
+                    let rest = match rest.strip_prefix('[') {
+                        Some(rest) => rest.split_once(']').map_or("", |(_, rest)| rest),
+                        None => rest,
+                    };
+                    section_owners = rest.split_whitespace().map(String::from).collect();
+                    continue;
+                }
+            }
+
+            let pattern = match parts.next() {
+                Some(pattern) => pattern,
+                None => continue,
+            };
+            let mut owners: Vec<String> = parts
+                .take_while(|part| !part.starts_with('#'))
+                .map(String::from)
+                .collect();
+            if owners.is_empty() && section.is_some() {
+                owners = section_owners.clone();
+            }
+
+            matchers.push(OwnerMatcher::new(pattern)?);
+            rules.push(OwnerRule {
+                pattern: pattern.to_string(),
+                owners,
+                section: section.clone(),
+            });
+        }
+
+        Ok(CodeOwners {
+            path,
+            rules,
+            matchers,
+        })
+    }
+
+    /// Owners of a path relative to the project directory. The last matching rule wins, per GitLab section
+    /// ```no_run
+    /// let owners = codeowners.owners_for("src/main.rs");
+    /// ```
+    pub fn owners_for<P: AsRef<Path>>(&self, path: P) -> Vec<String> {
+        let path = path.as_ref();
+        let mut sections: Vec<(&Option<String>, &Vec<String>)> = vec![];
+
+        for (rule, matcher) in self.rules.iter().zip(self.matchers.iter()) {
+            if !matcher.matches(path) {
+                continue;
+            }
+            match sections.iter_mut().find(|(section, _)| *section == &rule.section) {
+                Some(found) => found.1 = &rule.owners,
+                None => sections.push((&rule.section, &rule.owners)),
+            }
+        }
+
+        let mut owners: Vec<String> = vec![];
+        for owner in sections.into_iter().flat_map(|(_, owners)| owners.iter()) {
+            if !owners.contains(owner) {
+                owners.push(owner.clone());
+            }
+        }
+
+        owners
+    }
+}
+
+/// Finds and parses the CODEOWNERS file
+pub(crate) fn codeowners(vfs: &dyn Vfs) -> Result<Option<CodeOwners>> {
+    for location in LOCATIONS {
+        let path = Path::new(location);
+        if vfs.is_file(path) {
+            let content = vfs.read_to_string(path)?;
+            return Ok(Some(CodeOwners::parse(path.to_path_buf(), &content)?));
+        }
+    }
+
+    Ok(None)
+}
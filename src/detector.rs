@@ -12,129 +12,245 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::Result;
-use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
+use anyhow::{anyhow, Result};
 use std::{
-    collections::HashMap,
-    env,
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, HashMap},
     ffi::OsString,
-    fs::{read_to_string, write},
-    path::PathBuf,
-    sync::Mutex,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
 };
-use wax::Glob;
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Configs {
-    pub project_file_types: Vec<String>,
-    pub git_ignores: HashMap<String, Language>,
-}
-
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct Language {
-    key: String,
-    name: String,
-    #[serde(rename = "fileName")]
-    file_name: String,
-    pub contents: String,
-}
-
-pub static CONFIGS: Lazy<Mutex<Configs>> = Lazy::new(|| {
-    let project_types = vec![
-        "shard.yml",
-        "pubspec.yaml",
-        "pubspec.yml",
-        "pubspec.lock",
-        "mix.exs",
-        "elm.json",
-        "elm-package.json",
-        ".elm-version",
-        "rebar.config",
-        "erlang.mk",
-        "stack.yaml",
-        "Setup.hs",
-        "go.mod",
-        "go.sum",
-        "glide.yaml",
-        "Gopkg.yml",
-        "Gopkg.lock",
-        ".go-version",
-        "build.gradle",
-        "pom.xml",
-        "build.gradle.kts",
-        "build.sbt",
-        ".java.version",
-        "deps.edn",
-        "project.clj",
-        "build.boot",
-        "Project.toml",
-        "Manifest.toml",
-        "nim.cfg",
-        "package.json",
-        ".node-version",
-        ".nvmrc",
-        "dune",
-        "dune-project",
-        "jbuild",
-        "jbuild-ignore",
-        ".merlin",
-        "Makefile.PL",
-        "Build.PL",
-        "cpanfile",
-        "cpanfile.snapshot",
-        "META.json",
-        "META.yml",
-        ".perl-version",
-        "composer.json",
-        ".php-version",
-        "spago.dhall",
-        "packages.dhall",
-        "requirements.txt",
-        ".python-version",
-        "pyproject.toml",
-        "Pipfile",
-        "tox.ini",
-        "setup.py",
-        "__init__.py",
-        ".Rprofile",
-        "Gemfile",
-        ".ruby-version",
-        "Cargo.toml",
-        ".scalaenv",
-        ".sbtenv",
-        "build.sbt",
-        "Package.swift",
-    ];
-
-    let ignores_obj: HashMap<String, Language> = get_ignores().unwrap();
-
-    let configs = Configs {
-        project_file_types: project_types
-            .iter()
-            .map(|e| {
-                let derefd = *e;
-                derefd.into()
-            })
-            .collect(),
-        git_ignores: ignores_obj,
-    };
 
-    Mutex::new(configs)
-});
+use crate::project::{BuildSystem, ProjectError, ProjectKind, TieBreak};
+use crate::templates::{Configs, Template};
+use crate::assets::{self, FileCategory};
+use crate::code::{self, Stats};
+use crate::ruleset::RuleSet;
+use crate::stats::StatsLang;
+use crate::vfs::Vfs;
 
+/// A set of detectors, each named after the gitignore template of the language, tool or editor it recognizes
+/// ```no_run
+/// use project_parse::detector::{Detectors, FakeDirEntry};
+///
+/// let entries = FakeDirEntry::from_paths(["Cargo.toml", "src/main.rs", "README.md"]);
+/// assert_eq!(vec!["rust"], Detectors::default().detects(&entries));
+/// ```
 #[derive(Debug)]
 pub struct Detectors {
     detectors: Vec<Detector>,
 }
 
 impl Detectors {
+    /// Names of all the detectors
+    #[cfg(test)]
+    pub fn names(&self) -> Vec<String> {
+        self.detectors
+            .iter()
+            .map(|detector| detector.template.clone())
+            .collect()
+    }
+
+    /// Detectors for editor & IDE artifacts, named after their gitignore templates
+    pub fn editors() -> Self {
+        let detectors = vec![
+            Detector::new("visualstudiocode", [Matcher::by_dir_name(".vscode")]),
+            Detector::new(
+                "jetbrains",
+                [
+                    Matcher::by_dir_name(".idea"),
+                    Matcher::by_file_extension("iml"),
+                ],
+            ),
+            Detector::new(
+                "vim",
+                [
+                    Matcher::by_file_extension("swp"),
+                    Matcher::by_file_extension("swo"),
+                    Matcher::by_file_name("Session.vim"),
+                ],
+            ),
+            Detector::new(
+                "sublimetext",
+                [
+                    Matcher::by_file_extension("sublime-project"),
+                    Matcher::by_file_extension("sublime-workspace"),
+                ],
+            ),
+            Detector::new(
+                "emacs",
+                [
+                    Matcher::by_file_name(".dir-locals.el"),
+                    Matcher::by_file_name(".projectile"),
+                ],
+            ),
+        ];
+        Detectors { detectors }
+    }
+
+    /// Detectors for build & development tools recognized from their artifacts, named after their gitignore templates
+    pub fn tools() -> Self {
+        let detectors = vec![
+            Detector::new("cmake", [Matcher::by_file_name("CMakeLists.txt")]),
+            Detector::new(
+                "gradle",
+                [
+                    Matcher::by_file_name("build.gradle"),
+                    Matcher::by_file_name("build.gradle.kts"),
+                    Matcher::by_file_name("settings.gradle"),
+                    Matcher::by_file_name("settings.gradle.kts"),
+                    Matcher::by_file_name("gradlew"),
+                ],
+            ),
+            Detector::new(
+                "terraform",
+                [
+                    Matcher::by_file_extension("tf"),
+                    Matcher::by_file_name(".terraform.lock.hcl"),
+                ],
+            ),
+            Detector::new(
+                "jupyternotebooks",
+                [Matcher::by_file_extension("ipynb")],
+            ),
+            Detector::new(
+                "unity",
+                [Matcher::all_of([
+                    Matcher::by_dir_name("Assets"),
+                    Matcher::by_dir_name("ProjectSettings"),
+                ])],
+            ),
+            Detector::new(
+                "bazel",
+                [
+                    Matcher::by_file_name("WORKSPACE"),
+                    Matcher::by_file_name("WORKSPACE.bazel"),
+                    Matcher::by_file_name("MODULE.bazel"),
+                    Matcher::by_file_name("BUILD.bazel"),
+                    Matcher::by_file_name(".bazelrc"),
+                    Matcher::by_file_name(".bazelversion"),
+                ],
+            ),
+            Detector::new(
+                "buck",
+                [
+                    Matcher::by_file_name(".buckconfig"),
+                    Matcher::by_file_name("BUCK"),
+                ],
+            ),
+            Detector::new(
+                "nix",
+                [
+                    Matcher::by_file_name("flake.nix"),
+                    Matcher::by_file_name("default.nix"),
+                    Matcher::by_file_name("shell.nix"),
+                ],
+            ),
+            Detector::new("unrealengine", [Matcher::by_file_extension("uproject")]),
+            Detector::new("godot", [Matcher::by_file_name("project.godot")]),
+            Detector::new(
+                "xcode",
+                [
+                    Matcher::by_dir_extension("xcodeproj"),
+                    Matcher::by_dir_extension("xcworkspace"),
+                ],
+            )
+            .with_kind(ProjectKind::XcodeProject),
+            Detector::new("cocoapods", [Matcher::by_file_name("Podfile")])
+                .with_kind(ProjectKind::XcodeProject),
+            Detector::new(
+                "flutter",
+                [Matcher::all_of([
+                    Matcher::by_file_name("pubspec.yaml"),
+                    Matcher::by_dir_name("android"),
+                    Matcher::by_dir_name("ios"),
+                ])],
+            )
+            .with_kind(ProjectKind::FlutterApp),
+        ];
+        Detectors { detectors }
+    }
+
+    /// Nested paths, relative to the directory, used by the detectors
+    fn paths(&self) -> Vec<&Path> {
+        self.detectors
+            .iter()
+            .flat_map(|detector| detector.matchers.iter())
+            .flat_map(|matcher| matcher.paths())
+            .collect()
+    }
+
+    /// Names of the files the detectors look for at the top level, such as ```Cargo.toml``` or ```go.mod```, in detector
+    /// order without duplicates
+    pub fn project_file_types(&self) -> Vec<String> {
+        let mut names: Vec<String> = vec![];
+        for name in self.detectors.iter().flat_map(|detector| &detector.matchers).flat_map(Matcher::file_names) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        names
+    }
+
+    /// Names of the detectors matching any of the entries. Entries can come from the filesystem or be [FakeDirEntry]s
     pub fn detects<E: DirEntry>(&self, entries: &[E]) -> Vec<String> {
         self.detectors
             .iter()
             .filter_map(|detector| detector.detects(entries))
             .collect()
     }
+
+    /// Names of the detectors with at least ```min_evidence``` matching markers, most evidence first.
+    /// Detectors with as much evidence as each other are ordered by ```tie_break```
+    fn ranks<E: DirEntry>(&self, entries: &[E], min_evidence: usize, tie_break: &TieBreak) -> Vec<String> {
+        let mut ranked: Vec<(usize, &Detector, usize)> = self
+            .detectors
+            .iter()
+            .enumerate()
+            .map(|(index, detector)| (index, detector, detector.evidence(entries)))
+            .filter(|(_, _, evidence)| *evidence > 0 && *evidence >= min_evidence)
+            .collect();
+
+        ranked.sort_by(|(a_index, a, a_evidence), (b_index, b, b_evidence)| {
+            b_evidence.cmp(a_evidence).then_with(|| match tie_break {
+                TieBreak::DetectorOrder => a_index.cmp(b_index),
+                TieBreak::Alphabetical => a.template.cmp(&b.template),
+                TieBreak::Preferred(preferred) => {
+                    let position = |detector: &Detector| {
+                        preferred
+                            .iter()
+                            .position(|name| name == &detector.template)
+                            .unwrap_or(preferred.len())
+                    };
+                    position(a).cmp(&position(b)).then(a_index.cmp(b_index))
+                }
+            })
+        });
+
+        ranked
+            .into_iter()
+            .map(|(_, detector, _)| detector.template.clone())
+            .collect()
+    }
+
+    /// Project kinds read from the manifests of the detectors that match the entries
+    fn kinds<E: DirEntry>(&self, vfs: &dyn Vfs, entries: &[E]) -> Result<Vec<ProjectKind>> {
+        let mut kinds: Vec<ProjectKind> = vec![];
+
+        for detector in &self.detectors {
+            if detector.detects(entries).is_none() {
+                continue;
+            }
+            for kind in detector.inspect(vfs)? {
+                if !kinds.contains(&kind) {
+                    kinds.push(kind);
+                }
+            }
+        }
+
+        Ok(kinds)
+    }
 }
 
 impl Default for Detectors {
@@ -150,7 +266,16 @@ impl Default for Detectors {
                     Matcher::by_file_name("pubspec.lock"),
                 ],
             ),
-            Detector::new("elixir", [Matcher::by_file_name("mix.exs")]),
+            Detector::new(
+                "elixir",
+                [
+                    Matcher::by_file_name("mix.exs"),
+                    Matcher::all_of([
+                        Matcher::by_dir_name("lib"),
+                        Matcher::by_file_path("config/config.exs"),
+                    ]),
+                ],
+            ),
             Detector::new(
                 "elm",
                 [
@@ -172,6 +297,7 @@ impl Default for Detectors {
                     Matcher::by_file_extension("cabal"),
                     Matcher::by_file_name("stack.yaml"),
                     Matcher::by_file_name("Setup.hs"),
+                    Matcher::by_file_path("app/Main.hs"),
                 ],
             ),
             Detector::new(
@@ -196,6 +322,8 @@ impl Default for Detectors {
                     Matcher::by_file_name("deps.edn"),
                     Matcher::by_file_name("project.clj"),
                     Matcher::by_file_name("build.boot"),
+                    Matcher::by_dir_path("src/main/java"),
+                    Matcher::by_dir_path("src/main/kotlin"),
                 ],
             ),
             Detector::new(
@@ -213,7 +341,8 @@ impl Default for Detectors {
                     Matcher::by_file_name(".node-version"),
                     Matcher::by_file_name(".nvmrc"),
                 ],
-            ),
+            )
+            .with_inspector("package.json", inspect_package_json),
             Detector::new(
                 "ocaml",
                 [
@@ -270,15 +399,29 @@ impl Default for Detectors {
                     Matcher::by_file_extension("gemspec"),
                     Matcher::by_file_name("Gemfile"),
                     Matcher::by_file_name(".ruby-version"),
+                    Matcher::all_of([
+                        Matcher::by_dir_path("app/models"),
+                        Matcher::by_file_path("config/routes.rb"),
+                    ]),
                 ],
             ),
-            Detector::new("rust", [Matcher::by_file_name("Cargo.toml")]),
+            Detector::new(
+                "rust",
+                [
+                    Matcher::by_file_name("Cargo.toml"),
+                    Matcher::by_file_name("Cargo.lock"),
+                    Matcher::by_file_path("src/main.rs"),
+                    Matcher::by_file_path("src/lib.rs"),
+                ],
+            )
+                .with_inspector("Cargo.toml", inspect_cargo_toml),
             Detector::new(
                 "scala",
                 [
                     Matcher::by_file_name(".scalaenv"),
                     Matcher::by_file_name(".sbtenv"),
                     Matcher::by_file_name("build.sbt"),
+                    Matcher::by_dir_path("src/main/scala"),
                 ],
             ),
             Detector::new("swift", [Matcher::by_file_name("Package.swift")]),
@@ -292,6 +435,16 @@ impl Default for Detectors {
 struct Detector {
     template: String,
     matchers: Vec<Matcher>,
+    inspector: Option<Inspector>,
+    /// kind of the projects the detector matches, whatever their manifests say
+    kind: Option<ProjectKind>,
+}
+
+/// Manifest file opened once a detector matches, and the function classifying the project from its content
+#[derive(Debug)]
+struct Inspector {
+    file_name: &'static str,
+    inspect: fn(&dyn Vfs, &str) -> Vec<ProjectKind>,
 }
 
 impl Detector {
@@ -299,14 +452,58 @@ impl Detector {
         Detector {
             template: template.into(),
             matchers: matchers.into(),
+            inspector: None,
+            kind: None,
         }
     }
 
+    fn with_kind(mut self, kind: ProjectKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    fn with_inspector(
+        mut self,
+        file_name: &'static str,
+        inspect: fn(&dyn Vfs, &str) -> Vec<ProjectKind>,
+    ) -> Self {
+        self.inspector = Some(Inspector { file_name, inspect });
+        self
+    }
+
+    /// The kind of the detector, then the kinds read from the manifest, if the detector has an inspector and the file
+    /// exists
+    fn inspect(&self, vfs: &dyn Vfs) -> Result<Vec<ProjectKind>> {
+        let kinds: Vec<ProjectKind> = self.kind.iter().cloned().collect();
+        let inspected = match &self.inspector {
+            Some(inspector) => {
+                let path = Path::new(inspector.file_name);
+                if vfs.is_file(path) {
+                    let content = vfs.read_to_string(path)?;
+                    (inspector.inspect)(vfs, &content)
+                } else {
+                    vec![]
+                }
+            }
+            _ => vec![],
+        };
+
+        Ok([kinds, inspected].concat())
+    }
+
+    /// Number of the detector's markers found among the entries
+    fn evidence<E: DirEntry>(&self, entries: &[E]) -> usize {
+        self.matchers
+            .iter()
+            .filter(|matcher| matcher.matches_any(entries))
+            .count()
+    }
+
     fn detects<E: DirEntry>(&self, entries: &[E]) -> Option<String> {
         let result = self
             .matchers
             .iter()
-            .any(|matcher| entries.iter().any(|entry| matcher.matches(entry)));
+            .any(|matcher| matcher.matches_any(entries));
         if result {
             Some(self.template.clone())
         } else {
@@ -315,10 +512,20 @@ impl Detector {
     }
 }
 
+/// An entry of the directory being detected. Implement it to run detection over entries that are not on disk
 pub trait DirEntry {
+    /// name of the file or directory, without its parents
     fn file_name(&self) -> OsString;
+    /// extension of the file name, if any
     fn extension(&self) -> Option<OsString>;
+    /// whether the entry is a file
     fn is_file(&self) -> bool;
+    /// whether the entry is a directory
+    fn is_dir(&self) -> bool;
+    /// path relative to the directory being detected, top level entries by default
+    fn rel_path(&self) -> PathBuf {
+        PathBuf::from(self.file_name())
+    }
 }
 
 impl DirEntry for std::fs::DirEntry {
@@ -335,12 +542,179 @@ impl DirEntry for std::fs::DirEntry {
         let path = self.path();
         path.is_file()
     }
+
+    fn is_dir(&self) -> bool {
+        let path = self.path();
+        path.is_dir()
+    }
+}
+
+/// Entry that only exists in memory, for detection over file lists from an API response, a zip index, ```git ls-tree``` and so on
+/// Paths are relative to the project root, nested paths are used by the directory structure detectors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeDirEntry {
+    rel_path: PathBuf,
+    is_dir: bool,
+}
+
+impl FakeDirEntry {
+    /// A top level entry named ```file_name```, a file or a directory. The extension is read from the name, the one
+    /// given is only kept for files named without it
+    /// ```no_run
+    /// let entry = FakeDirEntry::new("Cargo.toml", Some("toml"), true);
+    /// ```
+    pub fn new<T: Into<OsString>>(file_name: T, extension: Option<T>, is_file: bool) -> Self {
+        let mut rel_path = PathBuf::from(file_name.into());
+        if let Some(extension) = extension.map(Into::into) {
+            if rel_path.extension().is_none() && !extension.is_empty() {
+                rel_path.set_extension(extension);
+            }
+        }
+
+        FakeDirEntry {
+            rel_path,
+            is_dir: !is_file,
+        }
+    }
+
+    /// A file entry
+    /// ```no_run
+    /// let entry = FakeDirEntry::file("src/main.rs");
+    /// ```
+    pub fn file<P: Into<PathBuf>>(rel_path: P) -> Self {
+        FakeDirEntry {
+            rel_path: rel_path.into(),
+            is_dir: false,
+        }
+    }
+
+    /// A directory entry
+    /// ```no_run
+    /// let entry = FakeDirEntry::dir(".vscode");
+    /// ```
+    pub fn dir<P: Into<PathBuf>>(rel_path: P) -> Self {
+        FakeDirEntry {
+            rel_path: rel_path.into(),
+            is_dir: true,
+        }
+    }
+
+    /// Entries for a list of paths, as listed by ```git ls-files``` or a zip index
+    /// Paths ending with a slash are directories, and the parent directories of every path are added too
+    /// ```no_run
+    /// let entries = FakeDirEntry::from_paths(["src/main/java/App.java", ".idea/"]);
+    /// ```
+    pub fn from_paths<I, S>(paths: I) -> Vec<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut entries: Vec<FakeDirEntry> = vec![];
+        let mut push = |entry: FakeDirEntry| {
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        };
+
+        for path in paths {
+            let path = path.as_ref().replace('\\', "/");
+            let is_dir = path.ends_with('/');
+            let path = PathBuf::from(path.trim_start_matches("./").trim_end_matches('/'));
+
+            for parent in path.ancestors().skip(1) {
+                if parent.as_os_str().is_empty() {
+                    break;
+                }
+                push(FakeDirEntry::dir(parent));
+            }
+
+            if is_dir {
+                push(FakeDirEntry::dir(path));
+            } else {
+                push(FakeDirEntry::file(path));
+            }
+        }
+
+        entries
+    }
+}
+
+impl DirEntry for FakeDirEntry {
+    fn file_name(&self) -> OsString {
+        self.rel_path.file_name().map(OsString::from).unwrap_or_default()
+    }
+
+    fn extension(&self) -> Option<OsString> {
+        self.rel_path.extension().map(OsString::from)
+    }
+
+    fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    fn rel_path(&self) -> PathBuf {
+        self.rel_path.clone()
+    }
+}
+
+/// Entry found in the project, either at the top level of the directory or by probing for a nested path
+#[derive(Debug)]
+struct FsEntry {
+    rel_path: PathBuf,
+    is_file: bool,
+    is_dir: bool,
+}
+
+impl FsEntry {
+    fn new(vfs: &dyn Vfs, rel_path: PathBuf) -> Self {
+        FsEntry {
+            is_file: vfs.is_file(&rel_path),
+            is_dir: vfs.is_dir(&rel_path),
+            rel_path,
+        }
+    }
+}
+
+impl DirEntry for FsEntry {
+    fn file_name(&self) -> OsString {
+        self.rel_path.file_name().map(OsString::from).unwrap_or_default()
+    }
+
+    fn extension(&self) -> Option<OsString> {
+        self.rel_path.extension().map(OsString::from)
+    }
+
+    fn is_file(&self) -> bool {
+        self.is_file
+    }
+
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    fn rel_path(&self) -> PathBuf {
+        self.rel_path.clone()
+    }
 }
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 enum Matcher {
     ByFileExtension(OsString),
     ByFileName(OsString),
+    ByDirName(OsString),
+    /// top level directory with the extension, such as the bundles of Xcode
+    ByDirExtension(OsString),
+    /// nested file, relative to the directory
+    ByFilePath(PathBuf),
+    /// nested directory, relative to the directory
+    ByDirPath(PathBuf),
+    /// every one of the matchers has to match
+    AllOf(Vec<Matcher>),
 }
 
 impl Matcher {
@@ -352,104 +726,205 @@ impl Matcher {
         Self::ByFileName(name.into())
     }
 
+    fn by_dir_name<T: Into<OsString>>(name: T) -> Self {
+        Self::ByDirName(name.into())
+    }
+
+    fn by_dir_extension<T: Into<OsString>>(extension: T) -> Self {
+        Self::ByDirExtension(extension.into())
+    }
+
+    fn by_file_path<T: Into<PathBuf>>(path: T) -> Self {
+        Self::ByFilePath(path.into())
+    }
+
+    fn by_dir_path<T: Into<PathBuf>>(path: T) -> Self {
+        Self::ByDirPath(path.into())
+    }
+
+    fn all_of<MS: Into<Vec<Matcher>>>(matchers: MS) -> Self {
+        Self::AllOf(matchers.into())
+    }
+
+    fn matches_any<E: DirEntry>(&self, entries: &[E]) -> bool {
+        match self {
+            Self::AllOf(matchers) => matchers.iter().all(|matcher| matcher.matches_any(entries)),
+            _ => entries.iter().any(|entry| self.matches(entry)),
+        }
+    }
+
     fn matches<E: DirEntry>(&self, entry: &E) -> bool {
+        // name & extension matchers only look at top level entries
+        let top_level = entry.rel_path().components().count() == 1;
+
         match self {
-            Self::ByFileName(name) => entry.is_file() && &entry.file_name() == name,
+            Self::ByFileName(name) => top_level && entry.is_file() && &entry.file_name() == name,
             Self::ByFileExtension(extension) => {
-                entry.is_file() && entry.extension() == Some(extension.clone())
+                top_level && entry.is_file() && entry.extension() == Some(extension.clone())
+            }
+            Self::ByDirName(name) => top_level && entry.is_dir() && &entry.file_name() == name,
+            Self::ByDirExtension(extension) => {
+                top_level && entry.is_dir() && entry.extension() == Some(extension.clone())
             }
+            Self::ByFilePath(path) => entry.is_file() && &entry.rel_path() == path,
+            Self::ByDirPath(path) => entry.is_dir() && &entry.rel_path() == path,
+            Self::AllOf(_) => false,
         }
     }
-}
 
-#[derive(Serialize, Deserialize, Debug)]
-struct FakeDirEntry {
-    file_name: OsString,
-    extension: Option<OsString>,
-    is_file: bool,
-}
+    /// names of the top level files this matcher looks for
+    fn file_names(&self) -> Vec<String> {
+        match self {
+            Self::ByFileName(name) => vec![name.to_string_lossy().to_string()],
+            Self::AllOf(matchers) => matchers.iter().flat_map(Matcher::file_names).collect(),
+            _ => vec![],
+        }
+    }
 
-impl FakeDirEntry {
-    fn new<T: Into<OsString>>(file_name: T, extension: Option<T>, is_file: bool) -> Self {
-        FakeDirEntry {
-            file_name: file_name.into(),
-            extension: extension.map(|pe| pe.into()),
-            is_file,
+    /// nested paths this matcher needs to know about
+    fn paths(&self) -> Vec<&Path> {
+        match self {
+            Self::ByFilePath(path) | Self::ByDirPath(path) => vec![path.as_path()],
+            Self::AllOf(matchers) => matchers.iter().flat_map(|matcher| matcher.paths()).collect(),
+            _ => vec![],
         }
     }
 }
 
-impl DirEntry for FakeDirEntry {
-    fn file_name(&self) -> OsString {
-        self.file_name.clone()
+/// Top level entries of the directory, plus entries for the nested paths the detectors look for, when they exist
+fn read_entries(vfs: &dyn Vfs, detectors: &Detectors) -> Result<Vec<FsEntry>> {
+    let mut entries = vec![];
+
+    for name in vfs.read_dir(Path::new(""))? {
+        entries.push(FsEntry::new(vfs, PathBuf::from(name)));
     }
 
-    fn extension(&self) -> Option<OsString> {
-        self.extension.clone()
+    for path in detectors.paths() {
+        let entry = FsEntry::new(vfs, path.to_path_buf());
+        if entry.is_file || entry.is_dir {
+            entries.push(entry);
+        }
     }
 
-    fn is_file(&self) -> bool {
-        self.is_file
+    Ok(entries)
+}
+
+pub(crate) fn detect_editors_from_dir(vfs: &dyn Vfs) -> Result<Vec<String>> {
+    let mut editors: Vec<String> = Vec::new();
+    if vfs.is_dir(Path::new("")) {
+        let detectors = Detectors::editors();
+        editors = detectors.detects(&read_entries(vfs, &detectors)?);
     }
+
+    Ok(editors)
 }
 
-fn get_ignores() -> Result<HashMap<String, Language>> {
-    let mut ignores_file = env::temp_dir();
-    ignores_file.push("git-ignores.json");
+pub(crate) fn detect_tools_from_dir(vfs: &dyn Vfs) -> Result<Vec<String>> {
+    let mut tools: Vec<String> = Vec::new();
+    if vfs.is_dir(Path::new("")) {
+        let detectors = Detectors::tools();
+        tools = detectors.detects(&read_entries(vfs, &detectors)?);
+    }
 
-    if ignores_file.exists() {
-        // read
-        let ignores_str: String = read_to_string(&ignores_file)?;
-        let ignores_obj: HashMap<String, Language> =
-            serde_json::from_str(&ignores_str).expect("Unable To Parse GitIgnore");
-        Ok(ignores_obj)
-    } else {
-        let git_ignore_url = "https://www.gitignore.io/api/list?format=json";
-        let ignores_str: String = ureq::get(git_ignore_url).call()?.into_string()?;
-        // save
-        write(&ignores_file, &ignores_str)?;
-        let ignores_obj: HashMap<String, Language> =
-            serde_json::from_str(&ignores_str).expect("Unable To Parse GitIgnore");
-        Ok(ignores_obj)
+    Ok(tools)
+}
+
+/// The build systems among the detected tools, in the order they were detected
+pub(crate) fn build_systems(tools: &[String]) -> Vec<BuildSystem> {
+    tools
+        .iter()
+        .filter_map(|tool| match tool.as_str() {
+            "cmake" => Some(BuildSystem::CMake),
+            "gradle" => Some(BuildSystem::Gradle),
+            "bazel" => Some(BuildSystem::Bazel),
+            "buck" => Some(BuildSystem::Buck),
+            "nix" => Some(BuildSystem::Nix),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Forgets the languages detected so far by the projects using [Configs::shared], see
+/// [Configs::clear_detection_cache]
+pub fn clear_detection_cache() {
+    Configs::shared().clear_detection_cache();
+}
+
+/// Hash of the top level entry names & modification times, of the nested paths the detectors look for, and of the
+/// detection settings. None when modification times are unknown, as for projects held in memory
+fn fingerprint(
+    vfs: &dyn Vfs,
+    detectors: &Detectors,
+    min_evidence: usize,
+    tie_break: &TieBreak,
+) -> Result<Option<u64>> {
+    let mut hasher = DefaultHasher::new();
+    min_evidence.hash(&mut hasher);
+    format!("{:?}", tie_break).hash(&mut hasher);
+
+    let mut names = vfs.read_dir(Path::new(""))?;
+    names.sort();
+    for name in names {
+        let modified = match vfs.modified(Path::new(&name)) {
+            Some(modified) => modified,
+            None => return Ok(None),
+        };
+        (name, modified).hash(&mut hasher);
     }
+
+    for path in detectors.paths() {
+        vfs.modified(path).hash(&mut hasher);
+    }
+
+    Ok(Some(hasher.finish()))
 }
 
-pub fn detect_lang(file_path: &PathBuf) -> Result<Vec<String>> {
-    let file_name = file_path.file_name().unwrap();
-    let ext = file_path.extension();
+/// Languages a single file points to, such as ```rust``` for ```Cargo.toml```, by its name alone
+/// ```no_run
+/// assert_eq!(vec!["rust"], detector::detect_lang("my/project/Cargo.toml")?);
+/// ```
+pub fn detect_lang<P: AsRef<Path>>(file_path: P) -> Result<Vec<String>> {
+    let file_name = file_path
+        .as_ref()
+        .file_name()
+        .ok_or_else(|| anyhow!("{:?} has no file name", file_path.as_ref()))?;
 
-    let entry = FakeDirEntry::new(file_name, ext, true);
-    let result = Detectors::default().detects(&Vec::from([entry]));
+    Ok(Detectors::default().detects(&[FakeDirEntry::file(file_name)]))
+}
 
-    Ok(result)
+/// Names of the files language detection looks for at the top level of a project, see [Detectors::project_file_types]
+pub fn project_file_types() -> Vec<String> {
+    Detectors::default().project_file_types()
 }
 
-pub fn detect_lang_from_dir(dir: &PathBuf) -> Result<Vec<String>> {
+/// Languages detected from the top level entries of the directory, answered from the detection cache of ```cache```
+/// while the directory is unchanged
+pub(crate) fn detect_lang_from_dir(
+    vfs: &dyn Vfs,
+    min_evidence: usize,
+    tie_break: &TieBreak,
+    cache: Option<&Configs>,
+) -> Result<Vec<String>> {
     //
     let mut langs: Vec<String> = Vec::new();
-    if dir.metadata().unwrap().is_dir() {
-        let configs = CONFIGS.lock().unwrap();
-
-        let types = &configs.project_file_types.join(",");
-        let dir_str = dir.to_str().unwrap();
+    if vfs.is_dir(Path::new("")) {
+        let detectors = Detectors::default();
 
-        let dir_str_trimmed = if &dir_str[dir_str.len() - 1..] == "/" {
-            &dir_str[..dir_str.len() - 1]
-        } else {
-            &dir_str
+        let fingerprint = match cache {
+            Some(_) => fingerprint(vfs, &detectors, min_evidence, tie_break)?,
+            None => None,
         };
+        if let (Some(fingerprint), Some(cache)) = (fingerprint, cache) {
+            if let Some(langs) = cache.cached_langs(vfs.root(), fingerprint) {
+                return Ok(langs);
+            }
+        }
 
-        let glob_search_str = format!("{}/{{*.{}}}", dir_str_trimmed, types);
-        //get any of the files used in detection
-        let glob = Glob::new(&glob_search_str[..]).unwrap();
-
-        // println!("{:?}", glob_search_str);
-        for entry in glob.walk("doc", usize::MAX) {
-            // pass entry path
-            let matched_file = entry.unwrap().path().to_path_buf();
-            // get detected langs & concat
-            let entry_langs = detect_lang(&matched_file).unwrap();
-            langs = [langs, entry_langs].concat();
+        // get detected langs from the top level entries
+        langs = detectors.ranks(&read_entries(vfs, &detectors)?, min_evidence, tie_break);
+
+        if let (Some(fingerprint), Some(cache)) = (fingerprint, cache) {
+            cache.cache_langs(vfs.root(), fingerprint, &langs);
         }
 
         //Langs
@@ -459,20 +934,377 @@ pub fn detect_lang_from_dir(dir: &PathBuf) -> Result<Vec<String>> {
     Ok(langs)
 }
 
-pub fn get_lang_gitignore(langs: &Option<Vec<String>>) -> Result<Option<Vec<String>>> {
-    let configs = CONFIGS.lock().unwrap();
+/// Classifies the project from the top level entries, the detected languages' manifests and the detected tools, such as
+/// Xcode or Flutter
+pub(crate) fn detect_kinds_from_dir(vfs: &dyn Vfs) -> Result<Vec<ProjectKind>> {
+    let mut kinds: Vec<ProjectKind> = Vec::new();
+    if vfs.is_dir(Path::new("")) {
+        for detectors in [Detectors::default(), Detectors::tools()] {
+            for kind in detectors.kinds(vfs, &read_entries(vfs, &detectors)?)? {
+                if !kinds.contains(&kind) {
+                    kinds.push(kind);
+                }
+            }
+        }
+    }
+
+    Ok(kinds)
+}
+
+/// [ProjectKind::Empty] or [ProjectKind::ConfigOnly] for a project without a single code file, None otherwise.
+/// Files whose language is also a [FileCategory], such as Markdown or YAML, are not code. Hidden entries are skipped
+/// Without the ```loc``` feature code files cannot be told apart, so only projects without any file are classified
+pub(crate) fn detect_codeless_kind(vfs: &dyn Vfs) -> Result<Option<ProjectKind>> {
+    let entries = vfs.walk(&mut |e| e.depth == 0 || !code::is_hidden(e))?;
+    let mut categories: Vec<FileCategory> = vec![];
+
+    if cfg!(not(feature = "loc")) && entries.iter().any(|e| e.is_file) {
+        return Ok(None);
+    }
+
+    for e in entries.iter().filter(|e| e.is_file) {
+        match assets::category(&e.path) {
+            Some(category) => categories.push(category),
+            None if StatsLang::of_path(&e.path.to_string_lossy()) != StatsLang::Unrecognized => return Ok(None),
+            None => {}
+        }
+    }
+
+    if !entries.iter().any(|e| e.is_file) {
+        return Ok(Some(ProjectKind::Empty));
+    }
+
+    categories.sort();
+    categories.dedup();
+
+    Ok(Some(ProjectKind::ConfigOnly(categories)))
+}
+
+/// electron & react dependencies win over the library/app split. Unparsable manifests are not classified
+fn inspect_package_json(_vfs: &dyn Vfs, content: &str) -> Vec<ProjectKind> {
+    let manifest: serde_json::Value = match serde_json::from_str(content) {
+        Ok(manifest) => manifest,
+        _ => return vec![],
+    };
+
+    let depends_on = |name: &str| {
+        ["dependencies", "devDependencies"]
+            .iter()
+            .any(|key| manifest[*key].get(name).is_some())
+    };
+
+    let kind = if depends_on("electron") {
+        ProjectKind::ElectronApp
+    } else if depends_on("react") {
+        ProjectKind::ReactApp
+    } else {
+        let is_private = manifest["private"].as_bool().unwrap_or(false);
+        let has_entry = ["main", "module", "exports"]
+            .iter()
+            .any(|key| manifest.get(*key).is_some());
+
+        if has_entry && !is_private {
+            ProjectKind::NodeLibrary
+        } else {
+            ProjectKind::NodeApp
+        }
+    };
+
+    vec![kind]
+}
+
+/// Cargo.toml is scanned for its section headers, falling back to cargo's default target paths
+fn inspect_cargo_toml(vfs: &dyn Vfs, content: &str) -> Vec<ProjectKind> {
+    let sections: Vec<&str> = content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| line.starts_with('['))
+        .collect();
+    let has_section = |name: &str| sections.contains(&name);
+
+    let mut kinds = vec![];
+
+    if has_section("[workspace]") {
+        kinds.push(ProjectKind::RustWorkspace);
+    }
+    // virtual manifests have no package, hence no targets
+    if has_section("[package]") {
+        if has_section("[[bin]]") || vfs.is_file(Path::new("src/main.rs")) {
+            kinds.push(ProjectKind::RustBinary);
+        }
+        if has_section("[lib]") || vfs.is_file(Path::new("src/lib.rs")) {
+            kinds.push(ProjectKind::RustLibrary);
+        }
+    }
+
+    kinds
+}
+
+/// What a detected language maps to: its gitignore templates, in order of preference, and the
+/// loc languages whose code counts towards it
+struct LangInfo {
+    name: &'static str,
+    templates: &'static [&'static str],
+    loc_langs: &'static [&'static str],
+}
+
+const LANGS: [LangInfo; 22] = [
+    LangInfo {
+        name: "crystal",
+        templates: &["crystal"],
+        loc_langs: &["Crystal"],
+    },
+    LangInfo {
+        name: "dart",
+        templates: &["dart", "flutter"],
+        loc_langs: &["Dart"],
+    },
+    LangInfo {
+        name: "elixir",
+        templates: &["elixir"],
+        loc_langs: &["Elixir"],
+    },
+    LangInfo {
+        name: "elm",
+        templates: &["elm"],
+        loc_langs: &["Elm"],
+    },
+    LangInfo {
+        name: "erlang",
+        templates: &["erlang"],
+        loc_langs: &["Erlang"],
+    },
+    LangInfo {
+        name: "haskell",
+        templates: &["haskell"],
+        loc_langs: &["Haskell"],
+    },
+    LangInfo {
+        name: "go",
+        templates: &["go", "golang"],
+        loc_langs: &["Go"],
+    },
+    LangInfo {
+        name: "java",
+        templates: &["java", "maven"],
+        loc_langs: &["Java", "Kotlin", "Groovy", "Clojure"],
+    },
+    LangInfo {
+        name: "julia",
+        templates: &["julia"],
+        loc_langs: &["Julia"],
+    },
+    LangInfo {
+        name: "nim",
+        templates: &["nim"],
+        loc_langs: &["Nim"],
+    },
+    LangInfo {
+        name: "node",
+        templates: &["node", "nodejs"],
+        loc_langs: &["JavaScript", "TypeScript", "JSX", "TSX"],
+    },
+    LangInfo {
+        name: "ocaml",
+        templates: &["ocaml"],
+        loc_langs: &["OCaml"],
+    },
+    LangInfo {
+        name: "perl",
+        templates: &["perl"],
+        loc_langs: &["Perl"],
+    },
+    LangInfo {
+        name: "composer",
+        templates: &["composer", "php", "laravel"],
+        loc_langs: &["PHP"],
+    },
+    LangInfo {
+        name: "purescript",
+        templates: &["purescript"],
+        loc_langs: &["PureScript"],
+    },
+    LangInfo {
+        name: "python",
+        templates: &["python"],
+        loc_langs: &["Python"],
+    },
+    LangInfo {
+        name: "r",
+        templates: &["r"],
+        loc_langs: &["R"],
+    },
+    LangInfo {
+        name: "ruby",
+        templates: &["ruby", "rails"],
+        loc_langs: &["Ruby"],
+    },
+    LangInfo {
+        name: "rust",
+        templates: &["rust"],
+        loc_langs: &["Rust"],
+    },
+    LangInfo {
+        name: "scala",
+        templates: &["scala", "sbt"],
+        loc_langs: &["Scala"],
+    },
+    LangInfo {
+        name: "swift",
+        templates: &["swift", "swiftpackagemanager"],
+        loc_langs: &["Swift"],
+    },
+    LangInfo {
+        name: "zig",
+        templates: &["zig"],
+        loc_langs: &["Zig"],
+    },
+];
+
+/// Resolve a detector name to its gitignore template. Aliases from the language table are tried in
+/// order, and keys are compared case insensitively so that "r" and "R" style keys both resolve.
+/// Names missing from the table (tools, editors, OSes) are looked up as they are.
+pub(crate) fn resolve_template<'t>(
+    name: &str,
+    templates: &'t HashMap<String, Template>,
+) -> Option<&'t Template> {
+    let aliases = match LANGS.iter().find(|info| info.name == name) {
+        Some(info) => info.templates.to_vec(),
+        None => vec![name],
+    };
+
+    aliases.iter().find_map(|alias| {
+        templates.get(*alias).or_else(|| {
+            templates
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(alias))
+                .map(|(_, template)| template)
+        })
+    })
+}
+
+/// The loc languages whose code counts towards a detected language, None for names that are not detected languages
+pub(crate) fn loc_langs(lang: &str) -> Option<&'static [&'static str]> {
+    LANGS.iter().find(|info| info.name == lang).map(|info| info.loc_langs)
+}
+
+/// The detected language a loc language counts towards, e.g. "node" for "TypeScript"
+pub(crate) fn lang_of_loc(loc_lang: &str) -> Option<&'static str> {
+    LANGS
+        .iter()
+        .find(|info| info.loc_langs.iter().any(|name| name.eq_ignore_ascii_case(loc_lang)))
+        .map(|info| info.name)
+}
+
+/// Display name of a detected language, the first loc language it covers, e.g. "JavaScript" for "node"
+pub(crate) fn display_name(lang: &str) -> String {
+    LANGS
+        .iter()
+        .find(|info| info.name == lang)
+        .and_then(|info| info.loc_langs.first())
+        .map_or_else(|| lang.to_string(), |name| name.to_string())
+}
+
+/// Rank languages by lines of code. Detected languages are kept even without code, and languages
+/// that were not detected from marker files but have code on disk are added.
+pub(crate) fn rank_langs_by_loc(langs: &[String], stats: &Stats) -> Vec<String> {
+    let code_lines = |lang: &str| -> u64 {
+        LANGS
+            .iter()
+            .filter(|info| info.name == lang)
+            .flat_map(|info| info.loc_langs.iter())
+            .map(|loc_lang| {
+                stats
+                    .iter()
+                    .filter(|(name, _)| name.as_str().eq_ignore_ascii_case(loc_lang))
+                    .map(|(_, count)| count.code)
+                    .sum::<u64>()
+            })
+            .sum()
+    };
+
+    let mut ranked: Vec<(String, u64)> = langs
+        .iter()
+        .map(|lang| (lang.clone(), code_lines(lang)))
+        .collect();
+
+    for info in LANGS.iter() {
+        let lines = code_lines(info.name);
+        if lines > 0 && !ranked.iter().any(|(name, _)| name == info.name) {
+            ranked.push((info.name.to_string(), lines));
+        }
+    }
+
+    // stable sort keeps detection order between equals
+    ranked.sort_by_key(|(_, lines)| Reverse(*lines));
+
+    ranked.into_iter().map(|(lang, _)| lang).collect()
+}
+
+/// Gitignore templates covering operating system junk files, either for the host OS only or for all of them
+pub(crate) fn os_templates(all: bool) -> Vec<String> {
+    let templates = if all {
+        vec!["macos", "windows", "linux"]
+    } else if cfg!(target_os = "macos") {
+        vec!["macos"]
+    } else if cfg!(windows) {
+        vec!["windows"]
+    } else {
+        vec!["linux"]
+    };
+
+    templates.into_iter().map(String::from).collect()
+}
+
+/// Local environment & toolchain directories that are safe to ignore when found in a project
+const ENV_DIRS: [&str; 5] = [".venv", "venv", ".tox", ".direnv", ".gradle"];
+
+/// The environment directories found anywhere in the project, relative to the project directory, in path order.
+/// Hidden directories, other than environment ones, and those ```ruleset``` ignores are not looked into, nor are the
+/// environment directories found
+pub(crate) fn detect_env_dirs(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<Vec<String>> {
+    let mut env_dirs = vec![];
+
+    vfs.walk(&mut |e| {
+        if e.depth == 0 {
+            return true;
+        }
+        if !e.is_dir {
+            return false;
+        }
+        if ENV_DIRS.iter().any(|name| e.file_name() == *name) {
+            let rel_path = e.path.strip_prefix(vfs.root()).unwrap_or(&e.path);
+            env_dirs.push(rel_path.to_string_lossy().replace('\\', "/"));
+            return false;
+        }
+        !(code::is_hidden(e) || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)))
+    })?;
+    env_dirs.sort();
+
+    Ok(env_dirs)
+}
 
-    let mut git_ignores: Vec<String> = vec![];
+pub(crate) fn get_lang_gitignore(
+    langs: &Option<Vec<String>>,
+    configs: &Configs,
+    offline: bool,
+) -> Result<Option<Vec<Template>>> {
+    let mut git_ignores: Vec<Template> = vec![];
 
     match langs {
-        Some(langs) => {
+        // only load templates when there is something to look up
+        Some(langs) if !langs.is_empty() => {
+            let templates = configs.git_ignores(offline);
+
             // ;
             for lang in langs{
                 // println!("LANG {:?}", lang);
-                match configs.git_ignores.get(lang){
+                match resolve_template(lang, templates){
                     Some(git_ignore)=>{
-                        let ignore = git_ignore.contents.clone();
-                        git_ignores.push(ignore);
+                        git_ignores.push(git_ignore.clone());
+                    },
+                    None if offline => {
+                        return Err(anyhow!(ProjectError::TemplateUnavailable(lang.clone())));
                     },
                     _=>()
                 }
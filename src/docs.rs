@@ -0,0 +1,307 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+use crate::vfs::Vfs;
+
+/// Markup format of a documentation file, from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupFormat {
+    /// .md, .markdown
+    Markdown,
+    /// .rst
+    ReStructuredText,
+    /// .adoc, .asciidoc
+    AsciiDoc,
+    /// .org
+    Org,
+    /// no extension or .txt
+    PlainText,
+}
+
+impl MarkupFormat {
+    fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+
+        match extension.as_deref() {
+            Some("md") | Some("markdown") | Some("mdown") => MarkupFormat::Markdown,
+            Some("rst") => MarkupFormat::ReStructuredText,
+            Some("adoc") | Some("asciidoc") => MarkupFormat::AsciiDoc,
+            Some("org") => MarkupFormat::Org,
+            _ => MarkupFormat::PlainText,
+        }
+    }
+
+    /// preference when a project has several READMEs
+    fn rank(&self) -> usize {
+        match self {
+            MarkupFormat::Markdown => 0,
+            MarkupFormat::ReStructuredText => 1,
+            MarkupFormat::AsciiDoc => 2,
+            MarkupFormat::Org => 3,
+            MarkupFormat::PlainText => 4,
+        }
+    }
+}
+
+/// The project README, returned by [method.readme]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Readme {
+    /// path of the README
+    pub path: PathBuf,
+    /// markup format, from the file extension
+    pub format: MarkupFormat,
+    /// the first heading, used as the project title
+    pub title: Option<String>,
+    /// the first paragraph after the title, used as the project description
+    pub description: Option<String>,
+}
+
+/// The project changelog, returned by [method.changelog]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changelog {
+    /// path of the changelog
+    pub path: PathBuf,
+    /// releases, as ordered in the file, usually the latest first
+    pub entries: Vec<ChangelogEntry>,
+}
+
+/// A release listed in the changelog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogEntry {
+    /// version without a leading **v**, or **Unreleased**
+    pub version: String,
+    /// release date as written, when it is a ```YYYY-MM-DD``` date
+    pub date: Option<String>,
+    /// the changes listed under the version, one per line with list markers removed
+    pub summary: Option<String>,
+}
+
+static VERSION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^\[?(?:version |release )?v?(\d+\.\d+(?:\.\d+)?(?:-[0-9a-z.]+)?(?:\+[0-9a-z.]+)?)|^\[?(unreleased)\b")
+        .unwrap()
+});
+static DATE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(\d{4}-\d{2}-\d{2})\b").unwrap());
+
+/// Top level files whose name, without extension, is one of ```names```, compared case insensitively
+pub(crate) fn find_files(vfs: &dyn Vfs, names: &[&str]) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = vfs
+        .read_dir(Path::new(""))?
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|path| vfs.is_file(path))
+        .filter(|path| {
+            path.file_stem().is_some_and(|stem| {
+                names
+                    .iter()
+                    .any(|name| stem.to_string_lossy().eq_ignore_ascii_case(name))
+            })
+        })
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+/// Finds the README, preferring markdown, and extracts its title & description
+pub(crate) fn readme(vfs: &dyn Vfs) -> Result<Option<Readme>> {
+    let path = find_files(vfs, &["README", "READ.ME", "READ_ME"])?
+        .into_iter()
+        .min_by_key(|path| MarkupFormat::from_path(path).rank());
+
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let format = MarkupFormat::from_path(&path);
+    let content = vfs.read_to_string(&path)?;
+    let (title, description) = title_and_description(&content, format);
+
+    Ok(Some(Readme {
+        path,
+        format,
+        title,
+        description,
+    }))
+}
+
+/// Title of a heading line, if the line is one. ```next``` is the line after, for underlined headings
+pub(crate) fn heading(line: &str, next: Option<&str>, format: MarkupFormat) -> Option<String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    let underlined = next.is_some_and(|next| {
+        let next = next.trim();
+        !next.is_empty()
+            && next.len() >= line.chars().count().min(3)
+            && next.chars().all(|c| c == next.chars().next().unwrap_or(' '))
+            && next.starts_with(|c: char| "=-~^*#+".contains(c))
+    });
+
+    let title = match format {
+        MarkupFormat::Markdown if line.starts_with('#') => {
+            Some(line.trim_start_matches('#').trim_end_matches('#'))
+        }
+        MarkupFormat::Markdown | MarkupFormat::ReStructuredText if underlined => Some(line),
+        MarkupFormat::AsciiDoc if line.starts_with('=') => Some(line.trim_start_matches('=')),
+        MarkupFormat::Org if line.to_lowercase().starts_with("#+title:") => line.get(8..),
+        MarkupFormat::Org if line.starts_with('*') => Some(line.trim_start_matches('*')),
+        _ => None,
+    };
+
+    title
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+/// Lines that do not read as prose: badges, images, html, tables, fences, directives and underlines
+fn is_prose(line: &str) -> bool {
+    let line = line.trim();
+    let markup = ["[![", "![", "<", "|", "```", "~~~", "..", ":", "#+", "----", "===="];
+
+    !markup.iter().any(|prefix| line.starts_with(prefix))
+}
+
+fn title_and_description(content: &str, format: MarkupFormat) -> (Option<String>, Option<String>) {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut title: Option<String> = None;
+    let mut paragraph: Vec<&str> = vec![];
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some(heading) = heading(line, lines.get(i + 1).copied(), format) {
+            if !paragraph.is_empty() {
+                break;
+            }
+            if title.is_none() {
+                title = Some(heading);
+            }
+            // skip underlines
+            if lines.get(i + 1).is_some_and(|next| !is_prose(next)) {
+                i += 1;
+            }
+        } else if line.trim().is_empty() || !is_prose(line) {
+            if !paragraph.is_empty() {
+                break;
+            }
+        } else if format == MarkupFormat::PlainText && title.is_none() {
+            title = Some(line.trim().to_string());
+        } else {
+            paragraph.push(line.trim());
+        }
+
+        i += 1;
+    }
+
+    let description = if paragraph.is_empty() {
+        None
+    } else {
+        Some(paragraph.join(" "))
+    };
+
+    (title, description)
+}
+
+/// Finds the changelog (CHANGELOG, HISTORY, CHANGES or NEWS) and parses its version headings
+pub(crate) fn changelog(vfs: &dyn Vfs) -> Result<Option<Changelog>> {
+    let path = find_files(vfs, &["CHANGELOG", "HISTORY", "CHANGES", "NEWS", "RELEASES"])?
+        .into_iter()
+        .min_by_key(|path| MarkupFormat::from_path(path).rank());
+
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let format = MarkupFormat::from_path(&path);
+    let content = vfs.read_to_string(&path)?;
+
+    Ok(Some(Changelog {
+        path,
+        entries: changelog_entries(&content, format),
+    }))
+}
+
+/// Versions are read from headings, such as keep-a-changelog's ```## [1.0.0] - 2017-06-20```, or from plain
+/// text lines starting with a version, such as ```1.0.0 (2017-06-20)```
+fn changelog_entries(content: &str, format: MarkupFormat) -> Vec<ChangelogEntry> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut entries: Vec<ChangelogEntry> = vec![];
+    let mut summary: Vec<String> = vec![];
+
+    let finish = |entries: &mut Vec<ChangelogEntry>, summary: &mut Vec<String>| {
+        if let Some(entry) = entries.last_mut() {
+            if !summary.is_empty() {
+                entry.summary = Some(summary.join("\n"));
+            }
+        }
+        summary.clear();
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        // plain text has no headings, lines starting with a version stand for them
+        let heading = match format {
+            MarkupFormat::PlainText => Some(line.trim().to_string()),
+            _ => heading(line, lines.get(i + 1).copied(), format),
+        };
+        let version = heading.as_ref().and_then(|heading| VERSION.captures(heading));
+
+        match (version, &heading) {
+            (Some(captures), Some(heading)) => {
+                finish(&mut entries, &mut summary);
+
+                let version = captures
+                    .get(1)
+                    .or_else(|| captures.get(2))
+                    .map_or("", |m| m.as_str());
+                let version = if version.eq_ignore_ascii_case("unreleased") {
+                    String::from("Unreleased")
+                } else {
+                    version.to_string()
+                };
+
+                entries.push(ChangelogEntry {
+                    version,
+                    date: DATE.captures(heading).map(|date| date[1].to_string()),
+                    summary: None,
+                });
+            }
+            // sub headings such as "### Added" are not changes
+            (None, Some(_)) if format != MarkupFormat::PlainText => (),
+            _ => {
+                let line = line.trim();
+                // neither are link references such as "[1.0.0]: https://..."
+                let is_link = line.starts_with('[') && line.contains("]: ");
+                if !entries.is_empty() && !line.is_empty() && is_prose(line) && !is_link {
+                    let change = line.trim_start_matches(|c: char| "-*+".contains(c)).trim();
+                    summary.push(change.to_string());
+                }
+            }
+        }
+    }
+    finish(&mut entries, &mut summary);
+
+    entries
+}
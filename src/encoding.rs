@@ -0,0 +1,118 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::code;
+use crate::ruleset::RuleSet;
+use crate::stats::StatsLang;
+use crate::vfs::Vfs;
+
+/// What is wrong with the encoding of a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum EncodingIssueKind {
+    /// the content is not valid UTF-8, as files saved in Latin-1 or Windows-1252 are
+    InvalidUtf8 {
+        /// the line of the first invalid byte, from 1
+        line: usize,
+        /// the offset of the first invalid byte
+        offset: usize,
+    },
+    /// the content holds ```U+FFFD``` replacement characters, left by a conversion that lost the original characters
+    ReplacementChars {
+        /// number of replacement characters
+        count: usize,
+        /// the line of the first one, from 1
+        line: usize,
+    },
+}
+
+/// A code file whose encoding is broken, in [EncodingReport]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EncodingIssue {
+    /// path relative to the project directory
+    pub path: PathBuf,
+    /// what is wrong with it
+    pub kind: EncodingIssueKind,
+}
+
+/// The code files that are not valid UTF-8 or hold replacement characters, returned by [method.encoding_report]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EncodingReport {
+    /// number of code files checked
+    pub files: usize,
+    /// the broken files keyed by language name, in walk order
+    pub langs: BTreeMap<String, Vec<EncodingIssue>>,
+}
+
+impl EncodingReport {
+    /// Whether every file checked is valid UTF-8 without replacement characters
+    pub fn is_clean(&self) -> bool {
+        self.langs.is_empty()
+    }
+}
+
+/// The issue of a file's content, if any
+fn check(bytes: &[u8]) -> Option<EncodingIssueKind> {
+    let line = |offset: usize| bytes[..offset].iter().filter(|byte| **byte == b'\n').count() + 1;
+
+    match std::str::from_utf8(bytes) {
+        Err(error) => Some(EncodingIssueKind::InvalidUtf8 {
+            line: line(error.valid_up_to()),
+            offset: error.valid_up_to(),
+        }),
+        Ok(content) => {
+            let first = content.find(char::REPLACEMENT_CHARACTER)?;
+            Some(EncodingIssueKind::ReplacementChars {
+                count: content.matches(char::REPLACEMENT_CHARACTER).count(),
+                line: line(first),
+            })
+        }
+    }
+}
+
+/// Reads the code files met walking the project, hidden & ignored entries aside
+pub(crate) fn report(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<EncodingReport> {
+    let entries = vfs.walk(&mut |e| {
+        e.depth == 0 || !(code::is_hidden(e) || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)))
+    })?;
+    let mut report = EncodingReport::default();
+
+    for e in entries.iter().filter(|e| e.is_file) {
+        let lang = match StatsLang::of_path(&e.path.to_string_lossy()) {
+            StatsLang::Unrecognized => continue,
+            lang => lang.to_string(),
+        };
+        let rel_path = match e.path.strip_prefix(vfs.root()) {
+            Ok(rel_path) => rel_path,
+            Err(_) => continue,
+        };
+        let bytes = match vfs.read(rel_path) {
+            Ok(bytes) => bytes,
+            Err(_) => continue,
+        };
+
+        report.files += 1;
+        if let Some(kind) = check(&bytes) {
+            report.langs.entry(lang).or_default().push(EncodingIssue {
+                path: rel_path.to_path_buf(),
+                kind,
+            });
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,68 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, path::Path, sync::Arc};
+
+use crate::code::Stats;
+
+type PathHook = Arc<dyn Fn(&Path) + Send + Sync>;
+type LangHook = Arc<dyn Fn(&str) + Send + Sync>;
+type StatsHook = Arc<dyn Fn(&Stats) + Send + Sync>;
+
+/// Callbacks registered with [method.on_file_visited], [method.on_language_detected], [method.on_ignored] and
+/// [method.on_stats_ready]. They are shared by clones of the project
+#[derive(Clone, Default)]
+pub(crate) struct Events {
+    pub file_visited: Option<PathHook>,
+    pub language_detected: Option<LangHook>,
+    pub ignored: Option<PathHook>,
+    pub stats_ready: Option<StatsHook>,
+}
+
+impl fmt::Debug for Events {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Events")
+            .field("file_visited", &self.file_visited.is_some())
+            .field("language_detected", &self.language_detected.is_some())
+            .field("ignored", &self.ignored.is_some())
+            .field("stats_ready", &self.stats_ready.is_some())
+            .finish()
+    }
+}
+
+impl Events {
+    pub fn file_visited(&self, path: &Path) {
+        if let Some(hook) = &self.file_visited {
+            hook(path);
+        }
+    }
+
+    pub fn language_detected(&self, lang: &str) {
+        if let Some(hook) = &self.language_detected {
+            hook(lang);
+        }
+    }
+
+    pub fn ignored(&self, path: &Path) {
+        if let Some(hook) = &self.ignored {
+            hook(path);
+        }
+    }
+
+    pub fn stats_ready(&self, stats: &Stats) {
+        if let Some(hook) = &self.stats_ready {
+            hook(stats);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use std::{fmt::Write as _, io::Write};
+
+use crate::stats::{CodeStats, LangStats};
+use crate::tree::FileTree;
+
+/// Rows written by [method.export_csv]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvGranularity {
+    /// one row per language: ```language,files,code,comment,blank```
+    Languages,
+    /// one row per non ignored file: ```path,language,bytes,code```. Language & code are empty for non-code files
+    Files,
+}
+
+/// Quotes fields containing commas, quotes or line breaks, as RFC 4180 has it
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn row<W: Write>(writer: &mut W, fields: &[String]) -> Result<()> {
+    let fields: Vec<String> = fields.iter().map(|value| field(value)).collect();
+    writeln!(writer, "{}", fields.join(","))?;
+
+    Ok(())
+}
+
+/// Writes the header then the rows, sorted by lines of code for languages and by path for files
+pub(crate) fn csv<W: Write>(
+    writer: &mut W,
+    granularity: CsvGranularity,
+    tree: &FileTree,
+    stats: &CodeStats,
+) -> Result<()> {
+    match granularity {
+        CsvGranularity::Languages => {
+            row(writer, &["language", "files", "code", "comment", "blank"].map(String::from))?;
+
+            let mut langs: Vec<_> = stats.langs.iter().collect();
+            langs.sort_by(|a, b| b.1.code.cmp(&a.1.code).then_with(|| a.0.cmp(b.0)));
+
+            for (lang, lang_stats) in langs {
+                row(
+                    writer,
+                    &[
+                        lang.clone(),
+                        lang_stats.files.to_string(),
+                        lang_stats.code.to_string(),
+                        lang_stats.comment.to_string(),
+                        lang_stats.blank.to_string(),
+                    ],
+                )?;
+            }
+        }
+        CsvGranularity::Files => {
+            row(writer, &["path", "language", "bytes", "code"].map(String::from))?;
+
+            let mut files: Vec<_> = tree
+                .iter()
+                .filter(|node| !node.is_dir && !node.is_ignored)
+                .collect();
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+
+            for node in files {
+                let code = stats.files.get(&node.path).map(|file| file.code.to_string());
+                row(
+                    writer,
+                    &[
+                        node.path.to_string_lossy().to_string(),
+                        node.lang.clone().unwrap_or_default(),
+                        node.size.to_string(),
+                        code.unwrap_or_default(),
+                    ],
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Escapes a label value as the exposition format has it
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// name, help text and value of a gauge
+type Gauge<'a> = (&'a str, &'a str, &'a dyn Fn(&LangStats) -> String);
+
+/// Renders the per language stats as gauges in the Prometheus text exposition format, labelled with the project name
+pub(crate) fn prometheus(project: &str, stats: &CodeStats) -> String {
+    let total = stats.total().code;
+
+    let mut langs: Vec<_> = stats.langs.iter().collect();
+    langs.sort_by(|a, b| a.0.cmp(b.0));
+
+    let gauges: [Gauge; 5] = [
+        ("code_lines", "Lines of code", &|lang| lang.code.to_string()),
+        ("comment_lines", "Comment lines", &|lang| lang.comment.to_string()),
+        ("blank_lines", "Blank lines", &|lang| lang.blank.to_string()),
+        ("files", "Number of code files", &|lang| lang.files.to_string()),
+        ("language_share", "Share of the lines of code, from 0 to 1", &|lang| {
+            if total == 0 {
+                String::from("0")
+            } else {
+                (lang.code as f64 / total as f64).to_string()
+            }
+        }),
+    ];
+
+    let mut out = String::new();
+    for (name, help, value) in gauges {
+        let _ = writeln!(out, "# HELP project_parse_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE project_parse_{} gauge", name);
+        for (lang, lang_stats) in &langs {
+            let _ = writeln!(
+                out,
+                "project_parse_{}{{project=\"{}\",language=\"{}\"}} {}",
+                name,
+                label(project),
+                label(lang),
+                value(lang_stats)
+            );
+        }
+    }
+
+    out
+}
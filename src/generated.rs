@@ -0,0 +1,50 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Number of lines at the top of a file searched for a generated marker
+pub(crate) const MARKER_LINES: usize = 10;
+
+/// Leaders of the comment lines code generators write their headers in
+const COMMENT_LEADERS: [&str; 9] = ["//", "#", "/*", "*", "<!--", "--", ";", "%", "\"\"\""];
+
+/// Header lines code generators write at the top of their output, compared case insensitively
+/// - ```@generated```, used by Facebook tools, Cargo & many others
+/// - ```Code generated ... DO NOT EDIT.```, Go's convention, on a single line
+/// - ```<auto-generated>```, the .NET convention
+/// - ```Generated by the protocol buffer compiler.  DO NOT EDIT!```, protoc's header
+/// - ```automatically generated by ...```, as rust-bindgen & others write it
+static HEADERS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?i)@generated\b",
+        r"|\bcode generated\b.*\bdo not edit\b",
+        r"|<auto-generated",
+        r"|\bgenerated by the protocol buffer compiler\b",
+        r"|\b(automatically|auto-generated|autogenerated)( generated)? by\b",
+    ))
+    .unwrap()
+});
+
+/// Whether a line is a comment holding one of the [HEADERS], rather than code or prose mentioning them
+fn is_header(line: &str) -> bool {
+    let line = line.trim_start();
+    COMMENT_LEADERS.iter().any(|leader| line.starts_with(leader)) && HEADERS.is_match(line)
+}
+
+/// Whether one of the first [MARKER_LINES] lines of a file is a generator header
+pub(crate) fn is_generated(content: &str) -> bool {
+    content.lines().take(MARKER_LINES).any(is_header)
+}
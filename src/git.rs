@@ -0,0 +1,181 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::ruleset::RuleSet;
+use crate::vfs::{normalize, Vfs};
+
+/// Git LFS pointers are under a kilobyte, larger files are never read to check
+pub(crate) const LFS_POINTER_MAX_SIZE: u64 = 1024;
+
+/// A submodule declared in .gitmodules, returned by [method.submodules]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Submodule {
+    /// name of the submodule section
+    pub name: String,
+    /// path of the submodule, relative to the project directory
+    pub path: PathBuf,
+    /// repository the submodule is cloned from
+    pub url: Option<String>,
+    /// branch the submodule tracks, if set
+    pub branch: Option<String>,
+}
+
+/// Submodules declared in the .gitmodules of the project, in the order they are declared. Submodules without a path
+/// are left out
+pub(crate) fn submodules(vfs: &dyn Vfs) -> Result<Vec<Submodule>> {
+    let content = match vfs.read_to_string(Path::new(".gitmodules")) {
+        Ok(content) => content,
+        Err(_) => return Ok(vec![]),
+    };
+
+    let mut submodules: Vec<Submodule> = vec![];
+    // whether the lines belong to a submodule section
+    let mut in_submodule = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        // [submodule "name"]
+        if let Some(section) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            let name = section.trim().strip_prefix("submodule");
+            in_submodule = name.is_some();
+            if let Some(name) = name {
+                submodules.push(Submodule {
+                    name: name.trim().trim_matches('"').to_string(),
+                    ..Default::default()
+                });
+            }
+            continue;
+        }
+
+        let (submodule, (key, value)) = match (submodules.last_mut(), line.split_once('=')) {
+            (Some(submodule), Some(pair)) if in_submodule => (submodule, pair),
+            _ => continue,
+        };
+        let value = value.trim().trim_matches('"').to_string();
+
+        match key.trim() {
+            "path" => submodule.path = PathBuf::from(value),
+            "url" => submodule.url = Some(value),
+            "branch" => submodule.branch = Some(value),
+            _ => {}
+        }
+    }
+
+    submodules.retain(|submodule| !submodule.path.as_os_str().is_empty());
+
+    Ok(submodules)
+}
+
+/// The git directory of the project, relative to the project directory unless it lies elsewhere. That is
+/// ```.git``` for a regular checkout, the directory a ```.git``` file points to for linked worktrees & submodules, or
+/// the project directory itself for a bare repository
+pub(crate) fn git_dir(vfs: &dyn Vfs) -> Option<PathBuf> {
+    let dot_git = Path::new(".git");
+
+    if vfs.is_dir(dot_git) {
+        return Some(dot_git.to_path_buf());
+    }
+
+    // gitdir: /repo/.git/worktrees/name
+    if vfs.is_file(dot_git) {
+        let content = vfs.read_to_string(dot_git).ok()?;
+        let dir = content.lines().find_map(|line| line.trim().strip_prefix("gitdir:"))?.trim();
+        return match dir.is_empty() {
+            true => None,
+            false => Some(normalize(Path::new(dir))),
+        };
+    }
+
+    let bare = vfs.is_file(Path::new("HEAD")) && vfs.is_dir(Path::new("objects")) && vfs.is_dir(Path::new("refs"));
+    bare.then(PathBuf::new)
+}
+
+/// The directory holding what the worktrees of a repository share, such as its config & hooks. Linked worktrees
+/// name it in a ```commondir``` file, other git directories are their own
+pub(crate) fn common_dir(vfs: &dyn Vfs, git_dir: &Path) -> PathBuf {
+    match vfs.read_to_string(&git_dir.join("commondir")) {
+        Ok(content) if !content.trim().is_empty() => normalize(&git_dir.join(content.trim())),
+        _ => git_dir.to_path_buf(),
+    }
+}
+
+/// Directories of the project, other than submodules, holding a git repository of their own, relative to the project
+/// directory. Hidden directories & node_modules are not searched, nor are the nested repositories themselves
+pub(crate) fn nested_repos(vfs: &dyn Vfs, submodules: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let root = vfs.root().to_path_buf();
+    let mut repos: Vec<PathBuf> = vec![];
+
+    vfs.walk(&mut |e| {
+        let name = e.file_name();
+        if name == ".git" {
+            if e.depth > 1 {
+                let repo = e.path.parent().and_then(|dir| dir.strip_prefix(&root).ok());
+                if let Some(repo) = repo {
+                    repos.push(repo.to_path_buf());
+                }
+            }
+            return false;
+        }
+
+        e.depth == 0 || !(e.is_dir && (name.to_string_lossy().starts_with('.') || name == "node_modules"))
+    })?;
+
+    repos.sort();
+    repos.retain(|repo| !submodules.contains(repo));
+    // repositories within another one belong to it
+    let outer = repos.clone();
+    repos.retain(|repo| !outer.iter().any(|other| other != repo && repo.starts_with(other)));
+
+    Ok(repos)
+}
+
+/// Rules matching the paths .gitattributes stores with Git LFS, None when it has none
+pub(crate) fn lfs_ruleset(vfs: &dyn Vfs) -> Result<Option<RuleSet>> {
+    let content = match vfs.read_to_string(Path::new(".gitattributes")) {
+        Ok(content) => content,
+        Err(_) => return Ok(None),
+    };
+
+    // *.psd filter=lfs diff=lfs merge=lfs -text
+    let patterns: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next()?;
+            fields.any(|attr| attr == "filter=lfs").then_some(pattern)
+        })
+        .collect();
+
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(RuleSet::new(&vfs.root().to_path_buf(), patterns)?))
+}
+
+/// Whether content is a Git LFS pointer, the stub checked out in place of a file whose content was not fetched
+/// See https://github.com/git-lfs/git-lfs/blob/main/docs/spec.md
+pub(crate) fn is_lfs_pointer(content: &str) -> bool {
+    content.starts_with("version https://git-lfs.github.com/spec/")
+        && content.lines().any(|line| line.starts_with("oid sha256:"))
+        && content.lines().any(|line| line.starts_with("size "))
+}
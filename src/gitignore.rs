@@ -0,0 +1,173 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+/// A line of a gitignore file, kept as written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    /// an empty or whitespace only line
+    Blank(String),
+    /// a line starting with ```#```
+    Comment(String),
+    /// a pattern, negated or not
+    Rule(String),
+}
+
+impl Line {
+    fn parse(line: &str) -> Self {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            Line::Blank(line.to_string())
+        } else if trimmed.starts_with('#') {
+            Line::Comment(line.to_string())
+        } else {
+            Line::Rule(line.to_string())
+        }
+    }
+
+    /// The line as written
+    pub fn as_str(&self) -> &str {
+        match self {
+            Line::Blank(line) | Line::Comment(line) | Line::Rule(line) => line,
+        }
+    }
+
+    /// The pattern of a rule, without surrounding whitespace, None for other lines
+    pub fn rule(&self) -> Option<&str> {
+        match self {
+            Line::Rule(line) => Some(line.trim()),
+            _ => None,
+        }
+    }
+}
+
+/// Lines up to & including the blank lines that end them, usually a commented group of rules
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Section {
+    /// the lines of the section, in order
+    pub lines: Vec<Line>,
+}
+
+impl Section {
+    /// The patterns of the section
+    pub fn rules(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(Line::rule)
+    }
+
+    fn ends_blank(&self) -> bool {
+        matches!(self.lines.last(), Some(Line::Blank(_)))
+    }
+}
+
+/// A gitignore modelled as sections of comments, rules & blank lines, so that gitignores can be merged without losing
+/// comments or ordering. Parsing and printing back gives the same text
+/// ```no_run
+/// use project_parse::gitignore::Gitignore;
+///
+/// let mut gitignore = Gitignore::parse("# build\ntarget/\n");
+/// gitignore.merge(&Gitignore::parse("# logs\n*.log\n\n# build\ntarget/\n"));
+/// assert_eq!("# build\ntarget/\n\n# logs\n*.log\n", gitignore.to_string());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Gitignore {
+    /// the sections, in order
+    pub sections: Vec<Section>,
+    /// whether the text ends with a line break
+    pub trailing_newline: bool,
+}
+
+impl Gitignore {
+    /// Parses gitignore content, starting a new section after each run of blank lines
+    pub fn parse(content: &str) -> Self {
+        let mut sections: Vec<Section> = vec![];
+        let mut section = Section::default();
+
+        for line in content.lines() {
+            let line = Line::parse(line);
+            if section.ends_blank() && !matches!(line, Line::Blank(_)) {
+                sections.push(std::mem::take(&mut section));
+            }
+            section.lines.push(line);
+        }
+        if !section.lines.is_empty() {
+            sections.push(section);
+        }
+
+        Gitignore {
+            sections,
+            trailing_newline: content.ends_with('\n'),
+        }
+    }
+
+    /// All the patterns, in order
+    pub fn rules(&self) -> impl Iterator<Item = &str> {
+        self.sections.iter().flat_map(Section::rules)
+    }
+
+    /// Whether the gitignore has a pattern, compared without surrounding whitespace
+    pub fn contains(&self, rule: &str) -> bool {
+        self.rules().any(|existing| existing == rule.trim())
+    }
+
+    /// Appends the sections of ```other``` that bring new patterns, along with their comments. Patterns already
+    /// present are left out, so merging the same gitignore again changes nothing
+    pub fn merge(&mut self, other: &Gitignore) {
+        for section in &other.sections {
+            let mut lines: Vec<Line> = vec![];
+            for line in &section.lines {
+                match line.rule() {
+                    Some(rule) if self.contains(rule) || lines.iter().any(|line| line.rule() == Some(rule)) => {}
+                    _ => lines.push(line.clone()),
+                }
+            }
+
+            if !lines.iter().any(|line| line.rule().is_some()) {
+                continue;
+            }
+            // sections are separated as they are appended
+            while matches!(lines.last(), Some(Line::Blank(_))) {
+                lines.pop();
+            }
+
+            if let Some(last) = self.sections.last_mut() {
+                if !last.ends_blank() {
+                    last.lines.push(Line::Blank(String::new()));
+                }
+            }
+            self.sections.push(Section { lines });
+        }
+
+        if !other.sections.is_empty() {
+            self.trailing_newline = self.trailing_newline || other.trailing_newline;
+        }
+    }
+}
+
+impl fmt::Display for Gitignore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<&str> = self
+            .sections
+            .iter()
+            .flat_map(|section| section.lines.iter().map(Line::as_str))
+            .collect();
+
+        f.write_str(&lines.join("\n"))?;
+        if self.trailing_newline && !lines.is_empty() {
+            f.write_str("\n")?;
+        }
+
+        Ok(())
+    }
+}
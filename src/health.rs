@@ -0,0 +1,281 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::code;
+use crate::ruleset::RuleSet;
+use crate::vfs::Vfs;
+
+/// A signal the health score is made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum Criterion {
+    /// a license was identified
+    License,
+    /// a README was found
+    Readme,
+    /// test files or directories were found
+    Tests,
+    /// the configuration of a CI service was found
+    Ci,
+    /// share of comment lines in the code, against [HealthConfig::target_comment_ratio]
+    CommentRatio,
+    /// share of the entries the language templates ignore that the project .gitignore ignores too
+    GitignoreCoverage,
+}
+
+/// Weights of the criteria and the comment ratio aimed at, passed to [method.health]
+/// ```no_run
+/// let mut config = HealthConfig::default();
+/// config.weight(Criterion::CommentRatio, 0).target_comment_ratio = 0.2;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthConfig {
+    /// weight of each criterion. Criteria left out or weighing 0 are not scored
+    pub weights: Vec<(Criterion, u32)>,
+    /// share of comment lines among the code & comment lines scoring full marks, lower shares scoring in proportion
+    pub target_comment_ratio: f64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        HealthConfig {
+            weights: vec![
+                (Criterion::License, 15),
+                (Criterion::Readme, 20),
+                (Criterion::Tests, 25),
+                (Criterion::Ci, 15),
+                (Criterion::CommentRatio, 10),
+                (Criterion::GitignoreCoverage, 15),
+            ],
+            target_comment_ratio: 0.15,
+        }
+    }
+}
+
+impl HealthConfig {
+    /// Sets the weight of a criterion, 0 leaving it out of the score
+    pub fn weight(&mut self, criterion: Criterion, weight: u32) -> &mut Self {
+        self.weights.retain(|(c, _)| *c != criterion);
+        self.weights.push((criterion, weight));
+        self
+    }
+}
+
+/// How a project fares on one [Criterion]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CriterionScore {
+    /// the criterion
+    pub criterion: Criterion,
+    /// its weight in the score
+    pub weight: u32,
+    /// from 0 to 1
+    pub score: f64,
+    /// what was found, e.g. **MIT** or **3 of 4 ignored entries covered**
+    pub detail: String,
+}
+
+/// Health score of a project, returned by [method.health]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Health {
+    /// from 0 to 100, the weighted average of the criteria scores
+    pub score: u8,
+    /// the criteria scored, in the order of the weights
+    pub criteria: Vec<CriterionScore>,
+}
+
+/// The signals the score is computed from
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Signals {
+    pub license: Option<String>,
+    pub readme: Option<String>,
+    pub tests: usize,
+    pub ci: Vec<&'static str>,
+    /// comment & code lines
+    pub comments: Option<(u64, u64)>,
+    /// entries ignored by both the project .gitignore & the templates, and by the templates. None without a .gitignore
+    pub gitignore: Option<(usize, usize)>,
+}
+
+/// Files & directories of CI services, with the service name
+const CI_CONFIGS: [(&str, &str); 9] = [
+    (".github/workflows", "GitHub Actions"),
+    (".gitlab-ci.yml", "GitLab CI"),
+    (".circleci", "CircleCI"),
+    (".travis.yml", "Travis CI"),
+    ("Jenkinsfile", "Jenkins"),
+    ("azure-pipelines.yml", "Azure Pipelines"),
+    ("bitbucket-pipelines.yml", "Bitbucket Pipelines"),
+    (".drone.yml", "Drone"),
+    (".buildkite", "Buildkite"),
+];
+
+/// CI services configured in the project
+pub(crate) fn ci_services(vfs: &dyn Vfs) -> Vec<&'static str> {
+    CI_CONFIGS
+        .iter()
+        .filter(|(path, _)| vfs.exists(Path::new(path)))
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// Whether a path is a test directory or a test file by the naming conventions of the common test runners, as
+/// ```tests/```, ```__tests__/```, ```test_x.py```, ```x_test.go``` or ```x.spec.ts```
+fn is_test_path(path: &Path, is_dir: bool) -> bool {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_lowercase(),
+        None => return false,
+    };
+
+    if is_dir {
+        return matches!(name.as_str(), "test" | "tests" | "__tests__" | "spec" | "specs");
+    }
+
+    let stem = name.split('.').next().unwrap_or_default();
+
+    name.contains(".test.")
+        || name.contains(".spec.")
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || (stem.ends_with("test") && name.ends_with(".java"))
+}
+
+/// Number of test files & directories, hidden & ignored entries aside. The files within test directories are not
+/// counted apart
+pub(crate) fn count_tests(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<usize> {
+    let mut tests = 0;
+
+    vfs.walk(&mut |e| {
+        if e.depth == 0 {
+            return true;
+        }
+        if code::is_hidden(e) || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)) {
+            return false;
+        }
+        if is_test_path(&e.path, e.is_dir) {
+            tests += 1;
+            return false;
+        }
+
+        true
+    })?;
+
+    Ok(tests)
+}
+
+/// Entries the template rules ignore and how many of them the project .gitignore ignores too, the ignored directories
+/// not being walked. None when the project has no .gitignore
+pub(crate) fn gitignore_coverage(
+    vfs: &dyn Vfs,
+    templates: Option<&RuleSet>,
+    own: Option<&RuleSet>,
+) -> Result<Option<(usize, usize)>> {
+    let own = match own {
+        Some(own) => own,
+        None => return Ok(None),
+    };
+    let templates = match templates {
+        Some(templates) => templates,
+        None => return Ok(Some((0, 0))),
+    };
+    let (mut covered, mut total) = (0, 0);
+
+    vfs.walk(&mut |e| {
+        if e.depth == 0 {
+            return true;
+        }
+        if e.file_name() == ".git" {
+            return false;
+        }
+        if code::is_ignored(templates, e) {
+            total += 1;
+            if code::is_ignored(own, e) {
+                covered += 1;
+            }
+            return false;
+        }
+
+        true
+    })?;
+
+    Ok(Some((covered, total)))
+}
+
+fn score_of(criterion: Criterion, signals: &Signals, config: &HealthConfig) -> (f64, String) {
+    let found = |name: &Option<String>| match name {
+        Some(name) => (1.0, name.clone()),
+        None => (0.0, String::from("none found")),
+    };
+
+    match criterion {
+        Criterion::License => found(&signals.license),
+        Criterion::Readme => found(&signals.readme),
+        Criterion::Tests => match signals.tests {
+            0 => (0.0, String::from("none found")),
+            tests => (1.0, format!("{} test files or directories", tests)),
+        },
+        Criterion::Ci if signals.ci.is_empty() => (0.0, String::from("none found")),
+        Criterion::Ci => (1.0, signals.ci.join(", ")),
+        Criterion::CommentRatio => match signals.comments {
+            Some((comments, code)) if comments + code > 0 => {
+                let ratio = comments as f64 / (comments + code) as f64;
+                let score = if config.target_comment_ratio > 0.0 {
+                    (ratio / config.target_comment_ratio).min(1.0)
+                } else {
+                    1.0
+                };
+                (score, format!("{:.0}% comments", ratio * 100.0))
+            }
+            _ => (0.0, String::from("no code")),
+        },
+        Criterion::GitignoreCoverage => match signals.gitignore {
+            None => (0.0, String::from("no .gitignore")),
+            Some((_, 0)) => (1.0, String::from("nothing to ignore")),
+            Some((covered, total)) => (
+                covered as f64 / total as f64,
+                format!("{} of {} ignored entries covered", covered, total),
+            ),
+        },
+    }
+}
+
+/// Scores the signals, each criterion weighing as the config has it
+pub(crate) fn score(signals: &Signals, config: &HealthConfig) -> Health {
+    let criteria: Vec<CriterionScore> = config
+        .weights
+        .iter()
+        .filter(|(_, weight)| *weight > 0)
+        .map(|(criterion, weight)| {
+            let (score, detail) = score_of(*criterion, signals, config);
+            CriterionScore {
+                criterion: *criterion,
+                weight: *weight,
+                score,
+                detail,
+            }
+        })
+        .collect();
+
+    let weights: u32 = criteria.iter().map(|c| c.weight).sum();
+    let points: f64 = criteria.iter().map(|c| c.score * c.weight as f64).sum();
+    let score = match weights {
+        0 => 0,
+        _ => (points * 100.0 / weights as f64).round() as u8,
+    };
+
+    Health { score, criteria }
+}
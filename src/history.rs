@@ -0,0 +1,132 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{anyhow, Result};
+use git2::{Repository, Sort};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::tree::FileTree;
+use crate::vfs::Vfs;
+
+/// A file changed by commits since a date, returned by [method.change_hotspots]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangeHotspot {
+    /// path relative to the project directory
+    pub path: PathBuf,
+    /// language of code files, as named by the code stats
+    pub lang: Option<String>,
+    /// number of commits that changed the file, merges aside
+    pub commits: usize,
+    /// number of lines of the file
+    pub lines: usize,
+    /// size in bytes
+    pub size: u64,
+    /// whether the file is both among the most changed & the largest files, in the top quarter of each: the files most
+    /// likely to hold bugs
+    pub is_risk: bool,
+}
+
+/// Number of commits changing each path since ```since```, merges aside, keyed by path relative to ```dir```
+fn commit_counts(dir: &Path, since: SystemTime) -> Result<HashMap<PathBuf, usize>> {
+    let repo = Repository::discover(dir)?;
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir.canonicalize()?,
+        None => return Err(anyhow!("{:?} is a bare repository, without files to rank", dir)),
+    };
+    // the project may be a directory of the repository
+    let prefix = dir.strip_prefix(&workdir).unwrap_or(Path::new("")).to_path_buf();
+    let since = since.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs() as i64);
+
+    let mut revwalk = repo.revwalk()?;
+    // an empty repository has no history
+    if revwalk.push_head().is_err() {
+        return Ok(HashMap::new());
+    }
+    revwalk.set_sorting(Sort::TIME)?;
+
+    let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        // commits come newest first
+        if commit.time().seconds() < since {
+            break;
+        }
+        if commit.parent_count() > 1 {
+            continue;
+        }
+
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        for delta in diff.deltas() {
+            if let Some(path) = delta.new_file().path().and_then(|path| path.strip_prefix(&prefix).ok()) {
+                *counts.entry(path.to_path_buf()).or_default() += 1;
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+/// The value at the start of the top quarter of the values
+fn top_quarter(mut values: Vec<usize>) -> usize {
+    values.sort_unstable();
+    values.get(values.len() * 3 / 4).copied().unwrap_or(usize::MAX)
+}
+
+/// The non ignored files of the tree changed since ```since```, the most changed first, larger files first among those
+/// changed as often
+pub(crate) fn hotspots(vfs: &dyn Vfs, dir: &Path, tree: &FileTree, since: SystemTime) -> Result<Vec<ChangeHotspot>> {
+    let counts = commit_counts(dir, since)?;
+
+    let mut hotspots: Vec<ChangeHotspot> = tree
+        .iter()
+        .filter(|node| !node.is_dir && !node.is_ignored)
+        .filter_map(|node| {
+            let commits = *counts.get(&node.path)?;
+            // binary files have no lines
+            let lines = vfs.read_to_string(&node.path).map_or(0, |content| content.lines().count());
+            Some(ChangeHotspot {
+                path: node.path.clone(),
+                lang: node.lang.clone(),
+                commits,
+                lines,
+                size: node.size,
+                is_risk: false,
+            })
+        })
+        .collect();
+
+    let most_commits = top_quarter(hotspots.iter().map(|hotspot| hotspot.commits).collect());
+    let most_lines = top_quarter(hotspots.iter().map(|hotspot| hotspot.lines).collect());
+    for hotspot in hotspots.iter_mut() {
+        hotspot.is_risk = hotspot.commits >= most_commits && hotspot.lines >= most_lines;
+    }
+
+    hotspots.sort_by(|a, b| {
+        b.commits
+            .cmp(&a.commits)
+            .then(b.lines.cmp(&a.lines))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    Ok(hotspots)
+}
@@ -0,0 +1,267 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+
+use crate::vfs::Vfs;
+
+/// Tool a git hook is configured with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookManager {
+    /// [pre-commit](https://pre-commit.com), configured in .pre-commit-config.yaml
+    PreCommit,
+    /// [husky](https://typicode.github.io/husky), configured in .husky/ or package.json
+    Husky,
+    /// [lefthook](https://github.com/evilmartians/lefthook), configured in lefthook.yml
+    Lefthook,
+    /// a script installed straight into .git/hooks
+    Git,
+}
+
+/// A configured git hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHook {
+    /// tool the hook is configured with
+    pub manager: HookManager,
+    /// the git hook it runs on, e.g. **pre-commit** or **pre-push**
+    pub stage: String,
+    /// the hook id (pre-commit), command name (lefthook) or script (husky, git)
+    pub name: String,
+}
+
+/// The hooks git runs, see https://git-scm.com/docs/githooks
+const GIT_HOOKS: [&str; 13] = [
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "pre-auto-gc",
+];
+
+static PRE_COMMIT_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*-?\s*id:\s*([^\s#]+)").unwrap());
+static PRE_COMMIT_STAGES: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*(?:stages|default_stages):\s*\[([^\]]*)\]").unwrap());
+
+fn hook(manager: HookManager, stage: &str, name: &str) -> GitHook {
+    GitHook {
+        manager,
+        stage: stage.to_string(),
+        name: name.trim().trim_matches(|c| c == '"' || c == '\'').to_string(),
+    }
+}
+
+/// pre-commit stages are named after the hooks, older configs use **commit** & **push**
+fn pre_commit_stage(stage: &str) -> String {
+    let stage = stage.trim().trim_matches(|c| c == '"' || c == '\'');
+    match stage {
+        "commit" => String::from("pre-commit"),
+        "push" => String::from("pre-push"),
+        "merge-commit" => String::from("pre-merge-commit"),
+        _ => stage.to_string(),
+    }
+}
+
+/// Hook ids, each run on its own stages, or the default ones, or on pre-commit
+fn pre_commit_hooks(content: &str) -> Vec<GitHook> {
+    let stages_of = |line: &str| -> Option<Vec<String>> {
+        PRE_COMMIT_STAGES
+            .captures(line)
+            .map(|captures| captures[1].split(',').map(pre_commit_stage).collect())
+    };
+
+    let default_stages = content
+        .lines()
+        .filter(|line| line.trim_start().starts_with("default_stages:"))
+        .find_map(stages_of)
+        .unwrap_or_else(|| vec![String::from("pre-commit")]);
+
+    // (id, stages) in file order. Stages follow the id they belong to
+    let mut ids: Vec<(String, Option<Vec<String>>)> = vec![];
+    for line in content.lines() {
+        if let Some(captures) = PRE_COMMIT_ID.captures(line) {
+            ids.push((captures[1].to_string(), None));
+        } else if line.trim_start().starts_with("stages:") {
+            if let Some(last) = ids.last_mut() {
+                last.1 = stages_of(line);
+            }
+        }
+    }
+
+    ids.into_iter()
+        .flat_map(|(id, stages)| {
+            stages
+                .unwrap_or_else(|| default_stages.clone())
+                .into_iter()
+                .map(move |stage| hook(HookManager::PreCommit, &stage, &id))
+        })
+        .collect()
+}
+
+/// Commands listed under the top level hook keys, e.g. ```pre-commit: commands: lint: run: ...```
+fn lefthook_hooks(content: &str) -> Vec<GitHook> {
+    let mut hooks = vec![];
+    let mut stage: Option<&str> = None;
+    let mut in_commands = false;
+    let mut command_indent: Option<usize> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+
+        if indent == 0 {
+            let key = trimmed.trim_end_matches(':');
+            stage = GIT_HOOKS.iter().find(|hook| **hook == key).copied();
+            in_commands = false;
+            continue;
+        }
+
+        let stage = match stage {
+            Some(stage) => stage,
+            None => continue,
+        };
+
+        if trimmed == "commands:" || trimmed == "scripts:" {
+            in_commands = true;
+            command_indent = None;
+            continue;
+        }
+        if !in_commands {
+            continue;
+        }
+
+        // the first key below commands sets the indentation of the command names
+        match command_indent {
+            None => {
+                command_indent = Some(indent);
+                hooks.push(hook(HookManager::Lefthook, stage, trimmed.trim_end_matches(':')));
+            }
+            Some(command) if indent == command => {
+                hooks.push(hook(HookManager::Lefthook, stage, trimmed.trim_end_matches(':')));
+            }
+            Some(command) if indent < command => in_commands = false,
+            _ => (),
+        }
+    }
+
+    hooks
+}
+
+/// Script lines of a hook file, leaving out the shebang, comments and husky's own setup
+fn script(content: &str) -> String {
+    content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| !line.contains("husky.sh"))
+        .collect::<Vec<&str>>()
+        .join("; ")
+}
+
+/// Hook scripts of a directory, named after git hooks
+fn hook_scripts(vfs: &dyn Vfs, dir: &Path, manager: HookManager) -> Result<Vec<GitHook>> {
+    let mut hooks = vec![];
+    if !vfs.is_dir(dir) {
+        return Ok(hooks);
+    }
+
+    let mut names = vfs.read_dir(dir)?;
+    names.sort();
+
+    for name in names {
+        let stage = name.to_string_lossy().to_string();
+        let path = dir.join(&name);
+        if GIT_HOOKS.contains(&stage.as_str()) && vfs.is_file(&path) {
+            let content = vfs.read_to_string(&path).unwrap_or_default();
+            hooks.push(hook(manager, &stage, &script(&content)));
+        }
+    }
+
+    Ok(hooks)
+}
+
+/// husky v4 hooks, configured in package.json under ```husky.hooks```
+fn package_json_hooks(content: &str) -> Vec<GitHook> {
+    let manifest: serde_json::Value = match serde_json::from_str(content) {
+        Ok(manifest) => manifest,
+        _ => return vec![],
+    };
+
+    match manifest["husky"]["hooks"].as_object() {
+        Some(hooks) => hooks
+            .iter()
+            .filter_map(|(stage, command)| {
+                command
+                    .as_str()
+                    .map(|command| hook(HookManager::Husky, stage, command))
+            })
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Finds the hooks configured with pre-commit, husky and lefthook, and those installed in the hooks directory of the
+/// repository, ```.git/hooks``` in a regular checkout
+pub(crate) fn detect_hooks(vfs: &dyn Vfs, hooks_dir: Option<&Path>) -> Result<Vec<GitHook>> {
+    let mut hooks = vec![];
+
+    for file in [".pre-commit-config.yaml", ".pre-commit-config.yml"] {
+        if let Ok(content) = vfs.read_to_string(Path::new(file)) {
+            hooks.extend(pre_commit_hooks(&content));
+        }
+    }
+
+    hooks.extend(hook_scripts(vfs, Path::new(".husky"), HookManager::Husky)?);
+    if let Ok(content) = vfs.read_to_string(Path::new("package.json")) {
+        hooks.extend(package_json_hooks(&content));
+    }
+
+    for file in [
+        "lefthook.yml",
+        "lefthook.yaml",
+        ".lefthook.yml",
+        ".lefthook.yaml",
+    ] {
+        if let Ok(content) = vfs.read_to_string(Path::new(file)) {
+            hooks.extend(lefthook_hooks(&content));
+        }
+    }
+
+    // hooks installed by the managers are left out, the managers already report them
+    let installed = match hooks_dir {
+        Some(hooks_dir) => hook_scripts(vfs, hooks_dir, HookManager::Git)?,
+        None => vec![],
+    };
+    hooks.extend(installed.into_iter().filter(|installed| {
+        let managed = ["pre-commit", "husky", "lefthook"]
+            .iter()
+            .any(|manager| installed.name.contains(manager));
+        !managed
+    }));
+
+    Ok(hooks)
+}
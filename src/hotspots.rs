@@ -0,0 +1,271 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::tree::FileTree;
+use crate::vfs::Vfs;
+
+/// A file listed in [Hotspots]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLength {
+    /// path of the file
+    pub path: PathBuf,
+    /// language, as named by the code stats
+    pub lang: String,
+    /// lines of code, leaving out comments & blanks
+    pub code: u64,
+    /// size in bytes
+    pub bytes: u64,
+}
+
+/// A function listed in [Hotspots]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionLength {
+    /// path of the file the function is in
+    pub path: PathBuf,
+    /// language, as named by the code stats
+    pub lang: String,
+    /// function name
+    pub name: String,
+    /// line the function starts on, starting at 1
+    pub line: usize,
+    /// number of lines from the signature to the end of the body
+    pub lines: usize,
+}
+
+/// Complexity hotspots, returned by [method.hotspots]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hotspots {
+    /// the largest code files by bytes, largest first
+    pub largest_files: Vec<FileLength>,
+    /// the longest files by lines of code, keyed by language, longest first
+    pub longest_files: BTreeMap<String, Vec<FileLength>>,
+    /// functions at least as long as the threshold, longest first
+    pub longest_functions: Vec<FunctionLength>,
+}
+
+/// How function bodies are delimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Body {
+    Braces,
+    Indent,
+}
+
+static RUST_FN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern\s+"[^"]*")\s+)*fn\s+(\w+)"#,
+    )
+    .unwrap()
+});
+static GO_FUNC: Lazy<Regex> = Lazy::new(|| Regex::new(r"^func\s+(?:\([^)]*\)\s*)?(\w+)").unwrap());
+static JS_FUNCTION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*(\w+)|^\s*(?:export\s+)?(?:const|let|var)\s+(\w+)\s*=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*=>|\w+\s*=>)").unwrap()
+});
+static KEYWORD_FUNCTION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:[\w@]+\s+)*(?:fun|func|function|def)\s+(?:<[^>]*>\s*)?(\w+)").unwrap()
+});
+/// C family signatures: a return type then a name followed by parameters, with no statement ending on the line
+static C_FUNCTION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:[\w<>\[\],*&:]+\s+)+[*&]*([A-Za-z_][\w:~]*)\s*\([^;]*$").unwrap()
+});
+static PYTHON_DEF: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\s*)(?:async\s+)?def\s+(\w+)").unwrap());
+static LITERALS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""(?:\\.|[^"\\])*"|'(?:\\.|[^'\\])'|//.*$"#).unwrap());
+
+const NOT_FUNCTIONS: [&str; 9] = [
+    "if", "for", "while", "switch", "catch", "return", "else", "new", "sizeof",
+];
+
+/// Name of the function declared on a line, in languages whose functions are simple to spot
+fn function_name(lang: &str, line: &str) -> Option<(String, Body)> {
+    let (captures, body) = match lang {
+        "Rust" => (RUST_FN.captures(line), Body::Braces),
+        "Go" => (GO_FUNC.captures(line), Body::Braces),
+        "JavaScript" | "TypeScript" | "Jsx" => (JS_FUNCTION.captures(line), Body::Braces),
+        "Kotlin" | "Swift" | "PHP" | "Scala" => (KEYWORD_FUNCTION.captures(line), Body::Braces),
+        "C" | "C++" | "C/C++ Header" | "C#" | "Java" | "Dart" => {
+            (C_FUNCTION.captures(line), Body::Braces)
+        }
+        "Python" => {
+            return PYTHON_DEF
+                .captures(line)
+                .map(|captures| (captures[2].to_string(), Body::Indent))
+        }
+        _ => return None,
+    };
+
+    let captures = captures?;
+    let name = captures
+        .get(1)
+        .or_else(|| captures.get(2))?
+        .as_str()
+        .to_string();
+
+    if NOT_FUNCTIONS.contains(&name.as_str()) {
+        return None;
+    }
+
+    Some((name, body))
+}
+
+/// Index of the line closing the body opened on or after ```start```. Declarations without a body have none
+fn brace_body_end(lines: &[&str], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut opened = false;
+
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        // braces within strings, chars & comments do not count
+        let line = LITERALS.replace_all(line, "");
+        for c in line.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => depth -= 1,
+                ';' if !opened => return None,
+                _ => (),
+            }
+            if opened && depth <= 0 {
+                return Some(i);
+            }
+        }
+    }
+
+    None
+}
+
+/// Index of the last line indented deeper than the signature
+fn indent_body_end(lines: &[&str], start: usize) -> usize {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let signature = indent(lines[start]);
+    let mut end = start;
+
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if indent(line) <= signature {
+            break;
+        }
+        end = i;
+    }
+
+    end
+}
+
+/// Functions of a file spanning at least ```min_lines``` lines
+fn long_functions(content: &str, lang: &str, min_lines: usize) -> Vec<(String, usize, usize)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut functions = vec![];
+
+    for (i, line) in lines.iter().enumerate() {
+        let (name, body) = match function_name(lang, line) {
+            Some(function) => function,
+            None => continue,
+        };
+
+        let end = match body {
+            Body::Braces => brace_body_end(&lines, i),
+            Body::Indent => Some(indent_body_end(&lines, i)),
+        };
+
+        if let Some(end) = end {
+            let length = end - i + 1;
+            if length >= min_lines {
+                functions.push((name, i + 1, length));
+            }
+        }
+    }
+
+    functions
+}
+
+/// Ranks the non ignored code files of the tree. Paths are relative to the project directory
+pub(crate) fn hotspots(
+    vfs: &dyn Vfs,
+    root: &Path,
+    tree: &FileTree,
+    top: usize,
+    min_function_lines: usize,
+) -> Result<Hotspots> {
+    let mut files: Vec<FileLength> = vec![];
+    let mut functions: Vec<FunctionLength> = vec![];
+
+    for node in tree.iter().filter(|node| !node.is_dir && !node.is_ignored) {
+        let lang = match &node.lang {
+            Some(lang) => lang,
+            None => continue,
+        };
+
+        let count = node.count(vfs, root)?;
+
+        files.push(FileLength {
+            path: node.path.clone(),
+            lang: lang.clone(),
+            code: count.code,
+            bytes: node.size,
+        });
+
+        if let Ok(content) = vfs.read_to_string(&node.path) {
+            for (name, line, lines) in long_functions(&content, lang, min_function_lines) {
+                functions.push(FunctionLength {
+                    path: node.path.clone(),
+                    lang: lang.clone(),
+                    name,
+                    line,
+                    lines,
+                });
+            }
+        }
+    }
+
+    let mut largest_files = files.clone();
+    largest_files.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    largest_files.truncate(top);
+
+    let mut longest_files: BTreeMap<String, Vec<FileLength>> = BTreeMap::new();
+    for file in files {
+        longest_files
+            .entry(file.lang.clone())
+            .or_default()
+            .push(file);
+    }
+    for files in longest_files.values_mut() {
+        files.sort_by(|a, b| b.code.cmp(&a.code).then_with(|| a.path.cmp(&b.path)));
+        files.truncate(top);
+    }
+
+    functions.sort_by(|a, b| {
+        b.lines
+            .cmp(&a.lines)
+            .then_with(|| (&a.path, a.line).cmp(&(&b.path, b.line)))
+    });
+    functions.truncate(top);
+
+    Ok(Hotspots {
+        largest_files,
+        longest_files,
+        longest_functions: functions,
+    })
+}
@@ -0,0 +1,78 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Line endings & whitespace of the counted files, recorded by [method.get_code_stats] when [method.line_hygiene] is
+/// set and returned by [method.hygiene_report]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HygieneReport {
+    /// number of files looked at
+    pub files: usize,
+    /// number of lines ending with ```\n```
+    pub lf_lines: usize,
+    /// number of lines ending with ```\r\n```
+    pub crlf_lines: usize,
+    /// number of files all of whose line breaks are ```\n```
+    pub lf_files: usize,
+    /// number of files all of whose line breaks are ```\r\n```
+    pub crlf_files: usize,
+    /// files mixing both line endings
+    pub mixed_endings: Vec<PathBuf>,
+    /// files with a line ending in spaces or tabs
+    pub trailing_whitespace: Vec<PathBuf>,
+    /// non empty files whose last line has no line break
+    pub missing_final_newline: Vec<PathBuf>,
+}
+
+impl HygieneReport {
+    /// Records the line endings & whitespace of a file
+    pub(crate) fn add(&mut self, path: &Path, content: &str) {
+        let (mut lf, mut crlf, mut trailing) = (0, 0, false);
+
+        for line in content.split_inclusive('\n') {
+            let line = match line.strip_suffix('\n') {
+                Some(line) => match line.strip_suffix('\r') {
+                    Some(line) => {
+                        crlf += 1;
+                        line
+                    }
+                    None => {
+                        lf += 1;
+                        line
+                    }
+                },
+                None => line,
+            };
+            trailing = trailing || line.ends_with([' ', '\t']);
+        }
+
+        self.files += 1;
+        self.lf_lines += lf;
+        self.crlf_lines += crlf;
+        match (lf > 0, crlf > 0) {
+            (true, true) => self.mixed_endings.push(path.to_path_buf()),
+            (true, false) => self.lf_files += 1,
+            (false, true) => self.crlf_files += 1,
+            (false, false) => {}
+        }
+        if trailing {
+            self.trailing_whitespace.push(path.to_path_buf());
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            self.missing_final_newline.push(path.to_path_buf());
+        }
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::code;
+use crate::ruleset::RuleSet;
+use crate::stats::StatsLang;
+use crate::vfs::Vfs;
+
+/// Whether lines are indented with tabs or spaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum IndentStyle {
+    /// tab characters
+    Tabs,
+    /// space characters
+    Spaces,
+}
+
+/// How the sampled files of a language are indented, in [IndentationProfile]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LangIndent {
+    /// the style of most indented lines
+    pub style: IndentStyle,
+    /// the most common step between an indented line and the line before it, in spaces. None for tabs, or when no
+    /// line is indented deeper than the one before it
+    pub width: Option<usize>,
+    /// number of files sampled
+    pub files: usize,
+    /// number of lines indented with tabs
+    pub tab_lines: usize,
+    /// number of lines indented with spaces
+    pub space_lines: usize,
+    /// the file patterns of the sampled files, ```*.<extension>``` or the file name for files without an extension
+    pub patterns: BTreeSet<String>,
+}
+
+/// Indentation of the project code per language, inferred from sampled files, returned by
+/// [method.indentation_profile]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IndentationProfile {
+    /// the languages with at least one indented line, keyed by language name
+    pub langs: BTreeMap<String, LangIndent>,
+}
+
+/// Number of files of each language read
+const SAMPLE: usize = 25;
+
+/// The widest indent step looked at, deeper steps being continuation lines
+const MAX_WIDTH: usize = 8;
+
+impl IndentationProfile {
+    /// The indentation as EditorConfig sections, one per language
+    /// ```no_run
+    /// std::fs::write("/my/project/.editorconfig", project.indentation_profile()?.to_editorconfig())?;
+    /// ```
+    pub fn to_editorconfig(&self) -> String {
+        let mut content = String::from("root = true\n");
+
+        for indent in self.langs.values() {
+            let patterns: Vec<&str> = indent.patterns.iter().map(String::as_str).collect();
+            let section = match patterns.as_slice() {
+                [pattern] => pattern.to_string(),
+                patterns => format!("{{{}}}", patterns.join(",")),
+            };
+
+            content.push_str(&format!("\n[{}]\n", section));
+            match (indent.style, indent.width) {
+                (IndentStyle::Tabs, _) => content.push_str("indent_style = tab\n"),
+                (IndentStyle::Spaces, Some(width)) => {
+                    content.push_str(&format!("indent_style = space\nindent_size = {}\n", width))
+                }
+                (IndentStyle::Spaces, None) => content.push_str("indent_style = space\n"),
+            }
+        }
+
+        content
+    }
+}
+
+/// Tallies of the indented lines of a language
+#[derive(Default)]
+struct Tally {
+    files: usize,
+    tab_lines: usize,
+    space_lines: usize,
+    /// how often each step between successive indents was met
+    steps: BTreeMap<usize, usize>,
+    patterns: BTreeSet<String>,
+}
+
+impl Tally {
+    fn add(&mut self, content: &str) {
+        let mut previous = 0;
+
+        for line in content.lines() {
+            let trimmed = line.trim_start();
+            // the stars of block comments are aligned by one space
+            if trimmed.is_empty() || trimmed.starts_with('*') {
+                continue;
+            }
+
+            let leading = &line[..line.len() - trimmed.len()];
+            if leading.starts_with('\t') {
+                self.tab_lines += 1;
+                previous = 0;
+                continue;
+            }
+
+            let indent = leading.chars().take_while(|c| *c == ' ').count();
+            if indent > 0 {
+                self.space_lines += 1;
+            }
+            if indent > previous && indent - previous <= MAX_WIDTH {
+                *self.steps.entry(indent - previous).or_default() += 1;
+            }
+            previous = indent;
+        }
+    }
+
+    fn indent(self) -> Option<LangIndent> {
+        if self.tab_lines == 0 && self.space_lines == 0 {
+            return None;
+        }
+
+        let style = if self.tab_lines > self.space_lines {
+            IndentStyle::Tabs
+        } else {
+            IndentStyle::Spaces
+        };
+        // the smaller step wins a tie, deeper ones being multiples of it
+        let width = match style {
+            IndentStyle::Tabs => None,
+            IndentStyle::Spaces => self
+                .steps
+                .iter()
+                .max_by(|(a_step, a_count), (b_step, b_count)| a_count.cmp(b_count).then(b_step.cmp(a_step)))
+                .map(|(step, _)| *step),
+        };
+
+        Some(LangIndent {
+            style,
+            width,
+            files: self.files,
+            tab_lines: self.tab_lines,
+            space_lines: self.space_lines,
+            patterns: self.patterns,
+        })
+    }
+}
+
+/// Reads the first files of each language met walking the project, hidden & ignored entries aside
+pub(crate) fn profile(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<IndentationProfile> {
+    let entries = vfs.walk(&mut |e| {
+        e.depth == 0 || !(code::is_hidden(e) || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)))
+    })?;
+    let mut tallies: BTreeMap<String, Tally> = BTreeMap::new();
+
+    for e in entries.iter().filter(|e| e.is_file) {
+        let lang = match StatsLang::of_path(&e.path.to_string_lossy()) {
+            StatsLang::Unrecognized => continue,
+            lang => lang.to_string(),
+        };
+        let tally = tallies.entry(lang).or_default();
+        if tally.files == SAMPLE {
+            continue;
+        }
+
+        let content = match e.path.strip_prefix(vfs.root()).map(|rel_path| vfs.read_to_string(rel_path)) {
+            Ok(Ok(content)) => content,
+            _ => continue,
+        };
+        tally.files += 1;
+        tally.patterns.insert(match e.path.extension() {
+            Some(extension) => format!("*.{}", extension.to_string_lossy()),
+            None => e.file_name().to_string_lossy().to_string(),
+        });
+        tally.add(&content);
+    }
+
+    let langs = tallies
+        .into_iter()
+        .filter_map(|(lang, tally)| Some((lang, tally.indent()?)))
+        .collect();
+
+    Ok(IndentationProfile { langs })
+}
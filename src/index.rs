@@ -0,0 +1,281 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::code;
+use crate::ruleset::RuleSet;
+use crate::stats::{Count, StatsLang};
+use crate::vfs::{Vfs, VfsEntry};
+
+/// An entry of the [FileIndex]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// path relative to the project directory, empty for the project directory itself
+    pub path: PathBuf,
+    /// depth below the project directory, which is 0
+    pub depth: usize,
+    /// whether the entry is a directory
+    pub is_dir: bool,
+    /// whether the entry is a file
+    pub is_file: bool,
+    /// whether the entry is a symbolic link, which is neither a file nor a directory as links are not followed
+    pub is_symlink: bool,
+    /// size of files in bytes, 0 for directories
+    pub size: u64,
+    /// last modification time of files, None for directories and when it is unknown
+    pub modified: Option<SystemTime>,
+    /// whether the gitignore rules ignore the entry. Ignored directories are not walked, so nothing within them is
+    /// indexed, and neither is anything within .git
+    pub is_ignored: bool,
+    /// language of code files, as named by the code stats
+    pub lang: Option<String>,
+}
+
+impl IndexEntry {
+    /// directories whose content was left out of the index
+    fn is_leaf(&self) -> bool {
+        self.is_dir && self.depth > 0 && (self.is_ignored || self.path.file_name() == Some(".git".as_ref()))
+    }
+}
+
+/// The project entries captured in a single walk by [method.index], which the detection, the code stats, the search,
+/// the tree & the reports then read instead of walking the disk again
+/// ```no_run
+/// project.parse()?;
+/// let index = project.index()?;
+/// println!("{} files", index.files().count());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileIndex {
+    /// the entries in walk order: the project directory first, each directory followed by its content
+    pub entries: Vec<IndexEntry>,
+    positions: HashMap<PathBuf, usize>,
+}
+
+impl FileIndex {
+    /// The entry at a path relative to the project directory
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&IndexEntry> {
+        self.positions.get(path.as_ref()).map(|position| &self.entries[*position])
+    }
+
+    /// The files the gitignore rules do not ignore
+    pub fn files(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.iter().filter(|entry| entry.is_file && !entry.is_ignored)
+    }
+
+    /// What the index knows of a path: Some of its entry, or Some(None) when its parent directory was walked without
+    /// meeting it. None when the index cannot tell, the path lying within a directory left out or being a symbolic
+    /// link, which the lookups follow but the walk does not
+    fn lookup(&self, path: &Path) -> Option<Option<&IndexEntry>> {
+        match self.get(path) {
+            Some(entry) if entry.is_symlink => None,
+            Some(entry) => Some(Some(entry)),
+            None => path
+                .parent()
+                .and_then(|parent| self.get(parent))
+                .filter(|parent| parent.is_dir && !parent.is_leaf())
+                .map(|_| None),
+        }
+    }
+}
+
+/// Walks the project once, marking what the ruleset ignores. Ignored directories and .git are indexed but not walked
+pub(crate) fn build(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<FileIndex> {
+    let root = vfs.root().to_path_buf();
+    // the order entries are met in, pruned directories included, as they are not returned by the walk
+    let mut order: Vec<(PathBuf, bool)> = vec![];
+    let mut pruned: HashMap<PathBuf, VfsEntry> = HashMap::new();
+
+    let walked = vfs.walk(&mut |e| {
+        let is_ignored = e.depth > 0 && ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e));
+        order.push((e.path.clone(), is_ignored));
+
+        let is_leaf = e.depth > 0 && e.is_dir && (is_ignored || e.file_name() == ".git");
+        if is_leaf {
+            pruned.insert(e.path.clone(), e.clone());
+        }
+        !is_leaf
+    })?;
+    let mut walked: HashMap<PathBuf, VfsEntry> = walked.into_iter().map(|e| (e.path.clone(), e)).collect();
+
+    let mut entries = vec![];
+    for (path, is_ignored) in order {
+        let e = match walked.remove(&path).or_else(|| pruned.remove(&path)) {
+            Some(e) => e,
+            None => continue,
+        };
+        let lang = match e.path.to_str() {
+            Some(path_str) if e.is_file => match StatsLang::of_path(path_str) {
+                StatsLang::Unrecognized => None,
+                lang => Some(lang.to_string()),
+            },
+            _ => None,
+        };
+
+        entries.push(IndexEntry {
+            path: e.path.strip_prefix(&root).unwrap_or(&e.path).to_path_buf(),
+            depth: e.depth,
+            is_dir: e.is_dir,
+            is_file: e.is_file,
+            is_symlink: e.is_symlink,
+            size: e.size,
+            modified: e.modified,
+            is_ignored,
+            lang,
+        });
+    }
+
+    let positions = entries
+        .iter()
+        .enumerate()
+        .map(|(position, entry)| (entry.path.clone(), position))
+        .collect();
+
+    Ok(FileIndex { entries, positions })
+}
+
+/// The project files as indexed, the index answering the walks & lookups it covers and the project files the rest
+#[derive(Debug)]
+pub(crate) struct IndexedFs {
+    inner: Arc<dyn Vfs>,
+    index: Arc<FileIndex>,
+}
+
+impl IndexedFs {
+    pub fn new(inner: Arc<dyn Vfs>, index: Arc<FileIndex>) -> Self {
+        IndexedFs { inner, index }
+    }
+
+    fn vfs_entry(&self, entry: &IndexEntry) -> VfsEntry {
+        VfsEntry {
+            path: self.inner.root().join(&entry.path),
+            depth: entry.depth,
+            is_dir: entry.is_dir,
+            is_file: entry.is_file,
+            is_symlink: entry.is_symlink,
+            size: entry.size,
+            modified: entry.modified,
+        }
+    }
+}
+
+impl Vfs for IndexedFs {
+    fn root(&self) -> &Path {
+        self.inner.root()
+    }
+
+    fn is_file(&self, rel_path: &Path) -> bool {
+        match self.index.lookup(rel_path) {
+            Some(entry) => entry.is_some_and(|entry| entry.is_file),
+            None => self.inner.is_file(rel_path),
+        }
+    }
+
+    fn is_dir(&self, rel_path: &Path) -> bool {
+        match self.index.lookup(rel_path) {
+            Some(entry) => entry.is_some_and(|entry| entry.is_dir),
+            None => self.inner.is_dir(rel_path),
+        }
+    }
+
+    fn read_to_string(&self, rel_path: &Path) -> Result<String> {
+        self.inner.read_to_string(rel_path)
+    }
+
+    fn read(&self, rel_path: &Path) -> Result<Vec<u8>> {
+        self.inner.read(rel_path)
+    }
+
+    fn read_dir(&self, rel_path: &Path) -> Result<Vec<OsString>> {
+        match self.index.get(rel_path) {
+            Some(dir) if dir.is_dir && !dir.is_leaf() => Ok(self
+                .index
+                .entries
+                .iter()
+                .filter(|entry| entry.depth == dir.depth + 1 && entry.path.parent() == Some(rel_path))
+                .filter_map(|entry| entry.path.file_name().map(OsString::from))
+                .collect()),
+            _ => self.inner.read_dir(rel_path),
+        }
+    }
+
+    fn sub(&self, rel_path: &Path) -> Arc<dyn Vfs> {
+        self.inner.sub(rel_path)
+    }
+
+    fn write(&self, rel_path: &Path, content: &str) -> Result<()> {
+        self.inner.write(rel_path, content)
+    }
+
+    fn modified(&self, rel_path: &Path) -> Option<SystemTime> {
+        match self.index.get(rel_path) {
+            Some(entry) if entry.is_file => entry.modified,
+            _ => self.inner.modified(rel_path),
+        }
+    }
+
+    fn mode(&self, rel_path: &Path) -> Option<u32> {
+        self.inner.mode(rel_path)
+    }
+
+    fn count(&self, entry: &VfsEntry, content: &str) -> Result<Count> {
+        self.inner.count(entry, content)
+    }
+
+    /// Replays the walk from the index. Directories the index did not walk are walked on the project files when the
+    /// filter keeps them, so that walks with other rules than the gitignore ones see their content
+    fn walk(&self, filter: &mut dyn FnMut(&VfsEntry) -> bool) -> Result<Vec<VfsEntry>> {
+        let mut entries = vec![];
+        let mut rejected: Option<&Path> = None;
+
+        for entry in &self.index.entries {
+            // the content of a directory directly follows it
+            if rejected.is_some_and(|dir| entry.path.starts_with(dir)) {
+                continue;
+            }
+            rejected = None;
+
+            let e = self.vfs_entry(entry);
+            if !filter(&e) {
+                rejected = Some(&entry.path);
+                continue;
+            }
+            entries.push(e);
+
+            if entry.is_leaf() {
+                let depth = entry.depth;
+                let within = self.inner.sub(&entry.path).walk(&mut |e| {
+                    e.depth == 0 || filter(&VfsEntry {
+                        depth: e.depth + depth,
+                        ..e.clone()
+                    })
+                })?;
+                entries.extend(within.into_iter().filter(|e| e.depth > 0).map(|e| VfsEntry {
+                    depth: e.depth + depth,
+                    ..e
+                }));
+            }
+        }
+
+        Ok(entries)
+    }
+}
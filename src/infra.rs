@@ -0,0 +1,121 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::code::{self, FileContent};
+use crate::ruleset::RuleSet;
+use crate::vfs::{Vfs, VfsEntry};
+
+/// What an [InfraComponent] deploys with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum InfraKind {
+    /// a Helm chart, a directory with a Chart.yaml, its values & templates
+    HelmChart,
+    /// a directory of raw Kubernetes manifests, YAML documents with an ```apiVersion``` & a ```kind```
+    Kubernetes,
+}
+
+/// A part of the project describing how it is deployed rather than what it does, returned by
+/// [method.infra_components]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InfraComponent {
+    /// the directory, relative to the project directory
+    pub path: PathBuf,
+    /// what it deploys with
+    pub kind: InfraKind,
+    /// its YAML files, relative to the project directory: all of those of a chart, the manifests of a manifest
+    /// directory
+    pub files: Vec<PathBuf>,
+}
+
+const CHART: &str = "Chart.yaml";
+
+pub(crate) fn is_yaml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "yaml" || extension == "yml")
+}
+
+/// Whether one of the documents of a YAML file has the top level ```apiVersion``` & ```kind``` of a Kubernetes object
+pub(crate) fn is_manifest(content: &str) -> bool {
+    content.split("\n---").any(|document| {
+        let has_key = |key: &str| document.lines().any(|line| line.starts_with(key));
+        has_key("apiVersion:") && has_key("kind:")
+    })
+}
+
+/// Directories holding a Chart.yaml among the walked entries
+pub(crate) fn chart_dirs(entries: &[VfsEntry]) -> Vec<PathBuf> {
+    entries
+        .iter()
+        .filter(|e| e.is_file && e.file_name() == CHART)
+        .filter_map(|e| e.path.parent().map(Path::to_path_buf))
+        .collect()
+}
+
+/// Whether a walked YAML file deploys the project: it lies within one of the ```charts``` or is a manifest
+pub(crate) fn is_infra(e: &VfsEntry, charts: &[PathBuf], file: &FileContent) -> bool {
+    if !is_yaml(&e.path) {
+        return false;
+    }
+    if charts.iter().any(|chart| e.path.starts_with(chart)) {
+        return true;
+    }
+
+    file.get().is_some_and(is_manifest)
+}
+
+/// The Helm charts, then the directories of Kubernetes manifests lying outside of them, in path order. Hidden &
+/// ignored entries are not looked at
+pub(crate) fn detect(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<Vec<InfraComponent>> {
+    let entries = vfs.walk(&mut |e| {
+        e.depth == 0 || !(code::is_hidden(e) || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)))
+    })?;
+    let charts = chart_dirs(&entries);
+    let relative = |path: &Path| path.strip_prefix(vfs.root()).unwrap_or(path).to_path_buf();
+
+    let mut helm: BTreeMap<PathBuf, Vec<PathBuf>> = charts.iter().map(|chart| (relative(chart), vec![])).collect();
+    let mut kubernetes: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+
+    for e in entries.iter().filter(|e| e.is_file && is_yaml(&e.path)) {
+        // the files of nested charts, such as those of charts/, belong to the innermost
+        let chart = charts
+            .iter()
+            .filter(|chart| e.path.starts_with(chart))
+            .max_by_key(|chart| chart.components().count());
+
+        match chart {
+            Some(chart) => helm.entry(relative(chart)).or_default().push(relative(&e.path)),
+            None if is_infra(e, &[], &FileContent::new(vfs, e)) => {
+                let dir = e.path.parent().map(relative).unwrap_or_default();
+                kubernetes.entry(dir).or_default().push(relative(&e.path));
+            }
+            None => {}
+        }
+    }
+
+    let components = helm
+        .into_iter()
+        .map(|component| (component, InfraKind::HelmChart))
+        .chain(kubernetes.into_iter().map(|component| (component, InfraKind::Kubernetes)))
+        .map(|((path, files), kind)| InfraComponent { path, kind, files })
+        .collect();
+
+    Ok(components)
+}
@@ -0,0 +1,182 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::assets;
+use crate::code;
+use crate::ruleset::RuleSet;
+use crate::stats::StatsLang;
+use crate::vfs::{self, Vfs};
+
+/// What is wrong with an entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum IssueKind {
+    /// a symbolic link whose target does not exist
+    DanglingSymlink,
+    /// a source file without a single byte
+    EmptySource,
+    /// a path a manifest field points to that does not exist
+    MissingReference {
+        /// the manifest, e.g. **package.json**
+        manifest: PathBuf,
+        /// the field holding the path, e.g. **main** or **[[bin]] path**
+        field: String,
+    },
+}
+
+/// An entry of the project that is broken or left over, returned by [method.integrity_issues]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IntegrityIssue {
+    /// path relative to the project directory
+    pub path: PathBuf,
+    /// what is wrong with it
+    pub kind: IssueKind,
+}
+
+/// Reads the fields of a manifest holding paths, as field & path pairs
+type RefsReader = fn(&str) -> Vec<(String, String)>;
+
+/// Source files expected to be empty, such as python's package markers
+const EMPTY_BY_DESIGN: [&str; 1] = ["__init__.py"];
+
+/// package.json fields holding a path
+const PACKAGE_JSON_FIELDS: [&str; 5] = ["main", "module", "browser", "types", "typings"];
+
+/// Paths package.json points to: the entry points and the ```bin``` scripts, a single path or one per command
+fn package_json_refs(content: &str) -> Vec<(String, String)> {
+    let manifest: serde_json::Value = match serde_json::from_str(content) {
+        Ok(manifest) => manifest,
+        _ => return vec![],
+    };
+
+    let mut refs: Vec<(String, String)> = PACKAGE_JSON_FIELDS
+        .iter()
+        .filter_map(|field| Some((field.to_string(), manifest[*field].as_str()?.to_string())))
+        .collect();
+
+    match &manifest["bin"] {
+        serde_json::Value::String(path) => refs.push((String::from("bin"), path.clone())),
+        serde_json::Value::Object(commands) => {
+            for (command, path) in commands {
+                if let Some(path) = path.as_str() {
+                    refs.push((format!("bin.{}", command), path.to_string()));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    refs
+}
+
+/// Paths Cargo.toml points to: the ```path``` of the targets, the build script, the readme & the license file
+fn cargo_toml_refs(content: &str) -> Vec<(String, String)> {
+    let mut refs = vec![];
+    let mut section = "";
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line;
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+        // only plain string values hold paths, ```readme = false``` or ```build = false``` do not
+        let value = match value.strip_prefix('"').and_then(|value| value.split_once('"')) {
+            Some((value, _)) => value,
+            None => continue,
+        };
+
+        let is_ref = match section {
+            "[package]" => matches!(key, "build" | "readme" | "license-file"),
+            "[lib]" | "[[bin]]" | "[[test]]" | "[[example]]" | "[[bench]]" => key == "path",
+            _ => false,
+        };
+        if is_ref {
+            refs.push((format!("{} {}", section, key), value.to_string()));
+        }
+    }
+
+    refs
+}
+
+/// Manifest fields pointing to paths that do not exist
+fn missing_refs(vfs: &dyn Vfs) -> Vec<IntegrityIssue> {
+    let manifests: [(&str, RefsReader); 2] = [("package.json", package_json_refs), ("Cargo.toml", cargo_toml_refs)];
+    let mut issues = vec![];
+
+    for (manifest, refs) in manifests {
+        let content = match vfs.read_to_string(Path::new(manifest)) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        for (field, path) in refs(&content) {
+            let path = vfs::normalize(Path::new(&path));
+            if !vfs.exists(&path) {
+                issues.push(IntegrityIssue {
+                    path,
+                    kind: IssueKind::MissingReference {
+                        manifest: PathBuf::from(manifest),
+                        field,
+                    },
+                });
+            }
+        }
+    }
+
+    issues
+}
+
+/// Dangling symlinks & empty source files met walking the project, the ignored entries and .git aside, then the
+/// manifest fields pointing to missing paths
+pub(crate) fn issues(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<Vec<IntegrityIssue>> {
+    let entries = vfs.walk(&mut |e| {
+        e.depth == 0 || !(e.file_name() == ".git" || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)))
+    })?;
+    let mut issues = vec![];
+
+    for e in entries {
+        let rel_path = match e.path.strip_prefix(vfs.root()) {
+            Ok(rel_path) => rel_path.to_path_buf(),
+            Err(_) => continue,
+        };
+
+        // links are not followed by the walk, those whose target exists are files or directories when looked up
+        let kind = if e.is_symlink && !vfs.exists(&rel_path) {
+            IssueKind::DanglingSymlink
+        } else if e.is_file
+            && e.size == 0
+            && !EMPTY_BY_DESIGN.contains(&e.file_name().to_string_lossy().as_ref())
+            && assets::category(&e.path).is_none()
+            && StatsLang::of_path(&e.path.to_string_lossy()) != StatsLang::Unrecognized
+        {
+            IssueKind::EmptySource
+        } else {
+            continue;
+        };
+
+        issues.push(IntegrityIssue { path: rel_path, kind });
+    }
+
+    issues.extend(missing_refs(vfs));
+
+    Ok(issues)
+}
@@ -0,0 +1,130 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::detector;
+use crate::stats::StatsLang;
+
+/// Lowercase names, aliases & file extensions of the languages, with the name the code stats give them. Covers the
+/// names linguist & other tools use, the short names written after Markdown code fences, and notebook kernels
+pub const ALIASES: &[(&str, &str)] = &[
+    ("c", "C"),
+    ("c++", "C++"),
+    ("cpp", "C++"),
+    ("cxx", "C++"),
+    ("cc", "C++"),
+    ("hpp", "C/C++ Header"),
+    ("h", "C/C++ Header"),
+    ("c/c++ header", "C/C++ Header"),
+    ("c#", "C#"),
+    ("cs", "C#"),
+    ("csharp", "C#"),
+    ("css", "CSS"),
+    ("dart", "Dart"),
+    ("docker", "Docker"),
+    ("dockerfile", "Docker"),
+    ("elixir", "Elixir"),
+    ("ex", "Elixir"),
+    ("exs", "Elixir"),
+    ("erlang", "Erlang"),
+    ("erl", "Erlang"),
+    ("go", "Go"),
+    ("golang", "Go"),
+    ("haskell", "Haskell"),
+    ("hs", "Haskell"),
+    ("html", "HTML"),
+    ("htm", "HTML"),
+    ("java", "Java"),
+    ("javascript", "JavaScript"),
+    ("js", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("node", "JavaScript"),
+    ("json", "JSON"),
+    ("jsx", "Jsx"),
+    ("kotlin", "Kotlin"),
+    ("kt", "Kotlin"),
+    ("lua", "Lua"),
+    ("makefile", "Makefile"),
+    ("make", "Makefile"),
+    ("markdown", "Markdown"),
+    ("md", "Markdown"),
+    ("objective-c", "Objective-C"),
+    ("objc", "Objective-C"),
+    ("m", "Objective-C"),
+    ("php", "PHP"),
+    ("python", "Python"),
+    ("python3", "Python"),
+    ("py", "Python"),
+    ("r", "R"),
+    ("ruby", "Ruby"),
+    ("rb", "Ruby"),
+    ("rust", "Rust"),
+    ("rs", "Rust"),
+    ("scala", "Scala"),
+    ("bourne shell", "Bourne Shell"),
+    ("shell", "Bourne Shell"),
+    ("bash", "Bourne Shell"),
+    ("sh", "Bourne Shell"),
+    ("zsh", "Bourne Shell"),
+    ("sql", "SQL"),
+    ("swift", "Swift"),
+    ("toml", "TOML"),
+    ("typescript", "TypeScript"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("xml", "XML"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("zig", "Zig"),
+    ("julia", "Julia"),
+    ("jl", "Julia"),
+];
+
+/// The language a name, alias or file extension stands for, compared case insensitively: ```"TypeScript"```,
+/// ```"typescript"```, ```"ts"``` and ```".ts"``` all give [StatsLang::TypeScript]. The names [method.parse] detects
+/// languages by, such as ```"node"``` or ```"composer"```, are accepted too. None when the input is not known
+/// ```no_run
+/// use project_parse::languages;
+///
+/// assert_eq!(Some(StatsLang::Shell), languages::normalize("bash"));
+/// ```
+pub fn normalize(input: &str) -> Option<StatsLang> {
+    let input = input.trim();
+    let key = input.strip_prefix('.').unwrap_or(input).to_lowercase();
+
+    if let Some((_, name)) = ALIASES.iter().find(|(alias, _)| *alias == key) {
+        return Some(StatsLang::from(*name));
+    }
+
+    detector::loc_langs(&key)
+        .and_then(|langs| langs.first())
+        .map(|name| StatsLang::from(*name))
+}
+
+/// The aliases of a language in [ALIASES], its lowercase name included
+pub fn aliases(lang: &StatsLang) -> Vec<&'static str> {
+    ALIASES
+        .iter()
+        .filter(|(_, name)| *name == lang.as_str())
+        .map(|(alias, _)| *alias)
+        .collect()
+}
+
+/// The name [method.parse] detects the language an input stands for by, such as ```"node"``` for ```"ts"```, None
+/// when no detector covers it
+pub fn detector_lang(input: &str) -> Option<&'static str> {
+    let lang = normalize(input)?;
+
+    detector::lang_of_loc(lang.as_str())
+}
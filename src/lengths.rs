@@ -0,0 +1,78 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::stats::StatsLang;
+
+/// How long the lines of a language are, in characters, line breaks aside
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineLengths {
+    /// number of lines, blank ones included
+    pub lines: usize,
+    /// length of the longest line
+    pub max: usize,
+    /// average length
+    pub mean: f64,
+    /// length 95% of the lines do not exceed
+    pub p95: usize,
+    /// number of lines longer than the limit
+    pub over_limit: usize,
+}
+
+/// Line lengths keyed by language, recorded by [method.get_code_stats] when [method.line_limit] is set
+pub type LineLengthStats = BTreeMap<StatsLang, LineLengths>;
+
+/// Number of lines of each length met so far
+#[derive(Debug, Default)]
+pub(crate) struct LengthTally {
+    lengths: BTreeMap<usize, usize>,
+}
+
+impl LengthTally {
+    pub(crate) fn add(&mut self, content: &str) {
+        for line in content.lines() {
+            let length = line.trim_end_matches('\r').chars().count();
+            *self.lengths.entry(length).or_default() += 1;
+        }
+    }
+
+    /// The distribution, None without a single line
+    pub(crate) fn finish(&self, limit: usize) -> Option<LineLengths> {
+        let lines: usize = self.lengths.values().sum();
+        let max = *self.lengths.keys().next_back()?;
+        let total: usize = self.lengths.iter().map(|(length, count)| length * count).sum();
+
+        // the shortest length reached by 95% of the lines, counting from the shortest
+        let threshold = (lines * 95).div_ceil(100);
+        let mut seen = 0;
+        let p95 = self
+            .lengths
+            .iter()
+            .find(|(_, count)| {
+                seen += **count;
+                seen >= threshold
+            })
+            .map_or(max, |(length, _)| *length);
+
+        Some(LineLengths {
+            lines,
+            max,
+            mean: total as f64 / lines as f64,
+            p95,
+            over_limit: self.lengths.range(limit + 1..).map(|(_, count)| count).sum(),
+        })
+    }
+}
@@ -16,10 +16,10 @@
 //! project.parse()?;
 //! // Add some files to ignore
 //! let ignore_str = "files/to/ignore/1.js \n files/to/ignore/2.rs ";
-//! // Pass false for update_existing to update generic gitignore. True overwrites generic gitignore
-//! project.set_gitignore(ignore_str, &false)?;
+//! // Replace the generic gitignore. MergeStrategy::Append adds to it instead
+//! project.set_gitignore(ignore_str, MergeStrategy::Replace)?;
 //! // If you would like to also add the user defined gitignore
-//! project.use_project_gitignore(&true)?;
+//! project.use_project_gitignore(true)?;
 //! // Check if a specific file is ignored
 //! println!("1 {:?}", project.is_ignored("files/to/ignore/1.js"));
 //! // Get project code stats. 
@@ -30,17 +30,142 @@
 
 
 mod code;
-mod detector;
-mod ruleset;
+mod events;
+mod generated;
+mod license;
+#[cfg(feature = "loc")]
+mod loc_count;
+mod markdown;
+mod minified;
+mod notebook;
+mod sfc;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "serve")]
+pub use serve::serve;
+pub use analysis::Analysis;
+pub use stats::Count;
+pub use workspace::{scan_workspace, scan_workspace_with};
+mod vfs;
+
+/// Language, tool & editor detection, over directories on disk or arbitrary lists of entries
+pub mod detector;
 
 /// The main project module
 pub mod project;
+/// How long ago the project files were modified, and the directories left untouched
+pub mod age;
+/// The immutable result of [Project::analyze](project::Project::analyze)
+pub mod analysis;
+/// Categories of non-code files and their statistics
+pub mod assets;
+/// License header audit of the source files
+pub mod audit;
+/// Shields.io compatible badges
+pub mod badges;
+/// CODEOWNERS parsing and ownership lookup
+pub mod codeowners;
+/// README and other documentation files
+pub mod docs;
+/// Code files that are not valid UTF-8 or hold replacement characters
+pub mod encoding;
+/// CSV & Prometheus export of the code stats
+pub mod export;
+/// Gitignore content modelled as sections of comments, rules & blank lines
+pub mod gitignore;
+/// Git repository metadata, such as submodules
+pub mod git;
+/// Health score of a project, combining its license, README, tests, CI, comments & gitignore
+pub mod health;
+/// Git hooks configured with pre-commit, husky & lefthook
+pub mod hooks;
+/// A single walk of the project, read by the other methods instead of walking again
+pub mod index;
+/// Line endings, trailing whitespace & final newlines of the counted files
+pub mod hygiene;
+/// Tabs or spaces, and the indent width, of each language, and the EditorConfig sections they make
+pub mod indent;
+/// Line length distribution per language
+pub mod lengths;
+/// Helm charts & Kubernetes manifests deploying the project
+pub mod infra;
+/// Dangling symlinks, empty source files and missing paths referenced by manifests
+pub mod integrity;
+/// Language names, aliases & file extensions mapped onto the crate's languages
+pub mod languages;
+/// Longest files & functions, a quick list of complexity hotspots
+pub mod hotspots;
+/// Authorship of the surviving lines, from git blame
+#[cfg(feature = "git")]
+pub mod ownership;
+/// Files ranked by how often commits change them, from git history
+#[cfg(feature = "git")]
+pub mod history;
+/// Audit of the unix permissions of the project files
+pub mod permissions;
+/// Typed snapshot of the project files
+pub mod tree;
+/// JSON-RPC over stdin & stdout, for editor plugins and other host processes
+pub mod protocol;
+/// Periodic background re-scans
+pub mod schedule;
+/// Protobuf, GraphQL & SQL migration schemas and the code generators they feed
+pub mod schema;
+/// Regular expression search across the project files
+pub mod search;
+/// Gitignore rules compiled for matching, and their validation
+pub mod ruleset;
+/// JSON over HTTP server for live stats
+#[cfg(feature = "serve")]
+pub mod serve;
+/// Code snapshots and the changes between them
+pub mod stats;
+/// Test frameworks detected from the manifests & config files
+pub mod testing;
+/// Templating formats counted as a host language with another embedded in it
+pub mod templating;
+/// Gitignore templates: lookup of any template by key and control over how they are downloaded
+pub mod templates;
+/// Discovery & analysis of every project under a directory of checkouts
+pub mod workspace;
 
 #[cfg(test)]
 mod tests {
-    use super::project::Project;
+    #[cfg(feature = "loc")]
+    use super::code;
+    use super::assets::FileCategory;
+    use super::health::{Criterion, HealthConfig};
+    #[cfg(feature = "loc")]
+    use super::encoding::EncodingIssueKind;
+    #[cfg(feature = "loc")]
+    use super::indent::IndentStyle;
+    use super::infra::InfraKind;
+    use super::schema::{Codegen, MigrationDir};
+    use super::testing::TestFramework;
+    #[cfg(feature = "loc")]
+    use super::audit::HeaderAction;
+    use super::detector::{self, Detectors, FakeDirEntry};
+    use super::docs::MarkupFormat;
+    #[cfg(feature = "loc")]
+    use super::export::CsvGranularity;
+    use super::gitignore::Gitignore;
+    use super::hooks::HookManager;
+    use super::languages;
+    use super::project::{
+        BuildSystem, IgnoreSource, MergeStrategy, OsIgnores, ParseOptions, Project, ProjectError, ProjectKind, TieBreak,
+    };
+    #[cfg(feature = "loc")]
+    use super::project::{GeneratedCode, NestedRepos};
+    use super::ruleset::{self, DiagnosticKind, Dialect, RelPath, RuleOrigin, RuleSet};
+    use super::stats::StatsLang;
+    #[cfg(feature = "loc")]
+    use super::{stats::CodeStats, Count};
+    use super::templates;
     // use crate::project;
     use anyhow::*;
+    #[cfg(feature = "loc")]
+    use std::path::Path;
     use std::{env, path::PathBuf};
     use thiserror::Error;
 
@@ -58,6 +183,66 @@ mod tests {
         path.to_string_lossy().to_string()
     }
 
+    /// Serializes the tests replacing the crate-wide template fetcher
+    static FETCHER_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// Answers template downloads with the given bodies in turn, failing for None and once they run out, counting the
+    /// requests
+    #[derive(Clone)]
+    struct StubFetch {
+        responses: std::sync::Arc<std::sync::Mutex<Vec<Option<&'static str>>>>,
+        calls: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl StubFetch {
+        fn new(mut responses: Vec<Option<&'static str>>) -> Self {
+            responses.reverse();
+            StubFetch {
+                responses: std::sync::Arc::new(std::sync::Mutex::new(responses)),
+                calls: Default::default(),
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    impl templates::HttpFetch for StubFetch {
+        fn fetch(&self, url: &str) -> Result<String> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            match self.responses.lock().unwrap().pop() {
+                Some(Some(body)) => Ok(body.to_string()),
+                Some(None) => Err(anyhow!("{} is down", url)),
+                None => Err(anyhow!("no response left for {}", url)),
+            }
+        }
+    }
+
+    /// Sets the fetcher for the length of a test, putting the previous one back when dropped, even on panic
+    struct FetcherGuard {
+        previous: Option<std::sync::Arc<dyn templates::HttpFetch>>,
+        _lock: std::sync::MutexGuard<'static, ()>,
+    }
+
+    impl FetcherGuard {
+        fn set(fetcher: StubFetch) -> Self {
+            let lock = FETCHER_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            // the shared templates are loaded, or their download left to cool down, beforehand, so that the projects
+            // of other tests never reach the stub
+            templates::Configs::shared().git_ignores(false);
+            let previous = templates::default_fetcher();
+            templates::set_fetcher(fetcher);
+            FetcherGuard { previous, _lock: lock }
+        }
+    }
+
+    impl Drop for FetcherGuard {
+        fn drop(&mut self) {
+            *templates::FETCHER.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = self.previous.take();
+        }
+    }
+
     #[test]
     fn test_detect_rust_lang() -> Result<()> {
         let dir = test_dir("rust");
@@ -83,30 +268,3043 @@ mod tests {
     }
 
     #[test]
-    fn test_get_gitignore() -> Result<()> {
-        let dir = test_dir("node");
+    fn test_parse_with_detection_only() -> Result<()> {
+        let dir = test_dir("rust");
         let mut project = Project::new(&dir[..])?;
-        project.parse()?;
-        let gitignore = project.generic_gitignore.unwrap();
-        let gitignore = gitignore[0].as_str();
+        project.parse_with(ParseOptions {
+            fetch_templates: false,
+            build_ruleset: false,
+            ..Default::default()
+        })?;
 
-        // let result = vec![String::from("node")];
-        assert_eq!(Some(0), gitignore.find("\n### Node"));
-        // assert_eq!(1,3);
+        assert_eq!(Some(vec![String::from("rust")]), project.project_langs);
+        assert!(project.generic_gitignore.is_none());
+        assert!(project.gitignore_ruleset.is_none());
 
         Ok(())
     }
 
     #[test]
-    fn test_get_code_stats() -> Result<()> {
-        let dir = test_dir("node");
+    fn test_detect_java_from_structure() -> Result<()> {
+        let dir = test_dir("java");
         let mut project = Project::new(&dir[..])?;
         project.parse()?;
+        let result = vec![String::from("java")];
 
-        // get stats
+        assert_eq!(Some(result), project.project_langs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_from_paths() -> Result<()> {
+        let dir = PathBuf::from(test_dir("rust"));
+
+        let project = Project::try_from(dir.as_path())?;
+        assert_eq!(dir.canonicalize()?, project.dir);
+
+        let project = Project::try_from(PathBuf::from("test_projects/rust/../rust"))?;
+        assert!(project.dir.is_absolute());
+        assert_eq!(dir.canonicalize()?, project.dir);
+
+        assert!(Project::try_from(PathBuf::from("test_projects/missing")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_project_kinds() -> Result<()> {
+        let mut project = Project::new(&test_dir("rust")[..])?;
+        project.parse()?;
+        assert_eq!(Some(vec![ProjectKind::RustBinary]), project.project_kinds);
+
+        let mut project = Project::new(&test_dir("node")[..])?;
+        project.parse()?;
+        assert_eq!(Some(vec![ProjectKind::NodeLibrary]), project.project_kinds);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_detect_mobile_projects() -> Result<()> {
+        let options = || ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        };
+
+        let mut project = Project::from_entries([
+            ("App.xcodeproj/project.pbxproj", "// !$*UTF8*$!\n"),
+            ("Podfile", "platform :ios, '15.0'\n"),
+            ("App/AppDelegate.swift", "import UIKit\n"),
+        ])?;
+        project.parse_with(options())?;
+        assert_eq!(Some(vec![ProjectKind::XcodeProject]), project.project_kinds);
+        let tools = project.project_tools.clone().unwrap_or_default();
+        assert_eq!(vec!["xcode", "cocoapods"], tools);
+
+        let mut project = Project::from_entries([
+            ("pubspec.yaml", "name: app\ndependencies:\n  flutter:\n    sdk: flutter\n"),
+            ("lib/main.dart", "void main() {}\n"),
+            ("android/app/build.gradle", ""),
+            ("ios/Runner/AppDelegate.swift", ""),
+        ])?;
+        project.offline(true).parse()?;
+        assert!(project.project_kinds.unwrap_or_default().contains(&ProjectKind::FlutterApp));
+        assert!(project.project_tools.unwrap_or_default().contains(&String::from("flutter")));
+        let gitignore = project.generic_gitignore.unwrap_or_default().concat();
+        assert!(gitignore.contains(".flutter-plugins"));
+
+        // a Dart package without the mobile platforms is no Flutter app
+        let mut project = Project::from_entries([("pubspec.yaml", "name: package\n"), ("lib/package.dart", "")])?;
+        project.parse_with(options())?;
+        assert!(!project.project_kinds.unwrap_or_default().contains(&ProjectKind::FlutterApp));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_game_engines() -> Result<()> {
+        let projects = [
+            (vec![("Assets/Scenes/Main.unity", ""), ("ProjectSettings/ProjectVersion.txt", "")], "unity", "Library"),
+            (vec![("Game.uproject", "{}"), ("Source/Game/Game.cpp", "")], "unrealengine", "Intermediate/Build"),
+            (vec![("project.godot", "config_version=5\n"), ("main.gd", "extends Node\n")], "godot", ".godot"),
+        ];
+
+        for (entries, tool, ignored) in projects {
+            let mut project = Project::from_entries(entries)?;
+            project.offline(true).parse()?;
+            assert_eq!(Some(vec![tool.to_string()]), project.project_tools, "{}", tool);
+            assert!(project.is_ignored(ignored).is_some_and(|answer| answer.is_ignored()), "{}", tool);
+        }
+
+        // project settings alone do not make a Unity project
+        let mut project = Project::from_entries([("ProjectSettings/settings.json", "{}")])?;
+        project.offline(true).parse()?;
+        assert_eq!(Some(vec![]), project.project_tools);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_build_systems() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("WORKSPACE", ""),
+            ("BUILD.bazel", "cc_binary(name = \"app\")\n"),
+            ("flake.nix", "{ }\n"),
+            ("bazel-bin/app", ""),
+            ("result/bin/app", ""),
+        ])?;
+        project.offline(true).parse()?;
+        assert_eq!(Some(vec![BuildSystem::Bazel, BuildSystem::Nix]), project.build_systems);
+        for ignored in ["bazel-bin", "result"] {
+            assert!(project.is_ignored(ignored).is_some_and(|answer| answer.is_ignored()), "{}", ignored);
+        }
+
+        let mut project = Project::from_entries([(".buckconfig", "[project]\n"), ("BUCK", "")])?;
+        project.offline(true).parse()?;
+        assert_eq!(Some(vec![BuildSystem::Buck]), project.build_systems);
+        assert!(project.is_ignored("buck-out").is_some_and(|answer| answer.is_ignored()));
+
+        let mut project = Project::from_entries([("README.md", "# Notes\n")])?;
+        project.offline(true).parse()?;
+        assert_eq!(None, project.build_systems);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detection_evidence_and_tie_break() -> Result<()> {
+        use std::fs;
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_evidence");
+        fs::create_dir_all(dir.join("src"))?;
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"app\"\n")?;
+        fs::write(dir.join("src").join("main.rs"), "fn main() {}\n")?;
+        fs::write(dir.join("tox.ini"), "")?;
+        fs::write(dir.join(".nvmrc"), "18\n")?;
+
+        let mut project = Project::new(&dir)?;
+        project.parse_with(ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        })?;
+        let langs = ["rust", "node", "python"];
+        assert_eq!(Some(langs.iter().map(|s| s.to_string()).collect()), project.project_langs);
+
+        project
+            .tie_break(TieBreak::Preferred(vec!["python".into()]))
+            .parse()?;
+        let langs = ["rust", "python", "node"];
+        assert_eq!(Some(langs.iter().map(|s| s.to_string()).collect()), project.project_langs);
+
+        project.min_evidence(2).parse()?;
+        assert_eq!(Some(vec![String::from("rust")]), project.project_langs);
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_from_fake_entries() -> Result<()> {
+        let entries = FakeDirEntry::from_paths([
+            "Cargo.toml",
+            "src/main/java/App.java",
+            ".vscode/",
+            "docs/index.md",
+        ]);
+
+        assert_eq!(vec!["java", "rust"], Detectors::default().detects(&entries));
+        assert_eq!(vec!["visualstudiocode"], Detectors::editors().detects(&entries));
+        assert!(Detectors::tools().detects(&entries).is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_in_memory_project() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("src/main.rs", "fn main() {\n\n    println!(\"hi\");\n}\n"),
+            ("target/debug/app.rs", "fn main() {}\n"),
+            (".gitignore", "*.log\n"),
+            ("logs/", ""),
+        ])?;
+        project.offline(true).parse()?;
+        project.use_project_gitignore(true)?;
+
+        assert_eq!(Some(vec![String::from("rust")]), project.project_langs);
+        assert_eq!(Some(vec![ProjectKind::RustBinary]), project.project_kinds);
+        let ruleset = project.gitignore_ruleset.as_ref().unwrap();
+        assert!(ruleset.is_ignored(project.dir.join("target"), true));
+        assert!(ruleset.is_ignored(project.dir.join("logs/today.log"), false));
+        assert!(!ruleset.is_ignored(project.dir.join("src/main.rs"), false));
+
+        let stats = project.get_code_stats()?.unwrap();
+        assert_eq!(3, stats["Rust"].code);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_file_tree() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/lib.rs", "pub mod a;\n"),
+            ("target/debug/app", "binary"),
+        ])?;
+        project.offline(true).parse()?;
+        let tree = project.file_tree()?;
+
+        let src = tree.get("src").unwrap();
+        assert!(src.is_dir && !src.is_ignored);
+        assert_eq!(vec!["lib.rs", "main.rs"], src.children.iter().map(|n| &n.name).collect::<Vec<_>>());
+        assert_eq!(24, src.size);
+        assert_eq!(Some("Rust"), tree.get("src/main.rs").unwrap().lang.as_deref());
+
+        let target = tree.get("target").unwrap();
+        assert!(target.is_ignored && target.children.is_empty());
+        assert_eq!(Some(tree), project.tree);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_tree() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/bin/tool.rs", "fn main() {}\n"),
+            ("target/debug/app", "binary"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let expected = "\
+.
+├── Cargo.toml
+└── src
+    ├── bin
+    └── main.rs
+
+2 directories, 2 files
+";
+        assert_eq!(expected, project.render_tree(Some(2), false)?);
+
+        let rendered = project.render_tree(None, true)?;
+        assert!(rendered.contains("│   └── tool.rs\n"));
+        assert!(rendered.contains("└── target [ignored]\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_glob() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/bin/tool.rs", "fn main() {}\n"),
+            ("target/debug/build.rs", "fn main() {}\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let expected = vec![project.dir.join("src/bin/tool.rs"), project.dir.join("src/main.rs")];
+        assert_eq!(expected, project.glob("src/**/*.rs")?);
+        assert_eq!(vec![project.dir.join("src/main.rs")], project.glob("src/*.rs")?);
+        assert!(project.glob("**/build.rs")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {\n    // TODO: greet\n}\n"),
+            ("src/lib.rs", "// TODO one\n"),
+            ("assets/logo.png", "\0PNG TODO"),
+            ("target/debug/build.rs", "// TODO ignored\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let matches = project.search(r"TODO")?;
+        assert_eq!(2, matches.len());
+        assert_eq!(project.dir.join("src/lib.rs"), matches[0].path);
+        assert_eq!((2, 8), (matches[1].line, matches[1].column));
+        assert_eq!("    // TODO: greet", matches[1].text);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_asset_stats() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("Cargo.lock", "# lock\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("assets/logo.png", "png"),
+            ("assets/icon.svg", "<svg/>"),
+            ("fonts/mono.woff2", "font"),
+            ("data/rows.csv", "a,b\n"),
+        ])?;
+        project.offline(true).parse()?;
         project.get_code_stats()?;
 
-        assert_eq!(true, project.code_stats.unwrap().contains_key("JSON"));
+        let asset_stats = project.asset_stats.unwrap();
+        let images = asset_stats[&FileCategory::Image];
+        assert_eq!((2, 9), (images.files, images.bytes));
+        assert_eq!(1, asset_stats[&FileCategory::Font].files);
+        assert_eq!(1, asset_stats[&FileCategory::Data].files);
+        assert_eq!(1, asset_stats[&FileCategory::Lockfile].files);
+        assert_eq!(1, asset_stats[&FileCategory::Config].files);
+        assert!(!asset_stats.contains_key(&FileCategory::Doc));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_readme() -> Result<()> {
+        let project = Project::from_entries([
+            ("README.txt", "plain\n"),
+            (
+                "README.md",
+                "[![CI](https://ci/badge.svg)](https://ci)\n\n# My App #\n\nParses projects\nquickly.\n\n## Usage\n",
+            ),
+        ])?;
+        let readme = project.readme()?.unwrap();
+        assert_eq!(MarkupFormat::Markdown, readme.format);
+        assert_eq!(Some("My App"), readme.title.as_deref());
+        assert_eq!(Some("Parses projects quickly."), readme.description.as_deref());
+
+        let project = Project::from_entries([(
+            "readme.rst",
+            "=====\nTitle\n=====\n\n.. image:: badge.svg\n\nSome description.\n",
+        )])?;
+        let readme = project.readme()?.unwrap();
+        assert_eq!(Some("Title"), readme.title.as_deref());
+        assert_eq!(Some("Some description."), readme.description.as_deref());
+
+        assert!(Project::from_entries([("main.rs", "")])?.readme()?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_changelog() -> Result<()> {
+        let project = Project::from_entries([(
+            "CHANGELOG.md",
+            "# Changelog\n\n## [Unreleased]\n\n## [1.1.0] - 2019-02-15\n### Added\n- Spanish translation\n- Dark mode\n\n## v1.0.0\n* First release\n\n[1.1.0]: https://example.com/compare/v1.0.0...v1.1.0\n",
+        )])?;
+        let changelog = project.changelog()?.unwrap();
+
+        let versions: Vec<&str> = changelog.entries.iter().map(|e| e.version.as_str()).collect();
+        assert_eq!(vec!["Unreleased", "1.1.0", "1.0.0"], versions);
+        assert_eq!(Some("2019-02-15"), changelog.entries[1].date.as_deref());
+        assert_eq!(Some("Spanish translation\nDark mode"), changelog.entries[1].summary.as_deref());
+        assert_eq!(Some("First release"), changelog.entries[2].summary.as_deref());
+        assert_eq!(None, changelog.entries[0].summary);
+
+        let project = Project::from_entries([("HISTORY", "0.2.0 (2020-01-01)\n  fixes\n0.1.0\n  initial\n")])?;
+        let changelog = project.changelog()?.unwrap();
+        assert_eq!("0.2.0", changelog.entries[0].version);
+        assert_eq!(Some("initial"), changelog.entries[1].summary.as_deref());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_codeowners() -> Result<()> {
+        let mut project = Project::from_entries([
+            (
+                ".github/CODEOWNERS",
+                "# owners\n*.rs @rustaceans\n/docs/ @writers\nassets/* @design # direct only\n[Ops] @ops\nCargo.toml\n",
+            ),
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("docs/guide/intro.md", "# Intro\n"),
+            ("assets/logo.png", "png"),
+            ("assets/icons/app.svg", "<svg/>"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let codeowners = project.codeowners()?.unwrap();
+        assert_eq!(4, codeowners.rules.len());
+        assert_eq!(vec!["@rustaceans"], codeowners.owners_for("src/main.rs"));
+        assert_eq!(vec!["@writers"], codeowners.owners_for("docs/guide/intro.md"));
+        assert_eq!(vec!["@design"], codeowners.owners_for("assets/logo.png"));
+        assert_eq!(vec!["@ops"], codeowners.owners_for("Cargo.toml"));
+
+        let unowned = project.unowned_files()?;
+        assert_eq!(
+            vec![
+                project.dir.join(".github/CODEOWNERS"),
+                project.dir.join("assets/icons/app.svg")
+            ],
+            unowned
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_git_hooks() -> Result<()> {
+        let mut project = Project::from_entries([
+            (
+                ".pre-commit-config.yaml",
+                "repos:\n  - repo: https://github.com/psf/black\n    hooks:\n      - id: black\n      - id: mypy\n        stages: [push]\n",
+            ),
+            (".husky/pre-commit", "#!/bin/sh\n. \"$(dirname \"$0\")/_/husky.sh\"\nnpx lint-staged\n"),
+            (
+                "lefthook.yml",
+                "pre-push:\n  parallel: true\n  commands:\n    audit:\n      run: npm audit\n    test:\n      run: npm test\n",
+            ),
+        ])?;
+        project.offline(true).parse()?;
+
+        let hooks: Vec<(HookManager, &str, &str)> = project
+            .git_hooks
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|hook| (hook.manager, hook.stage.as_str(), hook.name.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                (HookManager::PreCommit, "pre-commit", "black"),
+                (HookManager::PreCommit, "pre-push", "mypy"),
+                (HookManager::Husky, "pre-commit", "npx lint-staged"),
+                (HookManager::Lefthook, "pre-push", "audit"),
+                (HookManager::Lefthook, "pre-push", "test"),
+            ],
+            hooks
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_hotspots() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            (
+                "src/main.rs",
+                "fn main() {\n    let s = \"}\";\n    if s.is_empty() {\n        run();\n    }\n}\n\nfn run() {}\n",
+            ),
+            ("src/lib.rs", "pub fn one() -> u8 {\n    1\n}\n"),
+            ("tools/gen.py", "def gen():\n    x = 1\n\n    return x\n\nprint(gen())\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let hotspots = project.hotspots(2, 3)?;
+        let functions: Vec<(&str, usize, usize)> = hotspots
+            .longest_functions
+            .iter()
+            .map(|function| (function.name.as_str(), function.line, function.lines))
+            .collect();
+        assert_eq!(vec![("main", 1, 6), ("gen", 1, 4)], functions);
+
+        let rust = &hotspots.longest_files["Rust"];
+        assert_eq!(2, rust.len());
+        assert_eq!(project.dir.join("src/main.rs"), rust[0].path);
+        assert_eq!(project.dir.join("src/main.rs"), hotspots.largest_files[0].path);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_stats_diff() -> Result<()> {
+        let mut before = Project::from_entries([
+            ("src/main.rs", "fn main() {\n    run();\n}\n"),
+            ("src/old.rs", "fn old() {}\n"),
+            ("app.py", "print(1)\n"),
+        ])?;
+        let mut after = Project::from_entries([
+            ("src/main.rs", "fn main() {\n    setup();\n    run();\n}\n"),
+            ("src/new.rs", "fn new() {}\n// new\n"),
+            ("app.py", "print(1)\n"),
+        ])?;
+
+        let older = before.code_snapshot()?;
+        let delta = after.code_snapshot()?.diff(&older);
+
+        assert_eq!(vec![PathBuf::from("src/new.rs")], delta.added_files);
+        assert_eq!(vec![PathBuf::from("src/old.rs")], delta.removed_files);
+        assert_eq!(vec![PathBuf::from("src/main.rs")], delta.changed_files);
+
+        let rust = delta.langs["Rust"];
+        assert_eq!((3, 1, 3), (rust.added, rust.removed, rust.files_changed));
+        assert!(!delta.langs.contains_key("Python"));
+        assert!(after.code_snapshot()?.diff(&after.code_snapshot()?).is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_stable_stats_order() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("main.rs", "fn main() {}\n"),
+            ("app.py", "import os\nprint(os.name)\n"),
+            ("lib.rs", "pub fn lib() {}\n"),
+            ("index.js", "console.log(1)\n"),
+        ])?;
+
+        let snapshot = project.code_snapshot()?;
+        let alphabetical: Vec<&str> = snapshot.alphabetical().iter().map(|(lang, _)| *lang).collect();
+        let mut sorted = alphabetical.clone();
+        sorted.sort();
+        assert_eq!(sorted, alphabetical);
+
+        let by_lines: Vec<u64> = snapshot.by_lines_desc().iter().map(|(_, stats)| stats.code).collect();
+        assert!(by_lines.windows(2).all(|pair| pair[0] >= pair[1]));
+
+        // the same project serializes the same way every time
+        let json = serde_json::to_string(&snapshot)?;
+        assert_eq!(json, serde_json::to_string(&project.code_snapshot()?)?);
+        let langs: Vec<String> = project.get_code_stats()?.unwrap_or_default().into_keys().map(String::from).collect();
+        assert_eq!(vec!["JavaScript", "Python", "Rust"], langs);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_languages_by_loc() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("package.json", "{}\n"),
+            ("requirements.txt", "flask\n"),
+            ("index.js", "console.log(1)\n"),
+            ("app.py", "import os\nprint(os.name)\nprint(os.sep)\n"),
+        ])?;
+        project.parse_with(ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        })?;
+        let detected = project.project_langs.clone().unwrap();
+        assert_eq!(vec!["node", "python"], detected);
+
+        // detection order until stats are generated
+        assert_eq!(detected, project.languages_by_loc());
+
+        project.get_code_stats()?;
+        assert_eq!(vec!["python", "node"], project.languages_by_loc());
+
+        // equal lines keep the detection order, languages no marker detected come after their peers
+        let count = |code| Count {
+            code,
+            ..Default::default()
+        };
+        project.code_stats = Some(code::Stats::from([
+            (StatsLang::Python, count(10)),
+            (StatsLang::JavaScript, count(4)),
+            (StatsLang::TypeScript, count(6)),
+            (StatsLang::Rust, count(10)),
+            (StatsLang::Go, count(20)),
+        ]));
+        assert_eq!(vec!["go", "node", "python", "rust"], project.languages_by_loc());
+
+        project.code_stats = Some(code::Stats::new());
+        assert_eq!(detected, project.languages_by_loc());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_stats_lang() -> Result<()> {
+        let mut project = Project::from_entries([("src/main.rs", "fn main() {}\n"), ("data.json", "{}\n")])?;
+        let stats = project.get_code_stats()?.unwrap();
+
+        assert_eq!(1, stats[&StatsLang::Rust].code);
+        assert_eq!(1, stats["JSON"].code);
+        assert!(stats.contains_key(&StatsLang::Json));
+
+        assert_eq!(StatsLang::Cpp, StatsLang::from("C++"));
+        assert_eq!("C++", StatsLang::Cpp.to_string());
+        assert_eq!(StatsLang::Other(String::from("Brainfuck")), StatsLang::from("Brainfuck"));
+        assert_eq!("\"Rust\"", serde_json::to_string(&StatsLang::Rust)?);
+        assert_eq!(StatsLang::Rust, serde_json::from_str::<StatsLang>("\"Rust\"")?);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_count() -> Result<()> {
+        let a = Count { code: 6, comment: 2, blank: 2, lines: 10 };
+        let b = Count { code: 1, comment: 3, blank: 0, lines: 4 };
+
+        assert_eq!(Count { code: 7, comment: 5, blank: 2, lines: 14 }, a + b);
+        assert_eq!(Count { code: 5, comment: 0, blank: 2, lines: 6 }, a - b);
+        let mut total = Count::default();
+        total += a;
+        assert_eq!(a, total);
+
+        assert_eq!(0.6, a.code_ratio());
+        assert_eq!(0.2, a.comment_ratio());
+        assert_eq!(0.0, Count::default().blank_ratio());
+        assert_eq!(3.0, b.comments_per_code());
+
+        let mut project = Project::from_entries([("src/main.rs", "fn main() {\n\n}\n")])?;
+        let stats = project.get_code_stats()?.unwrap();
+        assert_eq!(Count { code: 2, comment: 0, blank: 1, lines: 3 }, stats["Rust"]);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_count_of_content() -> Result<()> {
+        // counting the content already read gives what loc gives reading the file
+        for dir in ["src", "test_projects"] {
+            for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(|entry| entry.ok()) {
+                let path_str = entry.path().to_string_lossy().to_string();
+                if !entry.file_type().is_file() || StatsLang::of_path(&path_str) == StatsLang::Unrecognized {
+                    continue;
+                }
+                let content = std::fs::read_to_string(entry.path())?;
+                assert_eq!(Count::from(loc::count(&path_str)), Count::of_content(&path_str, &content), "{}", path_str);
+            }
+        }
+
+        let content = "/* a\n * comment */\nlet a = 1; /* trailing */\n\n// line\nlet b = \"é\"; /* é */ let c = 2;\n";
+        assert_eq!(Count { code: 2, comment: 3, blank: 1, lines: 6 }, Count::of_content("app.js", content));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_stats_totals() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("src/main.rs", "fn main() {\n    run();\n}\n"),
+            ("src/lib.rs", "pub fn run() {}\n"),
+            ("app.py", "\nprint(1)\n"),
+            ("README.md", "# Readme\n"),
+        ])?;
+
+        let snapshot = project.code_snapshot()?;
+        let total = snapshot.total();
+        assert_eq!(snapshot.per_language().values().map(|lang| lang.code).sum::<u64>(), total.code);
+        assert_eq!(snapshot.files(), total.files);
+        assert_eq!(snapshot.per_language()["Rust"].files, 2);
+
+        let percentages = snapshot.percentages();
+        assert!((percentages.values().sum::<f64>() - 100.0).abs() < 1e-9);
+        assert!(percentages["Rust"] > percentages["Python"]);
+        assert!(CodeStats::default().percentages().is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_describe() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+            ("src/main.rs", "fn main() {\n    run();\n}\n"),
+            ("src/lib.rs", "pub fn run() {}\n"),
+            ("LICENSE", "MIT License\n\nPermission is hereby granted, free of charge, to any person obtaining a copy\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let description = project.describe()?;
+        assert!(description.starts_with("Rust project, "));
+        assert!(description.contains(" LOC ("));
+        assert!(description.contains("% Rust"));
+        assert!(description.ends_with(", MIT license"));
+        assert!(!description.contains("git repo"));
+
+        assert_eq!("Project, 0 LOC", Project::from_entries([("notes", "")])?.describe()?);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_audit_license_headers() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("src/main.rs", "// Copyright 2022 Acme\n// Licensed under MIT\n\nfn main() {}\n"),
+            ("src/lib.rs", "/*\n * Copyright 2022 Acme\n * Licensed under MIT\n */\npub fn run() {}\n"),
+            ("src/other.rs", "// Copyright 2020 Someone Else\nfn other() {}\n"),
+            ("src/bare.rs", "fn bare() {}\n"),
+            ("tool.py", "#!/usr/bin/env python\n# Copyright 2022 Acme\n# Licensed under MIT\nprint(1)\n"),
+            ("script.py", "print(2)\n"),
+            ("data.json", "{}\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let audit = project.audit_license_headers("Copyright 2022 Acme\nLicensed under MIT")?;
+        assert_eq!(6, audit.checked);
+        assert!(!audit.is_clean());
+        assert_eq!(vec![project.dir.join("src/other.rs")], audit.different["Rust"]);
+        assert_eq!(vec![project.dir.join("src/bare.rs")], audit.missing["Rust"]);
+        assert_eq!(vec![project.dir.join("script.py")], audit.missing["Python"]);
+
+        // the expected header can be given with its comment markers
+        let audit = project.audit_license_headers("// Copyright 2022 Acme\n// Licensed under MIT\n")?;
+        assert_eq!(1, audit.different.values().flatten().count());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_apply_license_header() -> Result<()> {
+        let header = "Copyright 2024 Acme\nLicensed under MIT";
+        let mut project = Project::from_entries([
+            ("src/main.rs", "//! The app\nfn main() {}\n"),
+            ("src/old.rs", "// Copyright 2020 Acme\n// All rights reserved\n\nfn old() {}\n"),
+            ("src/done.rs", "// Copyright 2024 Acme\n// Licensed under MIT\nfn done() {}\n"),
+            ("tool.py", "#!/usr/bin/env python\nprint(1)\n"),
+            ("style.css", "body {}\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let changes = project.apply_license_header(header, true)?;
+        let content = |path: &str| {
+            changes
+                .iter()
+                .find(|change| change.path == project.dir.join(path))
+                .map(|change| (change.action, change.content.as_str()))
+        };
+
+        assert_eq!(4, changes.len());
+        assert_eq!(None, content("src/done.rs"));
+        assert_eq!(
+            Some((HeaderAction::Inserted, "// Copyright 2024 Acme\n// Licensed under MIT\n\n//! The app\nfn main() {}\n")),
+            content("src/main.rs")
+        );
+        assert_eq!(
+            Some((HeaderAction::Updated, "// Copyright 2024 Acme\n// Licensed under MIT\n\nfn old() {}\n")),
+            content("src/old.rs")
+        );
+        assert_eq!(
+            Some((HeaderAction::Inserted, "#!/usr/bin/env python\n# Copyright 2024 Acme\n# Licensed under MIT\n\nprint(1)\n")),
+            content("tool.py")
+        );
+        assert_eq!(
+            Some((HeaderAction::Inserted, "/*\n * Copyright 2024 Acme\n * Licensed under MIT\n */\n\nbody {}\n")),
+            content("style.css")
+        );
+
+        // projects held in memory cannot be written
+        assert!(project.apply_license_header(header, false).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gitignore_merge() -> Result<()> {
+        let content = "# build\ntarget/\n\n\n# logs\n*.log\n!keep.log\n";
+        assert_eq!(content, Gitignore::parse(content).to_string());
+        assert_eq!("no newline", Gitignore::parse("no newline").to_string());
+
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            (".gitignore", "# local stuff\n.env\n\n# build output\ntarget/\n"),
+        ])?;
+        project.offline(true).parse()?;
+        project.use_project_gitignore(true)?;
+
+        let merged = project.gitignore().to_string();
+        assert!(merged.contains("# local stuff\n.env\n"));
+        assert_eq!(1, project.gitignore().rules().filter(|rule| *rule == ".env").count());
+        let ruleset = project.gitignore_ruleset.as_ref().unwrap();
+        assert!(ruleset.is_ignored(project.dir.join(".env"), false));
+
+        // merging again is a no-op
+        project.use_project_gitignore(true)?;
+        assert_eq!(merged, project.gitignore().to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gitignore_strategies() -> Result<()> {
+        let mut project = Project::from_entries([("Cargo.toml", "[package]\n"), ("debug.log", "")])?;
+        project.offline(true).parse()?;
+        let ignored = |project: &Project, path: &str| {
+            let ruleset = project.gitignore_ruleset.as_ref().unwrap();
+            ruleset.is_ignored(project.dir.join(path), false)
+        };
+
+        project.set_gitignore("*.log", MergeStrategy::Append)?;
+        assert!(ignored(&project, "debug.log"));
+
+        // prepended rules are overridden by the existing ones
+        project.set_gitignore("!debug.log", MergeStrategy::Prepend)?;
+        assert!(ignored(&project, "debug.log"));
+        project.set_gitignore("!debug.log", MergeStrategy::Append)?;
+        assert!(!ignored(&project, "debug.log"));
+
+        // rules already present are left out
+        project.set_gitignore("*.log\n*.tmp", MergeStrategy::MergeDeduped)?;
+        let strategies: Vec<(&str, MergeStrategy)> = project
+            .custom_rules
+            .iter()
+            .map(|custom| (custom.rule.as_str(), custom.strategy))
+            .collect();
+        assert_eq!(
+            vec![
+                ("!debug.log", MergeStrategy::Prepend),
+                ("*.log", MergeStrategy::Append),
+                ("!debug.log", MergeStrategy::Append),
+                ("*.tmp", MergeStrategy::MergeDeduped),
+            ],
+            strategies
+        );
+
+        project.set_gitignore("*.bak", MergeStrategy::Replace)?;
+        assert_eq!(1, project.custom_rules.len());
+        assert_eq!(vec!["*.bak"], project.gitignore().rules().collect::<Vec<&str>>());
+        assert!(!ignored(&project, "target/debug"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_gitignore() -> Result<()> {
+        let diagnostics = ruleset::validate("# comment\ntarget/\n[abc\n*.[ch]\n[]]x\n/\n");
+        let kinds: Vec<(usize, &DiagnosticKind)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, &diagnostic.kind))
+            .collect();
+        assert_eq!(2, kinds.len());
+        assert_eq!((3, &DiagnosticKind::UnmatchedBracket), kinds[0]);
+        assert!(matches!(kinds[1], (6, DiagnosticKind::NeverMatches(_))));
+        assert!(diagnostics[0].to_string().starts_with("line 3: \"[abc\""));
+
+        // the invalid line is reported and left out, the rest still applies
+        let mut project = Project::from_entries([("Cargo.toml", "[package]\n"), ("debug.log", "")])?;
+        project.offline(true).parse()?;
+        project.set_gitignore("[abc\n*.log", MergeStrategy::Replace)?;
+        assert!(project.warnings.iter().any(|warning| warning.contains("unmatched [")));
+        let ruleset = project.gitignore_ruleset.as_ref().unwrap();
+        assert!(ruleset.is_ignored(project.dir.join("debug.log"), false));
+
+        // lines are reported by source, numbered within it
+        assert!(project.warnings.contains(&String::from("gitignore custom line 1: \"[abc\" unmatched [")));
+        project.set_gitignore("*.tmp\nbuild[\n", MergeStrategy::Append)?;
+        assert!(project.warnings.contains(&String::from("gitignore custom line 2: \"build[\" unmatched [")));
+        let answer = project.is_ignored("debug.log").unwrap();
+        assert_eq!((Some("custom"), Some(2)), (answer.rule_source(), answer.rule_line()));
+        assert!(project.is_ignored("cache.tmp").unwrap().is_ignored());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignore_dialects() -> Result<()> {
+        let project = Project::from_entries([
+            (".dockerignore", "*.md\n!README.md\ntarget\n"),
+            ("package.json", "{}"),
+            ("README.md", "# Readme\n"),
+            ("docs/guide.md", "# Guide\n"),
+            ("notes.md", "notes\n"),
+            ("target/debug/app", ""),
+            ("src/index.js", "module.exports = 1;\n"),
+            ("node_modules/dep/index.js", ""),
+            ("test/index.test.js", ""),
+        ])?;
+
+        let relative = |files: Vec<PathBuf>| -> Vec<PathBuf> {
+            files
+                .into_iter()
+                .map(|file| file.strip_prefix(&project.dir).unwrap().to_path_buf())
+                .collect()
+        };
+
+        // anchored to the context: docs/guide.md is not matched by *.md
+        let image = relative(project.packaged_files(Dialect::Dockerignore)?);
+        assert!(image.contains(&PathBuf::from("README.md")));
+        assert!(image.contains(&PathBuf::from("docs/guide.md")));
+        assert!(!image.contains(&PathBuf::from("notes.md")));
+        assert!(!image.iter().any(|file| file.starts_with("target")));
+
+        // without a .npmignore nor a .gitignore, only the files npm always leaves out are
+        let package = relative(project.packaged_files(Dialect::Npmignore)?);
+        assert!(package.contains(&PathBuf::from("test/index.test.js")));
+        assert!(!package.iter().any(|file| file.starts_with("node_modules")));
+
+        let npm = RuleSet::from_dialect(&project.dir, "*.json\ntest/", Dialect::Npmignore)?;
+        assert!(!npm.is_ignored(project.dir.join("package.json"), false));
+        assert!(npm.is_ignored(project.dir.join("data.json"), false));
+        assert!(npm.is_ignored(project.dir.join("test"), true));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_submodules() -> Result<()> {
+        let entries = [
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            (
+                ".gitmodules",
+                "[submodule \"vendor/lib\"]\n\tpath = vendor/lib\n\turl = https://example.com/lib.git\n\tbranch = main\n[core]\n\tpath = ignored\n",
+            ),
+            ("vendor/lib/main.py", "print(1)\n"),
+        ];
+
+        let mut project = Project::from_entries(entries)?;
+        let submodules = project.submodules()?;
+        assert_eq!(1, submodules.len());
+        assert_eq!(PathBuf::from("vendor/lib"), submodules[0].path);
+        assert_eq!(Some("https://example.com/lib.git"), submodules[0].url.as_deref());
+        assert_eq!(Some("main"), submodules[0].branch.as_deref());
+
+        project.offline(true).parse()?;
+        let stats = project.get_code_stats()?.unwrap();
+        assert!(!stats.contains_key("Python"));
+
+        let mut project = Project::from_entries(entries)?;
+        project.include_submodules(true).offline(true).parse()?;
+        let stats = project.get_code_stats()?.unwrap();
+        assert!(stats.contains_key("Python"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_nested_repos() -> Result<()> {
+        let entries = [
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("tools/web/.git/HEAD", "ref: refs/heads/main\n"),
+            ("tools/web/.gitignore", "dist/\n"),
+            ("tools/web/package.json", "{}"),
+            ("tools/web/index.js", "console.log(1);\n"),
+            ("tools/web/dist/bundle.js", "console.log(1);\n"),
+        ];
+
+        let mut project = Project::from_entries(entries)?;
+        project.offline(true).parse()?;
+        assert_eq!(Some(vec![PathBuf::from("tools/web")]), project.nested_repos);
+        assert!(!project.get_code_stats()?.unwrap().contains_key("JavaScript"));
+
+        let mut nested = project.nested_projects()?;
+        assert_eq!(1, nested.len());
+        let web = &mut nested[0];
+        assert_eq!(project.dir.join("tools/web"), web.dir);
+        assert_eq!(Some(true), web.is_git);
+        web.parse_with(ParseOptions {
+            fetch_templates: false,
+            use_project_gitignore: true,
+            ..Default::default()
+        })?;
+        assert_eq!(Some(vec![String::from("node")]), web.project_langs);
+        let ruleset = web.gitignore_ruleset.as_ref().unwrap();
+        assert!(ruleset.is_ignored(web.dir.join("dist"), true));
+
+        let mut project = Project::from_entries(entries)?;
+        project.nested_repo_mode(NestedRepos::Merge).offline(true).parse()?;
+        assert!(project.get_code_stats()?.unwrap().contains_key("JavaScript"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_git_dir() -> Result<()> {
+        use std::fs;
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_worktree");
+        let repo = dir.join("repo");
+        let worktree = dir.join("wt");
+        fs::create_dir_all(repo.join(".git").join("hooks"))?;
+        fs::create_dir_all(repo.join(".git").join("worktrees").join("wt"))?;
+        fs::create_dir_all(&worktree)?;
+        fs::write(repo.join(".git").join("config"), "[core]\n\tignorecase = true\n")?;
+        fs::write(repo.join(".git").join("hooks").join("pre-push"), "#!/bin/sh\n")?;
+        fs::write(repo.join(".git").join("worktrees").join("wt").join("commondir"), "../..\n")?;
+        let git_dir = repo.join(".git").join("worktrees").join("wt");
+        fs::write(worktree.join(".git"), format!("gitdir: {}\n", git_dir.to_string_lossy()))?;
+        fs::write(worktree.join("Cargo.toml"), "[package]\n")?;
+
+        let mut project = Project::new(&worktree)?;
+        assert_eq!(Some(true), project.is_git);
+        assert_eq!(Some(git_dir), project.git_dir());
+        assert!(project.ignore_case);
+        project.offline(true).parse()?;
+        let hooks = project.git_hooks.clone().unwrap();
+        assert_eq!(vec!["pre-push"], hooks.iter().map(|hook| hook.stage.as_str()).collect::<Vec<&str>>());
+
+        fs::remove_dir_all(&dir)?;
+
+        let project = Project::from_entries([
+            ("HEAD", "ref: refs/heads/main\n"),
+            ("objects/info/packs", ""),
+            ("refs/heads/main", "0000000000000000000000000000000000000000\n"),
+        ])?;
+        assert_eq!(Some(true), project.is_git);
+        assert_eq!(Some(project.dir.clone()), project.git_dir());
+
+        let project = Project::from_entries([("Cargo.toml", "[package]\n")])?;
+        assert_eq!(Some(false), project.is_git);
+        assert_eq!(None, project.git_dir());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_lfs_pointers() -> Result<()> {
+        let pointer = "version https://git-lfs.github.com/spec/v1\noid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\nsize 12345\n";
+        let mut project = Project::from_entries([
+            ("app.py", "print(1)\n"),
+            ("data/model.py", pointer),
+            ("data/weights.bin", pointer),
+            (".gitattributes", "*.bin filter=lfs diff=lfs merge=lfs -text\n"),
+        ])?;
+
+        let stats = project.get_code_stats()?.unwrap();
+        assert_eq!(1, stats["Python"].code);
+        assert_eq!(2, project.walk_stats.unwrap().lfs_pointers);
+        let mut pointers = project.lfs_pointers.clone().unwrap();
+        pointers.sort();
+        assert_eq!(
+            vec![project.dir.join("data/model.py"), project.dir.join("data/weights.bin")],
+            pointers
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_ext_stats() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("src/app.ts", "let a = 1;\nlet b = 2;\n"),
+            ("src/app.test.ts", "test();\n"),
+            ("ci.yml", "a: 1\n"),
+            ("deploy.yaml", "b: 2\nc: 3\n"),
+        ])?;
+
+        project.get_code_stats()?;
+        assert!(project.ext_stats.is_none());
+
+        project.ext_breakdown(true).get_code_stats()?;
+        let ext_stats = project.ext_stats.clone().unwrap();
+        assert_eq!(2, ext_stats["TypeScript"][".ts"].code);
+        assert_eq!(1, ext_stats["TypeScript"][".test.ts"].code);
+        assert_eq!(1, ext_stats["YAML"][".yml"].code);
+        assert_eq!(2, ext_stats["YAML"][".yaml"].code);
+
+        assert_eq!(".min.js", code::ext_key(Path::new("vendor/jquery.min.js")));
+        assert_eq!(".js", code::ext_key(Path::new("jquery-3.5.1.js")));
+        assert_eq!("dockerfile", code::ext_key(Path::new("Dockerfile")));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_notebook_stats() -> Result<()> {
+        let notebook = r##"{
+            "metadata": {"kernelspec": {"language": "python", "name": "python3"}},
+            "cells": [
+                {"cell_type": "markdown", "source": ["# Analysis\n", "\n", "Loads the data"]},
+                {"cell_type": "code", "source": ["import pandas as pd\n", "# read it\n", "df = pd.read_csv('a.csv')"],
+                 "outputs": [{"text": ["a\n", "b\n"]}]},
+                {"cell_type": "raw", "source": "ignored"}
+            ]
+        }"##;
+        let mut project = Project::from_entries([("analysis.ipynb", notebook), ("app.py", "print(1)\n")])?;
+
+        let stats = project.get_code_stats()?.unwrap();
+        assert_eq!(3, stats["Python"].code);
+        assert_eq!(3, stats["Python"].comment);
+        assert_eq!(1, stats["Python"].blank);
+        assert!(!stats.contains_key("JSON"));
+
+        let snapshot = project.code_snapshot()?;
+        assert_eq!("Python", snapshot.files[Path::new("analysis.ipynb")].lang);
+        assert_eq!(2, snapshot.langs["Python"].files);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_fenced_code_stats() -> Result<()> {
+        let readme = "# Title\n\nSome prose\n\n```rust\n// add\nfn add() {}\n\n```\n\n~~~\nplain\n~~~\n";
+        let mut project = Project::from_entries([("README.md", readme)])?;
+
+        let stats = project.get_code_stats()?.unwrap();
+        assert_eq!(9, stats["Markdown"].code);
+        assert!(!stats.contains_key("Rust"));
+
+        let stats = project.fenced_code(true).get_code_stats()?.unwrap();
+        assert_eq!(7, stats["Markdown"].code);
+        assert_eq!(3, stats["Markdown"].blank);
+        assert_eq!(1, stats["Rust"].code);
+        assert_eq!(1, stats["Rust"].comment);
+        assert_eq!(1, stats["Rust"].blank);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_component_stats() -> Result<()> {
+        let vue = "<template>\n  <div>{{ msg }}</div>\n</template>\n\n<script setup lang=\"ts\">\n// greeting\nconst msg: string = 'hi'\n</script>\n\n<style scoped>\n/* layout\n   rules */\ndiv { color: red; }\n</style>\n";
+        let astro = "---\nconst title = 'Home'\n---\n<h1>{title}</h1>\n<script>\nconsole.log(title)\n</script>\n";
+        let mut project = Project::from_entries([("src/App.vue", vue), ("src/pages/index.astro", astro)])?;
+
+        let stats = project.get_code_stats()?.unwrap();
+        // the template, script & style tags of the vue component, the fences, h1 & script tags of the astro one
+        assert_eq!(12, stats["HTML"].code);
+        assert_eq!(2, stats["HTML"].blank);
+        assert_eq!(2, stats["TypeScript"].code);
+        assert_eq!(1, stats["TypeScript"].comment);
+        assert_eq!(1, stats["JavaScript"].code);
+        assert_eq!(1, stats["CSS"].code);
+        assert_eq!(2, stats["CSS"].comment);
+        assert!(!stats.contains_key("Unrecognized"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_embedded_lang_stats() -> Result<()> {
+        let erb = "<ul>\n<% @items.each do |item| %>\n  <li><%= item.name %></li>\n  <%# skipped %>\n<% end %>\n</ul>\n";
+        let php = "<html>\n<?php\n// list\n$items = [];\n?>\n<p><?= count($items) ?></p>\n</html>\n";
+        let jsx = "export const App = () => (\n  <div>\n    {title}\n  </div>\n);\n";
+        let mut project = Project::from_entries([
+            ("app/views/index.html.erb", erb),
+            ("index.php", php),
+            ("src/App.jsx", jsx),
+        ])?;
+
+        let stats = project.get_code_stats()?.unwrap();
+        assert_eq!(2, stats["Ruby"].code);
+        assert_eq!(1, stats["Ruby"].comment);
+        assert_eq!(3, stats["PHP"].code);
+        assert_eq!(1, stats["PHP"].comment);
+        assert_eq!(3, stats["JavaScript"].code);
+        // the ul, li & p lines, html tags and div tags
+        assert_eq!(8, stats["HTML"].code);
+
+        project.embedded_langs(vec![]).get_code_stats()?;
+        assert!(!project.code_stats.clone().unwrap().contains_key("Ruby"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_minified_files() -> Result<()> {
+        let long_line = format!("var a={};\n", "1+".repeat(400) + "1");
+        let mut project = Project::from_entries([
+            ("src/app.js", "const a = 1;\n"),
+            ("public/jquery.min.js", "!function(){}();\n"),
+            ("dist/main.js", "(function(modules) { // webpackBootstrap\n})([]);\n"),
+            ("dist/vendor.js", long_line.as_str()),
+        ])?;
+
+        let stats = project.get_code_stats()?.unwrap();
+        assert_eq!(1, stats["JavaScript"].code);
+        assert_eq!(3, project.walk_stats.unwrap().minified);
+        assert_eq!(3, project.minified_files.clone().unwrap().len());
+
+        let stats = project.count_minified(true).get_code_stats()?.unwrap();
+        assert_eq!(5, stats["JavaScript"].code);
+        assert!(project.minified_files.is_none());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_generated_files() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("src/main.go", "package main\n\nfunc main() {}\n"),
+            ("src/api.pb.go", "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api\n\ntype A struct{}\n"),
+            ("src/schema.rs", "// @generated\nstruct A;\n"),
+            ("src/notes.go", "package main\n\n// do not edit the autogenerated values by hand\nvar warning = \"DO NOT EDIT\"\n"),
+        ])?;
+
+        // generated files are counted by default
+        let stats = project.get_code_stats()?.unwrap();
+        assert_eq!(8, stats["Go"].code);
+        assert!(project.generated_files.is_none());
+
+        // mentioning "do not edit" does not make notes.go generated
+        let stats = project.generated_code(GeneratedCode::Separate).get_code_stats()?.unwrap();
+        assert_eq!(5, stats["Go"].code);
+        assert!(!stats.contains_key("Rust"));
+        let generated = project.generated_stats.clone().unwrap();
+        assert_eq!(3, generated["Go"].code);
+        assert_eq!(2, generated["Rust"].code);
+        assert_eq!(2, project.walk_stats.unwrap().generated);
+
+        project.generated_code(GeneratedCode::Skip).get_code_stats()?;
+        assert!(project.generated_stats.is_none());
+        assert_eq!(2, project.generated_files.clone().unwrap().len());
+
+        let stats = project.generated_code(GeneratedCode::Count).get_code_stats()?.unwrap();
+        assert_eq!(8, stats["Go"].code);
+        assert!(project.generated_files.is_none());
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "loc"))]
+    #[test]
+    fn test_detection_without_loc() -> Result<()> {
+        let mut project = Project::from_entries([("Cargo.toml", "[package]\n"), ("src/main.rs", "fn main() {}\n")])?;
+        project.offline(true).parse()?;
+
+        // detection & ignore matching work as they do with loc
+        assert_eq!(Some(vec![String::from("rust")]), project.project_langs);
+        assert_eq!(Some(vec![ProjectKind::RustBinary]), project.project_kinds);
+        assert!(project.is_ignored("target").unwrap().is_ignored());
+
+        // no file is recognized as code
+        let stats = project.get_code_stats()?.unwrap_or_default();
+        assert!(stats.keys().all(|lang| *lang == StatsLang::Unrecognized), "{:?}", stats);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_codeless_projects() -> Result<()> {
+        let options = || ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        };
+
+        let mut project = Project::from_entries([
+            ("README.md", "# Notes\n"),
+            ("config/app.yaml", "port: 80\n"),
+            ("docs/guide.md", "Guide\n"),
+            (".env", "KEY=1\n"),
+        ])?;
+        project.parse_with(options())?;
+        assert_eq!(None, project.project_langs);
+        #[cfg(feature = "loc")]
+        {
+            let kind = ProjectKind::ConfigOnly(vec![FileCategory::Doc, FileCategory::Config]);
+            assert_eq!(Some(vec![kind]), project.project_kinds);
+        }
+        // code files cannot be told apart without loc
+        #[cfg(not(feature = "loc"))]
+        assert_eq!(None, project.project_kinds);
+
+        // code without a manifest is neither
+        let mut project = Project::from_entries([("main.py", "print(1)\n"), ("x.c", "int x;\n")])?;
+        project.parse_with(options())?;
+        assert_eq!(None, project.project_kinds);
+
+        let mut project = Project::from_entries(Vec::<(&str, &str)>::new())?;
+        project.parse_with(options())?;
+        assert_eq!(Some(vec![ProjectKind::Empty]), project.project_kinds);
+
+        let mut project = Project::from_entries([("Cargo.toml", "[package]\n"), ("src/main.rs", "fn main() {}\n")])?;
+        project.parse_with(options())?;
+        assert_eq!(Some(vec![ProjectKind::RustBinary]), project.project_kinds);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\nname = \"app\"\nlicense = \"MIT\"\n"),
+            ("README.md", "# App\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/app_test.go", "package app\n"),
+            ("tests/cli.rs", "#[test]\nfn cli() {}\n"),
+            (".github/workflows/ci.yml", "on: push\n"),
+        ])?;
+        project.parse_with(ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        })?;
+
+        let health = project.health(&HealthConfig::default())?;
+        let score = |criterion| health.criteria.iter().find(|c| c.criterion == criterion).unwrap();
+        assert_eq!("MIT", score(Criterion::License).detail);
+        assert_eq!("README.md", score(Criterion::Readme).detail);
+        assert_eq!("2 test files or directories", score(Criterion::Tests).detail);
+        assert_eq!("GitHub Actions", score(Criterion::Ci).detail);
+        assert_eq!(0.0, score(Criterion::GitignoreCoverage).score);
+        // 15 + 20 + 25 + 15 points of 100, in-memory projects counting no comments
+        assert_eq!(75, health.score);
+
+        let mut config = HealthConfig::default();
+        config.weight(Criterion::CommentRatio, 0).weight(Criterion::GitignoreCoverage, 0);
+        let health = project.health(&config)?;
+        assert_eq!(4, health.criteria.len());
+        assert_eq!(100, health.score);
+
+        let mut project = Project::from_entries([("notes.txt", "todo\n")])?;
+        assert_eq!(0, project.health(&HealthConfig::default())?.score);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_encoding_report() -> Result<()> {
+        use std::fs;
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_encoding");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("good.rs"), "fn main() {}\n")?;
+        fs::write(dir.join("latin1.rs"), b"fn main() {}\n// caf\xe9\n")?;
+        fs::write(dir.join("lossy.rs"), "// caf\u{FFFD} au lait \u{FFFD}\n")?;
+
+        let project = Project::new(&dir)?;
+        let report = project.encoding_report()?;
+        assert_eq!(3, report.files);
+        assert!(!report.is_clean());
+
+        let mut issues: Vec<_> = report.langs["Rust"]
+            .iter()
+            .map(|issue| (issue.path.clone(), issue.kind.clone()))
+            .collect();
+        issues.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            vec![
+                (PathBuf::from("latin1.rs"), EncodingIssueKind::InvalidUtf8 { line: 2, offset: 19 }),
+                (PathBuf::from("lossy.rs"), EncodingIssueKind::ReplacementChars { count: 2, line: 1 }),
+            ],
+            issues
+        );
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_line_lengths() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("src/main.rs", "fn main() {\n    let long_name = 1;\n}\n"),
+            ("src/lib.rs", "\n"),
+        ])?;
+
+        project.get_code_stats()?;
+        assert_eq!(None, project.line_lengths);
+
+        project.line_limit(20).get_code_stats()?;
+        let lengths = project.line_lengths.unwrap_or_default();
+        let rust = &lengths[&StatsLang::Rust];
+        assert_eq!((4, 22, 22, 1), (rust.lines, rust.max, rust.p95, rust.over_limit));
+        assert!((rust.mean - 34.0 / 4.0).abs() < f64::EPSILON);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hygiene_report() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("src/lf.rs", "fn a() {}\n"),
+            ("src/crlf.rs", "fn b() {}\r\nfn c() {}\r\n"),
+            ("src/mixed.rs", "fn d() {}\r\nfn e() {} \nfn f() {}"),
+        ])?;
+
+        project.get_code_stats()?;
+        assert_eq!(None, project.hygiene);
+
+        let report = project.hygiene_report()?;
+        assert!(!project.line_hygiene);
+        assert_eq!(3, report.files);
+        assert_eq!((2, 3), (report.lf_lines, report.crlf_lines));
+        assert_eq!((1, 1), (report.lf_files, report.crlf_files));
+        assert_eq!(vec![PathBuf::from("/src/mixed.rs")], report.mixed_endings);
+        assert_eq!(vec![PathBuf::from("/src/mixed.rs")], report.trailing_whitespace);
+        assert_eq!(vec![PathBuf::from("/src/mixed.rs")], report.missing_final_newline);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_indentation_profile() -> Result<()> {
+        let project = Project::from_entries([
+            ("src/main.rs", "fn main() {\n    if true {\n        run();\n    }\n}\n"),
+            ("src/lib.rs", "/**\n * Docs\n */\nmod a {\n    fn b() {}\n}\n"),
+            ("main.go", "func main() {\n\tif true {\n\t\trun()\n\t}\n}\n"),
+            ("Makefile", "all:\n\tcargo build\n"),
+        ])?;
+
+        let profile = project.indentation_profile()?;
+        let rust = &profile.langs["Rust"];
+        assert_eq!((IndentStyle::Spaces, Some(4), 2), (rust.style, rust.width, rust.files));
+        let go = &profile.langs["Go"];
+        assert_eq!((IndentStyle::Tabs, None), (go.style, go.width));
+
+        let editorconfig = profile.to_editorconfig();
+        assert!(editorconfig.starts_with("root = true\n"));
+        assert!(editorconfig.contains("[*.rs]\nindent_style = space\nindent_size = 4\n"));
+        assert!(editorconfig.contains("[*.go]\nindent_style = tab\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_infra_components() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("deploy/chart/Chart.yaml", "apiVersion: v2\nname: app\n"),
+            ("deploy/chart/values.yaml", "replicas: 2\n"),
+            ("deploy/chart/templates/deployment.yaml", "apiVersion: apps/v1\nkind: Deployment\n"),
+            ("k8s/service.yml", "# the service\n---\napiVersion: v1\nkind: Service\n"),
+            ("k8s/notes.yaml", "todo: scale\n"),
+            ("config/app.yaml", "port: 8080\n"),
+        ])?;
+
+        let components = project.infra_components()?;
+        let found: Vec<_> = components
+            .iter()
+            .map(|component| (component.path.clone(), component.kind, component.files.len()))
+            .collect();
+        assert_eq!(
+            vec![
+                (PathBuf::from("deploy/chart"), InfraKind::HelmChart, 3),
+                (PathBuf::from("k8s"), InfraKind::Kubernetes, 1),
+            ],
+            found
+        );
+
+        project.get_code_stats()?;
+        let asset_stats = project.asset_stats.unwrap_or_default();
+        assert_eq!(4, asset_stats[&FileCategory::Infra].files);
+        assert_eq!(2, asset_stats[&FileCategory::Config].files);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_schemas() -> Result<()> {
+        let project = Project::from_entries([
+            ("Cargo.toml", "[package]\nname = \"api\"\n\n[build-dependencies]\ntonic-build = \"0.10\"\n"),
+            ("buf.yaml", "version: v1\n"),
+            ("proto/api.proto", "syntax = \"proto3\";\n"),
+            ("web/schema.graphql", "type Query { ping: String }\n"),
+            ("db/migrations/0001_init.sql", "CREATE TABLE users (id INT);\n"),
+            ("db/migrations/archive/0000_seed.sql", "INSERT INTO users VALUES (1);\n"),
+            ("scripts/report.sql", "SELECT 1;\n"),
+        ])?;
+
+        let schemas = project.schemas()?;
+        assert!(!schemas.is_empty());
+        assert_eq!(vec![PathBuf::from("proto/api.proto")], schemas.protobuf);
+        assert_eq!(vec![PathBuf::from("web/schema.graphql")], schemas.graphql);
+        assert_eq!(
+            vec![MigrationDir {
+                path: PathBuf::from("db/migrations"),
+                files: 2
+            }],
+            schemas.migrations
+        );
+        assert_eq!(vec![Codegen::Buf, Codegen::Prost], schemas.codegen);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_test_frameworks() -> Result<()> {
+        let project = Project::from_entries([
+            ("package.json", "{\"devDependencies\": {\"vitest\": \"^1.0.0\"}}"),
+            ("jest.config.js", "module.exports = {};\n"),
+            ("pyproject.toml", "[tool.pytest.ini_options]\naddopts = \"-q\"\n"),
+            ("tests/test_app.py", "import unittest\n"),
+            ("go.mod", "module app\n"),
+            ("app_test.go", "package app\n"),
+        ])?;
+        let frameworks = vec![
+            TestFramework::Jest,
+            TestFramework::Vitest,
+            TestFramework::Pytest,
+            TestFramework::Unittest,
+            TestFramework::GoTest,
+        ];
+        assert_eq!(frameworks, project.test_frameworks()?);
+
+        let project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            (".config/nextest.toml", "[profile.default]\n"),
+            ("Gemfile", "gem \"rspec\"\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ])?;
+        let frameworks = vec![TestFramework::CargoTest, TestFramework::Nextest, TestFramework::Rspec];
+        assert_eq!(frameworks, project.test_frameworks()?);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_integrity_issues() -> Result<()> {
+        use super::integrity::{IntegrityIssue, IssueKind};
+        use std::fs;
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_integrity_issues");
+        fs::create_dir_all(dir.join("src"))?;
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"app\"\n\n[[bin]]\npath = \"src/bin/tool.rs\"\n")?;
+        fs::write(dir.join("package.json"), "{\"main\": \"./index.js\", \"bin\": {\"app\": \"cli.js\"}}")?;
+        fs::write(dir.join("cli.js"), "#!/usr/bin/env node\n")?;
+        fs::write(dir.join("src").join("lib.rs"), "")?;
+        fs::write(dir.join("src").join("__init__.py"), "")?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("nowhere"), dir.join("link")).ok();
+
+        let project = Project::new(&dir)?;
+        let issues = project.integrity_issues()?;
+        let issue = |path: &str, kind: IssueKind| IntegrityIssue {
+            path: PathBuf::from(path),
+            kind,
+        };
+        let missing = |field: &str, manifest: &str| IssueKind::MissingReference {
+            manifest: PathBuf::from(manifest),
+            field: field.to_string(),
+        };
+
+        assert!(issues.contains(&issue("src/bin/tool.rs", missing("[[bin]] path", "Cargo.toml"))));
+        assert!(issues.contains(&issue("index.js", missing("main", "package.json"))));
+        assert!(issues.contains(&issue("src/lib.rs", IssueKind::EmptySource)));
+        #[cfg(unix)]
+        assert!(issues.contains(&issue("link", IssueKind::DanglingSymlink)));
+        assert_eq!(if cfg!(unix) { 4 } else { 3 }, issues.len());
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_permission_audit() -> Result<()> {
+        use super::permissions::PermissionIssueKind;
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_permission_audit");
+        fs::create_dir_all(&dir)?;
+        let files = [
+            ("build.sh", "#!/bin/sh\necho build\n", 0o644),
+            ("run.sh", "#!/bin/sh\necho run\n", 0o755),
+            ("main.rs", "fn main() {}\n", 0o755),
+            ("notes.md", "# Notes\n", 0o666),
+        ];
+        for (name, content, mode) in files {
+            fs::write(dir.join(name), content)?;
+            fs::set_permissions(dir.join(name), fs::Permissions::from_mode(mode))?;
+        }
+
+        let project = Project::new(&dir)?;
+        let mut issues: Vec<(String, PermissionIssueKind)> = project
+            .permission_audit()?
+            .into_iter()
+            .map(|issue| (issue.path.to_string_lossy().to_string(), issue.kind))
+            .collect();
+        issues.sort_by(|a, b| a.0.cmp(&b.0));
+        let expected = vec![
+            (String::from("build.sh"), PermissionIssueKind::NotExecutable),
+            (String::from("main.rs"), PermissionIssueKind::ExecutableSource),
+            (String::from("notes.md"), PermissionIssueKind::WorldWritable),
+        ];
+        assert_eq!(expected, issues);
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_age_report() -> Result<()> {
+        use std::{
+            fs,
+            time::{Duration, SystemTime},
+        };
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_age_report");
+        fs::create_dir_all(dir.join("old").join("deep"))?;
+        fs::create_dir_all(dir.join("docs"))?;
+        let day = Duration::from_secs(24 * 60 * 60);
+        let files = [("new.rs", 0), ("docs/guide.md", 100), ("old/a.rs", 400), ("old/deep/b.rs", 500)];
+        for (path, days) in files {
+            let file = fs::File::create(dir.join(path))?;
+            file.set_modified(SystemTime::now() - day * days)?;
+        }
+
+        let mut project = Project::new(&dir)?;
+        let report = project.age_report(12)?;
+        let files: Vec<usize> = report.buckets.iter().map(|bucket| bucket.files).collect();
+        assert_eq!(vec![1, 0, 0, 1, 0, 2, 0], files);
+        assert_eq!(1, report.stale_dirs.len());
+        assert_eq!(PathBuf::from("old"), report.stale_dirs[0].path);
+        assert_eq!(2, report.stale_dirs[0].files);
+        assert_eq!(0, report.unknown);
+
+        assert_eq!(2, project.age_report(3)?.stale_dirs.len());
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reparse() -> Result<()> {
+        use std::{fs, time::SystemTime};
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_reparse");
+        fs::create_dir_all(dir.join("src"))?;
+        fs::write(dir.join("src").join("main.rs"), "fn main() {}\n")?;
+
+        let mut project = Project::new(&dir)?;
+        assert!(project.is_stale()?);
+        project.parse_with(ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        })?;
+        assert!(project.last_parsed.is_some());
+        assert!(!project.is_stale()?);
+
+        let file = fs::File::create(dir.join("src").join("lib.rs"))?;
+        file.set_modified(SystemTime::now())?;
+        assert!(project.is_stale()?);
+
+        project.reparse()?;
+        assert!(!project.is_stale()?);
+        assert!(project.code_stats.is_some());
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_index() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("build/out.js", "var a = 1;\n"),
+            ("README.md", "# App\n"),
+        ])?;
+        project.offline(true).parse()?;
+        project.set_gitignore("build/", MergeStrategy::Replace)?;
+        let (stats, tree, langs) = (project.get_code_stats()?, project.file_tree()?, project.project_langs.clone());
+
+        let index = project.index()?;
+        assert!(index.get("build").unwrap().is_ignored);
+        assert!(index.get("build/out.js").is_none());
+        assert!(index.get("src/main.rs").unwrap().is_file);
+        assert_eq!(3, index.files().count());
+
+        // the index answers as the walks did
+        assert_eq!(stats, project.get_code_stats()?);
+        assert_eq!(tree, project.file_tree()?);
+        project.offline(true).parse()?;
+        assert_eq!(langs, project.project_langs);
+
+        // directories the index left out are walked when asked for
+        let files = project.packaged_files(Dialect::Dockerignore)?;
+        assert!(files.contains(&project.dir.join("build/out.js")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ruleset_matcher() -> Result<()> {
+        let root = PathBuf::from("/my/project");
+        let ruleset = ruleset::load_str(&root, "target/\n*.log\n!keep.log\n/build")?;
+        let matcher = ruleset.matcher()?;
+        assert!(ruleset.is_compiled());
+
+        let paths = [
+            ("target", true),
+            ("target", false),
+            ("logs/debug.log", false),
+            ("logs/keep.log", false),
+            ("build", true),
+            ("src/build", true),
+            ("src/main.rs", false),
+        ];
+        for (path, is_dir) in paths {
+            let expected = ruleset.is_ignored(root.join(path), is_dir);
+            assert_eq!(expected, matcher.matches_relative(&RelPath::new(path), is_dir), "{}", path);
+            assert_eq!(expected, matcher.is_ignored(root.join(path), is_dir), "{}", path);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ruleset_rules() -> Result<()> {
+        let root = PathBuf::from("/my/project");
+        let mut ruleset = ruleset::load_str(&root, "# build output\ntarget/\n\n!/src/*.rs")?;
+        ruleset.add_rules("*.log")?;
+
+        let rules: Vec<_> = ruleset
+            .rules()
+            .map(|rule| (rule.pattern.as_str(), rule.anchored, rule.dir_only, rule.negation, rule.origin))
+            .collect();
+        assert_eq!(
+            vec![
+                ("**/target", false, true, false, RuleOrigin::Line(2)),
+                ("src/*.rs", true, false, true, RuleOrigin::Line(4)),
+                ("**/*.log", false, false, false, RuleOrigin::Line(5)),
+            ],
+            rules
+        );
+
+        let npm = RuleSet::from_dialect(&root, "coverage", Dialect::Npmignore)?;
+        let mut origins = npm.rules().map(|rule| rule.origin);
+        assert_eq!(Some(RuleOrigin::Line(1)), origins.next());
+        assert!(origins.all(|origin| origin == RuleOrigin::Npm));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_languages() {
+        for input in ["TypeScript", "typescript", "ts", ".ts", " TSX "] {
+            assert_eq!(Some(StatsLang::TypeScript), languages::normalize(input));
+        }
+        assert_eq!(Some(StatsLang::Shell), languages::normalize("Shell"));
+        assert_eq!(Some(StatsLang::Php), languages::normalize("composer"));
+        assert_eq!(Some(StatsLang::from("Julia")), languages::normalize("jl"));
+        assert_eq!(None, languages::normalize("not-a-language"));
+
+        assert!(languages::aliases(&StatsLang::Yaml).contains(&"yml"));
+        assert_eq!(Some("node"), languages::detector_lang("ts"));
+        assert_eq!(Some("rust"), languages::detector_lang("Rust"));
+        assert_eq!(None, languages::detector_lang("css"));
+    }
+
+    #[test]
+    fn test_split_html() -> Result<()> {
+        let page = "---\nlayout: default\n---\n<html>\n<style>\nbody { margin: 0; }\n</style>\n<script>\n// init\ninit();\n</script>\n<script src=\"app.js\"></script>\n</html>\n";
+        let mut project = Project::from_entries([("index.html", page)])?;
+
+        let stats = project.get_code_stats()?.unwrap();
+        assert!(!stats.contains_key("JavaScript"));
+
+        let stats = project.split_html(true).get_code_stats()?.unwrap();
+        assert_eq!(1, stats["CSS"].code);
+        assert_eq!(1, stats["JavaScript"].code);
+        assert_eq!(1, stats["JavaScript"].comment);
+        // the front matter is markup, not code
+        assert!(!stats.contains_key("TypeScript"));
+        assert_eq!(10, stats["HTML"].code);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gitignore_bom_crlf() -> Result<()> {
+        let root = PathBuf::from("/project");
+        let ruleset = ruleset::load_str(&root, "\u{feff}target/\r\n*.log\r\n")?;
+        assert!(ruleset.is_ignored(root.join("target"), true));
+        assert!(ruleset.is_ignored(root.join("debug.log"), false));
+
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            (".gitignore", "\u{feff}dist/\r\nbuild\r\n"),
+        ])?;
+        project.use_project_gitignore(false)?;
+        let ruleset = project.gitignore_ruleset.as_ref().unwrap();
+        assert!(ruleset.is_ignored(project.dir.join("dist"), true));
+        assert!(ruleset.is_ignored(project.dir.join("build"), true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escaped_rules() -> Result<()> {
+        let content = "foo\\[\n\\#notes\n\\!important\ntrailing\\ \n";
+        assert!(ruleset::validate(content).is_empty());
+
+        let root = PathBuf::from("/project");
+        let ruleset = ruleset::load_str(&root, content)?;
+        for path in ["foo[", "#notes", "!important", "trailing "] {
+            assert!(ruleset.is_ignored(root.join(path), false), "{}", path);
+        }
+        for path in ["foo", "notes", "important", "trailing"] {
+            assert!(!ruleset.is_ignored(root.join(path), false), "{}", path);
+        }
+
+        // the rules are kept, not blanked as invalid lines are
+        let mut project = Project::from_entries([("foo[", ""), ("#notes", "")])?;
+        project.set_gitignore(content, MergeStrategy::Replace)?;
+        assert!(project.warnings.is_empty(), "{:?}", project.warnings);
+        let answer = project.is_ignored("foo[").unwrap();
+        assert!(answer.is_ignored());
+        assert_eq!((Some("foo\\["), Some(1)), (answer.rule(), answer.rule_line()));
+        assert!(project.is_ignored("#notes").unwrap().is_ignored());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_ignored_details() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("target/debug/app", ""),
+        ])?;
+
+        let answer = project.is_ignored("src/main.rs").unwrap();
+        assert_eq!(IgnoreSource::NoRuleset, answer.source());
+        assert!(answer.exists() && !answer.is_ignored());
+
+        project.set_gitignore("target/\n!keep.log\n", MergeStrategy::Replace)?;
+
+        let answer = project.is_ignored("src/../target").unwrap();
+        assert_eq!(project.dir.join("target"), answer.path());
+        assert!(answer.exists() && answer.is_dir() && answer.is_ignored() && answer.is_from_rule());
+        assert!(!answer.is_outside_root());
+
+        let answer = project.is_ignored("keep.log").unwrap();
+        assert!(!answer.is_ignored() && answer.is_from_rule());
+        assert_eq!(Some("!keep.log"), answer.rule());
+        let ruleset = project.gitignore_ruleset.as_ref().unwrap();
+        assert_eq!(Some("target/"), ruleset.matching_line(project.dir.join("target"), true));
+
+        let answer = project.is_ignored("src/main.rs").unwrap();
+        assert_eq!(IgnoreSource::Default, answer.source());
+
+        let answer = project.is_ignored("../target").unwrap();
+        assert!(answer.is_outside_root() && !answer.is_ignored());
+        assert_eq!(IgnoreSource::Default, answer.source());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_ignored_rule_source() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            (".gitignore", "# local stuff\n\n*.log\n"),
+            ("target/debug/app", ""),
+        ])?;
+        project.offline(true).parse()?;
+        project.use_project_gitignore(true)?;
+        project.set_gitignore("# scratch\n*.tmp\n", MergeStrategy::Append)?;
+
+        // lines are numbered within the template, not within all the rules
+        let answer = project.is_ignored("target/debug").unwrap();
+        assert_eq!(Some("Rust.gitignore"), answer.rule_source());
+        let configs = templates::Configs::shared();
+        let template = &configs.git_ignores(true)["rust"].contents;
+        let line = answer.rule_line().unwrap();
+        assert_eq!(answer.rule(), template.split('\n').nth(line - 1));
+
+        let answer = project.is_ignored("app.log").unwrap();
+        assert_eq!((Some(".gitignore"), Some(3)), (answer.rule_source(), answer.rule_line()));
+
+        let answer = project.is_ignored("notes.tmp").unwrap();
+        assert_eq!((Some("custom"), Some(2)), (answer.rule_source(), answer.rule_line()));
+
+        // rebuilding the rules keeps the numbering
+        project.auto_ignore_env_dirs(true)?;
+        let answer = project.is_ignored("notes.tmp").unwrap();
+        assert_eq!((Some("custom"), Some(2)), (answer.rule_source(), answer.rule_line()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_ignored_outside_filesystem_root() -> Result<()> {
+        let mut project = Project::from_entries([("target/app", "")])?;
+        project.set_gitignore("target/\n", MergeStrategy::Replace)?;
+        assert_eq!(PathBuf::from("/"), project.dir);
+
+        for path in ["../target", "src/../../target", "/../target"] {
+            let answer = project.is_ignored(path).unwrap();
+            assert!(answer.is_outside_root() && !answer.exists() && !answer.is_ignored(), "{}", path);
+        }
+        assert!(!project.is_ignored("src/../target").unwrap().is_outside_root());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_send_sync() -> Result<()> {
+        use std::{sync::Arc, thread};
+
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<Project>();
+        assert_shareable::<RuleSet>();
+
+        let mut project = Project::from_entries([("Cargo.toml", "[package]\n"), ("target/app", "")])?;
+        project.set_gitignore("target/\n", MergeStrategy::Replace)?;
+        let project = Arc::new(project);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let project = Arc::clone(&project);
+                thread::spawn(move || project.is_ignored("target").unwrap().is_ignored())
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+
+        let mut clone = (*project).clone();
+        clone.set_gitignore("Cargo.toml\n", MergeStrategy::Append)?;
+        assert!(clone.is_ignored("Cargo.toml").unwrap().is_ignored());
+        assert!(!project.is_ignored("Cargo.toml").unwrap().is_ignored());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_analyze() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("target/debug/build.rs", "fn main() {}\n"),
+        ])?;
+        project.offline(true);
+        let analysis = project.analyze_with(ParseOptions {
+            build_ruleset: false,
+            ..Default::default()
+        })?;
+
+        assert_eq!(["rust"], analysis.langs());
+        assert!(!analysis.is_git());
+        assert_eq!(1, analysis.code_stats()["Rust"].code);
+        assert_eq!(2, analysis.walk_stats().files);
+        assert!(analysis.is_ignored("target").unwrap().is_ignored());
+        assert!(analysis.ruleset().is_ignored(analysis.dir().join("target"), true));
+
+        let analysis = Project::analyze(test_dir("rust"), ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        })?;
+        assert!(analysis.langs().contains(&String::from("rust")));
+        assert!(analysis.code_stats().contains_key("Rust"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_scan_workspace() -> Result<()> {
+        use super::workspace::ScanOptions;
+        use std::fs;
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_workspace");
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in [
+            ("api/Cargo.toml", "[package]\n"),
+            ("api/src/main.rs", "fn main() {}\n"),
+            ("api/vendor/dep/Cargo.toml", "[package]\n"),
+            ("api/vendor/dep/.git/HEAD", "ref: refs/heads/main\n"),
+            ("team/web/package.json", "{}\n"),
+            ("team/web/index.js", "run();\nstop();\n"),
+            ("team/notes/todo.txt", "scan\n"),
+            (".cache/app/Cargo.toml", "[package]\n"),
+        ] {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, content)?;
+        }
+
+        let scan = super::scan_workspace_with(&dir, &ScanOptions {
+            offline: true,
+            ..Default::default()
+        })?;
+        let dirs: Vec<_> = scan.projects.iter().map(|project| project.dir.clone()).collect();
+        assert_eq!(
+            vec![PathBuf::from("api"), PathBuf::from("api/vendor/dep"), PathBuf::from("team/web")],
+            dirs
+        );
+        assert!(scan.failures.is_empty());
+        assert_eq!(["rust"], scan.projects[0].langs.as_slice());
+        // the TOML of the manifests is not code, and the nested checkout is counted on its own
+        assert_eq!(1, scan.projects[0].code_lines);
+        assert_eq!(1, scan.projects[0].code_stats["Toml"].code);
+        assert_eq!(0, scan.projects[1].code_lines);
+        assert_eq!(2, scan.projects[2].code_lines);
+        assert_eq!(3, scan.code_lines());
+
+        let sequential = super::scan_workspace_with(&dir, &ScanOptions {
+            offline: true,
+            parallel: false,
+            max_depth: 1,
+            ..Default::default()
+        })?;
+        // nested checkouts are found however deep they are
+        let dirs: Vec<_> = sequential.projects.iter().map(|project| project.dir.clone()).collect();
+        assert_eq!(vec![PathBuf::from("api"), PathBuf::from("api/vendor/dep")], dirs);
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_unreadable_dirs() {
+        use super::stats::Count;
+        use super::vfs::{MemFs, Vfs, VfsEntry};
+        use super::workspace;
+        use std::{ffi::OsString, path::Path, sync::Arc, time::SystemTime};
+
+        /// Fails to list one directory, as happens to the directories the user may not read
+        #[derive(Debug)]
+        struct Unreadable(MemFs, PathBuf);
+
+        impl Vfs for Unreadable {
+            fn root(&self) -> &Path {
+                self.0.root()
+            }
+            fn is_file(&self, rel_path: &Path) -> bool {
+                self.0.is_file(rel_path)
+            }
+            fn is_dir(&self, rel_path: &Path) -> bool {
+                self.0.is_dir(rel_path)
+            }
+            fn read_to_string(&self, rel_path: &Path) -> Result<String> {
+                self.0.read_to_string(rel_path)
+            }
+            fn read(&self, rel_path: &Path) -> Result<Vec<u8>> {
+                self.0.read(rel_path)
+            }
+            fn read_dir(&self, rel_path: &Path) -> Result<Vec<OsString>> {
+                if rel_path == self.1 {
+                    bail!("Permission denied (os error 13)");
+                }
+                self.0.read_dir(rel_path)
+            }
+            fn sub(&self, rel_path: &Path) -> Arc<dyn Vfs> {
+                self.0.sub(rel_path)
+            }
+            fn write(&self, rel_path: &Path, content: &str) -> Result<()> {
+                self.0.write(rel_path, content)
+            }
+            fn modified(&self, rel_path: &Path) -> Option<SystemTime> {
+                self.0.modified(rel_path)
+            }
+            fn mode(&self, rel_path: &Path) -> Option<u32> {
+                self.0.mode(rel_path)
+            }
+            fn count(&self, entry: &VfsEntry, content: &str) -> Result<Count> {
+                self.0.count(entry, content)
+            }
+            fn walk(&self, filter: &mut dyn FnMut(&VfsEntry) -> bool) -> Result<Vec<VfsEntry>> {
+                self.0.walk(filter)
+            }
+        }
+
+        let mut fs = MemFs::new(Path::new("/work"));
+        fs.add("locked/", String::new());
+        fs.add("team/api/.git/HEAD", "ref: refs/heads/main\n".to_string());
+        fs.add("web/.git/HEAD", "ref: refs/heads/main\n".to_string());
+        let fs = Unreadable(fs, PathBuf::from("locked"));
+
+        let (mut dirs, mut failures) = (vec![], vec![]);
+        workspace::discover(&fs, Path::new(""), 0, 3, &mut dirs, &mut failures);
+        // the directory is reported and the ones after it are still looked into
+        assert_eq!(vec![PathBuf::from("team/api"), PathBuf::from("web")], dirs);
+        assert_eq!(1, failures.len());
+        assert_eq!(Path::new("locked"), failures[0].dir);
+        assert_eq!("Permission denied (os error 13)", failures[0].error);
+    }
+
+    #[test]
+    fn test_per_project_configs() -> Result<()> {
+        use std::{collections::HashMap, sync::Arc, thread};
+
+        let configs = |rule: &str| {
+            let template = templates::Template {
+                key: String::from("rust"),
+                name: String::from("Rust"),
+                file_name: String::from("Rust.gitignore"),
+                contents: format!("{}\n", rule),
+            };
+            Arc::new(templates::Configs::from_templates(HashMap::from([(String::from("rust"), template)])))
+        };
+
+        let handles: Vec<_> = ["build/", "out/"]
+            .into_iter()
+            .map(|rule| {
+                let configs = configs(rule);
+                thread::spawn(move || -> Result<(bool, bool)> {
+                    let mut project = Project::from_entries([("Cargo.toml", "[package]\n")])?;
+                    project.configs(configs).offline(true).parse()?;
+                    Ok((
+                        project.is_ignored("build").unwrap().is_ignored(),
+                        project.is_ignored("out").unwrap().is_ignored(),
+                    ))
+                })
+            })
+            .collect();
+
+        let answers = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Result<Vec<(bool, bool)>>>()?;
+        assert_eq!(vec![(true, false), (false, true)], answers);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_export_csv() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {\n    // run\n}\n"),
+            ("docs/a, b.md", "# Doc\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let mut out = vec![];
+        project.export_csv(&mut out, CsvGranularity::Languages)?;
+        let csv = String::from_utf8(out)?;
+        assert_eq!(Some("language,files,code,comment,blank"), csv.lines().next());
+        assert!(csv.contains("\nRust,1,3,0,0\n"));
+
+        let mut out = vec![];
+        project.export_csv(&mut out, CsvGranularity::Files)?;
+        let csv = String::from_utf8(out)?;
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(4, rows.len());
+        assert_eq!("path,language,bytes,code", rows[0]);
+        assert!(rows[2].starts_with("\"docs/a, b.md\","));
+        assert_eq!("src/main.rs,Rust,25,3", rows[3]);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_to_prometheus() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("src/main.rs", "fn main() {\n    run();\n}\n"),
+            ("app.py", "print(1)\n"),
+        ])?;
+
+        let metrics = project.to_prometheus()?;
+        assert!(metrics.contains("# TYPE project_parse_code_lines gauge\n"));
+        assert!(metrics.contains("project_parse_code_lines{project=\"/\",language=\"Rust\"} 3\n"));
+        assert!(metrics.contains("project_parse_language_share{project=\"/\",language=\"Python\"} 0.25\n"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn test_ownership() -> Result<()> {
+        use git2::{Repository, Signature};
+        use std::fs;
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_ownership");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src"))?;
+        let repo = Repository::init(&dir)?;
+
+        let commit = |path: &str, content: &str, name: &str| -> Result<()> {
+            fs::write(dir.join(path), content)?;
+            let mut index = repo.index()?;
+            index.add_path(std::path::Path::new(path))?;
+            index.write()?;
+            let tree = repo.find_tree(index.write_tree()?)?;
+            let signature = Signature::now(name, &format!("{}@example.com", name))?;
+            let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+            let parents: Vec<_> = parent.iter().collect();
+            repo.commit(Some("HEAD"), &signature, &signature, name, &tree, &parents)?;
+            Ok(())
+        };
+        commit("src/main.rs", "fn main() {\n    run();\n}\n", "ada")?;
+        commit("src/lib.rs", "pub fn run() {}\n", "grace")?;
+        commit("src/main.rs", "fn main() {\n    run();\n    run();\n}\n", "grace")?;
+        fs::write(dir.join("src").join("draft.rs"), "fn draft() {}\n")?;
+
+        let mut project = Project::new(&dir)?;
+        let ownership = project.ownership("src")?;
+        let files: Vec<_> = ownership.files.iter().map(|file| (file.path.clone(), file.lines)).collect();
+        assert_eq!(vec![(PathBuf::from("src/lib.rs"), 1), (PathBuf::from("src/main.rs"), 4)], files);
+
+        let authors: Vec<_> = ownership.authors.iter().map(|author| (author.name.as_str(), author.lines)).collect();
+        assert_eq!(vec![("ada", 3), ("grace", 2)], authors);
+        assert_eq!(Some("ada"), ownership.owner().map(|owner| owner.name.as_str()));
+        assert_eq!(2, ownership.langs["Rust"].len());
+
+        let ownership = project.ownership("**/lib.rs")?;
+        assert_eq!(Some("grace"), ownership.owner().map(|owner| owner.name.as_str()));
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn test_change_hotspots() -> Result<()> {
+        use git2::{Repository, Signature};
+        use std::{fs, time::SystemTime};
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_change_hotspots");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src"))?;
+        let repo = Repository::init(&dir)?;
+
+        let commit = |path: &str, content: &str| -> Result<()> {
+            fs::write(dir.join(path), content)?;
+            let mut index = repo.index()?;
+            index.add_path(std::path::Path::new(path))?;
+            index.write()?;
+            let tree = repo.find_tree(index.write_tree()?)?;
+            let signature = Signature::now("ada", "ada@example.com")?;
+            let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+            let parents: Vec<_> = parent.iter().collect();
+            repo.commit(Some("HEAD"), &signature, &signature, path, &tree, &parents)?;
+            Ok(())
+        };
+        commit("src/main.rs", "fn main() {}\n")?;
+        commit("src/lib.rs", "pub fn run() {}\n")?;
+        commit("src/main.rs", "fn main() {\n    run();\n}\n")?;
+        commit("README.md", "# app\n")?;
+        commit("src/main.rs", "fn main() {\n    run();\n    run();\n}\n")?;
+
+        let mut project = Project::new(&dir)?;
+        let hotspots = project.change_hotspots(SystemTime::UNIX_EPOCH)?;
+        let ranked: Vec<_> = hotspots.iter().map(|hotspot| (hotspot.path.clone(), hotspot.commits)).collect();
+        assert_eq!(
+            vec![
+                (PathBuf::from("src/main.rs"), 3),
+                (PathBuf::from("README.md"), 1),
+                (PathBuf::from("src/lib.rs"), 1)
+            ],
+            ranked
+        );
+        assert_eq!(4, hotspots[0].lines);
+        assert_eq!(Some("Rust"), hotspots[0].lang.as_deref());
+        assert!(hotspots[0].is_risk);
+        assert!(!hotspots[1].is_risk);
+
+        let later = SystemTime::now() + std::time::Duration::from_secs(3600);
+        assert!(project.change_hotspots(later)?.is_empty());
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_export_sqlite() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\nlicense = \"MIT\"\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("target/debug/app", "bin"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let path = env::temp_dir().join("project_parse_export.db");
+        let _ = std::fs::remove_file(&path);
+        project.export_sqlite(&path)?;
+        let id = project.export_sqlite(&path)?;
+
+        let conn = rusqlite::Connection::open(&path)?;
+        let count = |sql: &str| -> Result<i64> { Ok(conn.query_row(sql, [id], |row| row.get(0))?) };
+        assert_eq!(2, id);
+        assert_eq!(1, count("SELECT code FROM languages WHERE project_id = ?1 AND lang = 'Rust'")?);
+        assert_eq!(1, count("SELECT COUNT(*) FROM files WHERE project_id = ?1 AND ignored AND path = 'target'")?);
+        assert_eq!(1, count("SELECT COUNT(*) FROM detections WHERE project_id = ?1 AND value = 'MIT'")?);
+
+        std::fs::remove_file(&path)?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_schedule() -> Result<()> {
+        use std::{
+            fs,
+            sync::mpsc,
+            time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+        };
+
+        // a directory of its own, so that files left by an earlier run are not there
+        let mut dir = env::temp_dir();
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+        dir.push(format!("project_parse_schedule_{}_{}", std::process::id(), nanos));
+        fs::create_dir_all(dir.join("src"))?;
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"app\"\n")?;
+        fs::write(dir.join("src").join("main.rs"), "fn main() {}\n")?;
+
+        let mut project = Project::new(&dir)?;
+        project.offline(true).parse()?;
+
+        let (tx, rx) = mpsc::channel();
+        let schedule = project.schedule(Duration::from_millis(50), move |diff| {
+            let _ = tx.send(diff);
+        });
+
+        let first = rx.recv_timeout(Duration::from_secs(5));
+
+        fs::write(dir.join("app.py"), "print(1)\n")?;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let diff = loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(remaining).ok() {
+                Some(diff) if !diff.is_empty() => break Some(diff),
+                Some(_) if !remaining.is_zero() => continue,
+                _ => break None,
+            }
+        };
+        schedule.stop();
+        fs::remove_dir_all(&dir)?;
+
+        assert!(first?.is_empty());
+        let diff = diff.expect("no re-scan saw app.py within 10 seconds");
+        assert_eq!(vec![PathBuf::from("app.py")], diff.stats.added_files);
+        assert_eq!(1, diff.snapshot.langs["Python"].code);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detection_cache() -> Result<()> {
+        use std::{fs, sync::atomic::Ordering, thread, time::Duration};
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_detection_cache");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("Cargo.toml"), "[package]\n")?;
+
+        // a cache of its own, which the projects of other tests leave alone
+        let configs = std::sync::Arc::new(templates::Configs::new());
+        let parse = |cache_detection: bool| -> Result<Option<Vec<String>>> {
+            let mut project = Project::new(&dir)?;
+            project.configs(configs.clone()).parse_with(ParseOptions {
+                fetch_templates: false,
+                cache_detection,
+                ..Default::default()
+            })?;
+            Ok(project.project_langs)
+        };
+        let hits = || configs.lang_cache_hits.load(Ordering::Relaxed);
+
+        assert_eq!(Some(vec![String::from("rust")]), parse(true)?);
+        assert_eq!(0, hits());
+        assert_eq!(Some(vec![String::from("rust")]), parse(true)?);
+        assert_eq!(1, hits());
+
+        // detection can skip the cache, and the cache can be cleared
+        assert_eq!(Some(vec![String::from("rust")]), parse(false)?);
+        assert_eq!(1, hits());
+        configs.clear_detection_cache();
+        assert_eq!(Some(vec![String::from("rust")]), parse(true)?);
+        assert_eq!(1, hits());
+
+        // coarse filesystem clocks would give the new entry the same modification time
+        thread::sleep(Duration::from_millis(20));
+        fs::remove_file(dir.join("Cargo.toml"))?;
+        fs::write(dir.join("package.json"), "{}")?;
+        assert_eq!(Some(vec![String::from("node")]), parse(true)?);
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lazy_shared_ruleset() -> Result<()> {
+        let mut rules = RuleSet::new(&PathBuf::from("/repo"), vec!["target/", "*.log"])?;
+        assert!(!rules.is_compiled());
+
+        let clone = rules.clone();
+        assert!(clone.is_ignored("/repo/debug.log", false));
+        assert!(rules.is_compiled());
+
+        rules.add_rules("*.tmp")?;
+        assert!(!rules.is_compiled());
+        assert!(clone.is_compiled());
+        assert!(rules.is_ignored("/repo/a.tmp", false));
+        assert!(!clone.is_ignored("/repo/a.tmp", false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk_stats() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            (".env", "KEY=1\n"),
+            ("target/debug/build.rs", "// ignored\n"),
+            ("target/debug/app.d", "app\n"),
+        ])?;
+        project.offline(true).parse()?;
+        project.get_code_stats()?;
+
+        let walk = project.walk_stats.unwrap();
+        assert_eq!((2, 2), (walk.dirs, walk.files));
+        assert_eq!((1, 1), (walk.skipped_dirs, walk.skipped_files));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_event_hooks() -> Result<()> {
+        use std::sync::{Arc, Mutex};
+
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("target/debug/build.rs", "// ignored\n"),
+        ])?;
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let (visited, langs, ignored, ready) = (events.clone(), events.clone(), events.clone(), events.clone());
+        project
+            .offline(true)
+            .on_file_visited(move |path| visited.lock().unwrap().push(format!("visited {}", path.display())))
+            .on_language_detected(move |lang| langs.lock().unwrap().push(format!("lang {}", lang)))
+            .on_ignored(move |path| ignored.lock().unwrap().push(format!("ignored {}", path.display())))
+            .on_stats_ready(move |stats| ready.lock().unwrap().push(format!("stats {}", stats.len())))
+            .parse()?;
+        project.get_code_stats()?;
+
+        let events = events.lock().unwrap();
+        assert_eq!("lang rust", events[0]);
+        assert!(events.contains(&String::from("ignored /target")));
+        assert!(events.contains(&String::from("visited /src/main.rs")));
+        assert!(!events.iter().any(|event| event.contains("build.rs")));
+        assert!(events.last().is_some_and(|event| event.starts_with("stats")));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_protocol() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let input = [
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "is_ignored", "params": {"path": "target/debug"}}"#,
+            r#"{"jsonrpc": "2.0", "method": "reparse"}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "stats"}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "nope"}"#,
+            r#"{"jsonrpc": "2.0", "method": "exit"}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "stats"}"#,
+        ]
+        .join("\n");
+
+        let mut output = vec![];
+        super::protocol::run(&mut project, input.as_bytes(), &mut output)?;
+        let responses: Vec<serde_json::Value> = String::from_utf8(output)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+
+        assert_eq!(3, responses.len());
+        assert_eq!(true, responses[0]["result"]["ignored"]["is_ignored"]);
+        assert_eq!(1, responses[1]["result"]["Rust"]["code"]);
+        assert_eq!(-32601, responses[2]["error"]["code"]);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serve")]
+    #[test]
+    fn test_serve_routes() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            ("src/main.rs", "fn main() {}\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let (status, body) = super::serve::route(&mut project, "/langs")?;
+        assert_eq!(200, status);
+        assert_eq!("rust", body["languages"][0]);
+
+        let (_, body) = super::serve::route(&mut project, "/stats")?;
+        assert_eq!(1, body["Rust"]["code"]);
+
+        let (_, body) = super::serve::route(&mut project, "/ignored?path=target%2Fdebug")?;
+        assert_eq!("target/debug", body["path"]);
+        assert_eq!(true, body["ignored"]["is_ignored"]);
+
+        let (status, _) = super::serve::route(&mut project, "/nope")?;
+        assert_eq!(404, status);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_badges() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\nname = \"app\"\nlicense = \"MIT OR Apache-2.0\"\n"),
+            ("src/main.rs", "fn main() {\n    println!(\"hi\");\n}\n"),
+        ])?;
+        project.offline(true).parse()?;
+
+        let badges = project.badges()?;
+        let messages: Vec<&str> = badges.iter().map(|badge| badge.message.as_str()).collect();
+        // the lines of Cargo.toml are counted in the stats but are not code
+        assert!(project.code_stats.as_ref().unwrap()["Toml"].code > 0);
+        assert_eq!(vec!["Rust", "3", "MIT OR Apache-2.0"], messages);
+        assert_eq!("dea584", badges[0].color);
+        assert!(badges[0].to_svg().contains(">Rust</text>"));
+
+        let mut project = Project::from_entries([(
+            "LICENSE",
+            "MIT License\n\nPermission is hereby granted,\nfree of charge, to any person",
+        )])?;
+        let badges = project.badges()?;
+        assert_eq!(Some("MIT"), badges.last().map(|badge| badge.message.as_str()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_gitignore() -> Result<()> {
+        let dir = test_dir("node");
+        let mut project = Project::new(&dir[..])?;
+        project.parse()?;
+        let gitignore = project.generic_gitignore.unwrap();
+        let gitignore = gitignore[0].as_str();
+
+        // let result = vec![String::from("node")];
+        assert_eq!(Some(0), gitignore.find("\n### Node"));
+        // assert_eq!(1,3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_offline_parse() -> Result<()> {
+        let dir = test_dir("node");
+        let mut project = Project::new(&dir[..])?;
+        project.offline(true).parse()?;
+
+        // served from the cache or the bundled templates
+        let gitignore = project.generic_gitignore.unwrap();
+        assert_eq!(Some(0), gitignore[0].find("\n### Node"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_offline_template_unavailable() -> Result<()> {
+        // templates for node only, none for the rust project
+        let node = templates::Configs::shared().git_ignores(true)["node"].clone();
+        let configs = std::sync::Arc::new(templates::Configs::from_templates(
+            [(String::from("node"), node)].into_iter().collect(),
+        ));
+
+        let mut project = Project::from_entries([("Cargo.toml", "[package]\n"), ("src/main.rs", "fn main() {}\n")])?;
+        let error = project.configs(configs.clone()).offline(true).parse().unwrap_err();
+        match error.downcast_ref::<ProjectError>() {
+            Some(ProjectError::TemplateUnavailable(lang)) => assert_eq!("rust", lang),
+            other => panic!("expected TemplateUnavailable, got {:?}", other),
+        }
+
+        // online, a missing template is only left out
+        project.offline(false).parse()?;
+        assert!(project.generic_gitignore.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_template() -> Result<()> {
+        let template = templates::get("rust").unwrap();
+        assert!(template.contents.contains("target/"));
+        assert!(templates::list().contains(&String::from("rust")));
+        let configs = templates::Configs::shared();
+        assert_eq!("rust", configs.resolve("RUST", true).unwrap().key);
+        assert!(configs.resolve("no-such-template", true).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_templates_not_pinned_by_fallback() -> Result<()> {
+        use std::fs;
+
+        let cache = env::temp_dir().join(format!("project_parse_templates_{}.json", std::process::id()));
+        let _ = fs::remove_file(&cache);
+        let downloaded = r#"{"stub": {"key": "stub", "name": "Stub", "fileName": "Stub.gitignore", "contents": "*.stub"}}"#;
+        let stub = StubFetch::new(vec![Some(downloaded)]);
+
+        // an offline first use neither downloads nor keeps the bundled templates for later uses
+        let configs = templates::Configs::with_cache(&cache).with_fetcher(stub.clone());
+        assert!(configs.git_ignores(true).contains_key("rust"));
+        assert_eq!(0, stub.calls());
+
+        let git_ignores = configs.git_ignores(false);
+        assert_eq!(vec!["stub"], git_ignores.keys().collect::<Vec<_>>());
+        assert_eq!(1, stub.calls());
+        assert!(configs.warning().is_none() && cache.exists());
+        // loaded once for all
+        assert!(configs.git_ignores(true).contains_key("stub"));
+        fs::remove_file(&cache)?;
+
+        // a failed download falls back with a warning and is not tried again straight away
+        let stub = StubFetch::new(vec![None, None, None]);
+        let configs = templates::Configs::with_cache(&cache).with_fetcher(stub.clone());
+        assert!(configs.git_ignores(false).contains_key("rust"));
+        assert_eq!(3, stub.calls());
+        assert!(configs.warning().is_some_and(|warning| warning.contains("is down")));
+        assert!(configs.git_ignores(false).contains_key("rust"));
+        assert_eq!(3, stub.calls());
+        assert!(!cache.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_configs_fetcher() -> Result<()> {
+        use std::fs;
+
+        let cache = env::temp_dir().join(format!("project_parse_configs_fetcher_{}.json", std::process::id()));
+        let _ = fs::remove_file(&cache);
+        let downloaded = r#"{"own": {"key": "own", "name": "Own", "fileName": "Own.gitignore", "contents": "*.own"}}"#;
+        let global = StubFetch::new(vec![]);
+        let _guard = FetcherGuard::set(global.clone());
+
+        // the configs download through their own fetcher, leaving the crate-wide one to the others
+        let own = StubFetch::new(vec![Some(downloaded)]);
+        let configs = templates::Configs::with_cache(&cache).with_fetcher(own.clone());
+        assert_eq!(vec!["own"], configs.git_ignores(false).keys().collect::<Vec<_>>());
+        assert_eq!((1, 0), (own.calls(), global.calls()));
+        assert_eq!("own", configs.resolve("OWN", false).unwrap().key);
+        fs::remove_file(&cache)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_fetcher_retries() -> Result<()> {
+        use std::{fs, time::Instant};
+
+        let cache = env::temp_dir().join(format!("project_parse_fetcher_{}.json", std::process::id()));
+        let _ = fs::remove_file(&cache);
+        let downloaded = r#"{"stub": {"key": "stub", "name": "Stub", "fileName": "Stub.gitignore", "contents": "*.stub"}}"#;
+        let fetcher = || {
+            let _lock = FETCHER_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            templates::default_fetcher()
+        };
+        let previous = fetcher();
+
+        {
+            // a failed request is tried again after a pause
+            let stub = StubFetch::new(vec![None, Some(downloaded)]);
+            let guard = FetcherGuard::set(stub.clone());
+            let started = Instant::now();
+            let configs = templates::Configs::with_cache(&cache);
+            assert!(configs.git_ignores(false).contains_key("stub"));
+            assert_eq!(2, stub.calls());
+            assert!(started.elapsed() >= std::time::Duration::from_millis(500));
+            assert!(configs.warning().is_none());
+            fs::remove_file(&cache)?;
+            drop(guard);
+
+            // the pause doubles between attempts, the bundled templates standing in once they are used up
+            let stub = StubFetch::new(vec![None, None, None, Some(downloaded)]);
+            let _guard = FetcherGuard::set(stub.clone());
+            let started = Instant::now();
+            let configs = templates::Configs::with_cache(&cache);
+            assert!(configs.git_ignores(false).contains_key("rust"));
+            assert_eq!(3, stub.calls());
+            assert!(started.elapsed() >= std::time::Duration::from_millis(1500));
+            assert!(configs.warning().is_some());
+        }
+
+        // the fetcher used before the test is back
+        match (previous, fetcher()) {
+            (Some(previous), Some(restored)) => assert!(std::sync::Arc::ptr_eq(&previous, &restored)),
+            (previous, restored) => assert_eq!(previous.is_none(), restored.is_none()),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_os_ignores() -> Result<()> {
+        let dir = test_dir("node");
+        let mut project = Project::new(&dir[..])?;
+        project.parse()?;
+        project.add_os_ignores(OsIgnores::All)?;
+
+        let ruleset = project.gitignore_ruleset.as_ref().unwrap();
+        assert!(ruleset.is_ignored(project.dir.join(".DS_Store"), false));
+        assert!(ruleset.is_ignored(project.dir.join("Thumbs.db"), false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_tools() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("CMakeLists.txt", "project(app)\n"),
+            ("build.gradle", "plugins {}\n"),
+            ("main.tf", "terraform {}\n"),
+            ("analysis.ipynb", "{}\n"),
+            ("Assets/Scenes/Main.unity", ""),
+            ("ProjectSettings/ProjectVersion.txt", ""),
+        ])?;
+        project.offline(true).parse()?;
+
+        let tools = project.project_tools.clone().unwrap();
+        for tool in ["cmake", "gradle", "terraform", "jupyternotebooks", "unity"] {
+            assert!(tools.contains(&String::from(tool)), "{} not in {:?}", tool, tools);
+        }
+
+        // the templates of the tools are part of the gitignore
+        let paths = [
+            ("CMakeCache.txt", "CMake.gitignore"),
+            ("gradle-app.setting", "Gradle.gitignore"),
+            ("terraform.tfstate", "Terraform.gitignore"),
+            (".ipynb_checkpoints", "JupyterNotebooks.gitignore"),
+            ("Library", "Unity.gitignore"),
+        ];
+        for (path, source) in paths {
+            let answer = project.is_ignored(path).unwrap();
+            assert!(answer.is_ignored(), "{}", path);
+            assert_eq!(Some(source), answer.rule_source(), "{}", path);
+        }
+
+        // Unity needs both of its directories
+        let mut project = Project::from_entries([("Assets/logo.png", "")])?;
+        project.offline(true).parse()?;
+        assert!(!project.project_tools.unwrap_or_default().contains(&String::from("unity")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_editor_ignores() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("Cargo.toml", "[package]\n"),
+            (".idea/workspace.xml", "<project/>\n"),
+            (".vscode/settings.json", "{}\n"),
+            (".vscode/state.json", "{}\n"),
+        ])?;
+        project.offline(true).parse()?;
+        assert!(!project.is_ignored(".idea/workspace.xml").unwrap().is_ignored());
+
+        project.add_editor_ignores()?;
+        let editors = project.editors.clone().unwrap();
+        assert!(editors.contains(&String::from("jetbrains")) && editors.contains(&String::from("visualstudiocode")));
+
+        let answer = project.is_ignored(".idea/workspace.xml").unwrap();
+        assert!(answer.is_ignored());
+        assert_eq!(Some("JetBrains.gitignore"), answer.rule_source());
+        let answer = project.is_ignored(".vscode/state.json").unwrap();
+        assert!(answer.is_ignored());
+        assert_eq!((Some("VisualStudioCode.gitignore"), Some(".vscode/*")), (answer.rule_source(), answer.rule()));
+        // shared settings are kept
+        assert!(!project.is_ignored(".vscode/settings.json").unwrap().is_ignored());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detectors_resolve_templates() -> Result<()> {
+        let configs = templates::Configs::shared();
+        let git_ignores = configs.git_ignores(true);
+
+        let detectors = [Detectors::default(), Detectors::tools(), Detectors::editors()];
+        for name in detectors.iter().flat_map(|detectors| detectors.names()) {
+            assert!(
+                detector::resolve_template(&name, git_ignores).is_some(),
+                "no gitignore template for {}",
+                name
+            );
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "loc")]
+    #[test]
+    fn test_get_code_stats() -> Result<()> {
+        let dir = test_dir("node");
+        let mut project = Project::new(&dir[..])?;
+        project.parse()?;
+
+        // get stats
+        project.get_code_stats()?;
+
+        assert_eq!(true, project.code_stats.unwrap().contains_key("JSON"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_ignore_env_dirs() -> Result<()> {
+        let dir = test_dir("python");
+        let mut project = Project::new(&dir[..])?;
+        project.parse()?;
+
+        assert_eq!(Some(vec![String::from("venv")]), project.env_dirs);
+
+        let content = project.get_content(&false, &false, &false)?;
+        assert!(!content.iter().any(|p| p.ends_with("venv")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_appended_rules_before_generated_rules() -> Result<()> {
+        let mut project = Project::from_entries([("requirements.txt", "flask\n"), ("venv/lib/site.py", "print(1)\n")])?;
+        project.parse_with(ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        })?;
+        project.auto_ignore_env_dirs(true)?;
+
+        // the environment directory rules come last, whether the ruleset is extended or rebuilt
+        project.set_gitignore("!venv/", MergeStrategy::Append)?;
+        let extended = project.is_ignored("venv").unwrap();
+        project.auto_ignore_env_dirs(true)?;
+        let rebuilt = project.is_ignored("venv").unwrap();
+
+        assert!(extended.is_ignored());
+        assert_eq!(Some("env-dirs"), extended.rule_source());
+        assert_eq!(
+            (extended.is_ignored(), extended.rule_source(), extended.rule_line()),
+            (rebuilt.is_ignored(), rebuilt.rule_source(), rebuilt.rule_line())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_env_dirs() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("backend/requirements.txt", "flask\n"),
+            ("backend/app.py", "print(1)\n"),
+            ("backend/.venv/lib/site.py", "print(2)\n"),
+            ("tools/.tox/py3/lib.py", "print(3)\n"),
+            ("venv/lib/site.py", "print(4)\n"),
+            // ignored directories are not looked into
+            ("node_modules/pkg/.venv/lib.py", "print(5)\n"),
+        ])?;
+        project.offline(true).parse()?;
+        project.set_gitignore("node_modules/\n", MergeStrategy::Append)?;
+        project.parse_with(ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        })?;
+
+        let expected = ["backend/.venv", "tools/.tox", "venv"].map(String::from).to_vec();
+        assert_eq!(Some(expected), project.env_dirs);
+
+        let answer = project.is_ignored("backend/.venv").unwrap();
+        assert!(answer.is_ignored());
+        assert_eq!((Some("env-dirs"), Some(1)), (answer.rule_source(), answer.rule_line()));
+        assert!(!project.is_ignored("backend").unwrap().is_ignored());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ruleset_add_remove_rules() -> Result<()> {
+        let root = PathBuf::from(test_dir("node"));
+        let mut ruleset = RuleSet::new(&root, vec!["*.log"])?;
+
+        ruleset.add_rules("build/\n*.tmp")?;
+        assert!(ruleset.is_ignored(root.join("build"), true));
+        assert!(ruleset.is_ignored(root.join("cache.tmp"), false));
+
+        assert_eq!(1, ruleset.remove_matching("build/")?);
+        assert!(!ruleset.is_ignored(root.join("build"), true));
+        assert!(ruleset.is_ignored(root.join("debug.log"), false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_rules_with_generated_rules() -> Result<()> {
+        let mut project = Project::from_entries([
+            ("requirements.txt", "flask\n"),
+            ("venv/lib/site.py", "print(1)\n"),
+            (".gitmodules", "[submodule \"vendor/lib\"]\n\tpath = vendor/lib\n"),
+            ("vendor/lib/main.py", "print(2)\n"),
+        ])?;
+        project.parse_with(ParseOptions {
+            fetch_templates: false,
+            ..Default::default()
+        })?;
+        let raw_rules = |project: &Project| -> Vec<String> {
+            let ruleset = project.gitignore_ruleset.as_ref().unwrap();
+            ruleset.rules().map(|rule| rule.raw.clone()).collect()
+        };
+
+        project.set_gitignore("*.log\n!/vendor/lib/", MergeStrategy::Append)?;
+        let appended = raw_rules(&project);
+        project.auto_ignore_env_dirs(true)?;
+        assert_eq!(raw_rules(&project), appended);
+        assert_eq!(vec!["*.log", "!/vendor/lib/", "venv/", "/vendor/lib/"], appended);
+        assert!(project.is_ignored("vendor/lib").unwrap().is_ignored());
+
+        // without generated rules the ruleset is extended in the same order
+        project.include_submodules(true).auto_ignore_env_dirs(false)?;
+        project.set_gitignore("*.tmp", MergeStrategy::Append)?;
+        let appended = raw_rules(&project);
+        project.auto_ignore_env_dirs(false)?;
+        assert_eq!(raw_rules(&project), appended);
+        assert_eq!(vec!["*.log", "!/vendor/lib/", "*.tmp"], appended);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_ignore_case() -> Result<()> {
+        let mut project = Project::from_entries([("Build/out.txt", "")])?;
+        project.set_gitignore("build/", MergeStrategy::Replace)?;
+        assert!(!project.is_ignored("Build").unwrap().is_ignored());
+
+        project.set_ignore_case(true)?;
+        assert!(project.is_ignored("Build").unwrap().is_ignored());
+        project.set_ignore_case(false)?;
+        assert!(!project.is_ignored("Build").unwrap().is_ignored());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ruleset_case_insensitive() -> Result<()> {
+        let root = PathBuf::from(test_dir("node"));
+        let mut ruleset = RuleSet::new(&root, vec!["Build/"])?;
+        assert!(!ruleset.is_ignored(root.join("build"), true));
+
+        ruleset.set_case_insensitive(true)?;
+        assert!(ruleset.is_ignored(root.join("build"), true));
+        assert!(ruleset.is_ignored(root.join("BUILD"), true));
+
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_paths() -> Result<()> {
+        let root = PathBuf::from(r"C:\projects\app");
+        let ruleset = RuleSet::new(&root, vec!["build/", "/src/*.log"])?;
+
+        assert!(ruleset.is_ignored(r"C:\projects\app\build", true));
+        assert!(ruleset.is_ignored(r"C:/projects/app/src/debug.log", false));
+        assert!(ruleset.is_ignored(r"\\?\C:\projects\app\src\debug.log", false));
+        assert!(!ruleset.is_ignored(r"C:\projects\app\lib\src\debug.log", false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_windows_path_normalization() -> Result<()> {
+        // the normalization Windows paths go through, checked on every platform
+        assert_eq!("C:/projects/app/build", ruleset::windows_path(r"\\?\C:\projects\app\build"));
+        assert_eq!("C:/projects/app/src/debug.log", ruleset::windows_path(r"C:/projects/app\src\debug.log"));
+        assert_eq!("src/main.rs", ruleset::windows_path("src/main.rs"));
+
+        let entries = FakeDirEntry::from_paths([r"src\main\java\App.java", r".\pom.xml"]);
+        assert!(entries.contains(&FakeDirEntry::dir("src/main")));
+        assert!(entries.contains(&FakeDirEntry::file("pom.xml")));
+        assert_eq!(vec!["java"], Detectors::default().detects(&entries));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_single_file() -> Result<()> {
+        assert_eq!(vec!["rust"], detector::detect_lang(PathBuf::from("my/project/Cargo.toml"))?);
+        assert!(detector::detect_lang("notes.txt")?.is_empty());
+
+        let types = detector::project_file_types();
+        assert!(types.contains(&String::from("Cargo.toml")) && types.contains(&String::from("go.mod")));
+        assert_eq!(1, types.iter().filter(|name| *name == "package.json").count());
+
+        let entry = FakeDirEntry::new("Cargo.toml", Some("toml"), true);
+        assert_eq!(FakeDirEntry::file("Cargo.toml"), entry);
+        assert_eq!(vec!["rust"], Detectors::default().detects(&[entry]));
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_non_utf8_paths() -> Result<()> {
+        use std::{ffi::OsStr, fs, os::unix::ffi::OsStrExt};
+
+        let mut dir = env::temp_dir();
+        dir.push("project_parse_non_utf8");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("package.json"), "{}")?;
+        fs::write(dir.join(OsStr::from_bytes(b"bad\xffname.js")), "let a = 1;\n")?;
+
+        let mut project = Project::new(&dir)?;
+        project.parse()?;
+        project.get_code_stats()?;
+
+        assert_eq!(1, project.skipped_paths.unwrap().len());
+
+        fs::remove_dir_all(&dir)?;
 
         Ok(())
     }
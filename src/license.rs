@@ -0,0 +1,81 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+
+use crate::docs;
+use crate::vfs::Vfs;
+
+/// SPDX ids and phrases found in the license texts, most specific first
+const TEXTS: [(&str, &[&str]); 12] = [
+    ("AGPL-3.0", &["GNU AFFERO GENERAL PUBLIC LICENSE"]),
+    ("LGPL-3.0", &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("LGPL-2.1", &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 2.1"]),
+    ("GPL-3.0", &["GNU GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("GPL-2.0", &["GNU GENERAL PUBLIC LICENSE", "Version 2"]),
+    ("Apache-2.0", &["Apache License", "Version 2.0"]),
+    ("MPL-2.0", &["Mozilla Public License Version 2.0"]),
+    (
+        "BSD-3-Clause",
+        &["Redistribution and use in source and binary forms", "Neither the name"],
+    ),
+    ("BSD-2-Clause", &["Redistribution and use in source and binary forms"]),
+    ("ISC", &["Permission to use, copy, modify, and/or distribute this software"]),
+    ("MIT", &["Permission is hereby granted, free of charge"]),
+    ("Unlicense", &["This is free and unencumbered software released into the public domain"]),
+];
+
+static MANIFEST_LICENSE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?m)^\s*license\s*=\s*(?:\{\s*text\s*=\s*)?"([^"]+)""#).unwrap());
+
+/// SPDX id of a license text
+pub(crate) fn identify(text: &str) -> Option<String> {
+    // line breaks are irrelevant to the phrases
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    TEXTS
+        .iter()
+        .find(|(_, phrases)| phrases.iter().all(|phrase| text.contains(phrase)))
+        .map(|(id, _)| id.to_string())
+}
+
+/// The project license, as an SPDX id or expression. Manifests are trusted first, then the LICENSE/COPYING texts
+pub(crate) fn detect_license(vfs: &dyn Vfs) -> Result<Option<String>> {
+    if let Ok(content) = vfs.read_to_string(Path::new("package.json")) {
+        let manifest: serde_json::Value = serde_json::from_str(&content).unwrap_or_default();
+        if let Some(license) = manifest["license"].as_str() {
+            return Ok(Some(license.to_string()));
+        }
+    }
+
+    for manifest in ["Cargo.toml", "pyproject.toml"] {
+        if let Ok(content) = vfs.read_to_string(Path::new(manifest)) {
+            if let Some(captures) = MANIFEST_LICENSE.captures(&content) {
+                return Ok(Some(captures[1].to_string()));
+            }
+        }
+    }
+
+    for path in docs::find_files(vfs, &["LICENSE", "LICENCE", "COPYING", "UNLICENSE"])? {
+        let content = vfs.read_to_string(&path).unwrap_or_default();
+        if let Some(id) = identify(&content) {
+            return Ok(Some(id));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,93 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The counting loop of ```loc::count``` over content already read, as loc only counts the files it reads itself.
+//! It follows loc 0.5.0, the version Cargo.toml pins, and ```test_count_of_content``` checks both count the sources of
+//! the crate alike, so that a loc bump changing the loop is caught rather than drifted from
+
+use crate::stats::Count;
+
+/// Line counts of a file from its content, with the comment delimiters loc knows for its language
+pub(crate) fn count_str(path_str: &str, content: &str) -> Count {
+    let (singles, multis) = loc::counter_config_for_lang(loc::lang_from_ext(path_str));
+    let mut count = Count::default();
+    let mut multi_stack: Vec<(&str, &str)> = vec![];
+
+    'line: for line in content.split_terminator('\n') {
+        count.lines += 1;
+
+        // blank lines within a comment count as blank
+        let line = line.trim_start();
+        if line.is_empty() {
+            count.blank += 1;
+            continue;
+        }
+
+        if multi_stack.is_empty() {
+            for single in singles.iter() {
+                if line.starts_with(single) {
+                    // a single line comment start may begin a multi line one, as // does /**
+                    if multis.iter().any(|(start, _)| line.starts_with(start)) {
+                        break;
+                    }
+                    count.comment += 1;
+                    continue 'line;
+                }
+            }
+
+            if multis.is_empty() || !multis.iter().any(|(start, end)| line.contains(start) || line.contains(end)) {
+                count.code += 1;
+                continue 'line;
+            }
+        }
+
+        // code outside of the comments counts the line as code
+        let mut pos = 0;
+        let mut found_code = 0;
+        'outer: while pos < line.len() {
+            for multi in multis.iter() {
+                let (start, end) = *multi;
+                let longest = start.len().max(end.len()) + 1;
+                if (pos..(pos + longest).min(line.len())).any(|i| !line.is_char_boundary(i)) {
+                    pos += 1;
+                    continue 'outer;
+                }
+
+                if line[pos..].starts_with(start) {
+                    pos += start.len();
+                    multi_stack.push(*multi);
+                    continue;
+                }
+
+                if let Some(&(_, end)) = multi_stack.last() {
+                    if line[pos..].starts_with(end) {
+                        multi_stack.pop();
+                        pos += end.len();
+                    }
+                } else if line[pos..].chars().next().is_some_and(|c| !c.is_whitespace()) {
+                    found_code += 1;
+                }
+            }
+            pos += 1;
+        }
+
+        if found_code >= multis.len() {
+            count.code += 1;
+        } else {
+            count.comment += 1;
+        }
+    }
+
+    count
+}
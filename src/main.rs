@@ -0,0 +1,478 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The ```project-parse``` command line
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::mpsc,
+    time::Duration,
+};
+
+use project_parse::export::CsvGranularity;
+use project_parse::gitignore::Gitignore;
+use project_parse::project::Project;
+use project_parse::schedule::ProjectDiff;
+use project_parse::stats::{CodeStats, LangStats};
+use project_parse::templates::Configs;
+
+#[cfg(feature = "tui")]
+mod tui;
+
+const USAGE: &str = "Usage: project-parse <command> [options] [dir]
+
+Commands:
+  stats     Count lines of code per language
+            --format json|table|csv   output format, table by default
+            --per-file                one row per file instead of per language
+  gitignore Print a .gitignore built from the templates of the detected languages & tools
+            --langs rust,node         use these templates instead of the detected ones
+            --merge-existing          keep the project .gitignore, adding the rules it lacks
+            --write                   write .gitignore instead of printing it
+  check-ignore [-v] [--dir dir] <path>...
+            Print the paths that are ignored, relative to the project directory, as git check-ignore does
+            -v                        also print the rule deciding, as <source>:<line>:<rule>, for paths it
+                                      keeps too. The source is the template, e.g. Rust.gitignore, or .gitignore
+            --dir dir                 the project directory, the current directory by default
+            Exits with 0 when a path is ignored, 1 when none is and 128 on errors
+  watch     Keep the stats on screen, refreshed as files change, until interrupted
+            --interval seconds        time between scans, 2 by default
+  tui       Browse the language breakdown, the tree with the lines of code of each directory and the ignore
+            rules deciding each entry. Needs the tui feature
+
+Options:
+  --offline   use cached or bundled gitignore templates only
+  -h, --help  print this help
+
+Exit codes: 0 on success, 1 when nothing was found, 2 on errors";
+
+/// Exit code when the command ran but found nothing, such as a project without code
+const NOTHING_FOUND: u8 = 1;
+/// Exit code of errors & invalid usage
+const FAILURE: u8 = 2;
+
+/// Arguments of a command: flags, options taking a value, and the positional arguments in order
+#[derive(Debug, Default)]
+struct Args {
+    flags: BTreeSet<String>,
+    values: BTreeMap<String, String>,
+    positionals: Vec<String>,
+}
+
+impl Args {
+    /// Splits the arguments of a command. ```flags``` & ```valued``` list the options it accepts, the global ones
+    /// being accepted by all commands. Values follow their option, as ```--format json``` or ```--format=json```
+    fn parse(args: &[String], flags: &[&str], valued: &[&str]) -> Result<Args> {
+        let mut parsed = Args::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') || arg == "-" {
+                parsed.positionals.push(arg.clone());
+                continue;
+            }
+
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+
+            if valued.contains(&name) {
+                let value = match value {
+                    Some(value) => value,
+                    None => args.next().cloned().ok_or_else(|| anyhow!("{} needs a value", name))?,
+                };
+                parsed.values.insert(name.to_string(), value);
+            } else if flags.contains(&name) || ["--offline", "-h", "--help"].contains(&name) {
+                parsed.flags.insert(name.to_string());
+            } else {
+                return Err(anyhow!("unknown option {}", name));
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    fn wants_help(&self) -> bool {
+        self.flag("-h") || self.flag("--help")
+    }
+
+    /// The project directory, the first positional argument or the current directory
+    fn dir(&self) -> PathBuf {
+        PathBuf::from(self.positionals.first().map_or(".", String::as_str))
+    }
+
+    /// Opens & parses the project in the directory
+    fn project(&self) -> Result<Project> {
+        let mut project = Project::new(self.dir())?;
+        project.offline(self.flag("--offline")).parse()?;
+
+        Ok(project)
+    }
+}
+
+/// Output formats of the stats command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Table,
+    Csv,
+}
+
+impl Format {
+    fn parse(format: Option<&str>) -> Result<Format> {
+        match format.unwrap_or("table") {
+            "json" => Ok(Format::Json),
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            other => Err(anyhow!("unknown format {}, expected json, table or csv", other)),
+        }
+    }
+}
+
+/// A file in the JSON output of ```stats --per-file```
+#[derive(Serialize)]
+struct FileRow<'a> {
+    path: &'a PathBuf,
+    lang: &'a str,
+    code: u64,
+}
+
+/// Renders rows as columns padded to their widest cell, numbers aligned right
+fn table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|cell| cell.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let render = |cells: Vec<String>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, width))| match i {
+                0 => format!("{:<width$}", cell, width = width),
+                _ => format!("{:>width$}", cell, width = width),
+            })
+            .collect();
+        cells.join("  ").trim_end().to_string()
+    };
+
+    let mut lines = vec![render(header.iter().map(|cell| cell.to_string()).collect())];
+    lines.extend(rows.iter().map(|row| render(row.clone())));
+
+    lines.join("\n")
+}
+
+fn lang_row(lang: &str, stats: &LangStats) -> Vec<String> {
+    vec![
+        lang.to_string(),
+        stats.files.to_string(),
+        stats.code.to_string(),
+        stats.comment.to_string(),
+        stats.blank.to_string(),
+    ]
+}
+
+fn stats_table(snapshot: &CodeStats, per_file: bool) -> String {
+    if per_file {
+        let rows: Vec<Vec<String>> = snapshot
+            .files
+            .iter()
+            .map(|(path, file)| vec![path.to_string_lossy().to_string(), file.lang.clone(), file.code.to_string()])
+            .collect();
+        return table(&["path", "language", "code"], &rows);
+    }
+
+    let mut rows: Vec<Vec<String>> = snapshot
+        .by_lines_desc()
+        .into_iter()
+        .map(|(lang, stats)| lang_row(lang, stats))
+        .collect();
+    rows.push(lang_row("Total", &snapshot.total()));
+
+    table(&["language", "files", "code", "comment", "blank"], &rows)
+}
+
+/// ```stats [--format json|table|csv] [--per-file] [dir]```
+fn stats(args: &[String]) -> Result<u8> {
+    let args = Args::parse(args, &["--per-file"], &["--format"])?;
+    if args.wants_help() {
+        println!("{}", USAGE);
+        return Ok(0);
+    }
+
+    let format = Format::parse(args.value("--format"))?;
+    let per_file = args.flag("--per-file");
+
+    let mut project = args.project()?;
+    let snapshot = project.code_snapshot()?;
+
+    match format {
+        Format::Json if per_file => {
+            let files: Vec<FileRow> = snapshot
+                .files
+                .iter()
+                .map(|(path, file)| FileRow {
+                    path,
+                    lang: &file.lang,
+                    code: file.code,
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&files)?);
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&snapshot.langs)?),
+        Format::Table => println!("{}", stats_table(&snapshot, per_file)),
+        Format::Csv => {
+            let granularity = match per_file {
+                true => CsvGranularity::Files,
+                false => CsvGranularity::Languages,
+            };
+            project.export_csv(io::stdout().lock(), granularity)?;
+        }
+    }
+
+    Ok(if snapshot.files.is_empty() { NOTHING_FOUND } else { 0 })
+}
+
+/// ```gitignore [--write] [--langs rust,node] [--merge-existing] [dir]```
+fn gitignore(args: &[String]) -> Result<u8> {
+    let args = Args::parse(args, &["--write", "--merge-existing"], &["--langs"])?;
+    if args.wants_help() {
+        println!("{}", USAGE);
+        return Ok(0);
+    }
+
+    let offline = args.flag("--offline");
+    let mut gitignore = match args.value("--langs") {
+        Some(langs) => {
+            let configs = Configs::shared();
+            let mut gitignore = Gitignore::default();
+            for lang in langs.split(',').map(str::trim).filter(|lang| !lang.is_empty()) {
+                let template = configs
+                    .resolve(lang, offline)
+                    .ok_or_else(|| anyhow!("no gitignore template for {}", lang))?;
+                gitignore.merge(&Gitignore::parse(&template.contents));
+            }
+            gitignore
+        }
+        None => args.project()?.gitignore(),
+    };
+
+    if gitignore.rules().next().is_none() {
+        eprintln!("project-parse: no gitignore template applies to {}", args.dir().display());
+        return Ok(NOTHING_FOUND);
+    }
+
+    let path = args.dir().join(".gitignore");
+    if args.flag("--merge-existing") {
+        if let Ok(existing) = fs::read_to_string(&path) {
+            let mut merged = Gitignore::parse(&existing);
+            merged.merge(&gitignore);
+            gitignore = merged;
+        }
+    }
+    gitignore.trailing_newline = true;
+
+    if args.flag("--write") {
+        fs::write(&path, gitignore.to_string())?;
+        eprintln!("wrote {} rules to {}", gitignore.rules().count(), path.display());
+    } else {
+        print!("{}", gitignore);
+    }
+
+    Ok(0)
+}
+
+/// Exit code of check-ignore errors, as git has it
+const CHECK_IGNORE_FATAL: u8 = 128;
+
+/// ```check-ignore [-v] [--dir dir] <path>...```
+fn check_ignore(args: &[String]) -> Result<u8> {
+    let args = Args::parse(args, &["-v"], &["--dir"])?;
+    if args.wants_help() {
+        println!("{}", USAGE);
+        return Ok(0);
+    }
+    if args.positionals.is_empty() {
+        return Err(anyhow!("no path specified"));
+    }
+
+    let mut project = Project::new(args.value("--dir").unwrap_or("."))?;
+    project.offline(args.flag("--offline")).parse()?;
+    project.use_project_gitignore(true)?;
+
+    let verbose = args.flag("-v");
+    let mut any_ignored = false;
+
+    for path in &args.positionals {
+        let answer = project
+            .is_ignored(path)
+            .ok_or_else(|| anyhow!("cannot check {}", path))?;
+        if answer.is_outside_root() {
+            return Err(anyhow!("{}: is outside the project directory", path));
+        }
+
+        any_ignored |= answer.is_ignored();
+        match (verbose, answer.rule()) {
+            // as git does, verbose output lists the paths a negated rule keeps too
+            (true, Some(rule)) => println!(
+                "{}:{}:{}\t{}",
+                answer.rule_source().unwrap_or_default(),
+                answer.rule_line().map(|line| line.to_string()).unwrap_or_default(),
+                rule,
+                path
+            ),
+            _ if answer.is_ignored() => println!("{}", path),
+            _ => {}
+        }
+    }
+
+    Ok(if any_ignored { 0 } else { NOTHING_FOUND })
+}
+
+/// What changed in the last scan, in a line
+fn change_summary(diff: &ProjectDiff) -> String {
+    let mut changes = vec![
+        format!("{} added", diff.stats.added_files.len()),
+        format!("{} removed", diff.stats.removed_files.len()),
+        format!("{} changed", diff.stats.changed_files.len()),
+    ];
+    changes.extend(diff.added_langs.iter().map(|lang| format!("+{}", lang)));
+    changes.extend(diff.removed_langs.iter().map(|lang| format!("-{}", lang)));
+
+    format!("last change: {}", changes.join(", "))
+}
+
+/// Redraws the stats, in place when writing to a terminal
+fn draw(dir: &Path, interval: Duration, snapshot: &CodeStats, diff: Option<&ProjectDiff>) {
+    if io::stdout().is_terminal() {
+        // clear the screen and move to its top
+        print!("\x1b[2J\x1b[H");
+    }
+
+    println!("{} every {:?}, Ctrl-C to stop\n", dir.display(), interval);
+    println!("{}", stats_table(snapshot, false));
+    if let Some(diff) = diff {
+        println!("\n{}", change_summary(diff));
+    }
+}
+
+/// ```watch [--interval seconds] [dir]```
+fn watch(args: &[String]) -> Result<u8> {
+    let args = Args::parse(args, &[], &["--interval"])?;
+    if args.wants_help() {
+        println!("{}", USAGE);
+        return Ok(0);
+    }
+
+    let interval = match args.value("--interval") {
+        Some(seconds) => seconds
+            .parse::<f64>()
+            .ok()
+            .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+            .map(Duration::from_secs_f64)
+            .ok_or_else(|| anyhow!("invalid interval {}, expected a number of seconds", seconds))?,
+        None => Duration::from_secs(2),
+    };
+
+    let mut project = args.project()?;
+    let snapshot = project.code_snapshot()?;
+    draw(&project.dir, interval, &snapshot, None);
+
+    // scans run in the background, the screen is only redrawn when something changed
+    let (sender, diffs) = mpsc::channel();
+    let _schedule = project.schedule(interval, move |diff| {
+        let _ = sender.send(diff);
+    });
+
+    for diff in diffs.iter().filter(|diff| !diff.is_empty()) {
+        draw(&project.dir, interval, &diff.snapshot, Some(&diff));
+    }
+
+    Ok(0)
+}
+
+/// ```tui [dir]```
+#[cfg(feature = "tui")]
+fn tui(args: &[String]) -> Result<u8> {
+    let args = Args::parse(args, &[], &[])?;
+    if args.wants_help() {
+        println!("{}", USAGE);
+        return Ok(0);
+    }
+
+    tui::run(args.project()?)?;
+
+    Ok(0)
+}
+
+#[cfg(not(feature = "tui"))]
+fn tui(_args: &[String]) -> Result<u8> {
+    Err(anyhow!("project-parse was built without the tui feature, rebuild it with --features tui"))
+}
+
+fn run(args: &[String]) -> Result<u8> {
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => {
+            eprintln!("{}", USAGE);
+            return Ok(FAILURE);
+        }
+    };
+
+    match command {
+        "stats" => stats(args),
+        "gitignore" => gitignore(args),
+        "watch" => watch(args),
+        "tui" => tui(args),
+        "check-ignore" => check_ignore(args).or_else(|e| {
+            eprintln!("fatal: {:#}", e);
+            Ok(CHECK_IGNORE_FATAL)
+        }),
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            Ok(0)
+        }
+        other => Err(anyhow!("unknown command {}, see project-parse --help", other)),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match run(&args) {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("project-parse: {:#}", e);
+            ExitCode::from(FAILURE)
+        }
+    }
+}
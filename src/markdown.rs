@@ -0,0 +1,95 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap, path::Path};
+
+use crate::languages;
+use crate::stats::{Count, StatsLang};
+
+/// Whether a file is Markdown, from its extension
+pub(crate) fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        let ext = ext.to_string_lossy().to_lowercase();
+        ext == "md" || ext == "markdown"
+    })
+}
+
+/// An opening or closing code fence: three or more backticks or tildes, returning the fence and its info string
+fn fence(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    let marker = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|c| *c == marker).count();
+    if len < 3 {
+        return None;
+    }
+
+    Some(line.split_at(len))
+}
+
+/// Language of the info string of a fence, such as ```rust,no_run``` or ```{.python}```
+fn fence_lang(info: &str) -> Option<StatsLang> {
+    let tag = info
+        .trim()
+        .trim_start_matches('{')
+        .trim_start_matches('.')
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '}')
+        .next()?;
+
+    languages::normalize(tag)
+}
+
+fn add_line(count: &mut Count, line: &str, comment: Option<&str>) {
+    let line = line.trim();
+    count.lines += 1;
+
+    if line.is_empty() {
+        count.blank += 1;
+    } else if comment.is_some_and(|prefix| line.starts_with(prefix)) {
+        count.comment += 1;
+    } else {
+        count.code += 1;
+    }
+}
+
+/// Line counts of a Markdown file with its fenced code blocks attributed to the languages they declare, e.g.
+/// ```rust``` blocks to Rust. The prose, the fences themselves, and blocks without a known language stay Markdown
+pub(crate) fn count_fenced(content: &str) -> Vec<(StatsLang, Count)> {
+    let mut counts: BTreeMap<StatsLang, Count> = BTreeMap::new();
+    // the fence that opened the current block and the language the block is counted as
+    let mut block: Option<(String, StatsLang)> = None;
+
+    for line in content.lines() {
+        match (&block, fence(line)) {
+            (None, Some((marker, info))) => {
+                let lang = fence_lang(info).unwrap_or(StatsLang::Markdown);
+                add_line(counts.entry(StatsLang::Markdown).or_default(), line, None);
+                block = Some((marker.to_string(), lang));
+            }
+            // closing fences are at least as long as the opening one, without an info string
+            (Some((open, _)), Some((marker, info)))
+                if marker.starts_with(open.as_str()) && info.trim().is_empty() =>
+            {
+                add_line(counts.entry(StatsLang::Markdown).or_default(), line, None);
+                block = None;
+            }
+            (Some((_, lang)), _) => {
+                let comment = (*lang != StatsLang::Markdown).then(|| lang.line_comment());
+                add_line(counts.entry(lang.clone()).or_default(), line, comment);
+            }
+            (None, None) => add_line(counts.entry(StatsLang::Markdown).or_default(), line, None),
+        }
+    }
+
+    counts.into_iter().collect()
+}
@@ -0,0 +1,63 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+/// Average length of the non blank lines above which a script or stylesheet is taken as minified
+const MINIFIED_AVG_LINE_LEN: usize = 300;
+
+/// Markers only the runtime of bundlers writes
+const BUNDLE_MARKERS: [&str; 4] = [
+    "__webpack_require__",
+    "webpackBootstrap",
+    "parcelRequire",
+    "System.register(",
+];
+
+/// Whether a file is a script or stylesheet, the only files minified or bundled
+pub(crate) fn is_candidate(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        let ext = ext.to_string_lossy().to_lowercase();
+        matches!(ext.as_str(), "js" | "mjs" | "cjs" | "css")
+    })
+}
+
+/// Whether the name of a file says it is minified or bundled, as in ```app.min.js``` or ```vendor.bundle.js```
+fn is_minified_name(path: &Path) -> bool {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_lowercase(),
+        None => return false,
+    };
+
+    [".min.", "-min.", ".bundle.", "-bundle.", ".chunk."]
+        .iter()
+        .any(|marker| name.contains(marker))
+}
+
+/// Whether a script or stylesheet is minified or a compiled bundle, from its name, the average length of its lines,
+/// or the runtime of the bundler that wrote it
+pub(crate) fn is_minified(path: &Path, content: &str) -> bool {
+    if is_minified_name(path) {
+        return true;
+    }
+
+    let lines: Vec<&str> = content.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.is_empty() {
+        return false;
+    }
+
+    let avg_len = lines.iter().map(|line| line.len()).sum::<usize>() / lines.len();
+
+    avg_len > MINIFIED_AVG_LINE_LEN || BUNDLE_MARKERS.iter().any(|marker| content.contains(marker))
+}
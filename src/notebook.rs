@@ -0,0 +1,79 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde_json::Value;
+use std::path::Path;
+
+use crate::languages;
+use crate::stats::{Count, StatsLang};
+
+/// Whether a file is a Jupyter notebook, from its extension
+pub(crate) fn is_notebook(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+/// The kernel language of a notebook, as named in the code stats. Notebooks that do not say are taken as Python,
+/// the default kernel
+fn kernel_lang(notebook: &Value) -> StatsLang {
+    let metadata = &notebook["metadata"];
+    let name = metadata["kernelspec"]["language"]
+        .as_str()
+        .or_else(|| metadata["language_info"]["name"].as_str())
+        .unwrap_or("python");
+
+    languages::normalize(name).unwrap_or_else(|| StatsLang::from(name))
+}
+
+/// The text of a cell, whose source is either a list of lines or a single string
+fn cell_source(cell: &Value) -> String {
+    match &cell["source"] {
+        Value::String(source) => source.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Line counts of a notebook, attributed to its kernel language: code cells are counted as code, their line comments
+/// as comments, and markdown cells as comments, being the notebook's documentation. Raw cells & outputs are left out
+/// None when the content is not a notebook
+pub(crate) fn count(content: &str) -> Option<(StatsLang, Count)> {
+    let notebook: Value = serde_json::from_str(content).ok()?;
+    let cells = notebook["cells"].as_array()?;
+
+    let lang = kernel_lang(&notebook);
+    let prefix = lang.line_comment();
+    let mut count = Count::default();
+
+    for cell in cells {
+        let is_code = match cell["cell_type"].as_str() {
+            Some("code") => true,
+            Some("markdown") => false,
+            _ => continue,
+        };
+
+        for line in cell_source(cell).lines().map(str::trim) {
+            count.lines += 1;
+            if line.is_empty() {
+                count.blank += 1;
+            } else if !is_code || line.starts_with(prefix) {
+                count.comment += 1;
+            } else {
+                count.code += 1;
+            }
+        }
+    }
+
+    Some((lang, count))
+}
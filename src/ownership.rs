@@ -0,0 +1,155 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{anyhow, Result};
+use git2::Repository;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::tree::{FileTree, TreeNode};
+
+/// Lines of an author, in [Ownership]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthorLines {
+    /// the author name
+    pub name: String,
+    /// the author email, which tells authors apart
+    pub email: String,
+    /// number of lines last changed by the author
+    pub lines: usize,
+}
+
+/// Authorship of the committed lines of a file, in [Ownership]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileOwnership {
+    /// path relative to the project directory
+    pub path: PathBuf,
+    /// language of code files, as named by the code stats
+    pub lang: Option<String>,
+    /// number of committed lines
+    pub lines: usize,
+    /// the authors of the lines, most lines first
+    pub authors: Vec<AuthorLines>,
+}
+
+/// Who last changed the lines surviving in a set of files, per file, per language & overall, returned by
+/// [method.ownership]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Ownership {
+    /// the files, in path order. Files git does not track are left out
+    pub files: Vec<FileOwnership>,
+    /// the authors of all the lines, most lines first
+    pub authors: Vec<AuthorLines>,
+    /// the authors of the lines of each language, most lines first, keyed by language name
+    pub langs: BTreeMap<String, Vec<AuthorLines>>,
+}
+
+impl Ownership {
+    /// The author of most lines, None when no line is committed
+    pub fn owner(&self) -> Option<&AuthorLines> {
+        self.authors.first()
+    }
+}
+
+/// Lines per author, keyed by email
+type Tally = BTreeMap<String, AuthorLines>;
+
+fn add(tally: &mut Tally, author: &AuthorLines) {
+    tally
+        .entry(author.email.clone())
+        .or_insert_with(|| AuthorLines {
+            lines: 0,
+            ..author.clone()
+        })
+        .lines += author.lines;
+}
+
+/// Most lines first, then by name
+fn ranked(tally: Tally) -> Vec<AuthorLines> {
+    let mut authors: Vec<AuthorLines> = tally.into_values().collect();
+    authors.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.name.cmp(&b.name)));
+    authors
+}
+
+/// The non ignored files at a path of the tree, every file within a directory, or matching a glob pattern, itself
+/// matching files or directories
+pub(crate) fn select<'a>(tree: &'a FileTree, path_or_glob: &str, case_insensitive: bool) -> Result<Vec<&'a TreeNode>> {
+    let path = path_or_glob.trim_start_matches("./").trim_end_matches('/');
+    let roots: Vec<PathBuf> = match tree.get(path) {
+        Some(node) => vec![node.path.clone()],
+        None => tree.glob(path_or_glob, case_insensitive)?,
+    };
+
+    Ok(tree
+        .iter()
+        .filter(|node| !node.is_dir && !node.is_ignored)
+        .filter(|node| roots.iter().any(|root| node.path.starts_with(root)))
+        .collect())
+}
+
+/// Blames the files at HEAD, in the repository holding the project directory
+pub(crate) fn ownership(dir: &Path, files: &[&TreeNode]) -> Result<Ownership> {
+    let repo = Repository::discover(dir)?;
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir.canonicalize()?,
+        None => return Err(anyhow!("{:?} is a bare repository, without files to blame", dir)),
+    };
+
+    let mut ownership = Ownership::default();
+    let mut authors = Tally::new();
+    let mut langs: BTreeMap<String, Tally> = BTreeMap::new();
+
+    for node in files {
+        let repo_path = match dir.join(&node.path).strip_prefix(&workdir) {
+            Ok(repo_path) => repo_path.to_path_buf(),
+            Err(_) => continue,
+        };
+        // files that are not committed cannot be blamed
+        let blame = match repo.blame_file(&repo_path, None) {
+            Ok(blame) => blame,
+            Err(_) => continue,
+        };
+
+        let mut file_authors = Tally::new();
+        for hunk in blame.iter() {
+            let signature = hunk.final_signature();
+            let author = AuthorLines {
+                name: signature.name().unwrap_or_default().to_string(),
+                email: signature.email().unwrap_or_default().to_string(),
+                lines: hunk.lines_in_hunk(),
+            };
+            add(&mut file_authors, &author);
+            add(&mut authors, &author);
+            if let Some(lang) = &node.lang {
+                add(langs.entry(lang.clone()).or_default(), &author);
+            }
+        }
+
+        ownership.files.push(FileOwnership {
+            path: node.path.clone(),
+            lang: node.lang.clone(),
+            lines: file_authors.values().map(|author| author.lines).sum(),
+            authors: ranked(file_authors),
+        });
+    }
+
+    ownership.files.sort_by(|a, b| a.path.cmp(&b.path));
+    ownership.authors = ranked(authors);
+    ownership.langs = langs.into_iter().map(|(lang, tally)| (lang, ranked(tally))).collect();
+
+    Ok(ownership)
+}
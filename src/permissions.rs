@@ -0,0 +1,95 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::code;
+use crate::ruleset::RuleSet;
+use crate::stats::StatsLang;
+use crate::vfs::Vfs;
+
+/// Why the permissions of a file are unexpected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum PermissionIssueKind {
+    /// a script starting with a ```#!``` shebang that cannot be run, no one having the executable bit
+    NotExecutable,
+    /// a source file without a shebang that has the executable bit set
+    ExecutableSource,
+    /// anyone can write to the file
+    WorldWritable,
+}
+
+/// A file whose permissions are unexpected, returned by [method.permission_audit]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PermissionIssue {
+    /// path relative to the project directory
+    pub path: PathBuf,
+    /// the permission bits, e.g. **0o644**
+    pub mode: u32,
+    /// what is unexpected about them
+    pub kind: PermissionIssueKind,
+}
+
+const EXECUTABLE: u32 = 0o111;
+const WORLD_WRITABLE: u32 = 0o002;
+
+/// Files with unexpected permissions, the ignored entries and .git aside. A file may have several issues. Empty when
+/// the permissions are unknown, as on other platforms than unix or for projects held in memory
+pub(crate) fn audit(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<Vec<PermissionIssue>> {
+    let entries = vfs.walk(&mut |e| {
+        e.depth == 0 || !(e.file_name() == ".git" || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)))
+    })?;
+    let mut issues = vec![];
+
+    for e in entries.iter().filter(|e| e.is_file) {
+        let rel_path = match e.path.strip_prefix(vfs.root()) {
+            Ok(rel_path) => rel_path,
+            Err(_) => continue,
+        };
+        let mode = match vfs.mode(rel_path) {
+            Some(mode) => mode,
+            None => continue,
+        };
+        let mut issue = |kind| {
+            issues.push(PermissionIssue {
+                path: rel_path.to_path_buf(),
+                mode,
+                kind,
+            })
+        };
+
+        // binary files cannot be read as text, and have no shebang
+        let has_shebang = vfs
+            .read_to_string(rel_path)
+            .is_ok_and(|content| content.starts_with("#!"));
+        let is_executable = mode & EXECUTABLE != 0;
+
+        if has_shebang && !is_executable {
+            issue(PermissionIssueKind::NotExecutable);
+        } else if !has_shebang
+            && is_executable
+            && StatsLang::of_path(&e.path.to_string_lossy()) != StatsLang::Unrecognized
+        {
+            issue(PermissionIssueKind::ExecutableSource);
+        }
+
+        if mode & WORLD_WRITABLE != 0 {
+            issue(PermissionIssueKind::WorldWritable);
+        }
+    }
+
+    Ok(issues)
+}
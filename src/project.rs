@@ -13,19 +13,57 @@
 // limitations under the License.
 
 use anyhow::{anyhow, Result};
-use loc::Count;
 use regex::Regex;
 use std::{
-    collections::HashMap,
-    fs::read_to_string,
-    path::{ PathBuf},
+    cmp::Reverse,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
 };
+use serde::Serialize;
 use thiserror::Error;
-use walkdir::{ WalkDir};
 
-use super::code;
+use super::age::{self, AgeReport};
+use super::analysis::Analysis;
+use super::assets::{AssetStats, FileCategory};
+use super::audit::{self, HeaderAudit, HeaderChange};
+use super::badges::{self, Badge};
+use super::code::{self, CountOptions, ExtStats, Stats};
+use super::codeowners::{self, CodeOwners};
 use super::detector;
-use super::ruleset;
+use super::docs::{self, Changelog, Readme};
+use super::events::Events;
+use super::hooks::{self, GitHook};
+use super::export::{self, CsvGranularity};
+use super::git::{self, Submodule};
+use super::gitignore::Gitignore;
+use super::health::{self, Health, HealthConfig, Signals};
+use super::hotspots::{self, Hotspots};
+use super::index::{self, FileIndex, IndexedFs};
+use super::encoding::{self, EncodingReport};
+use super::hygiene::HygieneReport;
+use super::indent::{self, IndentationProfile};
+use super::infra::{self, InfraComponent};
+use super::lengths::LineLengthStats;
+use super::integrity::{self, IntegrityIssue};
+use super::permissions::{self, PermissionIssue};
+use super::stats::{self, CodeStats, WalkStats};
+use super::license;
+use super::ruleset::{self, Dialect, DiagnosticKind, RuleOrigin, RuleSet};
+use super::schedule::{self, ProjectDiff, Schedule};
+use super::search::{self, SearchMatch};
+#[cfg(feature = "git")]
+use super::ownership::{self, Ownership};
+#[cfg(feature = "git")]
+use super::history::{self, ChangeHotspot};
+#[cfg(feature = "sqlite")]
+use super::sqlite;
+use super::templates::Configs;
+use super::templating::EmbeddedLang;
+use super::schema::{self, SchemaReport};
+use super::testing::{self, TestFramework};
+use super::tree::{self, FileTree};
+use super::vfs::{self, DiskFs, MemFs, Vfs};
 
 /// Custom Error for Project
 #[derive(Error, Debug)]
@@ -33,91 +71,1699 @@ pub enum ProjectError {
     /// the NotFound Error occurs when Project is initialized using [method.new] and the string passed points to a directory that doesn't exist
     #[error("Directory {0} Cannot be found!")]
     NotFound(String),
+    /// the TemplateUnavailable Error occurs in offline mode when no cached or bundled gitignore template exists for a detected language
+    #[error("No {0} gitignore template is available offline")]
+    TemplateUnavailable(String),
 }
 
 /// Project struct
-#[derive(Debug)]
+///
+/// A Project is Clone, Send & Sync, so that a parsed project can be kept in the shared state of a service behind an
+/// ```Arc``` and read from any thread. Methods taking ```&self```, such as [method.is_ignored], only read it and can
+/// run concurrently; those taking ```&mut self``` need exclusive access, through a ```Mutex``` or ```RwLock``` when the
+/// project is shared. Clones share the project files and the compiled rules, changing one leaves the others as they are
+/// ```no_run
+/// let project = Arc::new(project);
+/// let handler = {
+///     let project = Arc::clone(&project);
+///     std::thread::spawn(move || project.is_ignored("target").map(|answer| answer.is_ignored()))
+/// };
+/// ```
+#[derive(Debug, Clone)]
 pub struct Project {
     /// project directory path
     pub dir: PathBuf,
-    /// option that holds detected project languages
+    /// option that holds detected project languages, None when none was detected
     pub project_langs: Option<Vec<String>>,
-    /// option indicating if project directory is also a git directory
+    /// option that holds detected build & development tools (cmake, gradle, terraform...), named after their gitignore templates
+    pub project_tools: Option<Vec<String>>,
+    /// option holding the build systems among the detected tools, None when none was detected
+    pub build_systems: Option<Vec<BuildSystem>>,
+    /// option holding the refined classification of the detected languages, read from their manifest files. A crate with both src/main.rs and src/lib.rs is ```[RustBinary, RustLibrary]```. Projects without any code are [ProjectKind::Empty] or [ProjectKind::ConfigOnly]
+    pub project_kinds: Option<Vec<ProjectKind>>,
+    /// option indicating if project directory is a git checkout, a linked worktree or a bare repository
     pub is_git: Option<bool>,
     /// option populated with generic git content based on languages detected
     pub generic_gitignore: Option<Vec<String>>,
+    /// rules added by [method.set_gitignore], in precedence order, lowest first
+    pub custom_rules: Vec<CustomRule>,
     /// set of regex rules used to match files & directories to determine if they can be ignored
     pub gitignore_ruleset: Option<ruleset::RuleSet>,
+    /// the name & original content each part of ```generic_gitignore``` comes from, in the same order
+    gitignore_sources: Vec<(String, String)>,
+    /// the source & line, starting at 1, of each line of the ruleset, None for the lines joining the parts
+    rule_lines: Vec<Option<(Arc<str>, usize)>>,
+    /// option holding the editors & IDEs whose artifacts were found by [method.add_editor_ignores], named after their gitignore templates
+    pub editors: Option<Vec<String>>,
+    /// option holding the operating systems whose junk files were ignored by [method.add_os_ignores], named after their gitignore templates
+    pub os_ignores: Option<Vec<String>>,
+    /// option holding the git hooks configured with pre-commit, husky or lefthook, or installed in .git/hooks
+    pub git_hooks: Option<Vec<GitHook>>,
+    /// option holding local environment/toolchain directories (.venv, .tox, .gradle...) found in the project, at any
+    /// depth, relative to the project directory, e.g. ```backend/.venv```
+    pub env_dirs: Option<Vec<String>>,
+    /// whether detected environment directories are automatically added to the gitignore rules. Defaults to true
+    pub ignore_env_dirs: bool,
+    /// whether the content of git submodules is walked for stats, trees & content listings. Defaults to false, leaving
+    /// submodules out as code of other repositories
+    pub include_submodules: bool,
+    /// option holding the directories with a git repository of their own, other than submodules, found by [method.parse]
+    pub nested_repos: Option<Vec<PathBuf>>,
+    /// how nested git repositories are treated. Defaults to [NestedRepos::Separate]
+    pub nested_repo_mode: NestedRepos,
+    /// whether gitignore rules match case insensitively. Defaults to the repository's `core.ignoreCase` setting
+    pub ignore_case: bool,
+    /// minimum number of marker files (Cargo.toml, Cargo.lock, src/main.rs...) a language needs for [method.parse] to report it. Defaults to 1
+    pub min_evidence: usize,
+    /// how languages with the same number of markers are ordered. Languages with more markers always come first
+    pub tie_break: TieBreak,
+    /// when true, no network access is made and only locally cached or bundled gitignore templates are used
+    pub offline: bool,
+    /// the gitignore templates languages, tools & editors resolve to, [Configs::shared] unless set with [method.configs]
+    configs: Arc<Configs>,
+    /// non fatal problems met while parsing, such as falling back to bundled gitignore templates
+    pub warnings: Vec<String>,
     /// option populated with parsed code statistics for all code files in project directory
-    pub code_stats: Option<HashMap<String, loc::Count>>,
+    pub code_stats: Option<Stats>,
+    /// whether [method.get_code_stats] also breaks the lines of each language down by file extension. Defaults to false
+    pub ext_breakdown: bool,
+    /// option populated by [method.get_code_stats], when ```ext_breakdown``` is set, with the line counts of each
+    /// language keyed by file extension, so that ```.test.ts``` & ```.ts``` or ```.yml``` & ```.yaml``` can be told apart
+    pub ext_stats: Option<ExtStats>,
+    /// whether [method.get_code_stats] counts the fenced code blocks of Markdown files as the languages they declare
+    /// rather than as Markdown. Defaults to false
+    pub fenced_code: bool,
+    /// whether [method.get_code_stats] counts the inline ```<script>``` & ```<style>``` content of HTML pages as
+    /// JavaScript & CSS rather than as HTML. Defaults to false
+    pub split_html: bool,
+    /// templating formats, such as ERB or PHP mixed with HTML, whose host & embedded languages [method.get_code_stats]
+    /// counts apart. Defaults to [EmbeddedLang::defaults]
+    pub embedded_langs: Vec<EmbeddedLang>,
+    /// option populated by [method.get_code_stats] with the number & size of non-code files (images, fonts, data, docs, configs, lockfiles...) per category
+    pub asset_stats: Option<AssetStats>,
+    /// option holding files left out of the code statistics because their paths are not valid UTF-8
+    pub skipped_paths: Option<Vec<PathBuf>>,
+    /// option holding the Git LFS pointers, checked out in place of files whose content was not fetched, that
+    /// [method.get_code_stats] left out of the line counts
+    pub lfs_pointers: Option<Vec<PathBuf>>,
+    /// whether [method.get_code_stats] counts minified scripts & stylesheets and compiled bundles. Defaults to false,
+    /// as a single bundle can dwarf the source it was built from
+    pub count_minified: bool,
+    /// option holding the minified scripts & stylesheets and compiled bundles [method.get_code_stats] left out of the
+    /// line counts
+    pub minified_files: Option<Vec<PathBuf>>,
+    /// how [method.get_code_stats] treats files marked as generated. Defaults to [GeneratedCode::Count], leaving the
+    /// totals as they were
+    pub generated_code: GeneratedCode,
+    /// option holding the line counts of the generated files, when [GeneratedCode::Separate] counts them apart
+    pub generated_stats: Option<Stats>,
+    /// option holding the files [method.get_code_stats] found marked as generated and counted apart or left out
+    pub generated_files: Option<Vec<PathBuf>>,
+    /// counts of the entries walked & skipped by [method.get_code_stats]
+    pub walk_stats: Option<WalkStats>,
+    /// whether [method.get_code_stats] records the line endings & whitespace of the files it counts. Defaults to false,
+    /// as the whole content of every file is then scanned once more
+    pub line_hygiene: bool,
+    /// option holding the line endings & whitespace recorded by [method.get_code_stats] when ```line_hygiene``` is set
+    pub hygiene: Option<HygieneReport>,
+    /// the length above which [method.get_code_stats] counts lines as too long, the line lengths being recorded only
+    /// when it is set. Defaults to None
+    pub line_limit: Option<usize>,
+    /// option holding the line lengths per language recorded by [method.get_code_stats] when ```line_limit``` is set
+    pub line_lengths: Option<LineLengthStats>,
+    /// option holding the snapshot of the project files taken by [method.file_tree]
+    pub tree: Option<FileTree>,
+    /// option holding the entries captured by [method.index], which the other methods read instead of walking again
+    pub index: Option<Arc<FileIndex>>,
+    /// option holding when [method.parse] last started, which [method.is_stale] compares the project files with
+    pub last_parsed: Option<SystemTime>,
+    /// the steps of the last parse, which [method.reparse] performs again
+    parse_options: Option<ParseOptions>,
+    events: Events,
+    /// where the project files are read from, on disk or in memory, through the index once there is one
+    vfs: Arc<dyn Vfs>,
+    /// the project files without the index, while there is one
+    unindexed: Option<Arc<dyn Vfs>>,
+}
+
+/// Options used by [method.parse_with] to pick which parsing steps are performed
+/// The default options perform the same steps as [method.parse]
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// detect the main project language(s) and tools
+    pub detect_langs: bool,
+    /// fetch generic gitignore templates for the detected languages
+    pub fetch_templates: bool,
+    /// detect local environment directories such as **.venv/** to be ignored
+    pub detect_env_dirs: bool,
+    /// detect git hooks configured with pre-commit, husky or lefthook
+    pub detect_hooks: bool,
+    /// look for git repositories nested in the project, see [NestedRepos]
+    pub detect_nested_repos: bool,
+    /// compile the gitignore rules used by [method.is_ignored], [method.get_content] and [method.get_code_stats]
+    pub build_ruleset: bool,
+    /// merge the project's own .gitignore into the rules. Only applies when ```build_ruleset``` is true
+    pub use_project_gitignore: bool,
+    /// generate code stats once parsing is done
+    pub code_stats: bool,
+    /// reuse the languages detected before in the same unchanged directory, kept by the project configs. See
+    /// [Configs::clear_detection_cache]
+    pub cache_detection: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            detect_langs: true,
+            fetch_templates: true,
+            detect_env_dirs: true,
+            detect_hooks: true,
+            detect_nested_repos: true,
+            build_ruleset: true,
+            use_project_gitignore: false,
+            code_stats: false,
+            cache_detection: true,
+        }
+    }
+}
+
+/// Which operating systems' junk files (.DS_Store, Thumbs.db, *~...) [method.add_os_ignores] should ignore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OsIgnores {
+    /// only the OS the crate is running on
+    Host,
+    /// macOS, Windows and Linux
+    All,
+}
+
+/// How [method.set_gitignore] combines new rules with the existing gitignore. Later rules take precedence over earlier
+/// ones, as in git
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// after the existing rules, overriding them
+    Append,
+    /// before the existing rules, which override them
+    Prepend,
+    /// instead of the existing rules
+    Replace,
+    /// after the existing rules, leaving out those already present and keeping comments & order
+    MergeDeduped,
+}
+
+/// A rule added by [method.set_gitignore], kept in ```custom_rules```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomRule {
+    /// the pattern, as written
+    pub rule: String,
+    /// how it was added
+    pub strategy: MergeStrategy,
+}
+
+/// Source of the rules added by [method.set_gitignore], as [IsIgnored::rule_source] names it
+pub const CUSTOM_RULES: &str = "custom";
+/// Source of the rules ignoring the environment directories, see [method.auto_ignore_env_dirs]
+pub const ENV_DIR_RULES: &str = "env-dirs";
+/// Source of the rules leaving submodules out, see ```include_submodules```
+pub const SUBMODULE_RULES: &str = "submodules";
+/// Source of the rules leaving nested repositories out, see [NestedRepos::Separate]
+pub const NESTED_REPO_RULES: &str = "nested-repos";
+
+/// The line, starting at 1, of ```original``` each line of ```part``` was taken from, None for blank lines. A part
+/// holds the lines of its source in order, some possibly left out when merged
+fn source_lines(part: &str, original: &str) -> Vec<Option<usize>> {
+    let original = ruleset::normalize_content(original);
+    let original: Vec<&str> = original.split('\n').map(str::trim).collect();
+    let mut next = 0;
+
+    part.split('\n')
+        .map(|line| {
+            let line = line.trim();
+            if line.is_empty() {
+                return None;
+            }
+            let found = original[next..].iter().position(|original| *original == line)?;
+            next += found + 1;
+            Some(next)
+        })
+        .collect()
+}
+
+/// How git repositories nested in the project, other than submodules, are treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NestedRepos {
+    /// left out of the project walk, each being a project of its own returned by [method.nested_projects]
+    #[default]
+    Separate,
+    /// walked like any other directory, under the rules of the project
+    Merge,
+}
+
+/// How [method.get_code_stats] treats files whose first lines are a generator header, such as ```@generated```, Go's
+/// ```Code generated ... DO NOT EDIT.``` or protoc's, to keep the count of human-written lines honest. Only comment
+/// lines of the known header forms mark a file, not any mention of "do not edit"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GeneratedCode {
+    /// counted with the rest of the code, as before generated files were told apart
+    #[default]
+    Count,
+    /// counted apart, in ```generated_stats```, and left out of the code stats totals
+    Separate,
+    /// left out of the line counts
+    Skip,
+}
+
+/// How [method.parse] orders languages that were detected from the same number of marker files
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// the order of the built in detectors, which is roughly alphabetical
+    #[default]
+    DetectorOrder,
+    /// alphabetical order of the language names
+    Alphabetical,
+    /// the listed languages first, in the order given, then the rest in detector order
+    Preferred(Vec<String>),
+}
+
+/// A build system driving the project, told from its files and kept in ```build_systems```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum BuildSystem {
+    /// CMakeLists.txt
+    CMake,
+    /// build.gradle, settings.gradle or the gradle wrapper
+    Gradle,
+    /// a WORKSPACE, MODULE.bazel or BUILD.bazel file, or a .bazelrc
+    Bazel,
+    /// a .buckconfig or a BUCK file
+    Buck,
+    /// a flake.nix, default.nix or shell.nix
+    Nix,
+}
+
+/// Refined project classification, read from the contents of a detected language's manifest file
+/// (package.json, Cargo.toml...), or from the files of a project without any code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ProjectKind {
+    /// package.json publishing an entry point (```main```, ```module``` or ```exports```) and not marked private
+    NodeLibrary,
+    /// any other package.json
+    NodeApp,
+    /// package.json depending on electron
+    ElectronApp,
+    /// package.json depending on react
+    ReactApp,
+    /// Cargo.toml with a ```[[bin]]``` target or a src/main.rs
+    RustBinary,
+    /// Cargo.toml with a ```[lib]``` target or a src/lib.rs
+    RustLibrary,
+    /// Cargo.toml with a ```[workspace]``` section
+    RustWorkspace,
+    /// an Xcode project or workspace, or a Podfile
+    XcodeProject,
+    /// pubspec.yaml along with android/ & ios/ directories
+    FlutterApp,
+    /// no files at all, hidden ones aside
+    Empty,
+    /// no code, only docs, data, configs & other files, with the categories of the files found. Needs the loc feature
+    ConfigOnly(Vec<FileCategory>),
+}
+
+/// What decided an [IsIgnored] answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum IgnoreSource {
+    /// no rules were built, nothing is ignored
+    NoRuleset,
+    /// no rule matched the path, or it lies outside the project directory, so it is not ignored
+    Default,
+    /// a rule matched the path, ignoring it or, when negated, keeping it
+    Rule,
 }
 
 /// IsIgnored Struct. Returned by the [method.is_ignored] Project implementation
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct IsIgnored {
     exists: bool,
     is_dir: bool,
     is_ignored: bool,
+    path: PathBuf,
+    outside_root: bool,
+    source: IgnoreSource,
+    rule: Option<String>,
+    rule_source: Option<String>,
+    rule_line: Option<usize>,
+}
+
+impl IsIgnored {
+    /// Whether the path exists within the project
+    pub fn exists(&self) -> bool {
+        self.exists
+    }
+
+    /// Whether the path is a directory. Paths that do not exist are taken for files when they have an extension
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Whether the path is ignored
+    pub fn is_ignored(&self) -> bool {
+        self.is_ignored
+    }
+
+    /// The absolute path evaluated, relative paths being joined to the project directory and ```.``` & ```..```
+    /// resolved
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the path lies outside the project directory, where the rules do not apply
+    pub fn is_outside_root(&self) -> bool {
+        self.outside_root
+    }
+
+    /// What decided the answer
+    pub fn source(&self) -> IgnoreSource {
+        self.source
+    }
+
+    /// Whether the answer comes from a rule rather than from the default of ignoring nothing
+    pub fn is_from_rule(&self) -> bool {
+        self.source == IgnoreSource::Rule
+    }
+
+    /// The line of the rule that decided, as written, e.g. ```target/``` or ```!keep.log```
+    pub fn rule(&self) -> Option<&str> {
+        self.rule.as_deref()
+    }
+
+    /// Where the rule that decided comes from: the template file, e.g. ```Rust.gitignore```, ```.gitignore``` for the
+    /// project's own, or one of [CUSTOM_RULES], [ENV_DIR_RULES], [SUBMODULE_RULES] & [NESTED_REPO_RULES]
+    pub fn rule_source(&self) -> Option<&str> {
+        self.rule_source.as_deref()
+    }
+
+    /// The line of the rule that decided within its [IsIgnored::rule_source], starting at 1
+    pub fn rule_line(&self) -> Option<usize> {
+        self.rule_line
+    }
+}
+
+impl TryFrom<&Path> for Project {
+    type Error = anyhow::Error;
+
+    fn try_from(dir_path: &Path) -> Result<Project> {
+        Project::new(dir_path)
+    }
+}
+
+impl TryFrom<PathBuf> for Project {
+    type Error = anyhow::Error;
+
+    fn try_from(dir_path: PathBuf) -> Result<Project> {
+        Project::new(dir_path)
+    }
 }
 
-impl Project {
-    // create new project
-    /// Initializes the Project struct by taking a project directory
+impl Project {
+    // create new project
+    /// Initializes the Project struct by taking a project directory, as a ```&str```, ```Path``` or ```PathBuf```
+    /// The directory is canonicalized, so that relative paths and symlinks resolve to the same ```dir```
+    /// ```no_run
+    /// let dir = "/my/project/directory";
+    /// //Needs to be a mutable variable for other methods to use and update Project
+    /// let mut project = project::Project::new(dir)?;
+    /// // Adding a file to the generic gitignore
+    /// ```
+    pub fn new<P: AsRef<Path>>(dir_path: P) -> Result<Project> {
+        let dir_path = dir_path.as_ref();
+
+        // check that dir exists
+        if !dir_path.exists() {
+            return Err(anyhow!(ProjectError::NotFound(
+                dir_path.to_string_lossy().to_string()
+            )));
+        }
+        let dir_path = dir_path.canonicalize()?;
+        let vfs = Arc::new(DiskFs::new(&dir_path));
+
+        Project::with_vfs(dir_path, vfs)
+    }
+
+    // the parts of initialization shared by projects on disk and in memory
+    fn with_vfs(dir: PathBuf, vfs: Arc<dyn Vfs>) -> Result<Project> {
+        //init
+        let mut project = Project {
+            dir,
+            project_langs: None,
+            project_tools: None,
+            build_systems: None,
+            project_kinds: None,
+
+            is_git: None,
+            generic_gitignore: None,
+            custom_rules: vec![],
+            gitignore_ruleset: None,
+            gitignore_sources: vec![],
+            rule_lines: vec![],
+            editors: None,
+            os_ignores: None,
+            git_hooks: None,
+            env_dirs: None,
+            ignore_env_dirs: true,
+            include_submodules: false,
+            nested_repos: None,
+            nested_repo_mode: NestedRepos::default(),
+            ignore_case: false,
+            min_evidence: 1,
+            tie_break: TieBreak::default(),
+            offline: false,
+            configs: Configs::shared(),
+            warnings: vec![],
+
+            code_stats: None,
+            ext_breakdown: false,
+            ext_stats: None,
+            fenced_code: false,
+            split_html: false,
+            embedded_langs: EmbeddedLang::defaults(),
+            asset_stats: None,
+            skipped_paths: None,
+            lfs_pointers: None,
+            count_minified: false,
+            minified_files: None,
+            generated_code: GeneratedCode::default(),
+            generated_stats: None,
+            generated_files: None,
+            walk_stats: None,
+            line_hygiene: false,
+            hygiene: None,
+            line_limit: None,
+            line_lengths: None,
+            tree: None,
+            index: None,
+            last_parsed: None,
+            parse_options: None,
+            events: Events::default(),
+            vfs,
+            unindexed: None,
+        };
+
+        project.is_git()?;
+        project.git_ignore_case()?;
+
+        Ok(project)
+    }
+
+    /// Initializes a Project held in memory from ```(path, content)``` pairs, without touching the filesystem
+    /// Paths are relative to the project directory, and those ending with a slash are empty directories
+    /// Handy to unit test code using the crate, or to parse a project listed by an API
+    /// Code stats of in-memory projects do not recognize comments, comment lines count as code
+    /// ```no_run
+    /// let mut project = project::Project::from_entries([
+    ///     ("Cargo.toml", "[package]\nname = \"app\""),
+    ///     ("src/main.rs", "fn main() {}"),
+    /// ])?;
+    /// project.parse()?;
+    /// ```
+    pub fn from_entries<I, P, C>(entries: I) -> Result<Project>
+    where
+        I: IntoIterator<Item = (P, C)>,
+        P: AsRef<str>,
+        C: Into<String>,
+    {
+        let dir = PathBuf::from("/");
+        let mut vfs = MemFs::new(&dir);
+        for (path, content) in entries {
+            vfs.add(path.as_ref(), content.into());
+        }
+
+        Project::with_vfs(dir, Arc::new(vfs))
+    }
+
+    /// Turns offline mode on or off. In offline mode the crate never touches the network:
+    /// gitignore templates are read from the local cache or the bundled templates, and parsing fails with [ProjectError::TemplateUnavailable] if a detected language has none
+    /// ```no_run
+    /// project.offline(true).parse()?;
+    /// ```
+    pub fn offline(&mut self, offline: bool) -> &mut Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Sets the gitignore templates the project resolves its languages, tools & editors against, instead of the
+    /// [Configs::shared] ones. Projects with different configs can be parsed concurrently
+    /// ```no_run
+    /// let configs = Arc::new(Configs::new());
+    /// project.configs(configs.clone()).parse()?;
+    /// ```
+    pub fn configs(&mut self, configs: Arc<Configs>) -> &mut Self {
+        self.configs = configs;
+        self
+    }
+
+    /// Includes or leaves out the content of git submodules, declared in .gitmodules, when the rules are next built, as
+    /// by [method.parse]. Submodules are left out by default
+    /// ```no_run
+    /// project.include_submodules(true).parse()?;
+    /// ```
+    pub fn include_submodules(&mut self, include: bool) -> &mut Self {
+        self.include_submodules = include;
+        self
+    }
+
+    /// Breaks the lines of each language down by file extension, into ```ext_stats```, when the code stats are next
+    /// generated. Compound extensions such as ```.test.ts``` or ```.min.js``` are kept apart from ```.ts``` & ```.js```
+    /// ```no_run
+    /// project.ext_breakdown(true).get_code_stats()?;
+    /// for (lang, exts) in project.ext_stats.as_ref().unwrap() {
+    ///     println!("{} {:?}", lang, exts.keys().collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn ext_breakdown(&mut self, by_ext: bool) -> &mut Self {
+        self.ext_breakdown = by_ext;
+        self
+    }
+
+    /// Counts the fenced code blocks of Markdown files, such as ```` ```rust ````, as the languages they declare when
+    /// the code stats are next generated, for docs-heavy and literate projects. The prose, and blocks whose language
+    /// is not given or not known, stay Markdown
+    /// ```no_run
+    /// let stats = project.fenced_code(true).get_code_stats()?;
+    /// ```
+    pub fn fenced_code(&mut self, fenced: bool) -> &mut Self {
+        self.fenced_code = fenced;
+        self
+    }
+
+    /// Counts the inline scripts & styles of HTML pages as JavaScript & CSS, the markup around them staying HTML, when
+    /// the code stats are next generated, so that legacy web projects report their true language mix
+    /// ```no_run
+    /// let stats = project.split_html(true).get_code_stats()?;
+    /// ```
+    pub fn split_html(&mut self, split: bool) -> &mut Self {
+        self.split_html = split;
+        self
+    }
+
+    /// Sets the templating formats whose host & embedded languages are counted apart when the code stats are next
+    /// generated, [EmbeddedLang::defaults] unless set. An empty list counts each file as a single language
+    /// ```no_run
+    /// let mut langs = EmbeddedLang::defaults();
+    /// langs.push(EmbeddedLang::new(&[".liquid"], StatsLang::Html, StatsLang::from("Liquid"), &[("{%", "%}"), ("{{", "}}")]));
+    /// project.embedded_langs(langs).get_code_stats()?;
+    /// ```
+    pub fn embedded_langs(&mut self, langs: Vec<EmbeddedLang>) -> &mut Self {
+        self.embedded_langs = langs;
+        self
+    }
+
+    /// Counts minified scripts & stylesheets and compiled bundles, such as ```app.min.js``` or a webpack output, when the
+    /// code stats are next generated. They are left out by default and listed in ```minified_files```
+    /// ```no_run
+    /// project.count_minified(true).get_code_stats()?;
+    /// ```
+    pub fn count_minified(&mut self, count: bool) -> &mut Self {
+        self.count_minified = count;
+        self
+    }
+
+    /// Records the line endings, trailing whitespace & missing final newlines of the counted files when the code stats
+    /// are next generated, see [method.hygiene_report]
+    /// ```no_run
+    /// project.line_hygiene(true).get_code_stats()?;
+    /// ```
+    pub fn line_hygiene(&mut self, record: bool) -> &mut Self {
+        self.line_hygiene = record;
+        self
+    }
+
+    /// Records the line lengths of each language when the code stats are next generated, counting the lines longer than
+    /// ```limit``` characters. They are kept in ```line_lengths```
+    /// ```no_run
+    /// project.line_limit(100).get_code_stats()?;
+    /// for (lang, lengths) in project.line_lengths.iter().flatten() {
+    ///     println!("{}: {} lines over 100, the longest is {}", lang, lengths.over_limit, lengths.max);
+    /// }
+    /// ```
+    pub fn line_limit(&mut self, limit: usize) -> &mut Self {
+        self.line_limit = Some(limit);
+        self
+    }
+
+    /// Sets how files marked as generated are counted when the code stats are next generated
+    /// ```no_run
+    /// project.generated_code(GeneratedCode::Skip).get_code_stats()?;
+    /// ```
+    pub fn generated_code(&mut self, mode: GeneratedCode) -> &mut Self {
+        self.generated_code = mode;
+        self
+    }
+
+    /// Sets how git repositories nested in the project are treated when the rules are next built, as by [method.parse]
+    /// ```no_run
+    /// project.nested_repo_mode(NestedRepos::Merge).parse()?;
+    /// ```
+    pub fn nested_repo_mode(&mut self, mode: NestedRepos) -> &mut Self {
+        self.nested_repo_mode = mode;
+        self
+    }
+
+    /// The git repositories nested in the project, found by [method.parse], as projects of their own, ready to be
+    /// parsed. Their content is judged by their own rules rather than by those of this project
+    /// ```no_run
+    /// for mut nested in project.nested_projects()? {
+    ///     nested.parse()?;
+    ///     println!("{:?}: {:?}", nested.dir, nested.get_code_stats()?);
+    /// }
+    /// ```
+    pub fn nested_projects(&self) -> Result<Vec<Project>> {
+        let mut projects = vec![];
+
+        for repo in self.nested_repos.iter().flatten() {
+            let mut project = Project::with_vfs(self.dir.join(repo), self.vfs.sub(repo))?;
+            project
+                .offline(self.offline)
+                .configs(self.configs.clone())
+                .nested_repo_mode(self.nested_repo_mode);
+            projects.push(project);
+        }
+
+        Ok(projects)
+    }
+
+    /// The git directory of the project: ```.git``` in a regular checkout, the directory the ```.git``` file points to
+    /// in a linked worktree, or the project directory itself for a bare repository. None outside of git
+    /// ```no_run
+    /// if let Some(git_dir) = project.git_dir() {
+    ///     println!("refs in {:?}", git_dir.join("refs"));
+    /// }
+    /// ```
+    pub fn git_dir(&self) -> Option<PathBuf> {
+        let git_dir = git::git_dir(self.vfs.as_ref())?;
+
+        match git_dir.as_os_str().is_empty() {
+            true => Some(self.dir.clone()),
+            false => Some(self.dir.join(git_dir)),
+        }
+    }
+
+    /// Git submodules declared in the project .gitmodules
+    /// ```no_run
+    /// for submodule in project.submodules()? {
+    ///     println!("{:?} from {:?}", submodule.path, submodule.url);
+    /// }
+    /// ```
+    pub fn submodules(&self) -> Result<Vec<Submodule>> {
+        git::submodules(self.vfs.as_ref())
+    }
+
+    /// Sets the minimum number of marker files a language needs to be detected, so that a stray ```tox.ini``` in a Rust repository does not make it a Python project
+    /// ```no_run
+    /// project.min_evidence(2).parse()?;
+    /// ```
+    pub fn min_evidence(&mut self, min_evidence: usize) -> &mut Self {
+        self.min_evidence = min_evidence;
+        self
+    }
+
+    /// Sets how languages detected from the same number of marker files are ordered in ```project_langs```
+    /// ```no_run
+    /// project
+    ///     .tie_break(project::TieBreak::Preferred(vec!["rust".into()]))
+    ///     .parse()?;
+    /// ```
+    pub fn tie_break(&mut self, tie_break: TieBreak) -> &mut Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// Calls ```hook``` with the path of every file visited by the code stats walk, as it happens
+    /// Hooks are kept by clones of the project and replace any hook set before
+    /// ```no_run
+    /// project
+    ///     .on_file_visited(|path| println!("visited {:?}", path))
+    ///     .on_ignored(|path| println!("ignored {:?}", path))
+    ///     .parse()?;
+    /// ```
+    pub fn on_file_visited<F: Fn(&Path) + Send + Sync + 'static>(&mut self, hook: F) -> &mut Self {
+        self.events.file_visited = Some(Arc::new(hook));
+        self
+    }
+
+    /// Calls ```hook``` with each language detected by [method.parse], in order of precedence
+    pub fn on_language_detected<F: Fn(&str) + Send + Sync + 'static>(&mut self, hook: F) -> &mut Self {
+        self.events.language_detected = Some(Arc::new(hook));
+        self
+    }
+
+    /// Calls ```hook``` with the path of every file & directory the gitignore rules leave out of the code stats walk
+    /// Ignored directories are reported once, their content is not walked
+    pub fn on_ignored<F: Fn(&Path) + Send + Sync + 'static>(&mut self, hook: F) -> &mut Self {
+        self.events.ignored = Some(Arc::new(hook));
+        self
+    }
+
+    /// Calls ```hook``` with the line counts per language once the code stats walk is over
+    pub fn on_stats_ready<F: Fn(&Stats) + Send + Sync + 'static>(&mut self, hook: F) -> &mut Self {
+        self.events.stats_ready = Some(Arc::new(hook));
+        self
+    }
+
+    /// Parses the Project initialized with [method.new]
+    /// Parsing will perform the following key tasks:
+    /// - Detect main project language(s) and tools such as CMake, Gradle or Terraform
+    /// - Classify the project (node library, electron app, rust workspace...) from the detected languages' manifests
+    /// - Generate a generic gitignore based on [gitignores](https://github.com/starship/starship/tree/master/src/configs)
+    /// - Detect local environment directories such as **.venv/**, **.tox/** or **.gradle/** so that they can be ignored
+    /// - Detect the git hooks configured with pre-commit, husky or lefthook
+    /// - Generate Regexp rules from the generic gitignore that are used to check if files and directories within the project should be git-ignored.
+    pub fn parse(&mut self) -> Result<()> {
+        self.parse_with(ParseOptions::default())
+    }
+
+    /// Opens, parses & counts a project in one call, returning an immutable [Analysis] with every field filled in
+    /// The steps are those enabled in ```options```, except that the rules are always built and the code always
+    /// counted, so that nothing is missing from the analysis
+    /// ```no_run
+    /// let analysis = Project::analyze("/my/project", ParseOptions::default())?;
+    /// println!("{:?}", analysis.code_stats());
+    /// ```
+    pub fn analyze<P: AsRef<Path>>(dir_path: P, options: ParseOptions) -> Result<Analysis> {
+        Project::new(dir_path)?.analyze_with(options)
+    }
+
+    /// Parses & counts a project, such as one built by [method.from_entries] or configured beforehand, returning an
+    /// immutable [Analysis]. See [method.analyze]
+    pub fn analyze_with(mut self, options: ParseOptions) -> Result<Analysis> {
+        self.parse_with(ParseOptions {
+            build_ruleset: true,
+            code_stats: true,
+            ..options
+        })?;
+
+        let ruleset = match self.gitignore_ruleset.clone() {
+            Some(ruleset) => ruleset,
+            None => ruleset::load_str(&self.dir, "")?,
+        };
+
+        Ok(Analysis::new(self, ruleset))
+    }
+
+    /// Parses the Project, performing only the steps enabled in ```options```
+    /// Callers that only want language detection can skip template fetching and ruleset compilation, and vice versa
+    /// ```no_run
+    /// let options = project::ParseOptions {
+    ///     fetch_templates: false,
+    ///     build_ruleset: false,
+    ///     ..Default::default()
+    /// };
+    /// project.parse_with(options)?;
+    /// ```
+    pub fn parse_with(&mut self, options: ParseOptions) -> Result<()> {
+        // entries modified while parsing are newer than the parse
+        self.last_parsed = Some(SystemTime::now());
+        self.parse_options = Some(options.clone());
+
+        // extend via impl methods
+        if options.detect_langs {
+            self.add_langs(options.cache_detection)?;
+        }
+        if options.fetch_templates {
+            self.add_gitignore()?;
+        }
+        if options.detect_env_dirs {
+            self.add_env_dirs()?;
+        }
+        if options.detect_hooks {
+            self.add_hooks()?;
+        }
+        if options.detect_nested_repos {
+            self.add_nested_repos()?;
+        }
+        if options.build_ruleset {
+            self.get_rules()?;
+
+            if options.use_project_gitignore {
+                self.use_project_gitignore(true)?;
+            }
+        }
+        if options.code_stats {
+            self.get_code_stats()?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses the project again with the steps of the last [method.parse_with], the default ones if it was never parsed,
+    /// and counts the code, refreshing the languages, the gitignore, the rules & the code stats in one call. An index
+    /// taken before is taken again, after the rules are rebuilt
+    /// ```no_run
+    /// if project.is_stale()? {
+    ///     project.reparse()?;
+    /// }
+    /// ```
+    pub fn reparse(&mut self) -> Result<()> {
+        let indexed = self.index.is_some();
+        // an index taken before would hide the files changed since
+        self.drop_index();
+
+        let options = self.parse_options.clone().unwrap_or_default();
+        self.parse_with(ParseOptions {
+            code_stats: true,
+            ..options
+        })?;
+
+        if indexed {
+            self.index()?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the project files changed since [method.parse] last started: a directory had entries added, removed or
+    /// renamed, or a file was written. Ignored entries & .git are not looked at. Always true before the first parse, and
+    /// false for projects held in memory, whose entries have no modification time
+    /// ```no_run
+    /// if project.is_stale()? {
+    ///     project.reparse()?;
+    /// }
+    /// ```
+    pub fn is_stale(&self) -> Result<bool> {
+        let last_parsed = match self.last_parsed {
+            Some(last_parsed) => last_parsed,
+            None => return Ok(true),
+        };
+        // the index holds the entries as they were
+        let vfs = self.unindexed.as_ref().unwrap_or(&self.vfs);
+        let ruleset = self.gitignore_ruleset.as_ref();
+
+        vfs::modified_since(vfs.as_ref(), last_parsed, &mut |e| {
+            e.depth == 0 || !(e.file_name() == ".git" || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)))
+        })
+    }
+
+    /// Generates code stats for all the project files that are:
+    /// - Code files. The following file types are supported
+    /// - Not ignored based on the gitignore rules, if any have been built
+    ///
+    /// Files whose paths are not valid UTF-8 cannot be counted, they are listed in ```skipped_paths``` instead
+    ///
+    /// Git LFS pointers are stubs rather than code, they are listed in ```lfs_pointers``` instead
+    ///
+    /// Minified scripts & stylesheets and compiled bundles, told by their names, their long lines or the runtime of the
+    /// bundler, are listed in ```minified_files``` instead, unless [method.count_minified] is set
+    ///
+    /// Files marked as generated, with ```@generated``` or ```DO NOT EDIT``` in their first lines, are counted apart in
+    /// ```generated_stats``` and listed in ```generated_files```, unless [method.generated_code] says otherwise
+    ///
+    /// Jupyter notebooks are counted as their kernel language, code cells as code and markdown cells as comments
+    ///
+    /// Vue, Svelte & Astro components are split by block: markup as HTML, scripts as JavaScript or TypeScript, and
+    /// styles as CSS
+    ///
+    /// Templating formats set with [method.embedded_langs], ERB, Jinja, PHP & JSX by default, count their host &
+    /// embedded languages apart
+    ///
+    /// With [method.split_html] set, the inline scripts & styles of HTML pages count as JavaScript & CSS
+    ///
+    /// With [method.fenced_code] set, the fenced code blocks of Markdown files count as the languages they declare
+    ///
+    /// With [method.ext_breakdown] set, the lines of each language are also kept per file extension in ```ext_stats```
+    ///
+    /// Non-code files are tallied per category (images, fonts, data, docs, configs, lockfiles...) in ```asset_stats``` during the same walk
+    ///
+    /// With [method.line_hygiene] set, the line endings & whitespace of the counted files are kept in ```hygiene```
+    ///
+    /// With [method.line_limit] set, the longest, average & 95th percentile line lengths of each language, and the
+    /// number of lines over the limit, are kept in ```line_lengths```
+    ///
+    /// Ignored directories such as node_modules or target are skipped before they are read. How many were skipped, and how
+    /// long the walk took, is kept in ```walk_stats```
+    pub fn get_code_stats(&mut self) -> Result<Option<Stats>> {
+        // rrr
+        let options = CountOptions {
+            by_ext: self.ext_breakdown,
+            fenced_code: self.fenced_code,
+            split_html: self.split_html,
+            embedded: &self.embedded_langs,
+            count_minified: self.count_minified,
+            generated: self.generated_code,
+            hygiene: self.line_hygiene,
+            line_limit: self.line_limit,
+        };
+        let found = code::dir_stats(self.vfs.as_ref(), &self.gitignore_ruleset, &self.events, options)?;
+        // lists are None rather than empty
+        let listed = |paths: Vec<PathBuf>| if paths.is_empty() { None } else { Some(paths) };
+
+        self.code_stats = found.stats.clone();
+        self.ext_stats = found.ext_stats;
+        self.generated_stats = found.generated_stats;
+        self.asset_stats = found.asset_stats;
+        self.walk_stats = Some(found.walk);
+        self.skipped_paths = listed(found.skipped);
+        self.lfs_pointers = listed(found.lfs_pointers);
+        self.minified_files = listed(found.minified);
+        self.generated_files = listed(found.generated);
+        self.hygiene = found.hygiene;
+        self.line_lengths = found.line_lengths;
+
+        Ok(found.stats)
+    }
+
+    /// CRLF & LF line counts, files mixing both, files with trailing whitespace and files missing a final newline,
+    /// among the files counted by [method.get_code_stats], generated files aside. The code stats are generated with
+    /// [method.line_hygiene] set first, unless they already recorded it
+    /// ```no_run
+    /// let report = project.hygiene_report()?;
+    /// for path in &report.missing_final_newline {
+    ///     println!("No newline at end of {:?}", path);
+    /// }
+    /// ```
+    pub fn hygiene_report(&mut self) -> Result<HygieneReport> {
+        if self.hygiene.is_none() {
+            let line_hygiene = self.line_hygiene;
+            self.line_hygiene(true).get_code_stats()?;
+            self.line_hygiene = line_hygiene;
+        }
+
+        Ok(self.hygiene.clone().unwrap_or_default())
+    }
+
+    /// Ranks the project languages by lines of code, using the stats generated by [method.get_code_stats]
+    /// A project with a package.json but mostly Python code is ranked ```["python", "node"]```
+    /// Languages with code on disk are included even when no marker file detected them
+    /// Returns the detected languages as they are if no code stats have been generated
+    /// ```no_run
+    /// project.get_code_stats()?;
+    /// let langs = project.languages_by_loc();
+    /// println!("Main language {:?}", langs.first());
+    /// ```
+    pub fn languages_by_loc(&self) -> Vec<String> {
+        let langs = self.project_langs.clone().unwrap_or_default();
+
+        match &self.code_stats {
+            Some(stats) => detector::rank_langs_by_loc(&langs, stats),
+            _ => langs,
+        }
+    }
+
+    /// Walks the project once and returns a typed tree of its directories & files, with their sizes, languages and
+    /// whether they are ignored. The tree is kept in ```tree``` so that it can be traversed again without walking the disk
+    /// Ignored directories are listed but not walked
+    /// ```no_run
+    /// let tree = project.file_tree()?;
+    /// for node in tree.iter().filter(|node| !node.is_dir && !node.is_ignored) {
+    ///     println!("{:?} {} bytes", node.path, node.size);
+    /// }
+    /// ```
+    pub fn file_tree(&mut self) -> Result<FileTree> {
+        let tree = tree::build(self.vfs.as_ref(), &self.dir, &self.gitignore_ruleset)?;
+
+        self.tree = Some(tree.clone());
+
+        Ok(tree)
+    }
+
+    /// Walks the project once, capturing the paths, sizes, modification times, languages and whether the gitignore
+    /// rules ignore them, of all its entries. From then on, the detection, the code stats, the search, the tree & the
+    /// reports read the index rather than walking the disk, so that calling several of them costs a single walk. File
+    /// contents are still read from the disk. Ignored directories are not walked, unless a method asks for their
+    /// content, such as [method.packaged_files] with rules of its own. Entries added or removed since are not seen until
+    /// the project is indexed again. Parse first, so that the rules are known
+    /// ```no_run
+    /// project.parse()?;
+    /// project.index()?;
+    /// let stats = project.get_code_stats()?;
+    /// let matches = project.search("TODO")?;
+    /// ```
+    pub fn index(&mut self) -> Result<Arc<FileIndex>> {
+        self.drop_index();
+
+        let index = Arc::new(index::build(self.vfs.as_ref(), self.gitignore_ruleset.as_ref())?);
+        let unindexed = self.vfs.clone();
+        self.vfs = Arc::new(IndexedFs::new(unindexed.clone(), index.clone()));
+        self.unindexed = Some(unindexed);
+        self.index = Some(index.clone());
+
+        Ok(index)
+    }
+
+    /// Reads the project files instead of the index
+    fn drop_index(&mut self) {
+        if let Some(unindexed) = self.unindexed.take() {
+            self.vfs = unindexed;
+        }
+        self.index = None;
+    }
+
+    /// Renders the project ```tree``` command style, down to ```depth``` levels (all of them when None)
+    /// Ignored entries are omitted, or listed and marked **[ignored]** when ```show_ignored``` is true, to eyeball what the rules exclude
+    /// Uses the tree from [method.file_tree] when there is one, taking it otherwise
+    /// ```no_run
+    /// println!("{}", project.render_tree(Some(2), true)?);
+    /// ```
+    pub fn render_tree(&mut self, depth: Option<usize>, show_ignored: bool) -> Result<String> {
+        let tree = match &self.tree {
+            Some(tree) => tree.clone(),
+            None => self.file_tree()?,
+        };
+
+        Ok(tree.render(depth, show_ignored))
+    }
+
+    /// Finds the non ignored files & directories matching a glob pattern, relative to the project directory
+    /// Patterns are matched against the tree from [method.file_tree], which is taken first if need be, so the disk is not walked again
+    /// Matching is case insensitive when ```ignore_case``` is set
+    /// ```no_run
+    /// for path in project.glob("src/**/*.rs")? {
+    ///     println!("{:?}", path);
+    /// }
+    /// ```
+    pub fn glob(&mut self, pattern: &str) -> Result<Vec<PathBuf>> {
+        if self.tree.is_none() {
+            self.file_tree()?;
+        }
+
+        let paths = match &self.tree {
+            Some(tree) => tree.glob(pattern, self.ignore_case)?,
+            None => vec![],
+        };
+
+        Ok(paths.into_iter().map(|path| self.dir.join(path)).collect())
+    }
+
+    /// Searches the non ignored, non binary files for a regular expression, reading them in parallel
+    /// Files are listed from the tree of [method.file_tree], which is taken first if need be
+    /// ```no_run
+    /// for m in project.search(r"TODO|FIXME")? {
+    ///     println!("{:?}:{}:{} {}", m.path, m.line, m.column, m.text);
+    /// }
+    /// ```
+    pub fn search(&mut self, pattern: &str) -> Result<Vec<SearchMatch>> {
+        let re = Regex::new(pattern)?;
+
+        if self.tree.is_none() {
+            self.file_tree()?;
+        }
+
+        let files: Vec<PathBuf> = match &self.tree {
+            Some(tree) => tree
+                .iter()
+                .filter(|node| !node.is_dir && !node.is_ignored)
+                .map(|node| node.path.clone())
+                .collect(),
+            None => vec![],
+        };
+
+        let matches = search::search(self.vfs.as_ref(), &files, &re)
+            .into_iter()
+            .map(|m| SearchMatch {
+                path: self.dir.join(&m.path),
+                ..m
+            })
+            .collect();
+
+        Ok(matches)
+    }
+
+    /// Finds the project README (README.md, README.rst, README.adoc, README...) and extracts its first heading as the
+    /// project title and the first paragraph as its description. Markdown is preferred when there are several
+    /// ```no_run
+    /// if let Some(readme) = project.readme()? {
+    ///     println!("{:?}: {:?}", readme.title, readme.description);
+    /// }
+    /// ```
+    pub fn readme(&self) -> Result<Option<Readme>> {
+        let readme = docs::readme(self.vfs.as_ref())?;
+
+        Ok(readme.map(|readme| Readme {
+            path: self.dir.join(&readme.path),
+            ..readme
+        }))
+    }
+
+    /// Finds the project changelog (CHANGELOG, HISTORY, CHANGES, NEWS...) and parses it into releases
+    /// Both [keep a changelog](https://keepachangelog.com) headings such as ```## [1.0.0] - 2017-06-20``` and plain version headings are understood
+    /// ```no_run
+    /// if let Some(changelog) = project.changelog()? {
+    ///     for entry in changelog.entries {
+    ///         println!("{} {:?}", entry.version, entry.date);
+    ///     }
+    /// }
+    /// ```
+    pub fn changelog(&self) -> Result<Option<Changelog>> {
+        let changelog = docs::changelog(self.vfs.as_ref())?;
+
+        Ok(changelog.map(|changelog| Changelog {
+            path: self.dir.join(&changelog.path),
+            ..changelog
+        }))
+    }
+
+    /// The test frameworks the project uses, read from its manifests & config files: cargo test & nextest, jest,
+    /// vitest & mocha, pytest & unittest, go test and rspec. Ignored files are not looked at
+    /// ```no_run
+    /// if project.test_frameworks()?.contains(&TestFramework::Jest) {
+    ///     println!("npx jest");
+    /// }
+    /// ```
+    pub fn test_frameworks(&self) -> Result<Vec<TestFramework>> {
+        testing::detect_frameworks(self.vfs.as_ref(), self.gitignore_ruleset.as_ref())
+    }
+
+    /// The code files, per language, that fail to decode as UTF-8, with the line of the first invalid byte, or that hold
+    /// ```U+FFFD``` replacement characters left by a lossy conversion. Such files are counted as they can by
+    /// [method.get_code_stats], yet most tools choke on them. Ignored files are not read
+    /// ```no_run
+    /// for (lang, issues) in &project.encoding_report()?.langs {
+    ///     println!("{}: {:?}", lang, issues);
+    /// }
+    /// ```
+    pub fn encoding_report(&self) -> Result<EncodingReport> {
+        encoding::report(self.vfs.as_ref(), self.gitignore_ruleset.as_ref())
+    }
+
+    /// Tabs or spaces, and the indent width, of each language, inferred from the first files of the language. The
+    /// profile renders as EditorConfig sections with [IndentationProfile::to_editorconfig]. Ignored files are not read
+    /// ```no_run
+    /// let profile = project.indentation_profile()?;
+    /// println!("{:?}", profile.langs.get("Rust"));
+    /// std::fs::write("/my/project/.editorconfig", profile.to_editorconfig())?;
+    /// ```
+    pub fn indentation_profile(&self) -> Result<IndentationProfile> {
+        indent::profile(self.vfs.as_ref(), self.gitignore_ruleset.as_ref())
+    }
+
+    /// The schemas & interface definitions of the project: its .proto & .graphql files and SQL migration directories,
+    /// along with the code generators configured to turn them into code, such as buf, prost, GraphQL Code Generator or
+    /// sqlc. The code they generate is listed in ```generated_files``` by [method.get_code_stats] when it is marked as
+    /// such. Ignored files are not looked at
+    /// ```no_run
+    /// let schemas = project.schemas()?;
+    /// println!("{} proto files, generated with {:?}", schemas.protobuf.len(), schemas.codegen);
+    /// ```
+    pub fn schemas(&self) -> Result<SchemaReport> {
+        schema::report(self.vfs.as_ref(), self.gitignore_ruleset.as_ref())
+    }
+
+    /// The infrastructure components of the project: its Helm charts, directories holding a Chart.yaml, and the
+    /// directories of raw Kubernetes manifests outside of them. Their YAML is counted as [FileCategory::Infra] by
+    /// [method.get_code_stats] rather than as config. Ignored files are not looked at
+    /// ```no_run
+    /// for component in project.infra_components()? {
+    ///     println!("{:?} {:?}: {} files", component.kind, component.path, component.files.len());
+    /// }
+    /// ```
+    pub fn infra_components(&self) -> Result<Vec<InfraComponent>> {
+        infra::detect(self.vfs.as_ref(), self.gitignore_ruleset.as_ref())
+    }
+
+    /// Broken & left over entries: dangling symlinks, zero-byte source files and the paths manifest fields point to
+    /// that do not exist, such as the ```main``` of package.json or the ```path``` of a Cargo.toml target. Ignored
+    /// entries are not looked at, yet a manifest may point to build output that is missing until the project is built
+    /// ```no_run
+    /// for issue in project.integrity_issues()? {
+    ///     println!("{:?} {:?}", issue.path, issue.kind);
+    /// }
+    /// ```
+    pub fn integrity_issues(&self) -> Result<Vec<IntegrityIssue>> {
+        integrity::issues(self.vfs.as_ref(), self.gitignore_ruleset.as_ref())
+    }
+
+    /// Files with unexpected unix permissions: scripts with a shebang but no executable bit, source files with it
+    /// set, and world-writable files. Ignored entries are not looked at. Empty on other platforms than unix
+    /// ```no_run
+    /// for issue in project.permission_audit()? {
+    ///     println!("{:?} {:o} {:?}", issue.path, issue.mode, issue.kind);
+    /// }
+    /// ```
+    pub fn permission_audit(&self) -> Result<Vec<PermissionIssue>> {
+        permissions::audit(self.vfs.as_ref(), self.gitignore_ruleset.as_ref())
+    }
+
+    /// Finds and parses the CODEOWNERS file, looking in .github/, the project directory, docs/ and .gitlab/ like GitHub & GitLab do
+    /// ```no_run
+    /// if let Some(codeowners) = project.codeowners()? {
+    ///     println!("{:?}", codeowners.owners_for("src/main.rs"));
+    /// }
+    /// ```
+    pub fn codeowners(&self) -> Result<Option<CodeOwners>> {
+        codeowners::codeowners(self.vfs.as_ref())
+    }
+
+    /// Lists the non ignored files no CODEOWNERS rule assigns an owner to, all of them if there is no CODEOWNERS file
+    /// Files are listed from the tree of [method.file_tree], which is taken first if need be
+    /// ```no_run
+    /// for path in project.unowned_files()? {
+    ///     println!("No owner for {:?}", path);
+    /// }
+    /// ```
+    pub fn unowned_files(&mut self) -> Result<Vec<PathBuf>> {
+        let codeowners = self.codeowners()?;
+
+        if self.tree.is_none() {
+            self.file_tree()?;
+        }
+
+        let unowned = match &self.tree {
+            Some(tree) => tree
+                .iter()
+                .filter(|node| !node.is_dir && !node.is_ignored)
+                .filter(|node| {
+                    codeowners
+                        .as_ref()
+                        .is_none_or(|codeowners| codeowners.owners_for(&node.path).is_empty())
+                })
+                .map(|node| self.dir.join(&node.path))
+                .collect(),
+            None => vec![],
+        };
+
+        Ok(unowned)
+    }
+
+    /// Who last changed the lines surviving at HEAD, per file, per language & overall, from git blame, to answer who owns
+    /// a module. ```path_or_glob``` is a file, a directory whose files are all blamed, or a glob pattern matching files
+    /// or directories, relative to the project directory. Ignored & uncommitted files are left out. Files are listed
+    /// from the tree of [method.file_tree], which is taken first if need be
+    /// ```no_run
+    /// let ownership = project.ownership("src/parser")?;
+    /// if let Some(owner) = ownership.owner() {
+    ///     println!("{} <{}> wrote {} lines", owner.name, owner.email, owner.lines);
+    /// }
+    /// ```
+    #[cfg(feature = "git")]
+    pub fn ownership(&mut self, path_or_glob: &str) -> Result<Ownership> {
+        if self.tree.is_none() {
+            self.file_tree()?;
+        }
+
+        match &self.tree {
+            Some(tree) => ownership::ownership(&self.dir, &ownership::select(tree, path_or_glob, self.ignore_case)?),
+            None => Ok(Ownership::default()),
+        }
+    }
+
+    /// The files changed by commits since ```since```, from git history, the most changed first and the larger first
+    /// among files changed as often. Merge commits are not counted. Files both in the top quarter by commits & by lines
+    /// are flagged as risks, large files that keep changing being where bugs gather. Ignored files and files that no
+    /// longer exist are left out. Files are listed from the tree of [method.file_tree], which is taken first if need be
+    /// ```no_run
+    /// let month_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(30 * 24 * 3600);
+    /// for hotspot in project.change_hotspots(month_ago)?.iter().filter(|hotspot| hotspot.is_risk) {
+    ///     println!("{:?} changed {} times, {} lines", hotspot.path, hotspot.commits, hotspot.lines);
+    /// }
+    /// ```
+    #[cfg(feature = "git")]
+    pub fn change_hotspots(&mut self, since: SystemTime) -> Result<Vec<ChangeHotspot>> {
+        if self.tree.is_none() {
+            self.file_tree()?;
+        }
+
+        match &self.tree {
+            Some(tree) => history::hotspots(self.vfs.as_ref(), &self.dir, tree, since),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Quick complexity hotspots: the ```top``` largest code files by bytes, the ```top``` longest files of each language
+    /// by lines of code, and the ```top``` longest functions spanning at least ```min_function_lines``` lines
+    /// Functions are found with simple brace & indentation heuristics, for Rust, Go, JavaScript, TypeScript, Python and
+    /// the C family languages. Files are listed from the tree of [method.file_tree], which is taken first if need be
+    /// ```no_run
+    /// let hotspots = project.hotspots(10, 80)?;
+    /// for function in hotspots.longest_functions {
+    ///     println!("{:?}:{} {} is {} lines long", function.path, function.line, function.name, function.lines);
+    /// }
+    /// ```
+    pub fn hotspots(&mut self, top: usize, min_function_lines: usize) -> Result<Hotspots> {
+        if self.tree.is_none() {
+            self.file_tree()?;
+        }
+
+        let mut hotspots = match &self.tree {
+            Some(tree) => hotspots::hotspots(self.vfs.as_ref(), &self.dir, tree, top, min_function_lines)?,
+            None => Hotspots::default(),
+        };
+
+        let files = hotspots
+            .largest_files
+            .iter_mut()
+            .chain(hotspots.longest_files.values_mut().flatten());
+        for file in files {
+            file.path = self.dir.join(&file.path);
+        }
+        for function in hotspots.longest_functions.iter_mut() {
+            function.path = self.dir.join(&function.path);
+        }
+
+        Ok(hotspots)
+    }
+
+    /// Age histogram of the non ignored files, from their modification times, and the directories with no file
+    /// modified in the last ```stale_months``` months of 30 days, the stalest first. The tree of [method.file_tree],
+    /// which holds the modification times, is taken first if need be
+    /// ```no_run
+    /// let report = project.age_report(12)?;
+    /// for dir in report.stale_dirs {
+    ///     println!("{:?} untouched since {:?}", dir.path, dir.last_modified);
+    /// }
+    /// ```
+    pub fn age_report(&mut self, stale_months: u32) -> Result<AgeReport> {
+        if self.tree.is_none() {
+            self.file_tree()?;
+        }
+
+        let stale_after = Duration::from_secs(u64::from(stale_months) * 30 * 24 * 60 * 60);
+
+        Ok(match &self.tree {
+            Some(tree) => age::report(tree, SystemTime::now(), stale_after),
+            None => AgeReport::default(),
+        })
+    }
+
+    /// Lists the non ignored source files that do not start with ```expected_header```, grouped by language
+    /// Comment markers and line breaks are disregarded, so the header can be given with or without them, and a file
+    /// passes when its leading comment contains the header. A shebang line may come first. Files of languages without
+    /// comments, such as JSON, are not audited. The tree of [method.file_tree] is taken first if need be
+    /// ```no_run
+    /// let audit = project.audit_license_headers("Licensed under the Apache License, Version 2.0")?;
+    /// for (lang, files) in &audit.missing {
+    ///     println!("{} files without a header: {:?}", lang, files);
+    /// }
+    /// ```
+    pub fn audit_license_headers(&mut self, expected_header: &str) -> Result<HeaderAudit> {
+        if self.tree.is_none() {
+            self.file_tree()?;
+        }
+
+        let mut audit = match &self.tree {
+            Some(tree) => audit::license_headers(self.vfs.as_ref(), tree, expected_header)?,
+            None => HeaderAudit::default(),
+        };
+
+        for file in audit.missing.values_mut().chain(audit.different.values_mut()).flatten() {
+            *file = self.dir.join(&*file);
+        }
+
+        Ok(audit)
+    }
+
+    /// Adds ```template``` as a license header to the non ignored source files that lack it, in the comment syntax of
+    /// their language, and returns the files changed. A leading comment mentioning a copyright or license is taken for
+    /// an outdated header and replaced, other leading comments stay below the new header. Shebang lines stay first
+    /// With ```dry_run``` nothing is written, the changes are only returned. Files already carrying the header, as
+    /// [method.audit_license_headers] has it, are left alone, so applying the same header twice changes nothing
     /// ```no_run
-    /// let dir = "/my/project/directory";
-    /// //Needs to be a mutable variable for other methods to use and update Project
-    /// let mut project = project::Project::new(dir)?;
-    /// // Adding a file to the generic gitignore
+    /// for change in project.apply_license_header("Copyright 2024 Acme\nLicensed under MIT", true)? {
+    ///     println!("{:?} {:?}", change.action, change.path);
+    /// }
     /// ```
-    pub fn new(dir_path: &str) -> Result<Project> {
-        let dir_path = PathBuf::from(dir_path);
+    pub fn apply_license_header(&mut self, template: &str, dry_run: bool) -> Result<Vec<HeaderChange>> {
+        if self.tree.is_none() {
+            self.file_tree()?;
+        }
 
-        // check that dir exists
-        if !dir_path.exists() {
-            return Err(anyhow!(ProjectError::NotFound(
-                dir_path.to_string_lossy().to_string()
-            )));
+        let mut changes = match &self.tree {
+            Some(tree) => audit::license_header_changes(self.vfs.as_ref(), tree, template)?,
+            None => vec![],
+        };
+
+        for change in changes.iter_mut() {
+            if !dry_run {
+                self.vfs.write(&change.path, &change.content)?;
+            }
+            change.path = self.dir.join(&change.path);
         }
-        //init
-        let mut project = Project {
-            dir: dir_path,
-            project_langs: None,
 
-            is_git: None,
-            generic_gitignore: None,
-            gitignore_ruleset: None,
+        Ok(changes)
+    }
 
-            code_stats: None,
+    /// Snapshot of the non ignored code files, with per language totals, to compare with a later snapshot through
+    /// [CodeStats::diff]. The project is walked afresh, refreshing ```tree```. Paths are relative to the project directory
+    /// so that snapshots of copies of the same directory, say before & after a merge, can be compared
+    /// ```no_run
+    /// let before = project.code_snapshot()?;
+    /// // ... merge
+    /// let delta = project.code_snapshot()?.diff(&before);
+    /// println!("{:?} files changed", delta.changed_files);
+    /// ```
+    pub fn code_snapshot(&mut self) -> Result<CodeStats> {
+        let tree = self.file_tree()?;
+
+        stats::snapshot(self.vfs.as_ref(), &self.dir, &tree)
+    }
+
+    /// Writes the code stats as CSV, either one row per language or one row per non ignored file, for spreadsheets
+    /// The project is walked afresh, as for [method.code_snapshot]. Paths are relative to the project directory
+    /// ```no_run
+    /// let file = std::fs::File::create("languages.csv")?;
+    /// project.export_csv(file, CsvGranularity::Languages)?;
+    /// ```
+    pub fn export_csv<W: std::io::Write>(&mut self, mut writer: W, granularity: CsvGranularity) -> Result<()> {
+        let snapshot = self.code_snapshot()?;
+
+        match &self.tree {
+            Some(tree) => export::csv(&mut writer, granularity, tree, &snapshot),
+            None => Err(anyhow!("The project tree could not be taken")),
+        }
+    }
+
+    /// Re-parses a copy of the project every ```interval``` in a background thread, calling ```callback``` after each scan with
+    /// what changed since the previous one: languages & tools detected or gone, lines added & removed, and the current totals
+    /// The callback is called even when nothing changed, see [ProjectDiff::is_empty]. Failed scans are skipped
+    /// ```no_run
+    /// let schedule = project.schedule(Duration::from_secs(60), |diff| {
+    ///     if !diff.is_empty() {
+    ///         println!("{:?} files changed", diff.stats.changed_files);
+    ///     }
+    /// });
+    /// // ...
+    /// schedule.stop();
+    /// ```
+    pub fn schedule<F>(&self, interval: Duration, callback: F) -> Schedule
+    where
+        F: FnMut(ProjectDiff) + Send + 'static,
+    {
+        schedule::schedule(self.clone(), interval, callback)
+    }
+
+    /// Renders the lines of code, comment & blank lines, file counts and share of the code of each language as Prometheus
+    /// gauges, labelled with the project directory name and the language, ready for a Pushgateway or the node exporter
+    /// textfile collector. The project is walked afresh, as for [method.code_snapshot]
+    /// ```no_run
+    /// std::fs::write("/var/lib/node_exporter/repo.prom", project.to_prometheus()?)?;
+    /// ```
+    pub fn to_prometheus(&mut self) -> Result<String> {
+        let snapshot = self.code_snapshot()?;
+        let name = self
+            .dir
+            .file_name()
+            .map_or_else(|| self.dir.to_string_lossy(), |name| name.to_string_lossy());
+
+        Ok(export::prometheus(&name, &snapshot))
+    }
+
+    /// Writes the analysis into a SQLite database: the files with their languages, sizes, lines of code & ignore decisions,
+    /// the per language stats, and what was detected (languages, tools, editors, project kinds, environment directories,
+    /// git hooks, license). Each export adds a row to the ```projects``` table, so that the analyses of many projects can be
+    /// queried together. Returns the id of that row. Requires the **sqlite** feature
+    /// ```no_run
+    /// project.parse()?;
+    /// project.export_sqlite("analytics.db")?;
+    /// // SELECT lang, SUM(code) FROM languages GROUP BY lang
+    /// ```
+    #[cfg(feature = "sqlite")]
+    pub fn export_sqlite<P: AsRef<Path>>(&mut self, path: P) -> Result<i64> {
+        let snapshot = self.code_snapshot()?;
+        let tree = match &self.tree {
+            Some(tree) => tree,
+            None => return Err(anyhow!("The project tree could not be taken")),
         };
 
-        project.is_git()?;
+        let mut detections: sqlite::Detections = vec![];
+        let lists = [
+            ("language", &self.project_langs),
+            ("tool", &self.project_tools),
+            ("editor", &self.editors),
+            ("env_dir", &self.env_dirs),
+        ];
+        for (kind, values) in lists {
+            for value in values.iter().flatten() {
+                detections.push((kind, value.clone()));
+            }
+        }
+        for kind in self.project_kinds.iter().flatten() {
+            detections.push(("project_kind", format!("{:?}", kind)));
+        }
+        for build_system in self.build_systems.iter().flatten() {
+            detections.push(("build_system", format!("{:?}", build_system)));
+        }
+        for hook in self.git_hooks.iter().flatten() {
+            detections.push(("git_hook", format!("{}: {}", hook.stage, hook.name)));
+        }
+        if let Some(license) = license::detect_license(self.vfs.as_ref())? {
+            detections.push(("license", license));
+        }
 
-        Ok(project)
+        sqlite::export(path.as_ref(), &self.dir, tree, &snapshot, &detections)
     }
 
-    /// Parses the Project initialized with [method.new]
-    /// Parsing will perform the following key tasks:
-    /// - Detect main project language(s)
-    /// - Generate a generic gitignore based on [gitignores](https://github.com/starship/starship/tree/master/src/configs)
-    /// - Generate Regexp rules from the generic gitignore that are used to check if files and directories within the project should be git-ignored.
-    pub fn parse(&mut self) -> Result<()> {
-        // extend via impl methods
-        self.add_langs()?;
-        self.add_gitignore()?;
-        self.get_rules()?;
-        Ok(())
+    /// Badges for the primary language, the lines of code and the license, in the shields.io endpoint format
+    /// Lines of config, data & documentation formats such as TOML, JSON or Markdown are not lines of code, see
+    /// [StatsLang::is_data](crate::stats::StatsLang::is_data). Each badge can be served as JSON for shields.io or
+    /// rendered to SVG. Code stats are generated first if need be
+    /// ```no_run
+    /// for badge in project.badges()? {
+    ///     println!("{}", badge.to_json()?);
+    ///     let svg = badge.to_svg();
+    /// }
+    /// ```
+    pub fn badges(&mut self) -> Result<Vec<Badge>> {
+        if self.code_stats.is_none() {
+            self.get_code_stats()?;
+        }
+
+        let mut badges = vec![];
+
+        if let Some(lang) = self.languages_by_loc().first() {
+            badges.push(Badge::language(&detector::display_name(lang)));
+        }
+
+        let lines: u64 = match &self.code_stats {
+            Some(stats) => stats
+                .iter()
+                .filter(|(lang, _)| !lang.is_data())
+                .map(|(_, count)| count.code)
+                .sum(),
+            None => 0,
+        };
+        badges.push(Badge::lines_of_code(lines));
+
+        let license = license::detect_license(self.vfs.as_ref())?;
+        badges.push(Badge::license(license.as_deref()));
+
+        Ok(badges)
     }
 
-    /// Generates code stats for all the project files that are:
-    /// - Code files. The following file types are supported
-    /// - Not ignored based on the gitignore rules
-    pub fn get_code_stats(&mut self) -> Result<Option<HashMap<String, Count>>> {
-        // rrr
-        let stats = code::dir_stats(&self.dir, &self.gitignore_ruleset)?;
+    /// One line description of the project for status bars and prompts, e.g.
+    /// ```Rust project, 12.4k LOC (86% Rust, 10% TOML), git repo, MIT license```
+    /// The share of the three biggest languages is given. Code stats are generated first if need be
+    /// ```no_run
+    /// println!("{}", project.describe()?);
+    /// ```
+    pub fn describe(&mut self) -> Result<String> {
+        if self.code_stats.is_none() {
+            self.get_code_stats()?;
+        }
+
+        let mut parts = vec![];
+
+        parts.push(match self.languages_by_loc().first() {
+            Some(lang) => format!("{} project", detector::display_name(lang)),
+            None => String::from("Project"),
+        });
+
+        let stats = self.code_stats.clone().unwrap_or_default();
+        let total: u64 = stats.values().map(|count| count.code).sum();
+        let mut langs: Vec<_> = stats.iter().filter(|(_, count)| count.code > 0).collect();
+        langs.sort_by_key(|(_, count)| Reverse(count.code));
+        let shares: Vec<String> = langs
+            .iter()
+            .take(3)
+            .map(|(lang, count)| format!("{:.0}% {}", count.code as f64 * 100.0 / total as f64, lang))
+            .collect();
+        parts.push(if shares.is_empty() {
+            format!("{} LOC", badges::short_count(total))
+        } else {
+            format!("{} LOC ({})", badges::short_count(total), shares.join(", "))
+        });
+
+        if self.is_git == Some(true) {
+            parts.push(String::from("git repo"));
+        }
+
+        if let Some(license) = license::detect_license(self.vfs.as_ref())? {
+            parts.push(format!("{} license", license));
+        }
+
+        Ok(parts.join(", "))
+    }
+
+    /// Health score of the project from 0 to 100, with the score of each criterion: license, README, tests, CI
+    /// configuration, comment ratio and the share of the entries ignored by the language templates that the project
+    /// .gitignore ignores too. Code stats are generated first if need be
+    /// ```no_run
+    /// let health = project.health(&HealthConfig::default())?;
+    /// println!("{}/100", health.score);
+    /// for criterion in health.criteria {
+    ///     println!("{:?} {:.2} {}", criterion.criterion, criterion.score, criterion.detail);
+    /// }
+    /// ```
+    pub fn health(&mut self, config: &HealthConfig) -> Result<Health> {
+        if self.code_stats.is_none() {
+            self.get_code_stats()?;
+        }
 
-        self.code_stats = stats.clone();
+        let vfs = self.vfs.as_ref();
+        let readme = docs::readme(vfs)?
+            .and_then(|readme| readme.path.file_name().map(|name| name.to_string_lossy().to_string()));
+        let comments = self.code_stats.as_ref().map(|stats| {
+            stats
+                .values()
+                .fold((0, 0), |(comments, code), count| (comments + count.comment, code + count.code))
+        });
+        let own = self.ignore_ruleset(Dialect::Gitignore)?;
 
-        Ok(stats)
+        let signals = Signals {
+            license: license::detect_license(vfs)?,
+            readme,
+            tests: health::count_tests(vfs, self.gitignore_ruleset.as_ref())?,
+            ci: health::ci_services(vfs),
+            comments,
+            gitignore: health::gitignore_coverage(vfs, self.gitignore_ruleset.as_ref(), own.as_ref())?,
+        };
+
+        Ok(health::score(&signals, config))
+    }
+
+    /// Rules of the project ignore file of a dialect, None when the project has none. Without a .npmignore, npm falls
+    /// back to the .gitignore, and so does [Dialect::Npmignore]
+    /// ```no_run
+    /// if let Some(ruleset) = project.ignore_ruleset(Dialect::Dockerignore)? {
+    ///     println!("{}", ruleset.is_ignored(project.dir.join("target"), true));
+    /// }
+    /// ```
+    pub fn ignore_ruleset(&self, dialect: Dialect) -> Result<Option<RuleSet>> {
+        let mut files = vec![dialect.file_name()];
+        if dialect == Dialect::Npmignore {
+            files.push(Dialect::Gitignore.file_name());
+        }
+
+        let content = files
+            .into_iter()
+            .find_map(|file| self.vfs.read_to_string(Path::new(file)).ok());
+
+        match content {
+            Some(content) => Ok(Some(RuleSet::from_dialect(&self.dir, &content, dialect)?)),
+            None if dialect == Dialect::Npmignore => Ok(Some(RuleSet::from_dialect(&self.dir, "", dialect)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The files that go into a docker image or an npm package, as the project ignore file of the dialect has it
+    /// Excluded directories are not walked, so exceptions for files within them are not honoured
+    /// ```no_run
+    /// for file in project.packaged_files(Dialect::Npmignore)? {
+    ///     println!("{:?}", file);
+    /// }
+    /// ```
+    pub fn packaged_files(&self, dialect: Dialect) -> Result<Vec<PathBuf>> {
+        let ruleset = self.ignore_ruleset(dialect)?;
+
+        let entries = self.vfs.walk(&mut |e| {
+            e.depth == 0 || !ruleset.as_ref().is_some_and(|ruleset| code::is_ignored(ruleset, e))
+        })?;
+
+        Ok(entries.into_iter().filter(|e| e.is_file).map(|e| e.path).collect())
     }
 
     /// Rets content of project dir whilst respecting all the gitignore rules applied
@@ -136,16 +1782,13 @@ impl Project {
         show_ignored: &bool,
         parents_only: &bool,
     ) -> Result<Vec<PathBuf>> {
-        let dir_str = self.dir.to_str().unwrap();
-
-        let walker = WalkDir::new(dir_str).into_iter();
         let ruleset = self.gitignore_ruleset.as_ref().unwrap();
 
         let mut res: Vec<PathBuf> = vec![];
         // let mut res: Vec<DirEntry> = vec![];
 
-        for entry in walker.filter_entry(|e| {
-            if e.depth() == 0 {
+        for e in self.vfs.walk(&mut |e| {
+            if e.depth == 0 {
                 return true;
             }
 
@@ -179,27 +1822,20 @@ impl Project {
             }
 
             !filters.contains(&false)
-        }) {
-            // let e = &entry.unwrap();
-            match &entry {
-                Ok(e) => {
-                    // do not return project dir
-                    if e.depth() > 0 {
-                        let d = e.clone();
-                        if *parents_only {
-                            let p = d.path().parent().unwrap().to_path_buf();
-                            // println!("{:?}=>{:?}", p, res.contains(&p));
-                            if !res.contains(&p) {
-                                res.push(d.path().to_path_buf());
-                            }
-                        }
-                        else{
-                            res.push(d.path().to_path_buf());
-                        }
-                        
+        })? {
+            // do not return project dir
+            if e.depth > 0 {
+                if *parents_only {
+                    let p = e.path.parent().unwrap().to_path_buf();
+                    // println!("{:?}=>{:?}", p, res.contains(&p));
+                    if !res.contains(&p) {
+                        res.push(e.path);
                     }
                 }
-                _ => (),
+                else{
+                    res.push(e.path);
+                }
+                
             }
         }
 
@@ -211,160 +1847,536 @@ impl Project {
     /// Check if directory or file within the project folder is ignored based on:
     /// - The project generic gitignore (based on )
     /// - Any extra gitignore rules passed via [method.set_gitignore] and [method.use_project_gitignore]
+    ///
+    /// The answer carries the absolute path evaluated, whether it lies outside the project, and whether a rule decided
+    /// ```no_run
+    /// let answer = project.is_ignored("src/../target").unwrap();
+    /// println!("{:?} ignored: {} (by a rule: {})", answer.path(), answer.is_ignored(), answer.is_from_rule());
+    /// ```
     pub fn is_ignored(&self, path_str: &str) -> Option<IsIgnored> {
         let mut blank_ignored = IsIgnored {
             exists: false,
             is_dir: false,
             is_ignored: false,
+            path: PathBuf::new(),
+            outside_root: false,
+            source: IgnoreSource::NoRuleset,
+            rule: None,
+            rule_source: None,
+            rule_line: None,
         };
 
-        //
-        let is_ignored = match &self.gitignore_ruleset {
-            Some(ruleset) => {
-                // get proper dir
-                let path = PathBuf::from(path_str);
-                let path = if path.is_relative() {
-                    let mut path = self.dir.clone();
-                    path.push(path_str);
-                    path
-                } else {
-                    path
-                };
-
-                // quick determine based on whether there is a file ext
-                let re = Regex::new(r"\.\w{2}$").unwrap();
-                let mut is_dir = !re.is_match(path_str);
+        // get proper dir
+        let path = PathBuf::from(path_str);
+        let path = if path.is_relative() {
+            let mut path = self.dir.clone();
+            path.push(path_str);
+            path
+        } else {
+            path
+        };
+        let path = vfs::normalize(&path);
+        // the ".." left after normalizing climb above the filesystem root, as "/../target" does for a project at "/"
+        blank_ignored.outside_root = path.components().any(|component| component == Component::ParentDir)
+            || !path.starts_with(vfs::normalize(&self.dir));
 
-                // only if path exists...
-                if path.exists() {
-                    blank_ignored.exists = true;
-                    // check if is dir from metadata
-                    is_dir = path.metadata().expect("Cannot get metadata").is_dir();
-                }
+        // quick determine based on whether there is a file ext
+        let re = Regex::new(r"\.\w{2}$").unwrap();
+        let mut is_dir = !re.is_match(path_str);
 
-                // update is dir
-                blank_ignored.is_dir = is_dir;
+        // only if path exists...
+        if !blank_ignored.outside_root {
+            let rel_path = path.strip_prefix(vfs::normalize(&self.dir)).unwrap_or(&path);
+            if self.vfs.exists(rel_path) {
+                blank_ignored.exists = true;
+                // check if is dir
+                is_dir = self.vfs.is_dir(rel_path);
+            }
+        }
 
-                // is it ignored based on the rules?
-                blank_ignored.is_ignored = ruleset.is_ignored(path, is_dir);
+        // update is dir
+        blank_ignored.is_dir = is_dir;
 
-                blank_ignored
+        // is it ignored based on the rules?
+        if let Some(ruleset) = &self.gitignore_ruleset {
+            let rule = match blank_ignored.outside_root {
+                true => None,
+                false => ruleset.deciding_rule(&path, is_dir),
+            };
+            blank_ignored.is_ignored = rule.is_some_and(|rule| !rule.negation);
+            blank_ignored.source = match rule {
+                Some(_) => IgnoreSource::Rule,
+                None => IgnoreSource::Default,
+            };
+            blank_ignored.rule = rule.map(|rule| rule.raw.clone());
+            if let Some(RuleOrigin::Line(line)) = rule.map(|rule| rule.origin) {
+                if let Some(Some((source, line))) = self.rule_lines.get(line - 1) {
+                    blank_ignored.rule_source = Some(source.to_string());
+                    blank_ignored.rule_line = Some(*line);
+                }
             }
-            // no ruleset???
-            _ => blank_ignored,
-        };
+        }
+
+        blank_ignored.path = path;
 
-        Some(is_ignored)
+        Some(blank_ignored)
     }
 
     /// Allows you to set your own gitignore rules by passing them as a &str param
-    /// You can set update_existing to true to update the generic gitignore from [gitignores](https://github.com/starship/starship/tree/master/src/configs) or false to overwrite it
+    /// The ```strategy``` decides how they combine with the generic gitignore from [gitignores](https://github.com/starship/starship/tree/master/src/configs)
+    /// and any rules set before, see [MergeStrategy]. The rules added are listed in ```custom_rules```, in precedence
+    /// order, with the strategy that added them
     /// **Example**
     /// ```no_run
     /// let ignore_str = "ignore/this/file.js";
-    /// project.set_gitignore(ignore_str, &true)?;
+    /// project.set_gitignore(ignore_str, MergeStrategy::Append)?;
     /// ```
     ///     
-    pub fn set_gitignore(&mut self, git_str: &str, update_existing: &bool) -> Result<()> {
+    pub fn set_gitignore(&mut self, git_str: &str, strategy: MergeStrategy) -> Result<()> {
+        self.set_gitignore_from(CUSTOM_RULES, git_str, strategy)
+    }
+
+    /// [method.set_gitignore] with the rules coming from ```source```, which [IsIgnored::rule_source] names
+    fn set_gitignore_from(&mut self, source: &str, git_str: &str, strategy: MergeStrategy) -> Result<()> {
+        let mut added: Vec<CustomRule> = Gitignore::parse(git_str)
+            .rules()
+            .map(|rule| CustomRule {
+                rule: rule.to_string(),
+                strategy,
+            })
+            .collect();
+
         //get new or updated ignore text
-        let mut ignore_text = match self.generic_gitignore.clone() {
-            Some(gitignore) => {
-                if *update_existing {
-                    gitignore.clone()
-                } else {
-                    vec![]
+        let mut ignore_text = self.generic_gitignore.clone().unwrap_or_default();
+        let mut sources = self.sources();
+        let part_source = (source.to_string(), git_str.to_string());
+
+        match strategy {
+            MergeStrategy::Append => {
+                // add git str ensuring we add new line first
+                ignore_text.push(format!("\n {}", git_str));
+                sources.push(part_source);
+            }
+            MergeStrategy::Prepend => {
+                ignore_text.insert(0, format!("{}\n", git_str));
+                sources.insert(0, part_source);
+            }
+            MergeStrategy::Replace => {
+                ignore_text = vec![format!("\n {}", git_str)];
+                sources = vec![part_source];
+                self.custom_rules.clear();
+            }
+            MergeStrategy::MergeDeduped => {
+                let mut merged = self.gitignore();
+                added.retain(|added| !merged.contains(&added.rule));
+                // only the sections bringing new rules are added, as a part of their own
+                let existing = merged.sections.len();
+                merged.merge(&Gitignore::parse(git_str));
+                let new_sections = Gitignore {
+                    sections: merged.sections.split_off(existing),
+                    trailing_newline: merged.trailing_newline,
+                };
+                if !new_sections.sections.is_empty() {
+                    ignore_text.push(new_sections.to_string());
+                    sources.push(part_source);
                 }
             }
-            _ => vec![],
-        };
-
-        // add git str ensuring we add new line first
-        ignore_text.push(format!("\n {}", git_str));
+        }
 
         // println!("{:?}", ignore_text);
         self.generic_gitignore = Some(ignore_text);
-        // update rules
-        self.get_rules()?;
+        self.gitignore_sources = sources;
+
+        if strategy == MergeStrategy::Prepend {
+            added.append(&mut self.custom_rules);
+            self.custom_rules = added;
+        } else {
+            self.custom_rules.append(&mut added);
+        }
+
+        // update rules, extending the existing ruleset if we can. Invalid lines are reported & left out by a full
+        // rebuild, which also keeps the generated rules after the appended ones
+        let extend = strategy == MergeStrategy::Append
+            && ruleset::validate(git_str).is_empty()
+            && self.generated_rules()?.is_empty();
+        match &mut self.gitignore_ruleset {
+            Some(ruleset) if extend => {
+                ruleset.add_rules(git_str)?;
+                let source: Arc<str> = Arc::from(source);
+                let lines = ruleset::normalize_content(git_str).split('\n').count();
+                self.rule_lines.extend((1..=lines).map(|line| Some((Arc::clone(&source), line))));
+            }
+            _ => self.get_rules()?,
+        }
 
         Ok(())
     }
 
     /// Allows one to use the project's own .gitignore file
     /// When ```update_generic``` is true, then the project .gitgnore is merged with a generic gitignore from [gitignores](https://github.com/starship/starship/tree/master/src/configs)
+    /// The merge keeps the comments & order of both and only adds the project rules missing from the generic gitignore,
+    /// so calling it again changes nothing
     /// ```no_run
-    /// project.use_project_gitignore(&false)?;
+    /// project.use_project_gitignore(false)?;
     /// ```
-    pub fn use_project_gitignore(&mut self, update_generic: &bool) -> Result<()> {
+    pub fn use_project_gitignore(&mut self, update_generic: bool) -> Result<()> {
         // read .gitignore
-        let mut path = self.dir.clone();
-        path.push(".gitignore");
+        let path = Path::new(".gitignore");
 
         // if path exists
-        let gitignore = if path.exists() {
+        let gitignore = if self.vfs.exists(path) {
             // read file
-            match read_to_string(path) {
-                Ok(s) => s,
+            match self.vfs.read_to_string(path) {
+                Ok(s) => ruleset::normalize_content(&s),
                 _ => "".into(),
             }
         } else {
             "".into()
         };
 
-        if *update_generic {
-            self.set_gitignore(&gitignore[..], &true)?;
+        if update_generic {
+            self.set_gitignore_from(".gitignore", &gitignore, MergeStrategy::MergeDeduped)?;
         } else {
-            self.generic_gitignore = Some(vec![gitignore]);
+            self.generic_gitignore = Some(vec![gitignore.clone()]);
+            self.gitignore_sources = vec![(String::from(".gitignore"), gitignore)];
+            self.custom_rules.clear();
             // update rules
             self.get_rules()?;
         }
 
         Ok(())
     }
+    /// Detects editor & IDE artifacts in the project (.vscode/, .idea/, vim swap files and so on) and appends
+    /// the matching gitignore templates to the existing gitignore
+    /// ```no_run
+    /// project.add_editor_ignores()?;
+    /// println!("{:?}", project.editors);
+    /// ```
+    pub fn add_editor_ignores(&mut self) -> Result<()> {
+        let editors = detector::detect_editors_from_dir(self.vfs.as_ref())?;
+
+        if let Some(git_ignores) =
+            detector::get_lang_gitignore(&Some(editors.clone()), &self.configs, self.offline)?
+        {
+            for template in git_ignores {
+                self.set_gitignore_from(&template.file_name, &template.contents, MergeStrategy::Append)?;
+            }
+        }
+
+        self.editors = Some(editors);
+
+        Ok(())
+    }
+
+    /// Appends OS junk file patterns (macOS .DS_Store, Windows Thumbs.db & desktop.ini, Linux *~...) to the existing gitignore,
+    /// each OS in its own labeled section. Pass [OsIgnores::Host] to only cover the OS the crate is running on
+    /// ```no_run
+    /// project.add_os_ignores(project::OsIgnores::All)?;
+    /// ```
+    pub fn add_os_ignores(&mut self, which: OsIgnores) -> Result<()> {
+        let os_templates = detector::os_templates(which == OsIgnores::All);
+
+        if let Some(git_ignores) =
+            detector::get_lang_gitignore(&Some(os_templates.clone()), &self.configs, self.offline)?
+        {
+            for template in git_ignores {
+                self.set_gitignore_from(&template.file_name, &template.contents, MergeStrategy::Append)?;
+            }
+        }
+
+        self.os_ignores = Some(os_templates);
+
+        Ok(())
+    }
+
+    /// Toggles automatic ignoring of local environment directories (virtualenvs, .tox, .gradle...) detected by [method.parse]
+    /// Auto ignoring is on by default, pass false to have these directories counted like any other
+    /// ```no_run
+    /// project.auto_ignore_env_dirs(false)?;
+    /// ```
+    pub fn auto_ignore_env_dirs(&mut self, enabled: bool) -> Result<()> {
+        self.ignore_env_dirs = enabled;
+        // update rules
+        self.get_rules()?;
+
+        Ok(())
+    }
+
+    /// Makes gitignore rules match case insensitively, the way git does on macOS & Windows when ```core.ignoreCase``` is set
+    /// Git repositories already pick this up from their config when the project is initialized
+    /// ```no_run
+    /// project.set_ignore_case(true)?;
+    /// ```
+    pub fn set_ignore_case(&mut self, ignore_case: bool) -> Result<()> {
+        self.ignore_case = ignore_case;
+
+        if let Some(ruleset) = &mut self.gitignore_ruleset {
+            ruleset.set_case_insensitive(self.ignore_case)?;
+        }
+
+        Ok(())
+    }
+
+    /// The gitignore content the rules are built from, as sections of comments, rules & blank lines
+    /// ```no_run
+    /// for rule in project.gitignore().rules() {
+    ///     println!("{}", rule);
+    /// }
+    /// ```
+    pub fn gitignore(&self) -> Gitignore {
+        match &self.generic_gitignore {
+            Some(git_ignores) => Gitignore::parse(&git_ignores.join("\n\n")),
+            None => Gitignore::default(),
+        }
+    }
+
+    /// The name & content each part of ```generic_gitignore``` comes from. Parts set without their sources, by
+    /// assigning ```generic_gitignore```, are taken for custom rules
+    fn sources(&self) -> Vec<(String, String)> {
+        let parts = self.generic_gitignore.clone().unwrap_or_default();
+        if parts.len() == self.gitignore_sources.len() {
+            return self.gitignore_sources.clone();
+        }
+
+        parts.into_iter().map(|part| (String::from(CUSTOM_RULES), part)).collect()
+    }
+
+    /// The rules added after the gitignore content, with their source: the detected environment directories, the
+    /// submodules and the nested repositories left out of the project
+    fn generated_rules(&self) -> Result<Vec<(&'static str, String)>> {
+        let mut generated: Vec<(&str, String)> = vec![];
+        // add detected environment dirs as directory rules
+        if self.ignore_env_dirs {
+            if let Some(env_dirs) = &self.env_dirs {
+                for env_dir in env_dirs {
+                    generated.push((ENV_DIR_RULES, format!("{}/", env_dir)));
+                }
+            }
+        }
+
+        // leave submodules out as directory rules anchored to the project directory
+        if !self.include_submodules {
+            for submodule in git::submodules(self.vfs.as_ref())? {
+                let rule = format!("/{}/", submodule.path.to_string_lossy().trim_matches('/'));
+                generated.push((SUBMODULE_RULES, rule));
+            }
+        }
+
+        // nested repositories are projects of their own
+        if self.nested_repo_mode == NestedRepos::Separate {
+            for repo in self.nested_repos.iter().flatten() {
+                generated.push((NESTED_REPO_RULES, format!("/{}/", repo.to_string_lossy().trim_matches('/'))));
+            }
+        }
+
+        Ok(generated)
+    }
+
     fn get_rules(&mut self) -> Result<()> {
         let dir = &self.dir;
-        let empty_ruleset = ruleset::RuleSet::new(&dir, vec![""])?;
-
-        let rule_set: ruleset::RuleSet = match &self.generic_gitignore {
-            Some(git_ignores) => {
-                // join multiple rules separating them with new lines
-                let content = git_ignores.join("\n\n");
-                match ruleset::load_str(&dir, &content[..]) {
-                    Ok(ruleset) => ruleset,
-                    _ => empty_ruleset,
+        let empty_ruleset = ruleset::RuleSet::new(dir, vec![""])?;
+
+        // join multiple rules separating them with new lines, keeping where each line comes from
+        let mut parts: Vec<String> = vec![];
+        let mut rule_lines: Vec<Option<(Arc<str>, usize)>> = vec![];
+        let mut sources: Vec<(Arc<str>, String)> = vec![];
+        for (part, (source, original)) in self.generic_gitignore.iter().flatten().zip(self.sources()) {
+            let part = ruleset::normalize_content(part);
+            if !parts.is_empty() {
+                rule_lines.push(None);
+            }
+            let source: Arc<str> = Arc::from(source);
+            rule_lines.extend(
+                source_lines(&part, &original)
+                    .into_iter()
+                    .map(|line| line.map(|line| (Arc::clone(&source), line))),
+            );
+            parts.push(part);
+            sources.push((source, original));
+        }
+        let mut content = parts.join("\n\n");
+        if parts.is_empty() {
+            rule_lines.push(None);
+        }
+
+        // the generated rules of each source are numbered from 1
+        for (source, rule) in self.generated_rules()? {
+            content.push_str(&format!("\n{}", rule));
+            match sources.iter_mut().find(|(other, _)| **other == *source) {
+                Some((source, text)) => {
+                    text.push_str(&format!("\n{}", rule));
+                    rule_lines.push(Some((Arc::clone(source), text.split('\n').count())));
+                }
+                None => {
+                    let source: Arc<str> = Arc::from(source);
+                    rule_lines.push(Some((Arc::clone(&source), 1)));
+                    sources.push((source, rule));
+                }
+            }
+        }
+
+        // each source is validated on its own, for the diagnostics to give the line within it. Lines that cannot
+        // compile are left blank, rather than the whole gitignore, and reported with the lines that can never match
+        let mut invalid: Vec<(Arc<str>, usize)> = vec![];
+        for (source, text) in &sources {
+            for diagnostic in ruleset::validate(text) {
+                if !matches!(diagnostic.kind, DiagnosticKind::NeverMatches(_)) {
+                    invalid.push((Arc::clone(source), diagnostic.line));
+                }
+                let warning = format!("gitignore {} {}", source, diagnostic);
+                if !self.warnings.contains(&warning) {
+                    self.warnings.push(warning);
                 }
             }
+        }
+        if !invalid.is_empty() {
+            content = content
+                .split('\n')
+                .enumerate()
+                .map(|(index, line)| match rule_lines.get(index) {
+                    // several parts may have the same name, such as rules set one after the other, each its own source
+                    Some(Some((source, number)))
+                        if invalid.iter().any(|(invalid, line)| Arc::ptr_eq(invalid, source) && line == number) =>
+                    {
+                        ""
+                    }
+                    _ => line,
+                })
+                .collect::<Vec<&str>>()
+                .join("\n");
+        }
+
+        let mut rule_set: ruleset::RuleSet = match ruleset::load_str(dir, &content[..]) {
+            Ok(ruleset) => ruleset,
             _ => empty_ruleset,
         };
+        rule_set.set_case_insensitive(self.ignore_case)?;
 
         self.gitignore_ruleset = Some(rule_set);
+        self.rule_lines = rule_lines;
 
         Ok(())
     }
 
-    fn add_langs(&mut self) -> Result<()> {
+    fn add_langs(&mut self, cached: bool) -> Result<()> {
         // get lang match pattern
-        let langs = Some(detector::detect_lang_from_dir(&self.dir)?);
+        let langs = Some(detector::detect_lang_from_dir(
+            self.vfs.as_ref(),
+            self.min_evidence,
+            &self.tie_break,
+            if cached { Some(self.configs.as_ref()) } else { None },
+        )?);
+        let tools = detector::detect_tools_from_dir(self.vfs.as_ref())?;
+        let build_systems = detector::build_systems(&tools);
+        let mut kinds = detector::detect_kinds_from_dir(self.vfs.as_ref())?;
+
+        for lang in langs.iter().flatten() {
+            self.events.language_detected(lang);
+        }
 
-        self.project_langs = langs.clone();
+        // no language detected is told apart from a project without any code, rather than left as an empty list
+        self.project_langs = langs.filter(|langs| !langs.is_empty());
+        self.project_tools = Some(tools);
+        self.build_systems = if build_systems.is_empty() { None } else { Some(build_systems) };
+        if self.project_langs.is_none() {
+            kinds.extend(detector::detect_codeless_kind(self.vfs.as_ref())?);
+        }
+        self.project_kinds = if kinds.is_empty() { None } else { Some(kinds) };
 
         Ok(())
     }
 
     fn add_gitignore(&mut self) -> Result<()> {
         // get lang match pattern
-        let git_ignores = detector::get_lang_gitignore(&self.project_langs)?;
+        // languages first, then tools
+        let keys = match (&self.project_langs, &self.project_tools) {
+            (Some(langs), Some(tools)) => Some([langs.clone(), tools.clone()].concat()),
+            (langs, None) => langs.clone(),
+            (None, tools) => tools.clone(),
+        };
+        let git_ignores = detector::get_lang_gitignore(&keys, &self.configs, self.offline)?.unwrap_or_default();
+
+        self.gitignore_sources = git_ignores
+            .iter()
+            .map(|template| (template.file_name.clone(), template.contents.clone()))
+            .collect();
+        self.generic_gitignore = match git_ignores.is_empty() {
+            true => None,
+            false => Some(git_ignores.into_iter().map(|template| template.contents).collect()),
+        };
+
+        if let Some(warning) = self.configs.warning() {
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_env_dirs(&mut self) -> Result<()> {
+        // the rules known so far keep the walk out of ignored directories, such as node_modules
+        let rules = ruleset::load_str(&self.dir, &self.gitignore().to_string()).ok();
+        let env_dirs = detector::detect_env_dirs(self.vfs.as_ref(), rules.as_ref())?;
+
+        self.env_dirs = if env_dirs.is_empty() {
+            None
+        } else {
+            Some(env_dirs)
+        };
+
+        Ok(())
+    }
+
+    fn add_nested_repos(&mut self) -> Result<()> {
+        let submodules: Vec<PathBuf> = git::submodules(self.vfs.as_ref())?
+            .into_iter()
+            .map(|submodule| submodule.path)
+            .collect();
+        let repos = git::nested_repos(self.vfs.as_ref(), &submodules)?;
+
+        self.nested_repos = if repos.is_empty() { None } else { Some(repos) };
+
+        Ok(())
+    }
+
+    fn add_hooks(&mut self) -> Result<()> {
+        // worktrees share the hooks of the repository
+        let hooks_dir = git::git_dir(self.vfs.as_ref())
+            .map(|git_dir| git::common_dir(self.vfs.as_ref(), &git_dir).join("hooks"));
+        let git_hooks = hooks::detect_hooks(self.vfs.as_ref(), hooks_dir.as_deref())?;
 
-        self.generic_gitignore = git_ignores.clone();
+        self.git_hooks = if git_hooks.is_empty() {
+            None
+        } else {
+            Some(git_hooks)
+        };
 
         Ok(())
     }
 
     fn is_git(&mut self) -> Result<()> {
-        // Check if .git dir exists within project
-        let mut dir = self.dir.clone();
-        dir.push(".git");
+        // a .git directory, a .git file pointing to a worktree's git directory, or a bare repository
+        self.is_git = Some(git::git_dir(self.vfs.as_ref()).is_some());
+
+        Ok(())
+    }
+
+    fn git_ignore_case(&mut self) -> Result<()> {
+        // read core.ignoreCase from the repository config, shared by its worktrees
+        let config = match git::git_dir(self.vfs.as_ref()) {
+            Some(git_dir) => git::common_dir(self.vfs.as_ref(), &git_dir).join("config"),
+            None => return Ok(()),
+        };
+        let config = config.as_path();
 
-        self.is_git = Some(dir.exists());
+        if self.vfs.is_file(config) {
+            let re = Regex::new(r"(?im)^\s*ignorecase\s*=\s*true\s*$").unwrap();
+            self.ignore_case = match self.vfs.read_to_string(config) {
+                Ok(s) => re.is_match(&s),
+                _ => false,
+            };
+        }
 
         Ok(())
     }
@@ -0,0 +1,136 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use thiserror::Error;
+
+use crate::project::Project;
+
+/// Errors answered to the host, with their JSON-RPC codes
+#[derive(Error, Debug)]
+pub(crate) enum CallError {
+    #[error("Unknown method {0}")]
+    MethodNotFound(String),
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+    #[error("{0}")]
+    Internal(anyhow::Error),
+}
+
+impl CallError {
+    fn code(&self) -> i64 {
+        match self {
+            CallError::MethodNotFound(_) => -32601,
+            CallError::InvalidParams(_) => -32602,
+            CallError::Internal(_) => -32603,
+        }
+    }
+}
+
+/// Runs a method against the project
+/// - ```is_ignored``` with ```{"path": "src/main.rs"}```
+/// - ```stats```, lines of code, comments & blanks per language, counted afresh
+/// - ```langs```, the detected languages, also ranked by lines of code, tools & editors
+/// - ```reparse```, parses the project again
+pub(crate) fn call(project: &mut Project, method: &str, params: &Value) -> Result<Value, CallError> {
+    let result = match method {
+        "is_ignored" => {
+            let path = params["path"]
+                .as_str()
+                .ok_or_else(|| CallError::InvalidParams(String::from("path must be a string")))?;
+            json!({
+                "path": path,
+                "ignored": project.is_ignored(path),
+            })
+        }
+        "stats" => json!(project.code_snapshot().map_err(CallError::Internal)?.langs),
+        "langs" => json!({
+            "languages": project.project_langs,
+            "by_loc": project.languages_by_loc(),
+            "tools": project.project_tools,
+            "build_systems": project.build_systems,
+            "editors": project.editors,
+        }),
+        "reparse" => {
+            project.parse().map_err(CallError::Internal)?;
+            json!(project.project_langs)
+        }
+        _ => return Err(CallError::MethodNotFound(method.to_string())),
+    };
+
+    Ok(result)
+}
+
+/// Answer to a request, None for notifications, which are requests without an id
+fn answer(project: &mut Project, request: &Value) -> Option<Value> {
+    let method = request["method"].as_str().unwrap_or_default();
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let response = call(project, method, &params);
+
+    let id = request.get("id")?;
+    Some(match response {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code(), "message": error.to_string() },
+        }),
+    })
+}
+
+/// Answers JSON-RPC 2.0 requests, one per line, until the input ends or an ```exit``` request comes in
+/// Responses are written one per line too, in the order of the requests
+/// ```no_run
+/// // host: {"jsonrpc": "2.0", "id": 1, "method": "is_ignored", "params": {"path": "target/debug"}}
+/// // reply: {"jsonrpc": "2.0", "id": 1, "result": {"path": "target/debug", "ignored": {...}}}
+/// project_parse::protocol::run(&mut project, input, output)?;
+/// ```
+pub fn run<R: BufRead, W: Write>(project: &mut Project, input: R, mut output: W) -> Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(request) if request["method"].as_str() == Some("exit") => break,
+            Ok(request) => answer(project, &request),
+            Err(error) => Some(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": error.to_string() },
+            })),
+        };
+
+        if let Some(response) = response {
+            writeln!(output, "{}", serde_json::to_string(&response)?)?;
+            output.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the protocol over stdin & stdout, for editor plugins and other host processes
+/// ```no_run
+/// let mut project = Project::new("/my/project/dir")?;
+/// project.parse()?;
+/// project_parse::protocol::run_stdio(project)?;
+/// ```
+pub fn run_stdio(mut project: Project) -> Result<()> {
+    let stdin = io::stdin();
+    run(&mut project, stdin.lock(), io::stdout())
+}
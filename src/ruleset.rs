@@ -13,9 +13,11 @@
 // limitations under the License.
 
 use anyhow::Result;
-use globset::{Candidate, GlobBuilder, GlobSet, GlobSetBuilder};
+use globset::{Candidate, Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use once_cell::sync::OnceCell;
 use std::fmt;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Represents a set of rules that can be checked against to see if a path should be ignored within
 /// a Git repository.
@@ -24,89 +26,374 @@ use std::path::{Path, PathBuf};
 /// instance of this to check as many paths against as possible - this is because the highest cost
 /// is in constructing it, but checking against the compiled patterns is extremely cheap.
 ///
+/// The patterns are compiled into a single set on the first check, not each time rules change, and the
+/// compiled set is shared by the clones of the ruleset until either of them changes its rules.
+///
+/// A RuleSet is Send & Sync: checks only read it, and the first one to compile the set does so once for every
+/// thread checking at the same time. Changing the rules takes ```&mut self```, so it cannot race with checks.
+///
 // #[derive(Copy)]
 
 #[derive(Clone)]
 pub struct RuleSet {
     root: PathBuf,
     pub(crate) rules: Vec<Rule>,
-    tester: GlobSet,
+    globs: Vec<Glob>,
+    tester: Arc<OnceCell<GlobSet>>,
+    case_insensitive: bool,
+    /// number of lines the rules were parsed from, which the lines of [RuleSet::add_rules] follow
+    lines: usize,
+}
+
+/// Ignore file dialects a [RuleSet] can be built from with [RuleSet::from_dialect]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// .gitignore
+    Gitignore,
+    /// .dockerignore, whose patterns are anchored to the build context, a matching directory excluding its content
+    Dockerignore,
+    /// .npmignore, gitignore patterns on top of the files npm always leaves out or always packs
+    Npmignore,
 }
 
+impl Dialect {
+    /// Name of the ignore file of the dialect
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Dialect::Gitignore => ".gitignore",
+            Dialect::Dockerignore => ".dockerignore",
+            Dialect::Npmignore => ".npmignore",
+        }
+    }
+}
+
+/// Files npm never packs, whatever the .npmignore says
+const NPM_EXCLUDED: [&str; 14] = [
+    ".git",
+    "CVS",
+    ".svn",
+    ".hg",
+    ".lock-wscript",
+    ".wafpickle-*",
+    ".*.swp",
+    ".DS_Store",
+    "._*",
+    "npm-debug.log",
+    ".npmrc",
+    "node_modules",
+    "config.gypi",
+    "package-lock.json",
+];
+
+/// Files npm always packs, whatever the .npmignore says
+const NPM_INCLUDED: [&str; 5] = ["/package.json", "/README*", "/LICENSE*", "/LICENCE*", "/CHANGELOG*"];
+
 impl RuleSet {
     /// Construct a ruleset, given a path that is the root of the repository, and a set of rules,
     /// which is a vector
     pub fn new(root: &PathBuf, raw_rules: Vec<&str>) -> Result<RuleSet> {
-        // FIXME: Is there a better way without needing to hardcode a path here?
+        let cleaned_root = Self::normalize(root);
+        let lines = raw_rules.len();
+
+        let rules = Self::parse_rules(raw_rules, 0)?;
+        let globs = rules
+            .iter()
+            .map(|rule| Self::build_glob(rule, false))
+            .collect::<Result<Vec<Glob>>>()?;
 
-        let cleaned_root = Self::strip_prefix(root, Path::new("./"));
+        Ok(RuleSet {
+            root: cleaned_root,
+            rules,
+            globs,
+            tester: Arc::new(OnceCell::new()),
+            case_insensitive: false,
+            lines,
+        })
+    }
+
+    /// Construct a ruleset from the content of an ignore file of the given dialect, so that [RuleSet::is_ignored]
+    /// answers what goes into a docker image or an npm package
+    /// ```no_run
+    /// let content = std::fs::read_to_string("/my/project/.dockerignore")?;
+    /// let ruleset = RuleSet::from_dialect(&PathBuf::from("/my/project"), &content, Dialect::Dockerignore)?;
+    /// ```
+    pub fn from_dialect(root: &PathBuf, content: &str, dialect: Dialect) -> Result<RuleSet> {
+        let content = &normalize_content(content);
+        match dialect {
+            Dialect::Gitignore => load_str(root, content),
+            Dialect::Npmignore => {
+                let lines = content.lines().count();
+                let raw_rules: Vec<String> = content
+                    .lines()
+                    .map(String::from)
+                    .chain(NPM_EXCLUDED.iter().map(|rule| rule.to_string()))
+                    .chain(NPM_INCLUDED.iter().map(|rule| format!("!{}", rule)))
+                    .collect();
+                let mut ruleset = RuleSet::new(root, raw_rules.iter().map(String::as_str).collect())?;
+
+                // the rules npm adds are not lines of the .npmignore
+                for rule in ruleset.rules.iter_mut() {
+                    if matches!(rule.origin, RuleOrigin::Line(line) if line > lines) {
+                        rule.origin = RuleOrigin::Npm;
+                    }
+                }
+                ruleset.lines = lines;
+
+                Ok(ruleset)
+            }
+            Dialect::Dockerignore => {
+                let rules: Vec<Rule> = content
+                    .lines()
+                    .enumerate()
+                    .flat_map(|(index, line)| Self::parse_docker_line(line, RuleOrigin::Line(index + 1)))
+                    .collect();
+                let globs = rules
+                    .iter()
+                    .map(|rule| Self::build_glob(rule, false))
+                    .collect::<Result<Vec<Glob>>>()?;
+
+                Ok(RuleSet {
+                    root: Self::normalize(root),
+                    rules,
+                    globs,
+                    tester: Arc::new(OnceCell::new()),
+                    case_insensitive: false,
+                    lines: content.lines().count(),
+                })
+            }
+        }
+    }
+
+    /// Rules of a .dockerignore line. Patterns are relative to the build context, ```*``` stops at separators, and
+    /// a second rule matches the content of a matching directory
+    fn parse_docker_line(line: &str, origin: RuleOrigin) -> Vec<Rule> {
+        let raw = line.trim();
+        let mut pattern = raw;
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return vec![];
+        }
+
+        let negation = pattern.starts_with('!');
+        if negation {
+            pattern = pattern.trim_start_matches('!').trim();
+        }
+
+        let pattern = pattern.trim_start_matches("./").trim_matches('/');
+        if pattern.is_empty() {
+            return vec![];
+        }
+
+        [pattern.to_string(), format!("{}/**", pattern)]
+            .into_iter()
+            .map(|pattern| Rule {
+                pattern,
+                anchored: true,
+                dir_only: false,
+                negation,
+                raw: raw.to_string(),
+                origin,
+            })
+            .collect()
+    }
+
+    /// Add the rules contained in `content` (one per line, gitignore syntax) to the ruleset.
+    /// Only the new patterns are compiled, the existing ones are reused when the tester is rebuilt.
+    /// The rules go after all the existing ones, as if `content` ended the content the ruleset was built from.
+    pub fn add_rules(&mut self, content: &str) -> Result<()> {
+        let content = normalize_content(content);
+        let rules = Self::parse_rules(content.split('\n'), self.lines)?;
+        self.lines += content.split('\n').count();
+
+        for rule in rules {
+            self.globs.push(Self::build_glob(&rule, self.case_insensitive)?);
+            self.rules.push(rule);
+        }
+
+        self.rebuild();
+
+        Ok(())
+    }
 
+    /// Remove every rule that is identical to the given raw rule once parsed, e.g. `node_modules/`.
+    /// Returns the number of rules removed.
+    pub fn remove_matching(&mut self, raw_rule: &str) -> Result<usize> {
+        let target = match RuleSet::parse_line(raw_rule)? {
+            ParsedLine::WithRule(rule) => rule,
+            _ => return Ok(0),
+        };
+
+        let before = self.rules.len();
+        let (rules, globs): (Vec<Rule>, Vec<Glob>) = self
+            .rules
+            .drain(..)
+            .zip(self.globs.drain(..))
+            .filter(|(rule, _)| !rule.same_pattern(&target))
+            .unzip();
+        self.rules = rules;
+        self.globs = globs;
+
+        let removed = before - self.rules.len();
+        if removed > 0 {
+            self.rebuild();
+        }
+
+        Ok(removed)
+    }
+
+    /// Switch between case sensitive (the default) and case insensitive matching, the way git
+    /// does when `core.ignoreCase` is set on case-insensitive filesystems. All the patterns are
+    /// recompiled when the mode changes.
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) -> Result<()> {
+        if self.case_insensitive == case_insensitive {
+            return Ok(());
+        }
+
+        self.case_insensitive = case_insensitive;
+        self.globs = self
+            .rules
+            .iter()
+            .map(|rule| Self::build_glob(rule, case_insensitive))
+            .collect::<Result<Vec<Glob>>>()?;
+
+        self.rebuild();
+
+        Ok(())
+    }
+
+    /// Whether patterns are matched case insensitively
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Have the tester rebuilt from the compiled globs on the next check. Clones keep the tester they share
+    fn rebuild(&mut self) {
+        self.tester = Arc::new(OnceCell::new());
+    }
+
+    /// The tester, built from the compiled globs if this is the first check since the rules changed
+    fn tester(&self) -> Result<&GlobSet> {
+        self.tester.get_or_try_init(|| {
+            let mut tester_builder = GlobSetBuilder::new();
+
+            // Add globs to globset.
+            for glob in self.globs.iter() {
+                tester_builder.add(glob.clone());
+            }
+
+            Ok(tester_builder.build()?)
+        })
+    }
+
+    /// Compiles the patterns now rather than on the first check, surfacing any error
+    pub fn compile(&self) -> Result<()> {
+        self.tester()?;
+
+        Ok(())
+    }
+
+    /// Whether the patterns have been compiled since the rules last changed
+    pub fn is_compiled(&self) -> bool {
+        self.tester.get().is_some()
+    }
+
+    /// Rules of the lines, numbered from the line after ```first_line```
+    fn parse_rules<I, R>(raw_rules: I, first_line: usize) -> Result<Vec<Rule>>
+    where
+        I: IntoIterator<Item = R>,
+        R: AsRef<str>,
+    {
         let lines = raw_rules
             .into_iter()
             .map(RuleSet::parse_line)
             .collect::<Result<Vec<ParsedLine>>>()?;
 
-        let rules: Vec<Rule> = lines
-            .iter()
-            .filter_map(|parsed_line| {
-                match parsed_line {
-                    // FIXME: Remove this clone if possible, it's rank.
-                    &ParsedLine::WithRule(ref rule) => Some(rule.clone()),
-                    _ => None,
-                }
+        let rules = lines
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, parsed_line)| match parsed_line {
+                ParsedLine::WithRule(rule) => Some(Rule {
+                    origin: RuleOrigin::Line(first_line + index + 1),
+                    ..rule
+                }),
+                _ => None,
             })
             .collect();
 
-        let mut tester_builder = GlobSetBuilder::new();
-
-        // Add globs to globset.
-        for rule in rules.iter() {
-            let mut glob_builder = GlobBuilder::new(&rule.pattern);
-            glob_builder.literal_separator(rule.anchored);
-            let glob = glob_builder.build()?;
-            tester_builder.add(glob);
-        }
+        Ok(rules)
+    }
 
-        let tester = tester_builder.build()?;
+    fn build_glob(rule: &Rule, case_insensitive: bool) -> Result<Glob> {
+        let mut glob_builder = GlobBuilder::new(&rule.pattern);
+        // a backslash escapes the next character in gitignore files on every OS
+        glob_builder
+            .literal_separator(rule.anchored)
+            .case_insensitive(case_insensitive)
+            .backslash_escape(true);
 
-        Ok(RuleSet {
-            root: cleaned_root,
-            rules,
-            tester,
-        })
+        Ok(glob_builder.build()?)
     }
 
     /// Check if the given path should be considered ignored as per the rules contained within
     /// the current ruleset.
     pub fn is_ignored<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> bool {
-        // FIXME: Is there a better way without needing to hardcode a path here?
-        let mut cleaned_path = Self::strip_prefix(path.as_ref(), Path::new("./"));
-        cleaned_path = Self::strip_prefix(cleaned_path.as_path(), &self.root);
+        self.deciding_rule(path, is_dir).is_some_and(|rule| !rule.negation)
+    }
 
-        let candidate = Candidate::new(&cleaned_path);
-        let results = self.tester.matches_candidate(&candidate);
+    /// The line of the rule deciding whether the path is ignored, as written, e.g. ```!keep.log```. None when no
+    /// rule matches the path
+    pub fn matching_line<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> Option<&str> {
+        self.deciding_rule(path, is_dir).map(|rule| rule.raw.as_str())
+    }
 
-        for idx in results.iter().rev() {
-            let ref rule = self.rules[*idx];
+    /// The last rule matching the path, which ignores it or keeps it when negated. None when no rule matches
+    pub(crate) fn deciding_rule<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> Option<&Rule> {
+        let mut cleaned_path = Self::normalize(path);
+        cleaned_path = Self::strip_prefix(cleaned_path.as_path(), &self.root);
 
-            // We must backtrack through the finds until we find one that is_dir
-            // and rule.dir_only agree on.
-            if rule.dir_only && !is_dir {
-                continue;
-            }
+        // a set that fails to compile ignores nothing, [RuleSet::compile] reports why
+        let tester = match self.tester() {
+            Ok(tester) => tester,
+            Err(_) => return None,
+        };
 
-            return !rule.negation;
-        }
+        decide(&self.rules, tester, &Candidate::new(&cleaned_path), is_dir)
+    }
 
-        false
+    /// The rules in order, the last one matching a path deciding whether it is ignored. Blank & comment lines are not
+    /// rules, .dockerignore lines give two
+    /// ```no_run
+    /// for rule in ruleset.rules() {
+    ///     println!("{:?} {} -> {}", rule.origin, rule.raw, rule.pattern);
+    /// }
+    /// ```
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter()
+    }
+
+    /// A handle for checking many paths, such as on every prompt render or watcher event. The patterns are compiled
+    /// once when the handle is taken, and the paths it checks are only stripped of the project directory, not
+    /// normalized, so they are expected as the walks & watchers give them
+    /// ```no_run
+    /// let matcher = ruleset.matcher()?;
+    /// let path = RelPath::new("target/debug/app");
+    /// if matcher.matches_relative(&path, false) {
+    ///     println!("ignored");
+    /// }
+    /// ```
+    pub fn matcher(&self) -> Result<Matcher<'_>> {
+        Ok(Matcher {
+            root: &self.root,
+            rules: &self.rules,
+            tester: self.tester()?,
+        })
     }
 
     /// Given a raw pattern, parse it and attempt to construct a rule out of it. The pattern pattern
     /// rules are implemented as described in the documentation for Git at
-    /// https://git-scm.com/docs/gitignore.
+    /// https://git-scm.com/docs/gitignore. The rule is taken as a first line, [RuleSet::parse_rules] numbers them.
     fn parse_line<R: AsRef<str>>(raw_rule: R) -> Result<ParsedLine> {
         // FIXME: Can we combine some of these string scans?
-        let mut pattern = raw_rule.as_ref().trim();
+        let mut pattern = trim_line(raw_rule.as_ref());
 
         if pattern.is_empty() {
             return Ok(ParsedLine::Empty);
@@ -118,12 +405,12 @@ impl RuleSet {
 
         let negation = pattern.starts_with('!');
         if negation {
-            pattern = pattern.trim_start_matches('!').trim();
+            pattern = trim_line(pattern.trim_start_matches('!'));
         }
 
         let dir_only = pattern.ends_with('/');
         if dir_only {
-            pattern = pattern.trim_end_matches('/').trim();
+            pattern = trim_line(pattern.trim_end_matches('/'));
         }
 
         let absolute = pattern.starts_with('/');
@@ -133,10 +420,11 @@ impl RuleSet {
 
         let anchored = absolute || pattern.contains('/');
 
+        // escapes such as \# or \[ are kept for the glob to match the character literally
         let mut cleaned_pattern = if !absolute && !pattern.starts_with("**/") {
-            format!("**/{}", pattern.replace(r"\", ""))
+            format!("**/{}", pattern)
         } else {
-            pattern.replace(r"\", "")
+            pattern.to_string()
         };
 
         // If the glob ends with `/**`, then we should only match everything
@@ -151,9 +439,26 @@ impl RuleSet {
             anchored,
             dir_only,
             negation,
+            raw: trim_line(raw_rule.as_ref()).to_string(),
+            origin: RuleOrigin::Line(1),
         }))
     }
 
+    /// Normalize a path so that roots and candidates agree before prefix stripping: the leading `./`
+    /// is removed and, on Windows, so is the verbatim `\\?\` prefix returned by `canonicalize`.
+    /// Separators are left to `Path`, which understands both `/` and `\` on Windows.
+    fn normalize<P: AsRef<Path>>(path: P) -> PathBuf {
+        // FIXME: Is there a better way without needing to hardcode a path here?
+        let path = Self::strip_prefix(path, Path::new("./"));
+
+        #[cfg(windows)]
+        if let Some(path_str) = path.to_str() {
+            return PathBuf::from(windows_path(path_str));
+        }
+
+        path
+    }
+
     /// Given a path and a prefix, strip the prefix off the path. If the path does not begin with
     /// the given prefix, then return the path as is.
     fn strip_prefix<P: AsRef<Path>, PR: AsRef<Path>>(path: P, prefix: PR) -> PathBuf {
@@ -170,8 +475,78 @@ impl fmt::Debug for RuleSet {
     }
 }
 
+/// The last of the rules matching a candidate that agrees with it being a directory or not
+fn decide<'r>(rules: &'r [Rule], tester: &GlobSet, candidate: &Candidate, is_dir: bool) -> Option<&'r Rule> {
+    // We must backtrack through the finds until we find one that is_dir
+    // and rule.dir_only agree on.
+    tester
+        .matches_candidate(candidate)
+        .iter()
+        .rev()
+        .map(|idx| &rules[*idx])
+        .find(|rule| !rule.dir_only || is_dir)
+}
+
+/// A path relative to the project directory, prepared once for matching, as taken by [Matcher::matches_relative]
+pub struct RelPath<'a> {
+    candidate: Candidate<'a>,
+}
+
+impl<'a> RelPath<'a> {
+    /// Prepares a path relative to the project directory, such as ```src/main.rs```
+    pub fn new<P: AsRef<Path> + ?Sized>(path: &'a P) -> Self {
+        RelPath {
+            candidate: Candidate::new(path.as_ref()),
+        }
+    }
+}
+
+/// Compiled rules checked without compiling or normalizing again, returned by [RuleSet::matcher]
+#[derive(Clone, Copy)]
+pub struct Matcher<'a> {
+    root: &'a Path,
+    rules: &'a [Rule],
+    tester: &'a GlobSet,
+}
+
+impl Matcher<'_> {
+    /// Whether a path relative to the project directory is ignored. The fast path: nothing is stripped nor allocated
+    pub fn matches_relative(&self, path: &RelPath, is_dir: bool) -> bool {
+        decide(self.rules, self.tester, &path.candidate, is_dir).is_some_and(|rule| !rule.negation)
+    }
+
+    /// Whether a path is ignored, a full path being stripped of the project directory first
+    pub fn is_ignored<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> bool {
+        let path = path.as_ref();
+        let relative = path.strip_prefix(self.root).unwrap_or(path);
+
+        self.matches_relative(&RelPath::new(relative), is_dir)
+    }
+}
+
+impl fmt::Debug for Matcher<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Matcher")
+            .field("root", &self.root)
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+/// Where a rule of a [RuleSet] comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleOrigin {
+    /// the line, starting at 1, of the content the ruleset was built from, the lines of [RuleSet::add_rules]
+    /// following on
+    Line(usize),
+    /// a file npm always leaves out or always packs, added by [Dialect::Npmignore]
+    Npm,
+}
+
+/// A parsed rule of a [RuleSet], returned by [RuleSet::rules]
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub(crate) struct Rule {
+pub struct Rule {
+    /// The glob the rule is compiled to, e.g. ```**/target``` for ```target/```
     pub pattern: String,
     /// Whether this rule is anchored. If a rule is anchored (contains a slash)
     /// then wildcards inside the rule are not allowed to match a `/` in the
@@ -182,6 +557,20 @@ pub(crate) struct Rule {
     /// Whether the rule should, if it matches, negate any previously matching
     /// patterns. This flag has no effect if no previous patterns had matched.
     pub negation: bool,
+    /// The line the rule was parsed from, without surrounding whitespace.
+    pub raw: String,
+    /// Where the line comes from
+    pub origin: RuleOrigin,
+}
+
+impl Rule {
+    /// Whether both rules match the same paths the same way, however they were written
+    fn same_pattern(&self, other: &Rule) -> bool {
+        self.pattern == other.pattern
+            && self.anchored == other.anchored
+            && self.dir_only == other.dir_only
+            && self.negation == other.negation
+    }
 }
 
 enum ParsedLine {
@@ -190,8 +579,158 @@ enum ParsedLine {
     WithRule(Rule),
 }
 
+/// Why a gitignore line is reported by [validate]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// a ```[``` without its closing ```]```
+    UnmatchedBracket,
+    /// the pattern could not be compiled, with the reason
+    InvalidGlob(String),
+    /// the pattern is valid but no path can match it on this OS, with the reason
+    NeverMatches(String),
+}
+
+/// A gitignore line that cannot work as intended, returned by [validate]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// line number, starting at 1
+    pub line: usize,
+    /// the line as written
+    pub text: String,
+    /// what is wrong with it
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match &self.kind {
+            DiagnosticKind::UnmatchedBracket => String::from("unmatched ["),
+            DiagnosticKind::InvalidGlob(reason) => format!("invalid pattern, {}", reason),
+            DiagnosticKind::NeverMatches(reason) => format!("never matches, {}", reason),
+        };
+        write!(f, "line {}: {:?} {}", self.line, self.text, reason)
+    }
+}
+
+/// A gitignore line without its surrounding whitespace, trailing spaces escaped with a backslash aside
+fn trim_line(line: &str) -> &str {
+    let line = line.trim_start();
+    let trimmed = line.trim_end();
+
+    // an odd number of backslashes escapes the space after them
+    let escapes = trimmed.len() - trimmed.trim_end_matches('\\').len();
+    match line[trimmed.len()..].chars().next() {
+        Some(space) if escapes % 2 == 1 => &line[..trimmed.len() + space.len_utf8()],
+        _ => trimmed,
+    }
+}
+
+/// Whether a ```[``` is left open, escaped brackets aside
+fn has_unmatched_bracket(pattern: &str) -> bool {
+    let mut open = false;
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' if !open => {
+                open = true;
+                // a ] right after the opening bracket, or after a negation, is part of the class
+                let mut rest = chars.clone();
+                match rest.next() {
+                    Some('!') | Some('^') if rest.next() == Some(']') => {
+                        chars.nth(1);
+                    }
+                    Some(']') => {
+                        chars.next();
+                    }
+                    _ => {}
+                }
+            }
+            ']' if open => open = false,
+            _ => {}
+        }
+    }
+
+    open
+}
+
+/// Why no path can match the pattern on this OS, if so
+fn never_matches(line: &str) -> Option<String> {
+    let pattern = line.trim().trim_start_matches('!').trim();
+
+    if pattern.trim_matches('/').is_empty() {
+        return Some(String::from("it only names the project directory, which is never ignored"));
+    }
+
+    if cfg!(windows) {
+        if let Some(c) = pattern.chars().find(|c| matches!(c, '<' | '>' | ':' | '"' | '|')) {
+            return Some(format!("{:?} is not allowed in Windows file names", c));
+        }
+    }
+
+    None
+}
+
+/// Checks gitignore ```content``` line by line, reporting the lines that cannot be compiled or can never match,
+/// rather than having them silently left out of the rules
+/// ```no_run
+/// for diagnostic in project_parse::ruleset::validate("target/\n[abc\n") {
+///     println!("{}", diagnostic);
+/// }
+/// ```
+pub fn validate(content: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    for (index, text) in normalize_content(content).lines().enumerate() {
+        let rule = match RuleSet::parse_line(text) {
+            Ok(ParsedLine::WithRule(rule)) => rule,
+            _ => continue,
+        };
+
+        let kind = if has_unmatched_bracket(text.trim()) {
+            Some(DiagnosticKind::UnmatchedBracket)
+        } else if let Err(error) = RuleSet::build_glob(&rule, false) {
+            Some(DiagnosticKind::InvalidGlob(error.to_string()))
+        } else {
+            never_matches(text).map(DiagnosticKind::NeverMatches)
+        };
+
+        if let Some(kind) = kind {
+            diagnostics.push(Diagnostic {
+                line: index + 1,
+                text: text.to_string(),
+                kind,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// A Windows path without its verbatim ```\\?\``` prefix and with forward slashes, so that
+/// ```\\?\C:\projects\app\build``` & ```C:/projects/app/build``` are stripped of the project directory alike
+// only used on Windows, but tested everywhere
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(crate) fn windows_path(path: &str) -> String {
+    path.strip_prefix(r"\\?\").unwrap_or(path).replace('\\', "/")
+}
+
+/// Ignore file content without a leading UTF-8 byte order mark, and with ```\n``` line endings. Files saved on
+/// Windows otherwise have their first rule start with the mark, so that it never matches
+pub fn normalize_content(content: &str) -> String {
+    content
+        .trim_start_matches('\u{feff}')
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+}
+
+/// Builds a ruleset from gitignore content, one rule per line. Byte order marks & CRLF line endings are tolerated
 pub fn load_str(root: &PathBuf, content: &str) -> Result<RuleSet> {
     //
+    let content = normalize_content(content);
     let split = content.split("\n");
     let lines = split.collect::<Vec<&str>>();
 
@@ -0,0 +1,119 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::project::Project;
+use crate::stats::{CodeStats, StatsDelta};
+
+/// What changed between two scans, passed to the [method.schedule] callback
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectDiff {
+    /// languages detected by this scan only
+    pub added_langs: Vec<String>,
+    /// languages detected by the previous scan only
+    pub removed_langs: Vec<String>,
+    /// tools detected by this scan only
+    pub added_tools: Vec<String>,
+    /// tools detected by the previous scan only
+    pub removed_tools: Vec<String>,
+    /// changes of the code since the previous scan
+    pub stats: StatsDelta,
+    /// the code as of this scan, for up to date totals
+    pub snapshot: CodeStats,
+}
+
+impl ProjectDiff {
+    /// Whether nothing changed since the previous scan
+    pub fn is_empty(&self) -> bool {
+        self.added_langs.is_empty()
+            && self.removed_langs.is_empty()
+            && self.added_tools.is_empty()
+            && self.removed_tools.is_empty()
+            && self.stats.is_empty()
+    }
+}
+
+/// Background re-scan started by [method.schedule]. Dropping it stops the re-scans too, without waiting
+#[derive(Debug)]
+pub struct Schedule {
+    stop: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Schedule {
+    /// Stops re-scanning, waiting for a scan in progress to end
+    pub fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+    }
+}
+
+/// Values of ```new``` missing from ```old```
+fn added(new: &Option<Vec<String>>, old: &Option<Vec<String>>) -> Vec<String> {
+    let old = old.as_deref().unwrap_or_default();
+
+    new.iter()
+        .flatten()
+        .filter(|value| !old.contains(value))
+        .cloned()
+        .collect()
+}
+
+/// Parses the project again and compares it with its previous state
+fn rescan(project: &mut Project, previous: &CodeStats) -> anyhow::Result<ProjectDiff> {
+    let (langs, tools) = (project.project_langs.clone(), project.project_tools.clone());
+
+    // an index taken before would hide the files changed since
+    if project.index.is_some() {
+        project.index()?;
+    }
+    project.parse()?;
+    let snapshot = project.code_snapshot()?;
+
+    Ok(ProjectDiff {
+        added_langs: added(&project.project_langs, &langs),
+        removed_langs: added(&langs, &project.project_langs),
+        added_tools: added(&project.project_tools, &tools),
+        removed_tools: added(&tools, &project.project_tools),
+        stats: snapshot.diff(previous),
+        snapshot,
+    })
+}
+
+pub(crate) fn schedule<F>(mut project: Project, interval: Duration, mut callback: F) -> Schedule
+where
+    F: FnMut(ProjectDiff) + Send + 'static,
+{
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    let thread = thread::spawn(move || {
+        let mut previous = project.code_snapshot().unwrap_or_default();
+
+        // a message or the Schedule being dropped both stop the loop
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            // a failed scan is skipped, the next one compares with the last successful one
+            if let Ok(diff) = rescan(&mut project, &previous) {
+                previous = diff.snapshot.clone();
+                callback(diff);
+            }
+        }
+    });
+
+    Schedule { stop, thread }
+}
@@ -0,0 +1,149 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::code;
+use crate::ruleset::RuleSet;
+use crate::vfs::Vfs;
+
+/// A code generator turning schemas into code, in [SchemaReport]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum Codegen {
+    /// [buf](https://buf.build), configured in buf.yaml or buf.gen.yaml
+    Buf,
+    /// prost-build or tonic-build, build dependencies of Cargo.toml
+    Prost,
+    /// [GraphQL Code Generator](https://the-guild.dev/graphql/codegen), a codegen config file or a package.json
+    /// dependency
+    GraphqlCodegen,
+    /// [gqlgen](https://gqlgen.com), configured in gqlgen.yml
+    Gqlgen,
+    /// [sqlc](https://sqlc.dev), configured in sqlc.yaml or sqlc.json
+    Sqlc,
+    /// [diesel](https://diesel.rs), configured in diesel.toml
+    Diesel,
+}
+
+/// A directory of SQL migrations, in [SchemaReport]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationDir {
+    /// path relative to the project directory
+    pub path: PathBuf,
+    /// number of SQL files within it
+    pub files: usize,
+}
+
+/// The schema & interface definition files of the project and the code generators configured to turn them into code,
+/// returned by [method.schemas]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SchemaReport {
+    /// the .proto files, relative to the project directory
+    pub protobuf: Vec<PathBuf>,
+    /// the .graphql & .gql files, relative to the project directory
+    pub graphql: Vec<PathBuf>,
+    /// the directories of SQL migrations
+    pub migrations: Vec<MigrationDir>,
+    /// the code generators configured, in [Codegen] order
+    pub codegen: Vec<Codegen>,
+}
+
+impl SchemaReport {
+    /// Whether the project has no schema at all
+    pub fn is_empty(&self) -> bool {
+        self.protobuf.is_empty() && self.graphql.is_empty() && self.migrations.is_empty()
+    }
+}
+
+/// Names of the directories holding migrations, such as migrations/ or Rails' db/migrate/
+const MIGRATION_DIRS: [&str; 2] = ["migrations", "migrate"];
+
+fn has_file(vfs: &dyn Vfs, names: &[&str]) -> bool {
+    names.iter().any(|name| vfs.is_file(Path::new(name)))
+}
+
+/// Code generators configured at the top of the project
+fn codegen(vfs: &dyn Vfs) -> Vec<Codegen> {
+    let read = |name: &str| vfs.read_to_string(Path::new(name)).unwrap_or_default();
+    let mut codegen = vec![];
+
+    if has_file(vfs, &["buf.yaml", "buf.gen.yaml"]) {
+        codegen.push(Codegen::Buf);
+    }
+    let cargo_toml = read("Cargo.toml");
+    if cargo_toml.contains("prost-build") || cargo_toml.contains("tonic-build") {
+        codegen.push(Codegen::Prost);
+    }
+    if has_file(vfs, &["codegen.yml", "codegen.yaml", "codegen.ts", "codegen.js"])
+        || read("package.json").contains("\"@graphql-codegen/cli\"")
+    {
+        codegen.push(Codegen::GraphqlCodegen);
+    }
+    if has_file(vfs, &["gqlgen.yml", "gqlgen.yaml"]) {
+        codegen.push(Codegen::Gqlgen);
+    }
+    if has_file(vfs, &["sqlc.yaml", "sqlc.yml", "sqlc.json"]) {
+        codegen.push(Codegen::Sqlc);
+    }
+    if has_file(vfs, &["diesel.toml"]) {
+        codegen.push(Codegen::Diesel);
+    }
+
+    codegen
+}
+
+/// Schema files & migration directories met walking the project, hidden & ignored entries aside, and the code
+/// generators configured. Migration directories within another are counted with the outermost
+pub(crate) fn report(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<SchemaReport> {
+    let entries = vfs.walk(&mut |e| {
+        e.depth == 0 || !(code::is_hidden(e) || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)))
+    })?;
+    let mut report = SchemaReport::default();
+
+    for e in entries.iter().filter(|e| e.is_file) {
+        let rel_path = match e.path.strip_prefix(vfs.root()) {
+            Ok(rel_path) => rel_path,
+            Err(_) => continue,
+        };
+        let extension = rel_path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+
+        match extension.as_str() {
+            "proto" => report.protobuf.push(rel_path.to_path_buf()),
+            "graphql" | "gql" => report.graphql.push(rel_path.to_path_buf()),
+            "sql" => {
+                let migrations = rel_path.ancestors().skip(1).filter(|dir| {
+                    dir.file_name()
+                        .is_some_and(|name| MIGRATION_DIRS.contains(&name.to_string_lossy().as_ref()))
+                });
+                // ancestors go up from the file, the outermost migration directory comes last
+                if let Some(dir) = migrations.last() {
+                    match report.migrations.iter_mut().find(|migration| migration.path == dir) {
+                        Some(migration) => migration.files += 1,
+                        None => report.migrations.push(MigrationDir {
+                            path: dir.to_path_buf(),
+                            files: 1,
+                        }),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    report.codegen = codegen(vfs);
+
+    Ok(report)
+}
@@ -0,0 +1,86 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use regex::Regex;
+use std::{
+    path::{Path, PathBuf},
+    thread,
+};
+
+use crate::vfs::Vfs;
+
+/// A match found by [method.search]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// path of the file
+    pub path: PathBuf,
+    /// line number, starting at 1
+    pub line: usize,
+    /// column of the first character of the match, starting at 1 and counted in characters
+    pub column: usize,
+    /// the whole line the match was found on
+    pub text: String,
+}
+
+/// Files that are not valid UTF-8 or contain NUL bytes are taken to be binary and skipped
+fn search_file(vfs: &dyn Vfs, path: &Path, re: &Regex) -> Vec<SearchMatch> {
+    let content = match vfs.read_to_string(path) {
+        Ok(content) if !content.contains('\0') => content,
+        _ => return vec![],
+    };
+
+    let mut matches = vec![];
+    for (i, line) in content.lines().enumerate() {
+        for m in re.find_iter(line) {
+            matches.push(SearchMatch {
+                path: path.to_path_buf(),
+                line: i + 1,
+                column: line[..m.start()].chars().count() + 1,
+                text: line.to_string(),
+            });
+        }
+    }
+
+    matches
+}
+
+/// Searches the files, relative to the project directory, spreading them over as many threads as there are cores
+/// Matches are sorted by path, line & column
+pub(crate) fn search(vfs: &dyn Vfs, files: &[PathBuf], re: &Regex) -> Vec<SearchMatch> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = files.len().div_ceil(threads).max(1);
+
+    let mut matches: Vec<SearchMatch> = thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .flat_map(|path| search_file(vfs, path, re))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_default())
+            .collect()
+    });
+
+    matches.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+
+    matches
+}
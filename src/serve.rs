@@ -0,0 +1,148 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+
+use crate::project::Project;
+use crate::protocol;
+
+/// Decodes %XX escapes and + of a query string value
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let byte = std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match byte {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Value of a query string parameter, parameters without a value being empty
+fn param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| decode(value))
+}
+
+/// Answers a request target such as ```/ignored?path=src/main.rs``` with a status and a JSON body
+/// Any endpoint re-parses the project first when ```refresh``` is passed
+pub(crate) fn route(project: &mut Project, target: &str) -> Result<(u16, Value)> {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    if param(query, "refresh").is_some() {
+        project.parse()?;
+    }
+
+    let body = match path {
+        "/langs" => protocol::call(project, "langs", &Value::Null)?,
+        "/stats" => protocol::call(project, "stats", &Value::Null)?,
+        "/ignored" => match param(query, "path") {
+            Some(path) => protocol::call(project, "is_ignored", &json!({ "path": path }))?,
+            None => return Ok((400, json!({ "error": "the path parameter is missing" }))),
+        },
+        _ => return Ok((404, json!({ "error": format!("{} not found", path) }))),
+    };
+
+    Ok((200, body))
+}
+
+fn respond(project: &mut Project, stream: TcpStream) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // the headers are not needed, they are read past so the client is not reset
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => match route(project, target) {
+            Ok(response) => response,
+            Err(error) => (500, json!({ "error": error.to_string() })),
+        },
+        _ => (405, json!({ "error": "only GET requests are served" })),
+    };
+
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+    let body = serde_json::to_string(&body)?;
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+
+    Ok(())
+}
+
+/// Serves the project stats as JSON over HTTP, answering one request at a time. Requires the **serve** feature
+/// - ```GET /langs``` the detected languages, also ranked by lines of code, tools & editors
+/// - ```GET /stats``` lines of code, comments & blanks per language, counted afresh on every request
+/// - ```GET /ignored?path=src/main.rs``` whether a path is ignored
+///
+/// Pass ```refresh``` to any endpoint, e.g. ```/langs?refresh```, to parse the project again before answering
+/// ```no_run
+/// let mut project = Project::new("/my/project/dir")?;
+/// project.parse()?;
+/// project_parse::serve("127.0.0.1:7878", project)?;
+/// ```
+pub fn serve<A: ToSocketAddrs>(addr: A, mut project: Project) -> Result<()> {
+    let listener = TcpListener::bind(addr)?;
+
+    // a broken connection only concerns its client
+    for stream in listener.incoming().flatten() {
+        let _ = respond(&mut project, stream);
+    }
+
+    Ok(())
+}
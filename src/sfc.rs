@@ -0,0 +1,161 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap, path::Path};
+
+use crate::stats::{Count, StatsLang};
+
+/// The lowercase extension of a file
+fn extension(path: &Path) -> Option<String> {
+    path.extension().map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// Whether a file is a Vue, Svelte or Astro single-file component, from its extension
+pub(crate) fn is_component(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| ext == "vue" || ext == "svelte" || ext == "astro")
+}
+
+/// Whether a file is an Astro component, whose frontmatter is code
+pub(crate) fn is_astro(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| ext == "astro")
+}
+
+/// Whether a file is a plain HTML page, from its extension
+pub(crate) fn is_html(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| ext == "html" || ext == "htm" || ext == "xhtml")
+}
+
+/// The value of an attribute in an opening tag, such as ```ts``` for ```lang="ts"```
+fn attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!("{}=", name))? + name.len() + 1;
+    let value = tag[start..].trim_start_matches(['"', '\'']);
+    value.split(['"', '\'', ' ', '>']).next()
+}
+
+/// Language of a script block, JavaScript unless its lang or type says TypeScript or JSON
+fn script_lang(tag: &str) -> StatsLang {
+    let lang = attr(tag, "lang").or_else(|| attr(tag, "type")).unwrap_or("js");
+
+    match lang.to_lowercase().as_str() {
+        "ts" | "tsx" | "typescript" | "text/typescript" => StatsLang::TypeScript,
+        "application/json" | "application/ld+json" | "importmap" => StatsLang::Json,
+        _ => StatsLang::JavaScript,
+    }
+}
+
+/// Counts lines of one language, following the block comments that span lines
+struct Counter {
+    counts: BTreeMap<StatsLang, Count>,
+    /// the end of the block comment the previous lines opened
+    in_comment: Option<&'static str>,
+}
+
+impl Counter {
+    fn add(&mut self, lang: &StatsLang, line: &str) {
+        let line = line.trim();
+        let count = self.counts.entry(lang.clone()).or_default();
+        count.lines += 1;
+
+        if line.is_empty() {
+            count.blank += 1;
+            return;
+        }
+
+        if let Some(end) = self.in_comment {
+            count.comment += 1;
+            if line.contains(end) {
+                self.in_comment = None;
+            }
+            return;
+        }
+
+        let (open, end) = match lang {
+            StatsLang::Html => ("<!--", "-->"),
+            _ => ("/*", "*/"),
+        };
+        let is_line_comment = matches!(lang, StatsLang::JavaScript | StatsLang::TypeScript) && line.starts_with("//");
+
+        if is_line_comment {
+            count.comment += 1;
+        } else if let Some(rest) = line.strip_prefix(open) {
+            count.comment += 1;
+            if !rest.contains(end) {
+                self.in_comment = Some(end);
+            }
+        } else {
+            count.code += 1;
+        }
+    }
+}
+
+/// Line counts of a single-file component or HTML page split by block: markup as HTML, ```<script>``` blocks as
+/// JavaScript or TypeScript, ```<style>``` blocks as CSS, and with ```frontmatter```, the ```---``` fenced frontmatter
+/// of Astro components as TypeScript. The lines of the block tags themselves are markup
+pub(crate) fn count(content: &str, frontmatter: bool) -> Vec<(StatsLang, Count)> {
+    let mut counter = Counter {
+        counts: BTreeMap::new(),
+        in_comment: None,
+    };
+    // the language of the block the line is in, with the tag that closes it; None for markup
+    let mut block: Option<(StatsLang, &str)> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+
+        // ---
+        // const title = "Astro frontmatter";
+        // ---
+        if frontmatter && trimmed == "---" && (index == 0 || matches!(block, Some((_, "---")))) {
+            counter.add(&StatsLang::Html, line);
+            block = match block {
+                None => Some((StatsLang::TypeScript, "---")),
+                Some(_) => None,
+            };
+            continue;
+        }
+
+        match &block {
+            Some((_, close)) if trimmed.starts_with(close) => {
+                counter.add(&StatsLang::Html, line);
+                counter.in_comment = None;
+                block = None;
+            }
+            Some((lang, _)) => {
+                let lang = lang.clone();
+                counter.add(&lang, line);
+            }
+            None => {
+                let opened = if counter.in_comment.is_some() {
+                    None
+                } else if trimmed.starts_with("<script") {
+                    Some((script_lang(trimmed), "</script"))
+                } else if trimmed.starts_with("<style") {
+                    Some((StatsLang::Css, "</style"))
+                } else {
+                    None
+                };
+
+                counter.add(&StatsLang::Html, line);
+                // blocks closed on the line they open on are markup
+                if let Some((lang, close)) = opened {
+                    if !trimmed.contains(close) {
+                        block = Some((lang, close));
+                    }
+                }
+            }
+        }
+    }
+
+    counter.counts.into_iter().collect()
+}
@@ -0,0 +1,120 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::stats::CodeStats;
+use crate::tree::FileTree;
+
+/// Tables are created if missing, so that one database can hold the analyses of many projects
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS projects (
+    id INTEGER PRIMARY KEY,
+    dir TEXT NOT NULL,
+    analyzed_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS languages (
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    lang TEXT NOT NULL,
+    files INTEGER NOT NULL,
+    code INTEGER NOT NULL,
+    comment INTEGER NOT NULL,
+    blank INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS files (
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    path TEXT NOT NULL,
+    is_dir INTEGER NOT NULL,
+    lang TEXT,
+    bytes INTEGER NOT NULL,
+    code INTEGER,
+    ignored INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS detections (
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS files_project ON files(project_id);
+";
+
+/// What was detected in a project, as (kind, value) rows: languages, tools, editors...
+pub(crate) type Detections = Vec<(&'static str, String)>;
+
+/// Writes one project into the database, in a single transaction, returning its id in the ```projects``` table
+pub(crate) fn export(
+    path: &Path,
+    dir: &Path,
+    tree: &FileTree,
+    stats: &CodeStats,
+    detections: &Detections,
+) -> Result<i64> {
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    let analyzed_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    tx.execute(
+        "INSERT INTO projects (dir, analyzed_at) VALUES (?1, ?2)",
+        params![dir.to_string_lossy().to_string(), analyzed_at],
+    )?;
+    let project_id = tx.last_insert_rowid();
+
+    for (lang, lang_stats) in &stats.langs {
+        tx.execute(
+            "INSERT INTO languages (project_id, lang, files, code, comment, blank) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                project_id,
+                lang,
+                lang_stats.files as i64,
+                lang_stats.code as i64,
+                lang_stats.comment as i64,
+                lang_stats.blank as i64
+            ],
+        )?;
+    }
+
+    // the project directory itself is left out
+    for node in tree.iter().filter(|node| !node.path.as_os_str().is_empty()) {
+        let code = stats.files.get(&node.path).map(|file| file.code as i64);
+        tx.execute(
+            "INSERT INTO files (project_id, path, is_dir, lang, bytes, code, ignored) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                project_id,
+                node.path.to_string_lossy().to_string(),
+                node.is_dir,
+                node.lang,
+                node.size as i64,
+                code,
+                node.is_ignored
+            ],
+        )?;
+    }
+
+    for (kind, value) in detections {
+        tx.execute(
+            "INSERT INTO detections (project_id, kind, value) VALUES (?1, ?2, ?3)",
+            params![project_id, kind, value],
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(project_id)
+}
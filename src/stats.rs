@@ -0,0 +1,698 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+#[cfg(feature = "loc")]
+use loc::Lang;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Borrow,
+    cmp::{Ordering, Reverse},
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet},
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{Add, AddAssign, Sub},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[cfg(feature = "loc")]
+use crate::loc_count;
+use crate::tree::FileTree;
+use crate::vfs::Vfs;
+
+/// Lines of a file or language, as counted by [method.get_code_stats]. Counts add up with ```+``` & ```+=```, and
+/// subtract with ```-```, which stops at zero
+/// ```no_run
+/// let stats = project.get_code_stats()?.unwrap_or_default();
+/// let total = stats.values().fold(Count::default(), |total, count| total + *count);
+/// println!("{:.0}% comments", total.comment_ratio() * 100.0);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Count {
+    /// lines of code
+    pub code: u64,
+    /// comment lines
+    pub comment: u64,
+    /// blank lines
+    pub blank: u64,
+    /// all lines
+    pub lines: u64,
+}
+
+impl Count {
+    /// ```part``` over all lines, 0 when there are no lines
+    fn ratio(&self, part: u64) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            part as f64 / self.lines as f64
+        }
+    }
+
+    /// Share of the lines that are code, from 0 to 1
+    pub fn code_ratio(&self) -> f64 {
+        self.ratio(self.code)
+    }
+
+    /// Share of the lines that are comments, from 0 to 1
+    pub fn comment_ratio(&self) -> f64 {
+        self.ratio(self.comment)
+    }
+
+    /// Share of the lines that are blank, from 0 to 1
+    pub fn blank_ratio(&self) -> f64 {
+        self.ratio(self.blank)
+    }
+
+    /// Comment lines per line of code, 0 without code
+    pub fn comments_per_code(&self) -> f64 {
+        if self.code == 0 {
+            0.0
+        } else {
+            self.comment as f64 / self.code as f64
+        }
+    }
+}
+
+impl Add for Count {
+    type Output = Count;
+
+    fn add(self, other: Count) -> Count {
+        Count {
+            code: self.code + other.code,
+            comment: self.comment + other.comment,
+            blank: self.blank + other.blank,
+            lines: self.lines + other.lines,
+        }
+    }
+}
+
+impl AddAssign for Count {
+    fn add_assign(&mut self, other: Count) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Count {
+    type Output = Count;
+
+    fn sub(self, other: Count) -> Count {
+        Count {
+            code: self.code.saturating_sub(other.code),
+            comment: self.comment.saturating_sub(other.comment),
+            blank: self.blank.saturating_sub(other.blank),
+            lines: self.lines.saturating_sub(other.lines),
+        }
+    }
+}
+
+impl Count {
+    /// Line counts of a file from its content, recognizing the comments of its language as ```loc::count``` does
+    /// reading the file. Without the ```loc``` feature nothing is counted
+    #[cfg(feature = "loc")]
+    pub(crate) fn of_content(path_str: &str, content: &str) -> Count {
+        loc_count::count_str(path_str, content)
+    }
+
+    /// Line counts of a file from its content. Without the ```loc``` feature nothing is counted
+    #[cfg(not(feature = "loc"))]
+    pub(crate) fn of_content(_path_str: &str, _content: &str) -> Count {
+        Count::default()
+    }
+}
+
+#[cfg(feature = "loc")]
+impl From<loc::Count> for Count {
+    fn from(count: loc::Count) -> Self {
+        Count {
+            code: count.code as u64,
+            comment: count.comment as u64,
+            blank: count.blank as u64,
+            lines: count.lines as u64,
+        }
+    }
+}
+
+/// Language of the line counts of [method.get_code_stats], named as the code stats name it. Languages without a
+/// variant of their own are kept as [StatsLang::Other]. Serialized as its name, e.g. ```"C++"``` for [StatsLang::Cpp]
+/// ```no_run
+/// let stats = project.get_code_stats()?.unwrap_or_default();
+/// if let Some(count) = stats.get(&StatsLang::Rust) {
+///     println!("{}: {} lines", StatsLang::Rust, count.code);
+/// }
+/// // keys can be looked up by name too
+/// let json = stats.get("JSON");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "String", into = "String")]
+pub enum StatsLang {
+    /// C
+    C,
+    /// C++
+    Cpp,
+    /// C/C++ Header
+    CCppHeader,
+    /// C#
+    CSharp,
+    /// CSS
+    Css,
+    /// Dart
+    Dart,
+    /// Docker
+    Docker,
+    /// Elixir
+    Elixir,
+    /// Erlang
+    Erlang,
+    /// Go
+    Go,
+    /// Haskell
+    Haskell,
+    /// HTML
+    Html,
+    /// Java
+    Java,
+    /// JavaScript
+    JavaScript,
+    /// JSON
+    Json,
+    /// Jsx
+    Jsx,
+    /// Kotlin
+    Kotlin,
+    /// Lua
+    Lua,
+    /// Makefile
+    Makefile,
+    /// Markdown
+    Markdown,
+    /// Objective-C
+    ObjectiveC,
+    /// PHP
+    Php,
+    /// Python
+    Python,
+    /// R
+    R,
+    /// Ruby
+    Ruby,
+    /// Rust
+    Rust,
+    /// Scala
+    Scala,
+    /// Bourne Shell
+    Shell,
+    /// SQL
+    Sql,
+    /// Swift
+    Swift,
+    /// TOML
+    Toml,
+    /// TypeScript
+    TypeScript,
+    /// XML
+    Xml,
+    /// YAML
+    Yaml,
+    /// Zig
+    Zig,
+    /// Unrecognized
+    Unrecognized,
+    /// any other language, by name
+    Other(String),
+}
+
+impl StatsLang {
+    /// Name of the language, e.g. ```"JavaScript"```
+    pub fn as_str(&self) -> &str {
+        match self {
+            StatsLang::C => "C",
+            StatsLang::Cpp => "C++",
+            StatsLang::CCppHeader => "C/C++ Header",
+            StatsLang::CSharp => "C#",
+            StatsLang::Css => "CSS",
+            StatsLang::Dart => "Dart",
+            StatsLang::Docker => "Docker",
+            StatsLang::Elixir => "Elixir",
+            StatsLang::Erlang => "Erlang",
+            StatsLang::Go => "Go",
+            StatsLang::Haskell => "Haskell",
+            StatsLang::Html => "HTML",
+            StatsLang::Java => "Java",
+            StatsLang::JavaScript => "JavaScript",
+            StatsLang::Json => "JSON",
+            StatsLang::Jsx => "Jsx",
+            StatsLang::Kotlin => "Kotlin",
+            StatsLang::Lua => "Lua",
+            StatsLang::Makefile => "Makefile",
+            StatsLang::Markdown => "Markdown",
+            StatsLang::ObjectiveC => "Objective-C",
+            StatsLang::Php => "PHP",
+            StatsLang::Python => "Python",
+            StatsLang::R => "R",
+            StatsLang::Ruby => "Ruby",
+            StatsLang::Rust => "Rust",
+            StatsLang::Scala => "Scala",
+            StatsLang::Shell => "Bourne Shell",
+            StatsLang::Sql => "SQL",
+            StatsLang::Swift => "Swift",
+            StatsLang::Toml => "TOML",
+            StatsLang::TypeScript => "TypeScript",
+            StatsLang::Xml => "XML",
+            StatsLang::Yaml => "YAML",
+            StatsLang::Zig => "Zig",
+            StatsLang::Unrecognized => "Unrecognized",
+            StatsLang::Other(name) => name,
+        }
+    }
+
+    /// Whether the language is a config, data or documentation format, such as TOML, JSON or Markdown, rather than code
+    /// ```no_run
+    /// assert!(StatsLang::Toml.is_data() && !StatsLang::Rust.is_data());
+    /// ```
+    pub fn is_data(&self) -> bool {
+        // loc names TOML "Toml", which is kept as [StatsLang::Other]
+        matches!(
+            self.as_str(),
+            "JSON" | "Markdown" | "TOML" | "Toml" | "XML" | "YAML" | "INI" | "Plain Text" | "reStructuredText"
+        )
+    }
+}
+
+impl From<&str> for StatsLang {
+    fn from(name: &str) -> Self {
+        match name {
+            "C" => StatsLang::C,
+            "C++" => StatsLang::Cpp,
+            "C/C++ Header" => StatsLang::CCppHeader,
+            "C#" => StatsLang::CSharp,
+            "CSS" => StatsLang::Css,
+            "Dart" => StatsLang::Dart,
+            "Docker" => StatsLang::Docker,
+            "Elixir" => StatsLang::Elixir,
+            "Erlang" => StatsLang::Erlang,
+            "Go" => StatsLang::Go,
+            "Haskell" => StatsLang::Haskell,
+            "HTML" => StatsLang::Html,
+            "Java" => StatsLang::Java,
+            "JavaScript" => StatsLang::JavaScript,
+            "JSON" => StatsLang::Json,
+            "Jsx" => StatsLang::Jsx,
+            "Kotlin" => StatsLang::Kotlin,
+            "Lua" => StatsLang::Lua,
+            "Makefile" => StatsLang::Makefile,
+            "Markdown" => StatsLang::Markdown,
+            "Objective-C" => StatsLang::ObjectiveC,
+            "PHP" => StatsLang::Php,
+            "Python" => StatsLang::Python,
+            "R" => StatsLang::R,
+            "Ruby" => StatsLang::Ruby,
+            "Rust" => StatsLang::Rust,
+            "Scala" => StatsLang::Scala,
+            "Bourne Shell" => StatsLang::Shell,
+            "SQL" => StatsLang::Sql,
+            "Swift" => StatsLang::Swift,
+            "TOML" => StatsLang::Toml,
+            "TypeScript" => StatsLang::TypeScript,
+            "XML" => StatsLang::Xml,
+            "YAML" => StatsLang::Yaml,
+            "Zig" => StatsLang::Zig,
+            "Unrecognized" => StatsLang::Unrecognized,
+            _ => StatsLang::Other(name.to_string()),
+        }
+    }
+}
+
+impl From<String> for StatsLang {
+    fn from(name: String) -> Self {
+        StatsLang::from(name.as_str())
+    }
+}
+
+impl From<StatsLang> for String {
+    fn from(lang: StatsLang) -> Self {
+        lang.as_str().to_string()
+    }
+}
+
+impl StatsLang {
+    /// Language of a file, from its extension, [StatsLang::Unrecognized] when it is not code. Without the ```loc```
+    /// feature no file is recognized
+    #[cfg(feature = "loc")]
+    pub(crate) fn of_path(path_str: &str) -> StatsLang {
+        StatsLang::from(loc::lang_from_ext(path_str))
+    }
+
+    /// Language of a file, from its extension, [StatsLang::Unrecognized] when it is not code. Without the ```loc```
+    /// feature no file is recognized
+    #[cfg(not(feature = "loc"))]
+    pub(crate) fn of_path(_path_str: &str) -> StatsLang {
+        StatsLang::Unrecognized
+    }
+}
+
+impl StatsLang {
+    /// How line comments start in the language, for lines counted without ```loc```
+    pub(crate) fn line_comment(&self) -> &'static str {
+        match self {
+            StatsLang::Sql | StatsLang::Haskell | StatsLang::Lua => "--",
+            StatsLang::Python | StatsLang::R | StatsLang::Shell | StatsLang::Ruby | StatsLang::Elixir => "#",
+            StatsLang::Toml | StatsLang::Yaml | StatsLang::Makefile | StatsLang::Docker => "#",
+            StatsLang::Erlang => "%",
+            StatsLang::Other(name) if name == "Julia" => "#",
+            _ => "//",
+        }
+    }
+}
+
+#[cfg(feature = "loc")]
+impl From<Lang> for StatsLang {
+    fn from(lang: Lang) -> Self {
+        StatsLang::from(lang.to_s())
+    }
+}
+
+impl fmt::Display for StatsLang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// compared by name, so that an Other named after a variant equals the variant and keys can be borrowed as names
+
+impl PartialEq for StatsLang {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for StatsLang {}
+
+impl Hash for StatsLang {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialOrd for StatsLang {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for StatsLang {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Borrow<str> for StatsLang {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Snapshot of the project code, taken by [method.code_snapshot]. It can be saved as JSON and compared with a later
+/// snapshot of the same directory using [CodeStats::diff]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeStats {
+    /// totals keyed by language
+    pub langs: BTreeMap<String, LangStats>,
+    /// code files keyed by their path relative to the project directory
+    pub files: BTreeMap<PathBuf, FileStats>,
+}
+
+/// Totals of a language in [CodeStats]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LangStats {
+    /// number of files
+    pub files: usize,
+    /// lines of code
+    pub code: u64,
+    /// comment lines
+    pub comment: u64,
+    /// blank lines
+    pub blank: u64,
+}
+
+/// A code file in [CodeStats]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileStats {
+    /// language, as named by the code stats
+    pub lang: String,
+    /// lines of code
+    pub code: u64,
+    /// hashes of the non blank lines, sorted, so that lines can be compared without keeping the content
+    pub lines: Vec<u64>,
+}
+
+/// Entries met by the code stats walk of [method.get_code_stats], kept in ```walk_stats```
+/// Skipped directories, hidden or ignored, are never read, so their content is not counted
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WalkStats {
+    /// directories walked, the project directory included
+    pub dirs: usize,
+    /// files walked
+    pub files: usize,
+    /// hidden or ignored directories left out without being read
+    pub skipped_dirs: usize,
+    /// hidden or ignored files left out
+    pub skipped_files: usize,
+    /// Git LFS pointers left out of the line counts
+    pub lfs_pointers: usize,
+    /// minified scripts & stylesheets and compiled bundles left out of the line counts
+    pub minified: usize,
+    /// files marked as generated, counted apart or left out
+    pub generated: usize,
+    /// time the walk & line counting took
+    pub duration: Duration,
+}
+
+/// Changes between two [CodeStats], returned by [CodeStats::diff]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatsDelta {
+    /// changes keyed by language. Languages without changes are left out
+    pub langs: BTreeMap<String, LangDelta>,
+    /// files present in the newer snapshot only
+    pub added_files: Vec<PathBuf>,
+    /// files present in the older snapshot only
+    pub removed_files: Vec<PathBuf>,
+    /// files present in both whose lines differ
+    pub changed_files: Vec<PathBuf>,
+}
+
+/// Changes of a language in [StatsDelta]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LangDelta {
+    /// non blank lines added
+    pub added: u64,
+    /// non blank lines removed
+    pub removed: u64,
+    /// files added, removed or changed
+    pub files_changed: usize,
+    /// difference in lines of code, newer minus older
+    pub code: i64,
+}
+
+impl StatsDelta {
+    /// Whether nothing changed between the snapshots
+    pub fn is_empty(&self) -> bool {
+        self.langs.is_empty()
+    }
+}
+
+/// Number of lines of ```a``` missing from ```b```, both sorted, repeated lines counted as many times as they appear
+fn missing(a: &[u64], b: &[u64]) -> u64 {
+    let (mut i, mut j, mut missing) = (0, 0, 0);
+
+    while i < a.len() {
+        if j >= b.len() || a[i] < b[j] {
+            missing += 1;
+            i += 1;
+        } else if a[i] > b[j] {
+            j += 1;
+        } else {
+            i += 1;
+            j += 1;
+        }
+    }
+
+    missing
+}
+
+impl CodeStats {
+    /// Totals of all the languages
+    /// ```no_run
+    /// let snapshot = project.code_snapshot()?;
+    /// println!("{} lines of code in {} files", snapshot.total().code, snapshot.files());
+    /// ```
+    pub fn total(&self) -> LangStats {
+        self.langs.values().fold(LangStats::default(), |total, lang| LangStats {
+            files: total.files + lang.files,
+            code: total.code + lang.code,
+            comment: total.comment + lang.comment,
+            blank: total.blank + lang.blank,
+        })
+    }
+
+    /// Totals keyed by language, in alphabetical order
+    pub fn per_language(&self) -> &BTreeMap<String, LangStats> {
+        &self.langs
+    }
+
+    /// Share of the lines of code of each language, in percent. Empty when there is no code
+    pub fn percentages(&self) -> BTreeMap<String, f64> {
+        let total = self.total().code;
+        if total == 0 {
+            return BTreeMap::new();
+        }
+
+        self.langs
+            .iter()
+            .map(|(lang, stats)| (lang.clone(), stats.code as f64 * 100.0 / total as f64))
+            .collect()
+    }
+
+    /// Number of code files
+    pub fn files(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Languages in alphabetical order, the order they are serialized in
+    pub fn alphabetical(&self) -> Vec<(&str, &LangStats)> {
+        self.langs.iter().map(|(lang, stats)| (lang.as_str(), stats)).collect()
+    }
+
+    /// Languages with the most lines of code first, languages with as many lines as each other in alphabetical order
+    /// ```no_run
+    /// for (lang, stats) in project.code_snapshot()?.by_lines_desc() {
+    ///     println!("{}: {} lines in {} files", lang, stats.code, stats.files);
+    /// }
+    /// ```
+    pub fn by_lines_desc(&self) -> Vec<(&str, &LangStats)> {
+        let mut langs = self.alphabetical();
+        // the sort is stable, ties keep their alphabetical order
+        langs.sort_by_key(|(_, stats)| Reverse(stats.code));
+        langs
+    }
+
+    /// Per language lines added & removed and files changed since an ```older``` snapshot of the same directory
+    /// Lines are compared regardless of their position, so moved lines are not counted as changes
+    /// ```no_run
+    /// let before: CodeStats = serde_json::from_str(&std::fs::read_to_string("before.json")?)?;
+    /// let delta = project.code_snapshot()?.diff(&before);
+    /// for (lang, change) in delta.langs {
+    ///     println!("{}: +{} -{} in {} files", lang, change.added, change.removed, change.files_changed);
+    /// }
+    /// ```
+    pub fn diff(&self, older: &CodeStats) -> StatsDelta {
+        let mut delta = StatsDelta::default();
+        let empty = FileStats::default();
+
+        let paths: BTreeSet<&PathBuf> = self.files.keys().chain(older.files.keys()).collect();
+
+        for path in paths {
+            let (new, old) = (self.files.get(path), older.files.get(path));
+            match (new, old) {
+                (Some(_), None) => delta.added_files.push(path.clone()),
+                (None, Some(_)) => delta.removed_files.push(path.clone()),
+                (Some(new), Some(old)) if new.lines != old.lines || new.lang != old.lang => {
+                    delta.changed_files.push(path.clone())
+                }
+                _ => continue,
+            }
+
+            let (new, old) = (new.unwrap_or(&empty), old.unwrap_or(&empty));
+
+            if new.lang == old.lang {
+                let change = delta.langs.entry(new.lang.clone()).or_default();
+                change.added += missing(&new.lines, &old.lines);
+                change.removed += missing(&old.lines, &new.lines);
+                change.files_changed += 1;
+                change.code += new.code as i64 - old.code as i64;
+            } else {
+                // added & removed files, and files whose language changed, count wholly
+                for (file, is_new) in [(new, true), (old, false)] {
+                    if file.lang.is_empty() {
+                        continue;
+                    }
+                    let change = delta.langs.entry(file.lang.clone()).or_default();
+                    let lines = file.lines.len() as u64;
+                    if is_new {
+                        change.added += lines;
+                        change.code += file.code as i64;
+                    } else {
+                        change.removed += lines;
+                        change.code -= file.code as i64;
+                    }
+                    change.files_changed += 1;
+                }
+            }
+        }
+
+        delta
+    }
+}
+
+fn line_hashes(content: &str) -> Vec<u64> {
+    let mut hashes: Vec<u64> = content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut hasher = DefaultHasher::new();
+            line.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    hashes.sort_unstable();
+
+    hashes
+}
+
+/// Counts the non ignored code files of the tree
+pub(crate) fn snapshot(vfs: &dyn Vfs, root: &Path, tree: &FileTree) -> Result<CodeStats> {
+    let mut stats = CodeStats::default();
+
+    for node in tree.iter().filter(|node| !node.is_dir && !node.is_ignored) {
+        let lang = match &node.lang {
+            Some(lang) => lang,
+            None => continue,
+        };
+
+        // the content is read once, for the counts & the line hashes alike. A file that cannot be read counts no lines
+        let content = vfs.read_to_string(&node.path).ok();
+        let count = match &content {
+            Some(content) => node.count_content(vfs, root, content)?,
+            None => Count::default(),
+        };
+        let lang_stats = stats.langs.entry(lang.clone()).or_default();
+        lang_stats.files += 1;
+        lang_stats.code += count.code;
+        lang_stats.comment += count.comment;
+        lang_stats.blank += count.blank;
+
+        stats.files.insert(
+            node.path.clone(),
+            FileStats {
+                lang: lang.clone(),
+                code: count.code,
+                lines: line_hashes(content.as_deref().unwrap_or_default()),
+            },
+        );
+    }
+
+    Ok(stats)
+}
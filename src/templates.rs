@@ -0,0 +1,347 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env, fmt,
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::detector;
+
+/// Templates shipped with the crate, used when the cache is empty and downloading is impossible or disabled
+const BUNDLED: &str = include_str!("../assets/gitignores.json");
+
+/// Number of download attempts before giving up
+const ATTEMPTS: u32 = 3;
+/// Delay before the first retry, doubled after every failed attempt
+const BACKOFF: Duration = Duration::from_millis(500);
+/// Time during which a failed download is not tried again, the bundled templates standing in
+const RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Languages detected in each project directory, along with the fingerprint of the directory they were detected in
+type LangCache = HashMap<PathBuf, (u64, Vec<String>)>;
+
+/// A gitignore template as served by [gitignore.io](https://www.toptal.com/developers/gitignore)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Template {
+    /// template key, e.g. **node** or **jetbrains**
+    pub key: String,
+    /// display name, e.g. **Node** or **JetBrains**
+    pub name: String,
+    /// name of the template file, e.g. **Node.gitignore**
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    /// the gitignore rules
+    pub contents: String,
+}
+
+/// The gitignore templates a Project resolves its languages, tools & editors against
+/// Projects share [Configs::shared] unless given their own with [crate::project::Project::configs], so that analyses
+/// with different template sets can run side by side. Templates are loaded once, on the first use that reads the cache
+/// or downloads them, after which they are read without locking. Until then the bundled templates stand in for them and
+/// the next use that may download tries again, a minute after a failed download, so that neither an offline project
+/// nor a failed download pins the bundled templates for the projects that follow. Downloads go through the fetcher
+/// given with [Configs::with_fetcher], or the one set crate-wide with [set_fetcher]
+/// ```no_run
+/// let mut custom = HashMap::new();
+/// custom.insert(String::from("rust"), templates::get("rust").unwrap());
+/// project.configs(Arc::new(Configs::from_templates(custom))).parse()?;
+/// ```
+pub struct Configs {
+    /// gitignore templates read from the cache, downloaded or given, set by the first load that succeeds
+    git_ignores: OnceCell<HashMap<String, Template>>,
+    /// the bundled templates, parsed when they first stand in for the others
+    bundled: OnceCell<HashMap<String, Template>>,
+    /// when the last download failed and the warning it recorded, cleared once templates are loaded
+    failure: Mutex<Option<(Instant, String)>>,
+    /// where downloaded templates are cached
+    cache_file: PathBuf,
+    /// the client downloading templates, the crate-wide one when None
+    fetcher: Option<Arc<dyn HttpFetch>>,
+    /// languages detected by the projects using these configs, see [Configs::clear_detection_cache]
+    lang_cache: Mutex<LangCache>,
+    /// number of detections answered from ```lang_cache```
+    pub(crate) lang_cache_hits: AtomicUsize,
+}
+
+impl Default for Configs {
+    fn default() -> Self {
+        Configs::with_cache(env::temp_dir().join("git-ignores.json"))
+    }
+}
+
+impl fmt::Debug for Configs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.git_ignores.get() {
+            Some(git_ignores) => write!(f, "Configs {{ {} templates }}", git_ignores.len()),
+            None => write!(f, "Configs {{ not loaded }}"),
+        }
+    }
+}
+
+static SHARED: Lazy<Arc<Configs>> = Lazy::new(|| Arc::new(Configs::new()));
+
+impl Configs {
+    /// Configs loading the cached, downloaded or bundled templates on first use
+    pub fn new() -> Self {
+        Configs::default()
+    }
+
+    /// Configs caching downloaded templates in ```cache_file``` rather than in the temp directory
+    /// ```no_run
+    /// let configs = Arc::new(Configs::with_cache("/var/cache/my-tool/git-ignores.json"));
+    /// ```
+    pub fn with_cache<P: Into<PathBuf>>(cache_file: P) -> Self {
+        Configs {
+            git_ignores: OnceCell::new(),
+            bundled: OnceCell::new(),
+            failure: Mutex::new(None),
+            cache_file: cache_file.into(),
+            fetcher: None,
+            lang_cache: Mutex::new(HashMap::new()),
+            lang_cache_hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// Downloads templates with ```fetcher``` rather than the crate-wide fetcher set with [set_fetcher], so that
+    /// projects with their own configs can go through their own client
+    /// ```no_run
+    /// let agent = ureq::AgentBuilder::new().timeout(std::time::Duration::from_secs(5)).build();
+    /// project.configs(Arc::new(Configs::new().with_fetcher(agent))).parse()?;
+    /// ```
+    pub fn with_fetcher<F: HttpFetch + 'static>(mut self, fetcher: F) -> Self {
+        self.fetcher = Some(Arc::new(fetcher));
+        self
+    }
+
+    /// Configs using the given templates only, keyed as gitignore.io keys them. Nothing is ever downloaded
+    pub fn from_templates(git_ignores: HashMap<String, Template>) -> Self {
+        let configs = Configs::new();
+        // the cell is empty, setting it cannot fail
+        let _ = configs.git_ignores.set(git_ignores);
+        configs
+    }
+
+    /// The configs every project uses unless given others
+    pub fn shared() -> Arc<Configs> {
+        SHARED.clone()
+    }
+
+    /// Get the gitignore templates, loading them if needed.
+    /// When offline, templates are only ever read from the local cache or the bundled set
+    pub fn git_ignores(&self, offline: bool) -> &HashMap<String, Template> {
+        if let Some(git_ignores) = self.git_ignores.get() {
+            return git_ignores;
+        }
+
+        let mut failure = self.failure.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let cooling = failure.as_ref().is_some_and(|(failed_at, _)| failed_at.elapsed() < RETRY_AFTER);
+
+        let fetcher = self.fetcher.clone().or_else(default_fetcher);
+        match self
+            .git_ignores
+            .get_or_try_init(|| get_ignores(&self.cache_file, fetcher.as_deref(), offline || cooling))
+        {
+            Ok(git_ignores) => {
+                *failure = None;
+                git_ignores
+            }
+            Err(warning) => {
+                if let Some(warning) = warning {
+                    *failure = Some((Instant::now(), warning));
+                }
+                self.bundled.get_or_init(bundled_ignores)
+            }
+        }
+    }
+
+    /// The template of a language, tool, editor or OS, by the name detection reports it under or by any key known
+    /// to gitignore.io, compared case insensitively
+    /// ```no_run
+    /// let template = Configs::shared().resolve("python", false);
+    /// ```
+    pub fn resolve(&self, name: &str, offline: bool) -> Option<&Template> {
+        detector::resolve_template(name, self.git_ignores(offline))
+    }
+
+    /// Forgets the languages detected so far by the projects using these configs. Detection results are kept per
+    /// directory and reused for as long as the names & modification times of its top level entries stay the same,
+    /// unless parsing with ```cache_detection``` off
+    /// ```no_run
+    /// Configs::shared().clear_detection_cache();
+    /// ```
+    pub fn clear_detection_cache(&self) {
+        self.lang_cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+    }
+
+    /// The languages detected in ```dir``` when it had the same fingerprint
+    pub(crate) fn cached_langs(&self, dir: &Path, fingerprint: u64) -> Option<Vec<String>> {
+        let cache = self.lang_cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (_, langs) = cache.get(dir).filter(|(key, _)| *key == fingerprint)?;
+        self.lang_cache_hits.fetch_add(1, Ordering::Relaxed);
+
+        Some(langs.clone())
+    }
+
+    /// Keeps the languages detected in ```dir``` until its fingerprint changes
+    pub(crate) fn cache_langs(&self, dir: &Path, fingerprint: u64, langs: &[String]) {
+        let mut cache = self.lang_cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        cache.insert(dir.to_path_buf(), (fingerprint, langs.to_vec()));
+    }
+
+    /// Warning recorded when the templates in use are a fallback
+    pub fn warning(&self) -> Option<String> {
+        let failure = self.failure.lock().ok()?;
+        failure.as_ref().map(|(_, warning)| warning.clone())
+    }
+}
+
+/// Get the template for any key known to gitignore.io, whether or not it was detected in a project.
+/// The content can then be fed to [crate::project::Project::set_gitignore]
+/// ```no_run
+/// if let Some(template) = templates::get("jetbrains") {
+///     project.set_gitignore(&template.contents, MergeStrategy::Append)?;
+/// }
+/// ```
+pub fn get(key: &str) -> Option<Template> {
+    Configs::shared().git_ignores(false).get(key).cloned()
+}
+
+/// List the keys of all available templates, sorted alphabetically
+/// ```no_run
+/// for key in templates::list() {
+///     println!("{}", key);
+/// }
+/// ```
+pub fn list() -> Vec<String> {
+    let mut keys: Vec<String> = Configs::shared().git_ignores(false).keys().cloned().collect();
+    keys.sort();
+    keys
+}
+
+/// The cached or downloaded templates. Fails with the warning to record when the bundled templates must stand in,
+/// None when downloading is not allowed or possible in the first place
+fn get_ignores(
+    ignores_file: &Path,
+    fetcher: Option<&dyn HttpFetch>,
+    offline: bool,
+) -> Result<HashMap<String, Template>, Option<String>> {
+    // read cached templates
+    if let Ok(ignores_str) = read_to_string(ignores_file) {
+        if let Ok(ignores_obj) = serde_json::from_str(&ignores_str) {
+            return Ok(ignores_obj);
+        }
+    }
+
+    // without the net feature templates are only downloaded through a fetcher given to the configs or set_fetcher
+    let fetcher = match fetcher {
+        Some(fetcher) if !offline => fetcher,
+        _ => return Err(None),
+    };
+
+    let git_ignore_url = "https://www.gitignore.io/api/list?format=json";
+    let downloaded = fetch(fetcher, git_ignore_url).and_then(|ignores_str| {
+        let ignores_obj: HashMap<String, Template> = serde_json::from_str(&ignores_str)?;
+        // save, a failed write only means we download again next time
+        let _ = write(ignores_file, &ignores_str);
+        Ok(ignores_obj)
+    });
+
+    downloaded.map_err(|e| {
+        Some(format!(
+            "Could not download gitignore templates ({}), using bundled templates instead",
+            e
+        ))
+    })
+}
+
+fn bundled_ignores() -> HashMap<String, Template> {
+    serde_json::from_str(BUNDLED).expect("Unable To Parse Bundled GitIgnore")
+}
+
+/// Performs the HTTP GET requests used to download gitignore templates.
+/// Implement it to route template downloads through your own client, or pass a configured [ureq::Agent]
+pub trait HttpFetch: Send + Sync {
+    /// Fetch ```url``` and return the response body
+    fn fetch(&self, url: &str) -> Result<String>;
+}
+
+#[cfg(feature = "net")]
+impl HttpFetch for ureq::Agent {
+    fn fetch(&self, url: &str) -> Result<String> {
+        Ok(self.get(url).call()?.into_string()?)
+    }
+}
+
+/// The fetcher used for template downloads by the configs without their own. Without the net feature there is none
+/// until one is set
+#[cfg(feature = "net")]
+pub(crate) static FETCHER: Lazy<RwLock<Option<Arc<dyn HttpFetch>>>> = Lazy::new(|| {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .build();
+
+    RwLock::new(Some(Arc::new(agent)))
+});
+
+#[cfg(not(feature = "net"))]
+pub(crate) static FETCHER: Lazy<RwLock<Option<Arc<dyn HttpFetch>>>> = Lazy::new(|| RwLock::new(None));
+
+/// Sets the client used for template downloads, for example to go through a proxy or use custom timeouts
+/// It applies to [Configs::shared] and to every other configs not given a fetcher with [Configs::with_fetcher]
+/// ```no_run
+/// let agent = ureq::AgentBuilder::new()
+///     .proxy(ureq::Proxy::new("http://proxy.example.com:8080")?)
+///     .timeout(std::time::Duration::from_secs(5))
+///     .build();
+/// templates::set_fetcher(agent);
+/// ```
+pub fn set_fetcher<F: HttpFetch + 'static>(fetcher: F) {
+    // the lock only guards a swap of the fetcher, which a panic cannot leave half done
+    *FETCHER.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Arc::new(fetcher));
+}
+
+/// The crate-wide fetcher, see [set_fetcher]
+pub(crate) fn default_fetcher() -> Option<Arc<dyn HttpFetch>> {
+    FETCHER.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// GET ```url``` using ```fetcher```, retrying with exponential backoff on failure
+fn fetch(fetcher: &dyn HttpFetch, url: &str) -> Result<String> {
+    let mut delay = BACKOFF;
+    let mut attempt = 1;
+
+    loop {
+        match fetcher.fetch(url) {
+            Ok(body) => return Ok(body),
+            Err(e) if attempt >= ATTEMPTS => return Err(e),
+            Err(_) => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap, path::Path};
+
+use crate::stats::{Count, StatsLang};
+
+/// How a templating format mixes a host language with an embedded one, so that [method.get_code_stats] counts them
+/// apart. A line goes to the embedded language when it only holds embedded code, and to the host language otherwise
+/// ```no_run
+/// let mut langs = EmbeddedLang::defaults();
+/// langs.push(EmbeddedLang::new(&[".hbs"], StatsLang::Html, StatsLang::from("Handlebars"), &[("{{", "}}")]));
+/// project.embedded_langs(langs).get_code_stats()?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedLang {
+    /// file name endings the format is recognized by, such as ```.html.erb```, compared case insensitively
+    pub suffixes: Vec<String>,
+    /// language of the text outside the embedded code
+    pub host: StatsLang,
+    /// language of the embedded code
+    pub embedded: StatsLang,
+    /// pairs of delimiters opening & closing embedded code, such as ```<%``` & ```%>```. Embedded code may span lines
+    pub delimiters: Vec<(String, String)>,
+    /// starts of the lines, leading whitespace aside, that are embedded code as a whole, such as ```<``` for the markup
+    /// of JSX
+    pub line_prefixes: Vec<String>,
+}
+
+impl EmbeddedLang {
+    /// A format whose embedded code is enclosed in delimiters
+    pub fn new(suffixes: &[&str], host: StatsLang, embedded: StatsLang, delimiters: &[(&str, &str)]) -> Self {
+        EmbeddedLang {
+            suffixes: suffixes.iter().map(|suffix| suffix.to_lowercase()).collect(),
+            host,
+            embedded,
+            delimiters: delimiters
+                .iter()
+                .map(|(open, close)| (open.to_string(), close.to_string()))
+                .collect(),
+            line_prefixes: vec![],
+        }
+    }
+
+    /// Sets the starts of the lines that are embedded code as a whole
+    pub fn line_prefixes(mut self, prefixes: &[&str]) -> Self {
+        self.line_prefixes = prefixes.iter().map(|prefix| prefix.to_string()).collect();
+        self
+    }
+
+    /// The formats counted by default: ERB, Jinja & Nunjucks templates, PHP mixed with HTML, and the markup of JSX
+    /// & TSX, counted as HTML
+    pub fn defaults() -> Vec<EmbeddedLang> {
+        let jinja = StatsLang::from("Jinja");
+
+        vec![
+            EmbeddedLang::new(&[".erb"], StatsLang::Html, StatsLang::Ruby, &[("<%", "%>")]),
+            EmbeddedLang::new(
+                &[".j2", ".jinja", ".jinja2", ".njk"],
+                StatsLang::Html,
+                jinja,
+                &[("{%", "%}"), ("{{", "}}"), ("{#", "#}")],
+            ),
+            EmbeddedLang::new(&[".php", ".phtml"], StatsLang::Html, StatsLang::Php, &[("<?php", "?>"), ("<?=", "?>")]),
+            EmbeddedLang::new(&[".jsx"], StatsLang::JavaScript, StatsLang::Html, &[]).line_prefixes(&["<", "/>"]),
+            EmbeddedLang::new(&[".tsx"], StatsLang::TypeScript, StatsLang::Html, &[]).line_prefixes(&["<", "/>"]),
+        ]
+    }
+
+    /// Whether a file is of the format, from the end of its name
+    pub fn matches(&self, path: &Path) -> bool {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_lowercase(),
+            None => return false,
+        };
+
+        self.suffixes.iter().any(|suffix| name.ends_with(suffix.as_str()))
+    }
+
+    /// Line counts of a file of the format, per language
+    pub(crate) fn count(&self, content: &str) -> Vec<(StatsLang, Count)> {
+        let mut counts: BTreeMap<StatsLang, Count> = BTreeMap::new();
+        // the closing delimiter of the embedded code the previous lines left open
+        let mut open_block: Option<&str> = None;
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+            let was_embedded = open_block.is_some();
+            let (has_host, has_embedded) = self.scan(trimmed, &mut open_block);
+
+            let is_embedded = match (has_host, has_embedded) {
+                (false, false) => was_embedded,
+                (host, embedded) => !host && embedded,
+            };
+            let is_embedded = is_embedded
+                || self.line_prefixes.iter().any(|prefix| trimmed.starts_with(prefix.as_str()));
+
+            let lang = if is_embedded { &self.embedded } else { &self.host };
+            let count = counts.entry(lang.clone()).or_default();
+            count.lines += 1;
+
+            // embedded comments follow the delimiter, as in <%# note %>
+            let body = match is_embedded {
+                true => self
+                    .delimiters
+                    .iter()
+                    .find_map(|(open, _)| trimmed.strip_prefix(open.as_str()))
+                    .unwrap_or(trimmed)
+                    .trim_start(),
+                false => trimmed,
+            };
+
+            if trimmed.is_empty() {
+                count.blank += 1;
+            } else if is_comment(lang, trimmed) || (is_embedded && is_comment(lang, body)) {
+                count.comment += 1;
+            } else {
+                count.code += 1;
+            }
+        }
+
+        counts.into_iter().collect()
+    }
+
+    /// Whether a line holds host text and embedded code, following the embedded code that spans lines
+    fn scan<'a>(&'a self, mut line: &str, open_block: &mut Option<&'a str>) -> (bool, bool) {
+        let (mut has_host, mut has_embedded) = (false, false);
+
+        while !line.is_empty() {
+            match *open_block {
+                Some(close) => match line.find(close) {
+                    Some(end) => {
+                        has_embedded = true;
+                        line = &line[end + close.len()..];
+                        *open_block = None;
+                    }
+                    None => {
+                        has_embedded = true;
+                        line = "";
+                    }
+                },
+                None => {
+                    let next = self
+                        .delimiters
+                        .iter()
+                        .filter_map(|(open, close)| line.find(open.as_str()).map(|start| (start, open, close)))
+                        .min_by_key(|(start, _, _)| *start);
+
+                    match next {
+                        Some((start, open, close)) => {
+                            has_host = has_host || !line[..start].trim().is_empty();
+                            has_embedded = true;
+                            line = &line[start + open.len()..];
+                            *open_block = Some(close.as_str());
+                        }
+                        None => {
+                            has_host = has_host || !line.trim().is_empty();
+                            line = "";
+                        }
+                    }
+                }
+            }
+        }
+
+        (has_host, has_embedded)
+    }
+}
+
+/// Whether a line is a comment of the language it is counted as
+fn is_comment(lang: &StatsLang, line: &str) -> bool {
+    match lang {
+        StatsLang::Html => line.starts_with("<!--"),
+        StatsLang::Other(name) if name == "Jinja" => line.starts_with("{#"),
+        _ => line.starts_with(lang.line_comment()),
+    }
+}
+
+/// Line counts of a templating file per language, None when no format matches it
+pub(crate) fn count(langs: &[EmbeddedLang], path: &Path, content: &str) -> Option<Vec<(StatsLang, Count)>> {
+    langs
+        .iter()
+        .find(|lang| lang.matches(path))
+        .map(|lang| lang.count(content))
+}
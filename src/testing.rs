@@ -0,0 +1,180 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::code;
+use crate::ruleset::RuleSet;
+use crate::vfs::Vfs;
+
+/// A test framework, returned by [method.test_frameworks]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum TestFramework {
+    /// ```cargo test```, built into every Cargo project
+    CargoTest,
+    /// [nextest](https://nexte.st), configured in .config/nextest.toml
+    Nextest,
+    /// [jest](https://jestjs.io), a package.json dependency or a jest.config file
+    Jest,
+    /// [vitest](https://vitest.dev), a package.json dependency or a vitest.config file
+    Vitest,
+    /// [mocha](https://mochajs.org), a package.json dependency or a .mocharc file
+    Mocha,
+    /// [pytest](https://pytest.org), configured in pytest.ini, pyproject.toml, setup.cfg or tox.ini, a conftest.py
+    /// or a requirement
+    Pytest,
+    /// python's unittest, imported by test files
+    Unittest,
+    /// ```go test```, run on the _test.go files of a Go module
+    GoTest,
+    /// [rspec](https://rspec.info), a .rspec file, spec/spec_helper.rb or a Gemfile dependency
+    Rspec,
+}
+
+const JS_CONFIG_EXTENSIONS: [&str; 6] = ["js", "cjs", "mjs", "ts", "cts", "mts"];
+
+fn read(vfs: &dyn Vfs, path: &str) -> Option<String> {
+    vfs.read_to_string(Path::new(path)).ok()
+}
+
+/// Whether one of the ```<name>.<extension>``` config files exists
+fn has_config(vfs: &dyn Vfs, name: &str, extensions: &[&str]) -> bool {
+    extensions
+        .iter()
+        .any(|extension| vfs.is_file(Path::new(&format!("{}.{}", name, extension))))
+}
+
+/// Frameworks among the dependencies of package.json, or configured under their own key as jest can be
+fn node_frameworks(content: &str) -> Vec<TestFramework> {
+    let manifest: serde_json::Value = match serde_json::from_str(content) {
+        Ok(manifest) => manifest,
+        _ => return vec![],
+    };
+    let depends = |name: &str| {
+        ["dependencies", "devDependencies"]
+            .iter()
+            .any(|deps| manifest[*deps].get(name).is_some())
+    };
+
+    let mut frameworks = vec![];
+    if depends("jest") || manifest.get("jest").is_some() {
+        frameworks.push(TestFramework::Jest);
+    }
+    if depends("vitest") {
+        frameworks.push(TestFramework::Vitest);
+    }
+    if depends("mocha") || manifest.get("mocha").is_some() {
+        frameworks.push(TestFramework::Mocha);
+    }
+
+    frameworks
+}
+
+fn uses_pytest(vfs: &dyn Vfs) -> bool {
+    if vfs.is_file(Path::new("pytest.ini")) || vfs.is_file(Path::new("conftest.py")) {
+        return true;
+    }
+
+    let configured = [
+        ("pyproject.toml", "[tool.pytest"),
+        ("setup.cfg", "[tool:pytest]"),
+        ("tox.ini", "[pytest]"),
+    ];
+    if configured
+        .iter()
+        .any(|(file, section)| read(vfs, file).is_some_and(|content| content.contains(section)))
+    {
+        return true;
+    }
+
+    ["requirements.txt", "requirements-dev.txt", "pyproject.toml", "Pipfile"]
+        .iter()
+        .filter_map(|file| read(vfs, file))
+        .any(|content| content.lines().any(|line| line.trim_start().trim_start_matches('"').starts_with("pytest")))
+}
+
+/// Test files of the languages whose frameworks are only told from them: Go test files & python test modules
+fn test_files(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<Vec<(PathBuf, bool)>> {
+    let entries = vfs.walk(&mut |e| {
+        e.depth == 0 || !(code::is_hidden(e) || ruleset.is_some_and(|ruleset| code::is_ignored(ruleset, e)))
+    })?;
+
+    Ok(entries
+        .iter()
+        .filter(|e| e.is_file)
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let is_go = name.ends_with("_test.go");
+            let is_python = name.ends_with(".py") && (name.starts_with("test") || name.ends_with("_test.py"));
+            let rel_path = e.path.strip_prefix(vfs.root()).ok()?;
+            (is_go || is_python).then(|| (rel_path.to_path_buf(), is_go))
+        })
+        .collect())
+}
+
+/// The test frameworks a project uses, from its manifests & config files, in [TestFramework] order. Go & python
+/// test files are read to tell ```go test``` & unittest, the entries the ruleset ignores aside
+pub(crate) fn detect_frameworks(vfs: &dyn Vfs, ruleset: Option<&RuleSet>) -> Result<Vec<TestFramework>> {
+    let mut frameworks = vec![];
+
+    if vfs.is_file(Path::new("Cargo.toml")) {
+        frameworks.push(TestFramework::CargoTest);
+    }
+    if vfs.is_file(Path::new(".config/nextest.toml")) {
+        frameworks.push(TestFramework::Nextest);
+    }
+
+    if let Some(content) = read(vfs, "package.json") {
+        frameworks.extend(node_frameworks(&content));
+    }
+    if has_config(vfs, "jest.config", &[&JS_CONFIG_EXTENSIONS[..], &["json"]].concat()) {
+        frameworks.push(TestFramework::Jest);
+    }
+    if has_config(vfs, "vitest.config", &JS_CONFIG_EXTENSIONS) {
+        frameworks.push(TestFramework::Vitest);
+    }
+    if has_config(vfs, ".mocharc", &["js", "cjs", "json", "jsonc", "yml", "yaml"]) {
+        frameworks.push(TestFramework::Mocha);
+    }
+
+    if uses_pytest(vfs) {
+        frameworks.push(TestFramework::Pytest);
+    }
+
+    let is_go_module = vfs.is_file(Path::new("go.mod"));
+    for (path, is_go) in test_files(vfs, ruleset)? {
+        if is_go && is_go_module {
+            frameworks.push(TestFramework::GoTest);
+        } else if !is_go {
+            let content = vfs.read_to_string(&path).unwrap_or_default();
+            if content.contains("import unittest") || content.contains("from unittest") {
+                frameworks.push(TestFramework::Unittest);
+            }
+        }
+    }
+
+    let rspec = vfs.is_file(Path::new(".rspec"))
+        || vfs.is_file(Path::new("spec/spec_helper.rb"))
+        || read(vfs, "Gemfile").is_some_and(|content| content.contains("rspec"));
+    if rspec {
+        frameworks.push(TestFramework::Rspec);
+    }
+
+    frameworks.sort();
+    frameworks.dedup();
+
+    Ok(frameworks)
+}
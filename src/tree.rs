@@ -0,0 +1,303 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use globset::GlobBuilder;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::code;
+use crate::notebook;
+use crate::stats::{Count, StatsLang};
+use crate::ruleset::RuleSet;
+use crate::vfs::{Vfs, VfsEntry};
+
+/// Snapshot of the project files, captured in a single walk by [method.file_tree]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTree {
+    /// the project directory
+    pub root: TreeNode,
+}
+
+/// A file or directory of the [FileTree]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    /// file or directory name
+    pub name: String,
+    /// path relative to the project directory
+    pub path: PathBuf,
+    /// whether the node is a directory
+    pub is_dir: bool,
+    /// size in bytes. The size of a directory is the total size of the files within it
+    pub size: u64,
+    /// last modification time of files, None for directories and when it is unknown, as for projects held in memory
+    pub modified: Option<SystemTime>,
+    /// language of code files, as named by the code stats
+    pub lang: Option<String>,
+    /// whether the gitignore rules ignore the node. Ignored directories are not walked, so they have no children
+    pub is_ignored: bool,
+    /// the directory content, sorted by name
+    pub children: Vec<TreeNode>,
+}
+
+impl FileTree {
+    /// All the nodes of the tree, parents before their children
+    /// ```no_run
+    /// let tree = project.file_tree()?;
+    /// let rust_files = tree.iter().filter(|node| node.lang.as_deref() == Some("Rust")).count();
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = &TreeNode> {
+        let mut stack = vec![&self.root];
+
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Renders the tree the way the ```tree``` command does, down to ```depth``` levels below the project directory
+    /// Ignored entries are left out, unless ```show_ignored``` is true in which case they are marked **[ignored]**
+    /// ```no_run
+    /// println!("{}", tree.render(Some(2), false));
+    /// ```
+    pub fn render(&self, depth: Option<usize>, show_ignored: bool) -> String {
+        let name = if self.root.name.is_empty() {
+            "."
+        } else {
+            &self.root.name
+        };
+        let mut out = format!("{}\n", name);
+        let (mut dirs, mut files) = (0, 0);
+
+        render_children(
+            &self.root,
+            "",
+            1,
+            depth,
+            show_ignored,
+            &mut out,
+            &mut dirs,
+            &mut files,
+        );
+
+        out.push_str(&format!("\n{} directories, {} files\n", dirs, files));
+        out
+    }
+
+    /// Paths, relative to the project directory, of the non ignored files & directories matching a glob pattern
+    /// such as ```src/**/*.rs```. ```*``` does not match across directories, ```**``` does
+    /// ```no_run
+    /// let sources = tree.glob("src/**/*.rs", false)?;
+    /// ```
+    pub fn glob(&self, pattern: &str, case_insensitive: bool) -> Result<Vec<PathBuf>> {
+        let matcher = GlobBuilder::new(pattern.trim_start_matches("./"))
+            .literal_separator(true)
+            .case_insensitive(case_insensitive)
+            .build()?
+            .compile_matcher();
+
+        let paths = self
+            .iter()
+            .filter(|node| !node.is_ignored && !node.path.as_os_str().is_empty())
+            .filter(|node| matcher.is_match(&node.path))
+            .map(|node| node.path.clone())
+            .collect();
+
+        Ok(paths)
+    }
+
+    /// Finds the node at a path relative to the project directory
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&TreeNode> {
+        let path = path.as_ref();
+        self.iter().find(|node| node.path == path)
+    }
+}
+
+impl TreeNode {
+    fn new(root: &Path, entry: &VfsEntry, is_ignored: bool) -> Self {
+        let path = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+        let lang = match entry.path.to_str() {
+            Some(path_str) if entry.is_file => match StatsLang::of_path(path_str) {
+                StatsLang::Unrecognized => None,
+                lang => Some(lang.to_string()),
+            },
+            _ => None,
+        };
+
+        TreeNode {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: path.to_path_buf(),
+            is_dir: entry.is_dir,
+            size: entry.size,
+            modified: entry.modified,
+            lang,
+            is_ignored,
+            children: vec![],
+        }
+    }
+
+    /// line counts of a code file, ```root``` being the project directory
+    pub(crate) fn count(&self, vfs: &dyn Vfs, root: &Path) -> Result<Count> {
+        // a file that cannot be read counts no lines
+        match vfs.read_to_string(&self.path) {
+            Ok(content) => self.count_content(vfs, root, &content),
+            Err(_) => Ok(Count::default()),
+        }
+    }
+
+    /// line counts of a code file whose ```content``` was already read
+    pub(crate) fn count_content(&self, vfs: &dyn Vfs, root: &Path, content: &str) -> Result<Count> {
+        if notebook::is_notebook(&self.path) {
+            if let Some((_, count)) = notebook::count(content) {
+                return Ok(count);
+            }
+        }
+
+        let entry = VfsEntry {
+            path: root.join(&self.path),
+            depth: self.path.components().count(),
+            is_dir: self.is_dir,
+            is_file: !self.is_dir,
+            is_symlink: false,
+            size: self.size,
+            modified: self.modified,
+        };
+        vfs.count(&entry, content)
+    }
+
+    /// directory whose entry has not been met yet
+    fn dir(path: &Path) -> Self {
+        TreeNode {
+            name: String::new(),
+            path: path.to_path_buf(),
+            is_dir: true,
+            size: 0,
+            modified: None,
+            lang: None,
+            is_ignored: false,
+            children: vec![],
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_children(
+    node: &TreeNode,
+    prefix: &str,
+    level: usize,
+    depth: Option<usize>,
+    show_ignored: bool,
+    out: &mut String,
+    dirs: &mut usize,
+    files: &mut usize,
+) {
+    if depth.is_some_and(|depth| level > depth) {
+        return;
+    }
+
+    let children: Vec<&TreeNode> = node
+        .children
+        .iter()
+        .filter(|child| child.name != ".git" && (show_ignored || !child.is_ignored))
+        .collect();
+
+    for (i, child) in children.iter().enumerate() {
+        let last = i == children.len() - 1;
+        let marker = if child.is_ignored { " [ignored]" } else { "" };
+        out.push_str(&format!(
+            "{}{}{}{}\n",
+            prefix,
+            if last { "└── " } else { "├── " },
+            child.name,
+            marker
+        ));
+
+        if child.is_dir {
+            *dirs += 1;
+            let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            render_children(child, &prefix, level + 1, depth, show_ignored, out, dirs, files);
+        } else {
+            *files += 1;
+        }
+    }
+}
+
+/// Walks the project once, marking what the ruleset ignores. Ignored directories and .git are kept as leaves
+pub(crate) fn build(vfs: &dyn Vfs, root: &Path, ruleset: &Option<RuleSet>) -> Result<FileTree> {
+    let mut pruned: Vec<VfsEntry> = vec![];
+
+    let walked = vfs.walk(&mut |e| {
+        let is_leaf = e.depth > 0
+            && e.is_dir
+            && (e.file_name() == ".git"
+                || ruleset.as_ref().is_some_and(|r| code::is_ignored(r, e)));
+        if is_leaf {
+            pruned.push(e.clone());
+        }
+        !is_leaf
+    })?;
+
+    let mut entries: Vec<(VfsEntry, bool)> = walked
+        .into_iter()
+        .map(|e| {
+            let is_ignored =
+                e.depth > 0 && ruleset.as_ref().is_some_and(|r| code::is_ignored(r, &e));
+            (e, is_ignored)
+        })
+        .collect();
+    entries.extend(pruned.into_iter().map(|e| {
+        let is_ignored = e.file_name() != ".git";
+        (e, is_ignored)
+    }));
+
+    // attach the deepest nodes first, so that directories are complete when they are attached to their parents
+    entries.sort_by_key(|(e, _)| std::cmp::Reverse(e.depth));
+
+    let mut nodes: HashMap<PathBuf, TreeNode> = HashMap::new();
+    let mut tree_root: Option<TreeNode> = None;
+
+    for (entry, is_ignored) in entries {
+        let mut node = TreeNode::new(root, &entry, is_ignored);
+        // the language of a notebook is that of its kernel, which only its content tells
+        if entry.is_file && notebook::is_notebook(&node.path) {
+            let counted = vfs.read_to_string(&node.path).ok().and_then(|content| notebook::count(&content));
+            if let Some((lang, _)) = counted {
+                node.lang = Some(lang.to_string());
+            }
+        }
+        if let Some(mut children) = nodes.remove(&node.path).map(|dir| dir.children) {
+            children.sort_by(|a, b| a.name.cmp(&b.name));
+            node.size = children.iter().map(|child| child.size).sum();
+            node.children = children;
+        }
+
+        match node.path.parent() {
+            Some(parent) if entry.depth > 0 => {
+                let parent = nodes
+                    .entry(parent.to_path_buf())
+                    .or_insert_with(|| TreeNode::dir(parent));
+                parent.children.push(node);
+            }
+            _ => tree_root = Some(node),
+        }
+    }
+
+    let root = tree_root.unwrap_or_else(|| TreeNode::dir(Path::new("")));
+
+    Ok(FileTree { root })
+}
@@ -0,0 +1,383 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The ```project-parse tui``` dashboard: the language breakdown, the project tree with the lines of code of each
+//! directory, and what the ignore rules decide for the selected entry
+
+use anyhow::Result;
+use crossterm::{
+    cursor::Show,
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Row, Table, Tabs, Wrap},
+    Frame, Terminal,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    io, panic,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use project_parse::project::{IgnoreSource, Project};
+use project_parse::stats::CodeStats;
+use project_parse::tree::{FileTree, TreeNode};
+
+const TABS: [&str; 2] = ["Languages", "Tree"];
+const HELP: &str = "q quit  tab switch view  up/down move  right/enter expand  left collapse";
+
+/// State of the dashboard
+struct App {
+    project: Project,
+    snapshot: CodeStats,
+    tree: FileTree,
+    /// lines of code of each file & directory, directories adding up what they hold
+    loc: HashMap<PathBuf, u64>,
+    /// directories whose content is listed
+    expanded: BTreeSet<PathBuf>,
+    tab: usize,
+    selected: ListState,
+}
+
+impl App {
+    fn new(mut project: Project) -> Result<App> {
+        let snapshot = project.code_snapshot()?;
+        let tree = project.file_tree()?;
+
+        let mut loc: HashMap<PathBuf, u64> = HashMap::new();
+        for (path, file) in &snapshot.files {
+            for ancestor in path.ancestors() {
+                *loc.entry(ancestor.to_path_buf()).or_default() += file.code;
+            }
+        }
+
+        let mut selected = ListState::default();
+        selected.select(Some(0));
+
+        Ok(App {
+            project,
+            snapshot,
+            tree,
+            loc,
+            expanded: BTreeSet::from([PathBuf::new()]),
+            tab: 0,
+            selected,
+        })
+    }
+
+    /// The nodes listed, with their depth: the children of the expanded directories, parents first
+    fn visible(&self) -> Vec<(usize, &TreeNode)> {
+        let mut visible = vec![];
+        let mut stack: Vec<(usize, &TreeNode)> = self.tree.root.children.iter().rev().map(|node| (0, node)).collect();
+
+        while let Some((depth, node)) = stack.pop() {
+            visible.push((depth, node));
+            if node.is_dir && self.expanded.contains(&node.path) {
+                stack.extend(node.children.iter().rev().map(|child| (depth + 1, child)));
+            }
+        }
+
+        visible
+    }
+
+    fn selected_node(&self) -> Option<&TreeNode> {
+        let index = self.selected.selected()?;
+        self.visible().get(index).map(|(_, node)| *node)
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        let count = self.visible().len();
+        if count == 0 {
+            return;
+        }
+
+        let index = self.selected.selected().unwrap_or(0) as isize + offset;
+        self.selected.select(Some(index.clamp(0, count as isize - 1) as usize));
+    }
+
+    fn set_expanded(&mut self, expanded: bool) {
+        let path = match self.selected_node() {
+            Some(node) if node.is_dir => node.path.clone(),
+            _ => return,
+        };
+
+        match expanded {
+            true => self.expanded.insert(path),
+            false => self.expanded.remove(&path),
+        };
+    }
+
+    /// Handles a key, returning false to quit
+    fn on_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Tab | KeyCode::BackTab => self.tab = (self.tab + 1) % TABS.len(),
+            KeyCode::Char('1') => self.tab = 0,
+            KeyCode::Char('2') => self.tab = 1,
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::Right | KeyCode::Enter => self.set_expanded(true),
+            KeyCode::Left => self.set_expanded(false),
+            _ => {}
+        }
+
+        true
+    }
+}
+
+fn block(title: &str) -> Block<'_> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+fn draw_langs(frame: &mut Frame, app: &App, area: Rect) {
+    let total = app.snapshot.total().code.max(1) as f64;
+    let mut rows: Vec<Row> = app
+        .snapshot
+        .by_lines_desc()
+        .into_iter()
+        .map(|(lang, stats)| {
+            let share = stats.code as f64 / total;
+            Row::new(vec![
+                lang.to_string(),
+                stats.files.to_string(),
+                stats.code.to_string(),
+                stats.comment.to_string(),
+                stats.blank.to_string(),
+                format!("{:5.1}% {}", share * 100.0, "█".repeat((share * 20.0).round() as usize)),
+            ])
+        })
+        .collect();
+
+    let totals = app.snapshot.total();
+    rows.push(
+        Row::new(vec![
+            String::from("Total"),
+            totals.files.to_string(),
+            totals.code.to_string(),
+            totals.comment.to_string(),
+            totals.blank.to_string(),
+            String::new(),
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    );
+
+    let widths = [
+        Constraint::Percentage(25),
+        Constraint::Length(8),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Min(28),
+    ];
+    let header = Row::new(vec!["language", "files", "code", "comment", "blank", "share"])
+        .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+    frame.render_widget(Table::new(rows, widths).header(header).block(block("Lines of code")), area);
+}
+
+/// What the rules decide for the selected entry
+fn inspector(app: &App, node: &TreeNode) -> Vec<Line<'static>> {
+    let path = node.path.to_string_lossy().to_string();
+    let label = |name: &str| Span::styled(format!("{:<10}", name), Style::default().fg(Color::Yellow));
+
+    let mut lines = vec![
+        Line::from(vec![label("path"), Span::raw(path.clone())]),
+        Line::from(vec![
+            label("kind"),
+            Span::raw(if node.is_dir { "directory" } else { "file" }),
+        ]),
+        Line::from(vec![label("size"), Span::raw(format!("{} bytes", node.size))]),
+        Line::from(vec![
+            label("language"),
+            Span::raw(node.lang.clone().unwrap_or_else(|| String::from("-"))),
+        ]),
+        Line::from(vec![
+            label("code"),
+            Span::raw(format!("{} lines", app.loc.get(&node.path).copied().unwrap_or(0))),
+        ]),
+        Line::from(""),
+    ];
+
+    match app.project.is_ignored(&path) {
+        Some(answer) => {
+            let (verdict, color) = match answer.is_ignored() {
+                true => ("ignored", Color::Red),
+                false => ("kept", Color::Green),
+            };
+            let decided_by = match (answer.source(), answer.rule()) {
+                (IgnoreSource::Rule, Some(rule)) => format!("rule {}", rule),
+                (IgnoreSource::NoRuleset, _) => String::from("no rules were built"),
+                _ => String::from("no rule matches"),
+            };
+
+            lines.push(Line::from(vec![
+                label("ignored"),
+                Span::styled(verdict, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            ]));
+            lines.push(Line::from(vec![label("decided"), Span::raw(decided_by)]));
+            lines.push(Line::from(vec![
+                label("checked"),
+                Span::raw(answer.path().to_string_lossy().to_string()),
+            ]));
+        }
+        None => lines.push(Line::from("the rules cannot be checked")),
+    }
+
+    lines
+}
+
+fn draw_tree(frame: &mut Frame, app: &mut App, area: Rect) {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(area);
+
+    let items: Vec<ListItem> = app
+        .visible()
+        .into_iter()
+        .map(|(depth, node)| {
+            let marker = match (node.is_dir, app.expanded.contains(&node.path)) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                _ => "  ",
+            };
+            let loc = app.loc.get(&node.path).copied().unwrap_or(0);
+            let style = match node.is_ignored {
+                true => Style::default().fg(Color::DarkGray),
+                false => Style::default(),
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}{}{}", "  ".repeat(depth), marker, node.name), style),
+                Span::styled(format!("  {}", loc), Style::default().fg(Color::Cyan)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block("Files (lines of code)"))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, columns[0], &mut app.selected);
+
+    let details = match app.selected_node() {
+        Some(node) => inspector(app, node),
+        None => vec![Line::from("the project is empty")],
+    };
+    frame.render_widget(
+        Paragraph::new(details)
+            .block(block("Ignore rules"))
+            .wrap(Wrap { trim: false }),
+        columns[1],
+    );
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)])
+        .split(frame.size());
+
+    let title = format!("project-parse {}", app.project.dir.display());
+    let tabs = Tabs::new(TABS.to_vec())
+        .select(app.tab)
+        .block(block(&title))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    frame.render_widget(tabs, rows[0]);
+
+    match app.tab {
+        0 => draw_langs(frame, app, rows[1]),
+        _ => draw_tree(frame, app, rows[1]),
+    }
+
+    frame.render_widget(Paragraph::new(HELP).style(Style::default().fg(Color::DarkGray)), rows[2]);
+}
+
+/// Leaves the alternate screen & raw mode and shows the cursor. Failures are ignored, nothing more can be done
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+}
+
+/// Raw mode & the alternate screen, left when dropped, on errors & panics alike
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> Result<TerminalGuard> {
+        enable_raw_mode()?;
+        // from here on, dropping the guard undoes raw mode should the screen fail to switch
+        let guard = TerminalGuard;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Runs the dashboard until the user quits, restoring the terminal whatever happens
+pub(crate) fn run(project: Project) -> Result<()> {
+    let mut app = App::new(project)?;
+
+    // the terminal is restored before a panic is reported, for the message to show on the main screen
+    let previous_hook: Arc<dyn Fn(&panic::PanicHookInfo) + Send + Sync> = Arc::from(panic::take_hook());
+    let hook = previous_hook.clone();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        hook(info);
+    }));
+
+    let result = run_in_terminal(&mut app);
+    panic::set_hook(Box::new(move |info| previous_hook(info)));
+
+    result
+}
+
+/// Takes over the terminal for the event loop, handing it back when the loop ends or fails
+fn run_in_terminal(app: &mut App) -> Result<()> {
+    let _guard = TerminalGuard::enter()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+    event_loop(&mut terminal, app)
+}
+
+fn event_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !app.on_key(key.code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
@@ -0,0 +1,401 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{anyhow, Result};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::OsString,
+    fmt::Debug,
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+use walkdir::WalkDir;
+
+use crate::stats::Count;
+
+/// Resolves the ```.``` & ```..``` components of a path without touching the file system
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// Whether an entry kept by ```filter``` was modified after ```since```. Directories are modified when entries are
+/// added to, removed from or renamed within them, files when they are written. Entries whose modification time is
+/// unknown, as those held in memory, are never modified
+pub(crate) fn modified_since(
+    vfs: &dyn Vfs,
+    since: SystemTime,
+    filter: &mut dyn FnMut(&VfsEntry) -> bool,
+) -> Result<bool> {
+    let entries = vfs.walk(filter)?;
+
+    Ok(entries.iter().any(|e| {
+        // the walk only reads the modification time of files
+        let modified = match e.modified {
+            Some(modified) => Some(modified),
+            None if e.is_dir => e.path.strip_prefix(vfs.root()).ok().and_then(|rel_path| vfs.modified(rel_path)),
+            None => None,
+        };
+        modified.is_some_and(|modified| modified > since)
+    }))
+}
+
+/// An entry met while walking the project
+#[derive(Debug, Clone)]
+pub(crate) struct VfsEntry {
+    /// full path, the project directory joined with the relative path
+    pub path: PathBuf,
+    /// depth below the project directory, which is 0
+    pub depth: usize,
+    pub is_dir: bool,
+    pub is_file: bool,
+    /// symbolic links are not followed, they are neither files nor directories
+    pub is_symlink: bool,
+    /// size of files in bytes, 0 for directories
+    pub size: u64,
+    /// last modification time of files, None for directories and when it is unknown
+    pub modified: Option<SystemTime>,
+}
+
+impl VfsEntry {
+    pub fn file_name(&self) -> OsString {
+        self.path.file_name().map(OsString::from).unwrap_or_default()
+    }
+}
+
+/// The files a Project is read from. Paths are relative to the project directory.
+/// Everything reading the project goes through this, so that projects can live on disk or in memory
+pub(crate) trait Vfs: Debug + Send + Sync {
+    /// the project directory
+    fn root(&self) -> &Path;
+    fn is_file(&self, rel_path: &Path) -> bool;
+    fn is_dir(&self, rel_path: &Path) -> bool;
+    fn read_to_string(&self, rel_path: &Path) -> Result<String>;
+    /// the bytes of a file, whatever their encoding
+    fn read(&self, rel_path: &Path) -> Result<Vec<u8>>;
+    /// names of the entries directly within a directory
+    fn read_dir(&self, rel_path: &Path) -> Result<Vec<OsString>>;
+    /// the files within a directory, as a project directory of their own
+    fn sub(&self, rel_path: &Path) -> Arc<dyn Vfs>;
+    /// replaces the content of a file
+    fn write(&self, rel_path: &Path, content: &str) -> Result<()>;
+    /// last modification time, None when it is unknown or the entry does not exist
+    fn modified(&self, rel_path: &Path) -> Option<SystemTime>;
+    /// unix permission bits, None when they are unknown, as on other platforms, or the entry does not exist
+    fn mode(&self, rel_path: &Path) -> Option<u32>;
+    /// line counts of a file whose language was recognized from its extension, from its ```content``` already read
+    fn count(&self, entry: &VfsEntry, content: &str) -> Result<Count>;
+    /// walks the project depth first, the project directory first. Entries ```filter``` returns false for are
+    /// left out together with everything within them, directories being pruned before they are read. The entries
+    /// passed to ```filter``` may have no size yet
+    fn walk(&self, filter: &mut dyn FnMut(&VfsEntry) -> bool) -> Result<Vec<VfsEntry>>;
+
+    fn exists(&self, rel_path: &Path) -> bool {
+        self.is_file(rel_path) || self.is_dir(rel_path)
+    }
+}
+
+/// Project directory on disk
+#[derive(Debug)]
+pub(crate) struct DiskFs {
+    root: PathBuf,
+}
+
+impl DiskFs {
+    pub fn new(root: &Path) -> Self {
+        DiskFs {
+            root: root.to_path_buf(),
+        }
+    }
+}
+
+impl Vfs for DiskFs {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn is_file(&self, rel_path: &Path) -> bool {
+        self.root.join(rel_path).is_file()
+    }
+
+    fn is_dir(&self, rel_path: &Path) -> bool {
+        self.root.join(rel_path).is_dir()
+    }
+
+    fn read_to_string(&self, rel_path: &Path) -> Result<String> {
+        Ok(fs::read_to_string(self.root.join(rel_path))?)
+    }
+
+    fn read(&self, rel_path: &Path) -> Result<Vec<u8>> {
+        Ok(fs::read(self.root.join(rel_path))?)
+    }
+
+    fn read_dir(&self, rel_path: &Path) -> Result<Vec<OsString>> {
+        let mut names = vec![];
+        for entry in fs::read_dir(self.root.join(rel_path))? {
+            names.push(entry?.file_name());
+        }
+
+        Ok(names)
+    }
+
+    fn sub(&self, rel_path: &Path) -> Arc<dyn Vfs> {
+        Arc::new(DiskFs::new(&self.root.join(rel_path)))
+    }
+
+    fn write(&self, rel_path: &Path, content: &str) -> Result<()> {
+        Ok(fs::write(self.root.join(rel_path), content)?)
+    }
+
+    fn modified(&self, rel_path: &Path) -> Option<SystemTime> {
+        fs::metadata(self.root.join(rel_path))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    #[cfg(unix)]
+    fn mode(&self, rel_path: &Path) -> Option<u32> {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::metadata(self.root.join(rel_path))
+            .map(|metadata| metadata.permissions().mode() & 0o7777)
+            .ok()
+    }
+
+    #[cfg(not(unix))]
+    fn mode(&self, _rel_path: &Path) -> Option<u32> {
+        None
+    }
+
+    fn count(&self, entry: &VfsEntry, content: &str) -> Result<Count> {
+        match entry.path.to_str() {
+            Some(path_str) => Ok(Count::of_content(path_str, content)),
+            None => Err(anyhow!("{:?} is not a valid UTF-8 path", entry.path)),
+        }
+    }
+
+    fn walk(&self, filter: &mut dyn FnMut(&VfsEntry) -> bool) -> Result<Vec<VfsEntry>> {
+        let mut entries = vec![];
+        let walker = WalkDir::new(&self.root).into_iter();
+
+        // the file type comes with the directory listing, entries are only stat'ed for the size of the files kept
+        for entry in walker.filter_entry(|e| {
+            filter(&VfsEntry {
+                path: e.path().to_path_buf(),
+                depth: e.depth(),
+                is_dir: e.file_type().is_dir(),
+                is_file: e.file_type().is_file(),
+                is_symlink: e.file_type().is_symlink(),
+                size: 0,
+                modified: None,
+            })
+        }) {
+            let e = entry?;
+            let is_file = e.file_type().is_file();
+            let metadata = if is_file { Some(e.metadata()?) } else { None };
+            entries.push(VfsEntry {
+                path: e.path().to_path_buf(),
+                depth: e.depth(),
+                is_dir: e.file_type().is_dir(),
+                is_file,
+                is_symlink: e.file_type().is_symlink(),
+                size: metadata.as_ref().map_or(0, |metadata| metadata.len()),
+                modified: metadata.and_then(|metadata| metadata.modified().ok()),
+            });
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Project held in memory: file contents keyed by their relative paths, plus the directories they imply
+#[derive(Debug, Default)]
+pub(crate) struct MemFs {
+    root: PathBuf,
+    files: BTreeMap<PathBuf, String>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl MemFs {
+    pub fn new(root: &Path) -> Self {
+        MemFs {
+            root: root.to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a file, or a directory when the path ends with a slash. Parent directories are added too
+    pub fn add(&mut self, path: &str, content: String) {
+        let is_dir = path.ends_with('/') || path.ends_with('\\');
+        let rel_path: PathBuf = Path::new(path)
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+
+        if rel_path.as_os_str().is_empty() {
+            return;
+        }
+
+        for parent in rel_path.ancestors().skip(1) {
+            if !parent.as_os_str().is_empty() {
+                self.dirs.insert(parent.to_path_buf());
+            }
+        }
+
+        if is_dir {
+            self.dirs.insert(rel_path);
+        } else {
+            self.files.insert(rel_path, content);
+        }
+    }
+
+    fn walk_dir(
+        &self,
+        rel_path: &Path,
+        depth: usize,
+        filter: &mut dyn FnMut(&VfsEntry) -> bool,
+        entries: &mut Vec<VfsEntry>,
+    ) -> Result<()> {
+        let entry = VfsEntry {
+            path: self.root.join(rel_path),
+            depth,
+            is_dir: self.is_dir(rel_path),
+            is_file: self.is_file(rel_path),
+            is_symlink: false,
+            size: self.files.get(rel_path).map_or(0, |content| content.len() as u64),
+            modified: None,
+        };
+
+        if !filter(&entry) {
+            return Ok(());
+        }
+        entries.push(entry);
+
+        if self.is_dir(rel_path) {
+            for name in self.read_dir(rel_path)? {
+                self.walk_dir(&rel_path.join(name), depth + 1, filter, entries)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Vfs for MemFs {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn is_file(&self, rel_path: &Path) -> bool {
+        self.files.contains_key(rel_path)
+    }
+
+    fn is_dir(&self, rel_path: &Path) -> bool {
+        rel_path.as_os_str().is_empty() || self.dirs.contains(rel_path)
+    }
+
+    fn read_to_string(&self, rel_path: &Path) -> Result<String> {
+        self.files
+            .get(rel_path)
+            .cloned()
+            .ok_or_else(|| anyhow!("{:?} not found", rel_path))
+    }
+
+    fn read(&self, rel_path: &Path) -> Result<Vec<u8>> {
+        self.read_to_string(rel_path).map(String::into_bytes)
+    }
+
+    fn read_dir(&self, rel_path: &Path) -> Result<Vec<OsString>> {
+        if !self.is_dir(rel_path) {
+            return Err(anyhow!("{:?} is not a directory", rel_path));
+        }
+
+        let names = self
+            .dirs
+            .iter()
+            .chain(self.files.keys())
+            .filter(|path| path.parent() == Some(rel_path))
+            .filter_map(|path| path.file_name().map(OsString::from))
+            .collect::<BTreeSet<OsString>>();
+
+        Ok(names.into_iter().collect())
+    }
+
+    fn sub(&self, rel_path: &Path) -> Arc<dyn Vfs> {
+        let within = |path: &PathBuf| path.strip_prefix(rel_path).ok().map(Path::to_path_buf);
+
+        Arc::new(MemFs {
+            root: self.root.join(rel_path),
+            files: self
+                .files
+                .iter()
+                .filter_map(|(path, content)| Some((within(path)?, content.clone())))
+                .collect(),
+            dirs: self
+                .dirs
+                .iter()
+                .filter_map(within)
+                .filter(|path| !path.as_os_str().is_empty())
+                .collect(),
+        })
+    }
+
+    /// Entries held in memory are read only
+    fn write(&self, rel_path: &Path, _content: &str) -> Result<()> {
+        Err(anyhow!("{:?} is held in memory and cannot be written", rel_path))
+    }
+
+    /// Entries held in memory have no modification time
+    fn modified(&self, _rel_path: &Path) -> Option<SystemTime> {
+        None
+    }
+
+    /// Entries held in memory have no permissions
+    fn mode(&self, _rel_path: &Path) -> Option<u32> {
+        None
+    }
+
+    /// Lines are counted without recognizing comments, which count as code
+    fn count(&self, _entry: &VfsEntry, content: &str) -> Result<Count> {
+        let lines = content.lines().count() as u64;
+        let blank = content.lines().filter(|line| line.trim().is_empty()).count() as u64;
+
+        Ok(Count {
+            code: lines - blank,
+            comment: 0,
+            blank,
+            lines,
+        })
+    }
+
+    fn walk(&self, filter: &mut dyn FnMut(&VfsEntry) -> bool) -> Result<Vec<VfsEntry>> {
+        let mut entries = vec![];
+        self.walk_dir(Path::new(""), 0, filter, &mut entries)?;
+
+        Ok(entries)
+    }
+}
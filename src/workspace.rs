@@ -0,0 +1,276 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::code::Stats;
+use crate::detector;
+use crate::git;
+use crate::project::{BuildSystem, ParseOptions, Project, ProjectError, ProjectKind, TieBreak};
+use crate::vfs::{DiskFs, Vfs};
+
+/// Options used by [scan_workspace_with] to find & analyze the projects
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// how deep below the root projects are looked for. Projects are not looked for within other projects, except for
+    /// the git checkouts nested in them, found however deep they are
+    pub max_depth: usize,
+    /// analyze several projects at once, one per available core
+    pub parallel: bool,
+    /// use the bundled gitignore templates rather than downloading them, see [Project::offline]
+    pub offline: bool,
+    /// the steps of [Project::analyze_with] performed on each project
+    pub parse: ParseOptions,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            max_depth: 3,
+            parallel: true,
+            offline: false,
+            parse: ParseOptions::default(),
+        }
+    }
+}
+
+/// What was found analyzing a project, in [WorkspaceScan]
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectSummary {
+    /// the project directory, relative to the root. Empty when the root is itself the project
+    pub dir: PathBuf,
+    /// the detected languages, main language first
+    pub langs: Vec<String>,
+    /// the project classification
+    pub kinds: Vec<ProjectKind>,
+    /// the build systems among the detected tools
+    pub build_systems: Vec<BuildSystem>,
+    /// whether the project is a git repository
+    pub is_git: bool,
+    /// line counts keyed by language
+    pub code_stats: Stats,
+    /// lines of code, config & data formats aside, see [StatsLang::is_data](crate::stats::StatsLang::is_data)
+    pub code_lines: u64,
+    /// number of files walked counting the code
+    pub files: usize,
+}
+
+/// A project that could not be analyzed, or a directory that could not be read, in [WorkspaceScan]
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanFailure {
+    /// the project directory, relative to the root
+    pub dir: PathBuf,
+    /// why the analysis failed
+    pub error: String,
+}
+
+/// The projects found under a root, returned by [scan_workspace]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WorkspaceScan {
+    /// the projects analyzed, in path order
+    pub projects: Vec<ProjectSummary>,
+    /// the projects whose analysis failed, and the directories that could not be looked into, in path order. One
+    /// failing project or directory does not stop the others
+    pub failures: Vec<ScanFailure>,
+}
+
+impl WorkspaceScan {
+    /// Lines of code of all the projects
+    pub fn code_lines(&self) -> u64 {
+        self.projects.iter().map(|project| project.code_lines).sum()
+    }
+}
+
+/// A directory is a project when it is a git checkout or languages are detected from its top level entries
+fn is_project(vfs: &dyn Vfs) -> Result<bool> {
+    Ok(vfs.exists(Path::new(".git")) || !detector::detect_lang_from_dir(vfs, 1, &TieBreak::default(), None)?.is_empty())
+}
+
+/// Collects the project directories at or below ```rel_path```, in path order, hidden directories aside. Projects are
+/// not looked for within projects, except for the git checkouts nested in them, which are left out of their stats.
+/// Directories that cannot be read are added to ```failures``` and the others looked into all the same
+pub(crate) fn discover(
+    vfs: &dyn Vfs,
+    rel_path: &Path,
+    depth: usize,
+    max_depth: usize,
+    dirs: &mut Vec<PathBuf>,
+    failures: &mut Vec<ScanFailure>,
+) {
+    if let Err(error) = discover_in(vfs, rel_path, depth, max_depth, dirs, failures) {
+        failures.push(ScanFailure {
+            dir: rel_path.to_path_buf(),
+            error: error.to_string(),
+        });
+    }
+}
+
+fn discover_in(
+    vfs: &dyn Vfs,
+    rel_path: &Path,
+    depth: usize,
+    max_depth: usize,
+    dirs: &mut Vec<PathBuf>,
+    failures: &mut Vec<ScanFailure>,
+) -> Result<()> {
+    let project = vfs.sub(rel_path);
+    if is_project(project.as_ref())? {
+        dirs.push(rel_path.to_path_buf());
+        for repo in git::nested_repos(project.as_ref(), &[])? {
+            discover(vfs, &rel_path.join(repo), depth, max_depth, dirs, failures);
+        }
+        return Ok(());
+    }
+    if depth == max_depth {
+        return Ok(());
+    }
+
+    let mut names = vfs.read_dir(rel_path)?;
+    names.sort();
+    for name in names {
+        let child = rel_path.join(&name);
+        if !name.to_string_lossy().starts_with('.') && vfs.is_dir(&child) {
+            discover(vfs, &child, depth + 1, max_depth, dirs, failures);
+        }
+    }
+
+    Ok(())
+}
+
+/// [summarize], with a panic turned into the error of the project rather than ending the scan
+fn analyze(root: &Path, dir: &Path, options: &ScanOptions) -> Result<ProjectSummary> {
+    panic::catch_unwind(AssertUnwindSafe(|| summarize(root, dir, options))).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(anyhow!("the analysis panicked: {}", message))
+    })
+}
+
+fn summarize(root: &Path, dir: &Path, options: &ScanOptions) -> Result<ProjectSummary> {
+    let mut project = Project::new(root.join(dir))?;
+    project.offline(options.offline);
+    let analysis = project.analyze_with(options.parse.clone())?;
+
+    Ok(ProjectSummary {
+        dir: dir.to_path_buf(),
+        langs: analysis.langs().to_vec(),
+        kinds: analysis.kinds().to_vec(),
+        build_systems: analysis.build_systems().to_vec(),
+        is_git: analysis.is_git(),
+        code_stats: analysis.code_stats().clone(),
+        code_lines: analysis
+            .code_stats()
+            .iter()
+            .filter(|(lang, _)| !lang.is_data())
+            .map(|(_, count)| count.code)
+            .sum(),
+        files: analysis.walk_stats().files,
+    })
+}
+
+/// Finds the projects under a directory of checkouts and analyzes them, several at once. See [scan_workspace_with]
+/// ```no_run
+/// let scan = project_parse::scan_workspace("/my/checkouts")?;
+/// for project in scan.projects {
+///     println!("{:?} {:?} {} LOC", project.dir, project.langs, project.code_lines);
+/// }
+/// ```
+pub fn scan_workspace<P: AsRef<Path>>(root: P) -> Result<WorkspaceScan> {
+    scan_workspace_with(root, &ScanOptions::default())
+}
+
+/// Finds the projects under ```root```, git checkouts and directories whose languages are detected, down to
+/// ```max_depth```, and analyzes each with [Project::analyze_with]. Git checkouts nested in a project are projects of
+/// their own, left out of its stats as [NestedRepos::Separate](crate::project::NestedRepos::Separate) does
+/// ```no_run
+/// let options = ScanOptions {
+///     parallel: false,
+///     offline: true,
+///     ..Default::default()
+/// };
+/// let scan = project_parse::scan_workspace_with("/my/checkouts", &options)?;
+/// println!("{} projects, {} LOC", scan.projects.len(), scan.code_lines());
+/// ```
+pub fn scan_workspace_with<P: AsRef<Path>>(root: P, options: &ScanOptions) -> Result<WorkspaceScan> {
+    let root = root.as_ref();
+    if !root.is_dir() {
+        return Err(anyhow!(ProjectError::NotFound(root.to_string_lossy().to_string())));
+    }
+    let root = root.canonicalize()?;
+
+    let mut dirs = vec![];
+    let mut failures = vec![];
+    discover(&DiskFs::new(&root), Path::new(""), 0, options.max_depth, &mut dirs, &mut failures);
+
+    let threads = if options.parallel {
+        thread::available_parallelism().map_or(1, usize::from).min(dirs.len())
+    } else {
+        1
+    };
+
+    // each worker takes the next project left until there are none
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, Result<ProjectSummary>)>> = Mutex::new(vec![]);
+    let work = || loop {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        let Some(dir) = dirs.get(i) else {
+            break;
+        };
+        let summary = analyze(&root, dir, options);
+        results.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push((i, summary));
+    };
+    if threads > 1 {
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(work);
+            }
+        });
+    } else {
+        work();
+    }
+
+    // analyses catch their panics, none can have poisoned the results
+    let mut results = results.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+    results.sort_by_key(|(i, _)| *i);
+
+    let mut scan = WorkspaceScan {
+        failures,
+        ..Default::default()
+    };
+    for (i, summary) in results {
+        match summary {
+            Ok(summary) => scan.projects.push(summary),
+            Err(error) => scan.failures.push(ScanFailure {
+                dir: dirs[i].clone(),
+                error: error.to_string(),
+            }),
+        }
+    }
+    scan.failures.sort_by(|a, b| a.dir.cmp(&b.dir));
+
+    Ok(scan)
+}
@@ -0,0 +1,7 @@
+package app;
+
+public class App {
+    public static void main(String[] args) {
+        System.out.println("Hello");
+    }
+}
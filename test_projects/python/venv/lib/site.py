@@ -0,0 +1,2 @@
+# vendored library file, should never be counted
+VERSION = "0.0.1"
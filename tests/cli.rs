@@ -0,0 +1,347 @@
+// Copyright 2022 Anthony Mugendi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Tells apart the directories of tests running at the same time
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes the files to a new directory of their own, removed when dropped
+struct TempProject {
+    dir: PathBuf,
+}
+
+impl TempProject {
+    fn new(files: &[(&str, &str)]) -> TempProject {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos();
+        let id = NEXT_DIR.fetch_add(1, Ordering::Relaxed);
+        let dir = env::temp_dir().join(format!("project_parse_cli_{}_{}_{}", std::process::id(), id, nanos));
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        TempProject { dir }
+    }
+
+    fn path(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Runs project-parse with the arguments, from the project directory
+fn run(project: &TempProject, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_project-parse"))
+        .args(args)
+        .current_dir(project.path())
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// The cells of a table line
+#[cfg(feature = "loc")]
+fn words(line: &str) -> Vec<&str> {
+    line.split_whitespace().collect()
+}
+
+fn rust_project() -> TempProject {
+    TempProject::new(&[
+        ("Cargo.toml", "[package]\nname = \"app\"\n"),
+        ("src/main.rs", "fn main() {\n    println!(\"hi\");\n}\n"),
+        (".gitignore", "# local stuff\n\n*.log\n"),
+    ])
+}
+
+#[test]
+fn check_ignore_prints_ignored_paths() {
+    let project = rust_project();
+
+    let output = run(&project, &["check-ignore", "--offline", "app.log", "src/main.rs"]);
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!("app.log\n", stdout(&output));
+
+    let output = run(&project, &["check-ignore", "--offline", "src/main.rs"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("", stdout(&output));
+}
+
+#[test]
+fn check_ignore_verbose_names_the_source_line() {
+    let project = rust_project();
+
+    let output = run(&project, &["check-ignore", "-v", "--offline", "app.log", "target/debug", "src/main.rs"]);
+    assert_eq!(Some(0), output.status.code());
+
+    let stdout = stdout(&output);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(2, lines.len());
+    // <source>:<line>:<pattern>\t<path>, as git check-ignore -v prints it
+    assert_eq!(".gitignore:3:*.log\tapp.log", lines[0]);
+    let (rule, path) = lines[1].split_once('\t').unwrap();
+    assert_eq!("target/debug", path);
+    let fields: Vec<&str> = rule.splitn(3, ':').collect();
+    assert_eq!("Rust.gitignore", fields[0]);
+    assert!(fields[1].parse::<usize>().unwrap() > 0);
+    assert!(["debug/", "target/", "/target/", "/target"].contains(&fields[2]), "{}", rule);
+}
+
+#[test]
+fn check_ignore_fails_outside_the_project() {
+    let project = rust_project();
+
+    let output = run(&project, &["check-ignore", "--offline", "../elsewhere.log"]);
+    assert_eq!(Some(128), output.status.code());
+}
+
+#[cfg(feature = "loc")]
+#[test]
+fn stats_formats() {
+    let project = rust_project();
+
+    let output = run(&project, &["stats", "--offline"]);
+    assert_eq!(Some(0), output.status.code());
+    let table = stdout(&output);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(vec!["language", "files", "code", "comment", "blank"], words(lines[0]));
+    assert!(lines.iter().any(|line| words(line) == ["Rust", "1", "3", "0", "0"]), "{}", table);
+    assert!(lines.last().unwrap().starts_with("Total"));
+
+    let output = run(&project, &["stats", "--offline", "--format", "json"]);
+    assert_eq!(Some(0), output.status.code());
+    let langs: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(3, langs["Rust"]["code"]);
+    assert_eq!(1, langs["Rust"]["files"]);
+
+    let output = run(&project, &["stats", "--offline", "--format=csv"]);
+    assert_eq!(Some(0), output.status.code());
+    let csv = stdout(&output);
+    assert_eq!(Some("language,files,code,comment,blank"), csv.lines().next());
+    assert!(csv.lines().any(|line| line == "Rust,1,3,0,0"), "{}", csv);
+}
+
+#[cfg(feature = "loc")]
+#[test]
+fn stats_per_file() {
+    let project = rust_project();
+
+    let output = run(&project, &["stats", "--offline", "--per-file", "--format", "json"]);
+    assert_eq!(Some(0), output.status.code());
+    let files: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let main = files.as_array().unwrap().iter().find(|file| file["path"] == "src/main.rs").unwrap();
+    assert_eq!("Rust", main["lang"]);
+    assert_eq!(3, main["code"]);
+
+    let output = run(&project, &["stats", "--offline", "--per-file"]);
+    assert_eq!(Some(0), output.status.code());
+    let table = stdout(&output);
+    assert!(table.lines().any(|line| words(line) == ["src/main.rs", "Rust", "3"]), "{}", table);
+}
+
+#[test]
+fn stats_exit_codes() {
+    // no code to count
+    let project = TempProject::new(&[("notes/.keep", "")]);
+    let output = run(&project, &["stats", "--offline"]);
+    assert_eq!(Some(1), output.status.code());
+
+    let project = rust_project();
+    let output = run(&project, &["stats", "--format", "xml"]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown format xml"));
+
+    let output = run(&project, &["stats", "--lines"]);
+    assert_eq!(Some(2), output.status.code());
+
+    let output = run(&project, &["stats", "--help"]);
+    assert_eq!(Some(0), output.status.code());
+    assert!(stdout(&output).contains("Commands:"));
+}
+
+#[test]
+fn gitignore_prints_detected_templates() {
+    let project = rust_project();
+
+    let output = run(&project, &["gitignore", "--offline"]);
+    assert_eq!(Some(0), output.status.code());
+    let gitignore = stdout(&output);
+    assert!(gitignore.contains("### Rust ###"), "{}", gitignore);
+    assert!(gitignore.lines().any(|line| line.contains("target")), "{}", gitignore);
+    // printing leaves the project .gitignore as it was
+    assert_eq!("# local stuff\n\n*.log\n", fs::read_to_string(project.path().join(".gitignore")).unwrap());
+
+    let output = run(&project, &["gitignore", "--offline", "--langs", "node"]);
+    assert_eq!(Some(0), output.status.code());
+    let gitignore = stdout(&output);
+    assert!(gitignore.contains("### Node ###"), "{}", gitignore);
+    assert!(!gitignore.contains("### Rust ###"), "{}", gitignore);
+}
+
+#[test]
+fn gitignore_merges_and_writes() {
+    let project = rust_project();
+
+    let output = run(&project, &["gitignore", "--offline", "--merge-existing"]);
+    assert_eq!(Some(0), output.status.code());
+    let merged = stdout(&output);
+    assert!(merged.starts_with("# local stuff\n\n*.log\n"), "{}", merged);
+    assert!(merged.contains("### Rust ###"), "{}", merged);
+
+    let output = run(&project, &["gitignore", "--offline", "--merge-existing", "--write"]);
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!("", stdout(&output));
+    assert_eq!(merged, fs::read_to_string(project.path().join(".gitignore")).unwrap());
+
+    // merging again adds nothing
+    let output = run(&project, &["gitignore", "--offline", "--merge-existing"]);
+    assert_eq!(merged, stdout(&output));
+}
+
+#[test]
+fn gitignore_exit_codes() {
+    // no language or tool to take a template from
+    let project = TempProject::new(&[("notes/.keep", "")]);
+    let output = run(&project, &["gitignore", "--offline"]);
+    assert_eq!(Some(1), output.status.code());
+    assert!(!project.path().join(".gitignore").exists());
+
+    let project = rust_project();
+    let output = run(&project, &["gitignore", "--offline", "--langs", "no-such-lang"]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no gitignore template for no-such-lang"));
+
+    let output = run(&project, &["gitignore", "--langs"]);
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[cfg(feature = "loc")]
+#[test]
+fn watch_redraws_on_changes() {
+    use std::{
+        io::{BufRead, BufReader},
+        process::Stdio,
+        sync::mpsc,
+        thread,
+        time::Duration,
+    };
+
+    let project = rust_project();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_project-parse"))
+        .args(["watch", "--offline", "--interval", "0.1"])
+        .current_dir(project.path())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (sender, lines) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    // the lines printed until one satisfies the condition, or none if it does not come in time
+    let wait_for = |done: &dyn Fn(&str) -> bool, timeout: Duration| {
+        let mut seen = vec![];
+        while let Ok(line) = lines.recv_timeout(timeout) {
+            seen.push(line);
+            if done(seen.last().unwrap()) {
+                return Some(seen);
+            }
+        }
+        None
+    };
+
+    let first = wait_for(&|line| line.starts_with("Total"), Duration::from_secs(10));
+    // the background scans take their own baseline, so the file keeps growing until a scan sees it change
+    let mut lib = String::new();
+    let mut change = None;
+    for i in 0..50 {
+        lib.push_str(&format!("pub fn lib{}() {{}}\n", i));
+        fs::write(project.path().join("src/lib.rs"), &lib).unwrap();
+        change = wait_for(&|line| line.starts_with("last change"), Duration::from_millis(200));
+        if change.is_some() {
+            break;
+        }
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let first = first.unwrap();
+    assert!(first[0].ends_with("every 100ms, Ctrl-C to stop"), "{:?}", first);
+    assert!(first.iter().any(|line| words(line) == ["Rust", "1", "3", "0", "0"]), "{:?}", first);
+
+    let change = change.unwrap();
+    assert!(change.iter().any(|line| line.starts_with("Rust") && words(line)[1] == "2"), "{:?}", change);
+    let summary = change.last().unwrap().as_str();
+    let expected = ["last change: 1 added, 0 removed, 0 changed", "last change: 0 added, 0 removed, 1 changed"];
+    assert!(expected.contains(&summary), "{}", summary);
+}
+
+#[test]
+fn watch_exit_codes() {
+    let project = rust_project();
+
+    for interval in ["0", "-1", "soon"] {
+        let output = run(&project, &["watch", "--interval", interval]);
+        assert_eq!(Some(2), output.status.code(), "{}", interval);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("invalid interval"));
+    }
+
+    let output = run(&project, &["watch", "--help"]);
+    assert_eq!(Some(0), output.status.code());
+    assert!(stdout(&output).contains("--interval seconds"));
+}
+
+#[cfg(not(feature = "tui"))]
+#[test]
+fn tui_needs_the_feature() {
+    let project = rust_project();
+
+    let output = run(&project, &["tui"]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("rebuild it with --features tui"));
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn tui_exit_codes() {
+    let project = rust_project();
+
+    let output = run(&project, &["tui", "--help"]);
+    assert_eq!(Some(0), output.status.code());
+    assert!(stdout(&output).contains("tui "));
+
+    let output = run(&project, &["tui", "--format", "json"]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown option --format"));
+}